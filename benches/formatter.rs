@@ -0,0 +1,72 @@
+//! Benchmarks of the ASCII formatter, run with `cargo bench`.
+//!
+//! Width calculation measures every cell, so these track the cost of stripping
+//! ANSI escape sequences from plain and colored input, and of measuring and
+//! rendering tables of a million rows.
+
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use rcol::cell::Cell;
+use rcol::formatter::{FormatOptions, column_widths, format_to_string, format_to_writer};
+use rcol::processor::TableData;
+use std::io;
+
+const ROWS: usize = 20_000;
+
+const LARGE_ROWS: usize = 1_000_000;
+
+/// A table of `rows` rows with five columns, every cell colored if `colored`.
+fn table(rows: usize, colored: bool) -> TableData {
+    let cell = |text: String| -> Cell {
+        if colored {
+            format!("\x1b[32m{}\x1b[0m", text).into()
+        } else {
+            text.into()
+        }
+    };
+    TableData {
+        headers: ["name", "namespace", "status", "restarts", "age"]
+            .map(Cell::from)
+            .to_vec(),
+        rows: (0..rows)
+            .map(|i| {
+                vec![
+                    cell(format!("pod-{}", i)),
+                    cell(format!("team-{}", i % 17)),
+                    cell("Running".to_string()),
+                    cell((i % 5).to_string()),
+                    cell(format!("{}d", i % 90)),
+                ]
+            })
+            .collect(),
+        original_column_indices: (0..5).collect(),
+    }
+}
+
+fn bench_ascii(c: &mut Criterion) {
+    let opts = FormatOptions::default();
+    let plain = table(ROWS, false);
+    c.bench_function("ascii_plain", |b| {
+        b.iter(|| format_to_string(black_box(&plain), &opts).unwrap())
+    });
+    let colored = table(ROWS, true);
+    c.bench_function("ascii_colored", |b| {
+        b.iter(|| format_to_string(black_box(&colored), &opts).unwrap())
+    });
+}
+
+fn bench_large(c: &mut Criterion) {
+    let opts = FormatOptions::default();
+    let data = table(LARGE_ROWS, false);
+    let mut group = c.benchmark_group("1m_rows");
+    group.sample_size(10);
+    group.bench_function("column_widths", |b| {
+        b.iter(|| column_widths(black_box(&data), &opts))
+    });
+    group.bench_function("ascii", |b| {
+        b.iter(|| format_to_writer(black_box(&data), &opts, &mut io::sink()).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_ascii, bench_large);
+criterion_main!(benches);
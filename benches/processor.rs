@@ -0,0 +1,86 @@
+//! Benchmarks of the processing pipeline, run with `cargo bench`.
+//!
+//! Besides the time, every benchmark prints the number of heap allocations of
+//! one run. Cells share the text of their input line, so it grows with the
+//! number of lines and of cells rewritten on the way through the pipeline.
+
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use rcol::processor::{ProcessOptions, process_input};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const LINES: usize = 100_000;
+
+/// The system allocator, counting the allocations.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// A header and `LINES` lines with five blank separated columns.
+fn lines() -> Vec<String> {
+    let mut lines = vec!["name namespace status restarts age".to_string()];
+    lines.extend(
+        (0..LINES).map(|i| format!("pod-{} team-{} Running {} {}d", i, i % 17, i % 5, i % 90)),
+    );
+    lines
+}
+
+fn bench_process(c: &mut Criterion) {
+    let lines = lines();
+    let cases = [
+        ("process_all_columns", ProcessOptions::default()),
+        (
+            "process_selected_columns",
+            ProcessOptions {
+                columns: vec!["3".to_string(), "1".to_string(), "5".to_string()],
+                ..ProcessOptions::default()
+            },
+        ),
+        (
+            "process_sorted_grouped",
+            ProcessOptions {
+                sortcol: Some(2),
+                gcol: Some(2),
+                ..ProcessOptions::default()
+            },
+        ),
+    ];
+    for (name, opts) in cases {
+        let input = lines.clone();
+        let before = ALLOCATIONS.load(Ordering::Relaxed);
+        let data = process_input(input, &opts).unwrap();
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+        drop(data);
+        eprintln!("{}: {} allocations", name, allocations);
+
+        c.bench_function(name, |b| {
+            b.iter_batched(
+                || lines.clone(),
+                |lines| process_input(lines, &opts).unwrap(),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+}
+
+criterion_group!(benches, bench_process);
+criterion_main!(benches);
@@ -1,27 +1,171 @@
-use clap::Parser;
+use crate::cache::parse_ttl;
+use crate::diff::DIFF_HIGHLIGHTS;
+use crate::formatter::{FormatOptions, HeaderAlign};
+use crate::i18n::Lang;
+use crate::numeric::{NumFmt, NumStyle};
+use crate::preset::Preset;
+use crate::processor::{JoinType, ProcessOptions, Ragged, Trim};
+use clap::{Parser, ValueEnum};
+use clap_complete::Shell;
+use std::io::IsTerminal;
+use std::time::Duration;
 
-/// rcol - Rust Column Formatter
+/// When to color the output, see [`AppArgs::color_enabled`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ColorChoice {
+    /// Color on a terminal unless `NO_COLOR` is set
+    #[default]
+    Auto,
+    /// Always color
+    Always,
+    /// Never color
+    Never,
+}
+
+/// Rust Column Formatter: format and shape unformatted ASCII text into columns
 ///
-/// Format and shape unformatted ASCII text into columns.
+/// rcol formats unformatted ASCII text columns into neatly aligned columns. It reads
+/// input from standard input or a file, processes the data (sorting, grouping,
+/// filtering) and outputs plain text, CSV, JSON, YAML or HTML.
 #[derive(Parser, Debug, Clone)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about)]
 pub struct AppArgs {
     /// Read input from FILENAME
     #[arg(short = 'f', long)]
     pub file: Option<String>,
 
+    /// Run COMMAND with the shell and read its output instead of stdin, e.g. 'kubectl get pods -A'
+    #[arg(long, value_name = "COMMAND", conflicts_with = "file")]
+    pub cmd: Option<String>,
+
+    /// Retry a failing -cmd up to N times
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub retries: u32,
+
+    /// Wait MS milliseconds before the first retry of -cmd, doubling with every further retry
+    #[arg(long, value_name = "MS", default_value_t = 500)]
+    pub backoff: u64,
+
+    /// Reuse the table of an identical -cmd invocation for TTL (seconds, or e.g. 30s, 5m, 2h, 1d) instead of running the command again
+    #[arg(long, value_name = "TTL", value_parser = parse_ttl)]
+    pub cache: Option<Duration>,
+
+    /// Run the -cmd even if a cached table exists (the new table is still cached)
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Re-read the -file or re-run the -cmd every INTERVAL (seconds, or e.g. 30s, 5m; at least 1s) and redraw the table, keeping the column widths stable
+    #[arg(long, value_name = "INTERVAL", value_parser = parse_ttl, conflicts_with_all = ["sqlite", "json_stream", "since"])]
+    pub watch: Option<Duration>,
+
     /// Define a custom header line
     #[arg(short = 'H', long)]
     pub header: Option<String>,
 
-    /// Define the input separator
+    /// Rename output columns, given by number or header name, e.g. '2=Size,USER=Owner' (repeatable)
+    #[arg(long, value_name = "COLUMN=NAME,...")]
+    pub rename: Vec<String>,
+
+    /// Insert a column with the same value in every row at output column N, e.g. '1=env:prod'; later column numbers count it (repeatable)
+    #[arg(long, value_name = "N=HEADER:VALUE")]
+    pub insert: Vec<String>,
+
+    /// Split input column N at DELIMITER into one column per piece, e.g. '3:/'; headers get the suffixes .1, .2, ... and column numbers count the pieces (repeatable)
+    #[arg(long, value_name = "N:DELIMITER")]
+    pub split: Vec<String>,
+
+    /// Join the rows of the table in FILE with the same key (see --on), appending its columns without the key column
+    #[arg(long, value_name = "FILE")]
+    pub join: Option<String>,
+
+    /// Key columns of --join: input column N and column M (default N) of the joined table (default 1)
+    #[arg(long, value_name = "N[:M]", requires = "join")]
+    pub on: Option<String>,
+
+    /// Rows of --join: 'inner' with a match in both tables, 'left' all input rows, 'right' all rows of the joined table
+    #[arg(long, value_enum, value_name = "TYPE", default_value_t = JoinType::Inner, requires = "join")]
+    pub join_type: JoinType,
+
+    /// Define the input separator of one or more characters; 'auto' detects tab, comma, semicolon, pipe or multiple spaces from the first lines
     #[arg(short = 's', long, default_value = " ")]
     pub sep: String,
 
-    /// Treat multiple consecutive separators as a single delimiter
+    /// Define the input separator as regular expression, e.g. '\t|;' or ' {2,}'
+    #[arg(long, value_name = "PATTERN", conflicts_with = "sep")]
+    pub sep_regex: Option<String>,
+
+    /// Treat multiple consecutive separators as a single delimiter (with the default separator: any whitespace)
     #[arg(short = 'm', long)]
     pub mb: bool,
 
+    /// Split lines into at most N columns, the last column keeps the rest of the line (e.g. the COMMAND of ps aux)
+    #[arg(long, visible_alias = "rest-col", value_name = "N")]
+    pub max_cols: Option<usize>,
+
+    /// Don't split at separators within single or double quotes, e.g. keep "John Smith" in one column; the quotes are removed
+    #[arg(long)]
+    pub quoted: bool,
+
+    /// Keep the quotes in the cells of --quoted input
+    #[arg(long, requires = "quoted")]
+    pub keep_quotes: bool,
+
+    /// Expand tabs to spaces with tab stops every WIDTH columns (default 8) before splitting, e.g. --expand-tabs=4
+    #[arg(long, value_name = "WIDTH", num_args = 0..=1, require_equals = true, default_missing_value = "8")]
+    pub expand_tabs: Option<usize>,
+
+    /// Remove white space at the start and/or end of the input lines before splitting; 'none' keeps e.g. the indentation of tree output
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = Trim::Both)]
+    pub trim: Trim,
+
+    /// Remove leading and trailing white space from every column, e.g. of 'a , b' split at ','
+    #[arg(long)]
+    pub trim_cells: bool,
+
+    /// Skip lines starting with MARKER (after leading white space), e.g. '#'
+    #[arg(long, value_name = "MARKER")]
+    pub comment: Option<String>,
+
+    /// Show blank lines between data rows as separator rows; without it blank lines are skipped
+    #[arg(long)]
+    pub keep_blank: bool,
+
+    /// Drop rows whose cells are all empty, e.g. lines of separators only
+    #[arg(long)]
+    pub drop_empty_rows: bool,
+
+    /// Drop output columns whose data cells are all empty, e.g. from trailing separators
+    #[arg(long)]
+    pub drop_empty_cols: bool,
+
+    /// Lay out the input lines as a list in N columns, filled down, then across like 'ls' or 'column' without '-t'
+    #[arg(long, value_name = "N")]
+    pub fill_cols: Option<usize>,
+
+    /// Lay out the input lines as a list in as many columns as fit into WIDTH characters (two spaces between columns)
+    #[arg(long, value_name = "WIDTH", conflicts_with = "fill_cols")]
+    pub fill_width: Option<usize>,
+
+    /// Fill the --fill-cols or --fill-width columns across, then down
+    #[arg(long)]
+    pub fill_across: bool,
+
+    /// Input lines end with NUL instead of newline, e.g. of 'find -print0' or 'grep -z', so they may contain newlines
+    #[arg(short = 'z', long)]
+    pub null: bool,
+
+    /// End output lines with NUL instead of newline, e.g. for 'xargs -0'; newlines in cells are kept
+    #[arg(long)]
+    pub null_out: bool,
+
+    /// Split, filter and replace the rows and measure the columns on all cores, for inputs of millions of rows
+    #[arg(long)]
+    pub parallel: bool,
+
+    /// Keep memory bounded for huge inputs: process the input in chunks, spill the rows to a temporary file and print the table in a second pass
+    #[arg(long, conflicts_with_all = ["watch", "sqlite", "split_by", "chunk", "since", "diff", "query", "cache", "json_stream"])]
+    pub low_mem: bool,
+
     /// Set padding width between columns
     #[arg(short = 'w', long, default_value_t = 1)]
     pub w: usize,
@@ -30,22 +174,170 @@ pub struct AppArgs {
     #[arg(short = 'C', long, default_value = "│")]
     pub colsep: String,
 
-    /// Process only lines matching the given REGEX
+    /// Spaces between a cell and its column boundaries (default: -w); keeps the columns in place when toggling --cs and --pp
+    #[arg(long, value_name = "N")]
+    pub pad_inside: Option<usize>,
+
+    /// Spaces before the first and after the last column without --pp (default 1, the width of the --pp border); keeps the columns in place when toggling --cs and --pp
+    #[arg(long, value_name = "N")]
+    pub pad_outside: Option<usize>,
+
+    /// Process only lines matching the given REGEX (repeatable, a line matching any pattern is kept)
     #[arg(short = 'F', long)]
-    pub filter: Option<String>,
+    pub filter: Vec<String>,
+
+    /// Exclude lines matching the given REGEX (repeatable)
+    #[arg(long)]
+    pub filter_not: Vec<String>,
+
+    /// Keep only rows satisfying a column condition like '3>100' or '2=~^prod' (repeatable, all must hold)
+    #[arg(short = 'W', long = "where")]
+    pub r#where: Vec<String>,
+
+    /// Replace matches of REGEX with '***' in all cells (repeatable)
+    #[arg(long)]
+    pub redact: Vec<String>,
+
+    /// Redact matches of a built-in pattern set (available: secrets)
+    #[arg(long)]
+    pub redact_preset: Vec<String>,
 
     /// Sort output by column N (1-based index)
     #[arg(short = 'S', long)]
     pub sortcol: Option<usize>,
 
+    /// Sort descending instead of ascending (with -sortcol)
+    #[arg(long)]
+    pub sort_desc: bool,
+
+    /// Sort IPv4/IPv6 addresses (also with CIDR prefix or port) by address instead of as text (with -sortcol)
+    #[arg(long, requires = "sortcol")]
+    pub sort_ip: bool,
+
+    /// Reverse the order of the data rows
+    #[arg(long)]
+    pub reverse: bool,
+
+    /// Keep a uniform random sample of N data rows, in their input order unless --shuffle
+    #[arg(long, value_name = "N")]
+    pub sample: Option<usize>,
+
+    /// Put the data rows into random order (before sorting)
+    #[arg(long)]
+    pub shuffle: bool,
+
+    /// Seed of --sample and --shuffle, for the same rows on every run
+    #[arg(long, value_name = "N")]
+    pub seed: Option<u64>,
+
     /// Group by column N
     #[arg(short = 'g', long)]
     pub gcol: Option<usize>,
 
+    /// Compare ä/ö/ü/ß as ae/oe/ue/ss when sorting and grouping
+    #[arg(long)]
+    pub normalize_umlauts: bool,
+
+    /// Lenient: Clamp out-of-range -sortcol/-gcol to the available columns with a warning instead of failing
+    #[arg(long)]
+    pub lenient: bool,
+
     /// When using -gcol, keep the repeated values instead of replacing them with empty strings
     #[arg(long)]
     pub gcolval: bool,
 
+    /// When using -gcol, start every group with a banner row showing its value and drop the group column (ASCII output)
+    #[arg(long, requires = "gcol")]
+    pub gheader: bool,
+
+    /// When using -gcol, append a 'count' column with the number of rows of each group
+    #[arg(long, requires = "gcol")]
+    pub gcount: bool,
+
+    /// Collapse identical adjacent rows into one and append a 'count' column with their number, like 'uniq -c'
+    #[arg(long)]
+    pub uniq_c: bool,
+
+    /// Replace the table with the frequencies of the values in column N: value, count and percent, most frequent first
+    #[arg(long, value_name = "N", conflicts_with_all = ["sortcol", "gcol", "uniq_c"])]
+    pub freq: Option<usize>,
+
+    /// With --freq, add a bar of '#' of at most WIDTH characters (default 20), e.g. --freq-bar=40
+    #[arg(long, value_name = "WIDTH", num_args = 0..=1, require_equals = true, default_missing_value = "20", requires = "freq")]
+    pub freq_bar: Option<usize>,
+
+    /// Replace the table with count, min, max, mean, median and standard deviation of every numeric column
+    #[arg(long, conflicts_with_all = ["sortcol", "gcol", "uniq_c", "freq"])]
+    pub stats: bool,
+
+    /// Replace the table with a cross tabulation: the values of column N label the rows, those of column M become columns, the cells aggregate column V with FUNC (count, sum, min, max, avg or first; default sum, count without V), e.g. 'rows=1 cols=2 value=3 agg=sum'
+    #[arg(long, value_name = "rows=N cols=M [value=V] [agg=FUNC]", conflicts_with_all = ["sortcol", "gcol", "uniq_c", "freq", "stats"])]
+    pub pivot: Option<String>,
+
+    /// Default for cells missing in input column N of ragged rows, e.g. '3:n/a' (repeatable)
+    #[arg(long, value_name = "N:TEXT")]
+    pub fill: Vec<String>,
+
+    /// Rewrite the cells of output column N with a sed-style substitution, e.g. '2:s/^foo-//' (flags g and i; repeatable)
+    #[arg(long, value_name = "N:s/REGEX/TEXT/")]
+    pub replace: Vec<String>,
+
+    /// Apply string operations to output columns and their headers: upper, lower, title or trim, e.g. '1:upper,3:title' (repeatable)
+    #[arg(long, value_name = "N:OP,...")]
+    pub transform: Vec<String>,
+
+    /// Parse output column N with a strftime FORMAT, sort it chronologically and reformat it with the optional second format, e.g. '5:%d/%b/%Y:%H:%M:%S→%Y-%m-%d %H:%M' (repeatable)
+    #[arg(long, value_name = "N:FORMAT[→FORMAT]")]
+    pub datecol: Vec<String>,
+
+    /// Rewrite durations like '5m30s', '2h' or '01:02:03' in output column N as seconds ('N:seconds') or uniformly like '1h02m' ('N:hms') (repeatable)
+    #[arg(long, value_name = "N:seconds|hms")]
+    pub duration: Vec<String>,
+
+    /// Drop malformed rows (wrong number of cells, non-numeric -delta operands) and report their count on stderr
+    #[arg(long)]
+    pub skip_bad_rows: bool,
+
+    /// Fail with the line number on rows with a different number of cells than the header, and on selected or --where columns beyond the input
+    #[arg(long, conflicts_with_all = ["skip_bad_rows", "lenient"])]
+    pub strict: bool,
+
+    /// Don't print warnings about the input (dropped rows, clamped columns) on stderr
+    #[arg(short = 'q', long)]
+    pub quiet: bool,
+
+    /// Exit with status 5 if the output has no data rows, e.g. because no row matched the filters
+    #[arg(long)]
+    pub exit_nonempty: bool,
+
+    /// Rows with more or fewer cells than the header: 'pad' them with empty cells (see --na), 'truncate' extra cells, 'merge-last' joins extra cells into the last column, 'drop' the row with a warning
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = Ragged::Pad, conflicts_with_all = ["skip_bad_rows", "strict"])]
+    pub ragged: Ragged,
+
+    /// Append a column with the difference of output columns A and B, or with ':percent' the change in percent of B (repeatable)
+    #[arg(long, value_name = "A-B[:percent]")]
+    pub delta: Vec<String>,
+
+    /// Number of decimal places for computed values (sums, means, deltas), or with N:PLACES the places the numbers of output column N are rounded to, e.g. '3:2' (repeatable)
+    #[arg(long, value_name = "[N:]PLACES", value_parser = parse_precision)]
+    pub precision: Vec<String>,
+
+    /// Write the numbers of output column N with unit prefixes like 1.2M or 46G, with ':iec' in powers of 1024 like 1.2Mi (e.g. for bytes); sorting uses the original values (repeatable)
+    #[arg(long, value_name = "N[:iec]")]
+    pub humanize: Vec<String>,
+
+    /// Draw the numbers of output column N as bars, MAX (default: the largest value) is a full bar of 20 characters, e.g. '3:100' (repeatable)
+    #[arg(long, value_name = "N[:MAX]")]
+    pub bar: Vec<String>,
+
+    /// With --bar, keep the numbers in front of the bars
+    #[arg(long, requires = "bar")]
+    pub bar_values: bool,
+
+    /// Draw the comma-separated numbers in output column N as sparkline, e.g. '1,5,3,8' as '▁▅▃█' (repeatable)
+    #[arg(long, value_name = "N")]
+    pub spark: Vec<usize>,
+
     /// No Format: Do not align columns to a common width
     #[arg(long)]
     pub nf: bool,
@@ -54,10 +346,30 @@ pub struct AppArgs {
     #[arg(long)]
     pub nn: bool,
 
+    /// Understand numbers with grouping separators or decimal commas for sorting and right-alignment: 'de' (1.234,56), 'en' (1,234.56) or 'auto' (per value)
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    pub numfmt: Option<NumFmt>,
+
+    /// Rewrite all numbers as 'plain' (1234.56), 'de' (1.234,56) or 'en' (1,234.56), numbers with leading zeros are kept
+    #[arg(long, value_enum, value_name = "STYLE")]
+    pub numfmt_out: Option<NumStyle>,
+
+    /// Header alignment: 'marker' aligns right only headers prefixed with '-', 'with-data' also headers of numeric columns
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = HeaderAlign::Marker)]
+    pub align_headers: HeaderAlign,
+
+    /// Titles of column groups in a header row above the headers of ASCII and HTML output, e.g. 'Identity=1:2,Location=3:4'
+    #[arg(long, value_name = "TITLE=N:M,...")]
+    pub group_header: Option<String>,
+
     /// No Headline: Treat the first line of input as data, not a header
     #[arg(long)]
     pub nhl: bool,
 
+    /// Hide Header: Output no header row, e.g. with --nhl
+    #[arg(long)]
+    pub hide_header: bool,
+
     /// Title Separator: Draw a line between the header and data
     #[arg(long)]
     pub ts: bool,
@@ -82,6 +394,10 @@ pub struct AppArgs {
     #[arg(short = 'n', long)]
     pub num: bool,
 
+    /// Output format by name (ascii, csv, json, yaml, html, adoc, rst, jira, sql, screen-reader, rcol, or a format registered by an embedding application)
+    #[arg(long)]
+    pub format: Option<String>,
+
     /// Output as CSV
     #[arg(long)]
     pub csv: bool,
@@ -98,10 +414,192 @@ pub struct AppArgs {
     #[arg(long)]
     pub html: bool,
 
+    /// Output as AsciiDoc table
+    #[arg(long)]
+    pub adoc: bool,
+
+    /// Output as reStructuredText grid table
+    #[arg(long)]
+    pub rst: bool,
+
+    /// Output as Jira/Confluence wiki markup table
+    #[arg(long)]
+    pub jira: bool,
+
+    /// Output for screen readers and braille displays: one 'column: value' line per cell, a blank line between rows, no borders or colors
+    #[arg(long)]
+    pub screen_reader: bool,
+
+    /// Output as SQL INSERT statements into TABLE (default 'data'), e.g. --sql=pods
+    #[arg(long, value_name = "TABLE", num_args = 0..=1, require_equals = true, default_missing_value = "data")]
+    pub sql: Option<String>,
+
+    /// Write a CREATE TABLE statement with inferred column types before the -sql INSERTs
+    #[arg(long)]
+    pub sql_create: bool,
+
+    /// Write the table into the SQLite database FILE instead of printing it
+    #[arg(long, value_name = "FILE")]
+    pub sqlite: Option<String>,
+
+    /// Table of -sqlite, created from the headers with inferred column types if it doesn't exist
+    #[arg(long, value_name = "NAME", default_value = "data")]
+    pub table: String,
+
+    /// Write the rows of each value of output column N with the headers into a file of their own (see -split-out) instead of printing the table
+    #[arg(long, value_name = "N", requires = "split_out", conflicts_with_all = ["sqlite", "watch"])]
+    pub split_by: Option<usize>,
+
+    /// Path of the -split-by files, {value} is replaced by the value with / and control characters as _, e.g. 'out/{value}.txt'
+    #[arg(long, value_name = "PATH", requires = "split_by")]
+    pub split_out: Option<String>,
+
+    /// Output tables of at most N rows each, every one with the headers, or files with -chunk-out
+    #[arg(long, value_name = "N", conflicts_with_all = ["split_by", "sqlite", "watch"])]
+    pub chunk: Option<usize>,
+
+    /// Path of the -chunk files, {n} is replaced by the chunk number from 1, e.g. 'part{n}.csv'
+    #[arg(long, value_name = "PATH", requires = "chunk")]
+    pub chunk_out: Option<String>,
+
+    /// Run an SQL query over the processed table, loaded into an in-memory SQLite database as table 't', and output its result, e.g. 'SELECT name, sum(size) FROM t GROUP BY name'
+    #[arg(long, value_name = "SQL")]
+    pub query: Option<String>,
+
+    /// HTML link rule REGEX=URL, e.g. 'JIRA-\d+=https://jira/browse/{0}' ({0} is the match, {1}.. its groups; the first '=' ends the regex; repeatable)
+    #[arg(long, value_name = "REGEX=URL")]
+    pub link: Vec<String>,
+
+    /// In HTML output put every group of -gcol into a collapsible <details> section
+    #[arg(long)]
+    pub collapse_groups: bool,
+
+    /// Write HTML output as a standalone document with embedded CSS
+    #[arg(long)]
+    pub html_full: bool,
+
+    /// Caption of the HTML table (and title of the -html-full document), also the
+    /// title of text tables without -title
+    #[arg(long, value_name = "TEXT")]
+    pub caption: Option<String>,
+
+    /// Title centered above text tables (inside the border with -pp), also the
+    /// HTML caption without -caption
+    #[arg(long, value_name = "TEXT")]
+    pub title: Option<String>,
+
+    /// id attribute of the HTML table
+    #[arg(long, value_name = "ID")]
+    pub html_id: Option<String>,
+
+    /// class attribute of the HTML table
+    #[arg(long, value_name = "CLASS")]
+    pub html_class: Option<String>,
+
+    /// Keep ANSI color codes in every output format (by default only ASCII and rcol output keep them)
+    #[arg(long, conflicts_with = "strip_ansi")]
+    pub keep_ansi: bool,
+
+    /// Remove ANSI color codes from every output format, including ASCII
+    #[arg(long)]
+    pub strip_ansi: bool,
+
+    /// Keep OSC-8 hyperlinks (e.g. from `ls --hyperlink`) in ASCII output when ANSI codes are stripped
+    #[arg(long)]
+    pub keep_links: bool,
+
+    /// When to color the ASCII table: 'auto' colors on a terminal unless NO_COLOR is set
+    #[arg(long, value_enum, value_name = "WHEN", default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// Color theme of the ASCII table: plain, bold (bold header), dark or light (colored header and zebra rows)
+    #[arg(long, value_name = "NAME", default_value = "plain")]
+    pub theme: String,
+
+    /// Color cells matching PATTERN:COLOR[:row] in ASCII output, e.g. 'ERROR:red' or '3>90:yellow:row' (repeatable)
+    #[arg(long, value_name = "PATTERN:COLOR[:row]")]
+    pub highlight: Vec<String>,
+
+    /// Emit the table in another format: 'rcol' is a self-describing format for piping into `rcol --in rcol`
+    #[arg(long, value_parser = ["rcol"])]
+    pub emit: Option<String>,
+
+    /// Input format: 'text' (default), 'rcol' (output of `rcol --emit rcol`), 'md' or 'html' (the first Markdown or HTML table of the input), 'logfmt' (key=value pairs, one column per key)
+    #[arg(long = "in", value_parser = ["text", "rcol", "md", "html", "logfmt"])]
+    pub in_format: Option<String>,
+
+    /// Read the first Markdown table of the input, same as --in md
+    #[arg(long, conflicts_with_all = ["in_format", "html_in", "logfmt"])]
+    pub md_in: bool,
+
+    /// Read the first HTML table of the input, same as --in html
+    #[arg(long, conflicts_with_all = ["in_format", "logfmt"])]
+    pub html_in: bool,
+
+    /// Read log lines of key=value pairs (logfmt), with one column per key; missing keys are empty cells (see --na). Same as --in logfmt
+    #[arg(long, conflicts_with = "in_format")]
+    pub logfmt: bool,
+
+    /// Split the input like a well-known log format and name its columns: 'access-log' reads Apache/nginx common and combined log format, with the [time] and the quoted request, referer and user agent as one column each
+    #[arg(long, value_enum, value_name = "NAME", conflicts_with_all = ["in_format", "md_in", "html_in", "logfmt", "unbox"])]
+    pub preset: Option<Preset>,
+
+    /// Read a box-drawn table (e.g. rcol --pp output): drop border and separator lines and split rows at the vertical rules (│, |, ...)
+    #[arg(long)]
+    pub unbox: bool,
+
+    /// Show only rows and cells that changed since a table saved with `--emit rcol` to FILE
+    #[arg(long, value_name = "FILE")]
+    pub since: Option<String>,
+
+    /// Show only rows that were added (+), removed (-) or changed (~, cells as 'old→new') compared with the table in FILE, processed with the same options
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["since", "watch"])]
+    pub diff: Option<String>,
+
+    /// Output column N whose values match the rows of --diff
+    #[arg(long, value_name = "N", default_value_t = 1, requires = "diff")]
+    pub key: usize,
+
     /// JSON Title Column: Use the first column as the key for JSON objects
     #[arg(long)]
     pub jtc: bool,
 
+    /// Emit numbers, booleans (true/false) and null (empty cells) instead of strings in JSON/YAML output
+    #[arg(long)]
+    pub infer_types: bool,
+
+    /// Output as JSON on a single line instead of pretty-printed
+    #[arg(long)]
+    pub json_compact: bool,
+
+    /// Output one JSON object per row (NDJSON), written while the input is read if rows don't need to be sorted or grouped
+    #[arg(long)]
+    pub json_stream: bool,
+
+    /// Attach a footnote to output column N, e.g. '3:as reported by du -sh' (repeatable)
+    #[arg(long, value_name = "N:TEXT")]
+    pub note: Vec<String>,
+
+    /// Show values of a column (header name or number) as other text in ASCII/HTML output, e.g. 'STATUS:Running=🟢,Failed=🔴' (repeatable)
+    #[arg(long, value_name = "COL:VALUE=TEXT,...")]
+    pub map_values: Vec<String>,
+
+    /// Show TEXT in empty cells (also of ragged rows and hidden group values) in ASCII, HTML, AsciiDoc, RST, Jira and screen reader output; JSON/YAML write them as null
+    #[arg(long, value_name = "TEXT")]
+    pub na: Option<String>,
+
+    /// Stamp: Embed command line, timestamp and rcol version into the output
+    #[arg(long)]
+    pub stamp: bool,
+
+    /// Language of texts rcol generates itself: labels, warnings, errors about the table and error prefixes
+    #[arg(long, value_enum, default_value_t = Lang::En, value_name = "LANG")]
+    pub lang: Lang,
+
+    /// Read default options from FILE instead of ~/.config/rcol/config.toml
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<String>,
+
     /// Print parameter verification info
     #[arg(short = 'v', long)]
     pub verify: bool,
@@ -110,41 +608,410 @@ pub struct AppArgs {
     #[arg(trailing_var_arg = true)]
     pub columns: Vec<String>,
 
-    /// Output comprehensive man page
+    /// Print a completion script for SHELL (bash, zsh, fish, powershell, elvish)
+    #[arg(long, value_name = "SHELL")]
+    pub completions: Option<Shell>,
+
+    /// Print the man page in roff format, e.g. `rcol --manpage > rcol.1`
     #[arg(short = 'M', long)]
     pub manpage: bool,
+
+    /// Read all other options like util-linux column(1) (-t, -s, -o, -N, -J, -x, ...), also the default when rcol is called as 'column'
+    #[arg(long)]
+    pub compat_column: bool,
+}
+
+/// Checks a `-precision` value: decimal places, or `N:PLACES` for a column (checked
+/// when the table is processed).
+fn parse_precision(value: &str) -> Result<String, String> {
+    if value.contains(':') || value.parse::<usize>().is_ok() {
+        Ok(value.to_string())
+    } else {
+        Err(format!(
+            "Invalid precision '{}': expected PLACES or N:PLACES",
+            value
+        ))
+    }
 }
 
 impl Default for AppArgs {
     fn default() -> Self {
         Self {
             file: None,
+            cmd: None,
+            retries: 0,
+            backoff: 500,
+            cache: None,
+            no_cache: false,
+            watch: None,
             header: None,
+            rename: Vec::new(),
+            insert: Vec::new(),
+            split: Vec::new(),
+            join: None,
+            on: None,
+            join_type: JoinType::Inner,
             sep: " ".to_string(),
+            sep_regex: None,
+            max_cols: None,
+            quoted: false,
+            keep_quotes: false,
+            expand_tabs: None,
+            trim: Trim::Both,
+            trim_cells: false,
+            comment: None,
+            keep_blank: false,
+            drop_empty_rows: false,
+            drop_empty_cols: false,
+            fill_cols: None,
+            fill_width: None,
+            fill_across: false,
+            null: false,
+            null_out: false,
+            parallel: false,
+            low_mem: false,
             mb: false,
             w: 1,
             colsep: "│".to_string(),
-            filter: None,
+            pad_inside: None,
+            pad_outside: None,
+            filter: Vec::new(),
+            filter_not: Vec::new(),
+            r#where: Vec::new(),
+            redact: Vec::new(),
+            redact_preset: Vec::new(),
             sortcol: None,
+            sort_desc: false,
+            sort_ip: false,
+            reverse: false,
+            sample: None,
+            shuffle: false,
+            seed: None,
             gcol: None,
+            normalize_umlauts: false,
+            lenient: false,
             gcolval: false,
+            gheader: false,
+            gcount: false,
+            uniq_c: false,
+            freq: None,
+            freq_bar: None,
+            stats: false,
+            pivot: None,
+            fill: Vec::new(),
+            replace: Vec::new(),
+            transform: Vec::new(),
+            datecol: Vec::new(),
+            duration: Vec::new(),
+            skip_bad_rows: false,
+            strict: false,
+            quiet: false,
+            exit_nonempty: false,
+            ragged: Ragged::Pad,
+            delta: Vec::new(),
+            precision: Vec::new(),
+            humanize: Vec::new(),
+            bar: Vec::new(),
+            bar_values: false,
+            spark: Vec::new(),
             nf: false,
             nn: false,
+            numfmt: None,
+            numfmt_out: None,
+            align_headers: HeaderAlign::Marker,
+            group_header: None,
             nhl: false,
+            hide_header: false,
             ts: false,
             fs: false,
             cs: false,
             pp: false,
             rh: false,
             num: false,
+            format: None,
             csv: false,
             json: false,
             yaml: false,
             html: false,
+            adoc: false,
+            rst: false,
+            jira: false,
+            screen_reader: false,
+            sql: None,
+            sql_create: false,
+            sqlite: None,
+            split_by: None,
+            split_out: None,
+            chunk: None,
+            chunk_out: None,
+            query: None,
+            table: "data".to_string(),
+            link: Vec::new(),
+            collapse_groups: false,
+            html_full: false,
+            caption: None,
+            title: None,
+            html_id: None,
+            html_class: None,
+            keep_ansi: false,
+            strip_ansi: false,
+            keep_links: false,
+            color: ColorChoice::Auto,
+            theme: "plain".to_string(),
+            highlight: Vec::new(),
+            emit: None,
+            in_format: None,
+            md_in: false,
+            html_in: false,
+            logfmt: false,
+            preset: None,
+            unbox: false,
+            since: None,
+            diff: None,
+            key: 1,
             jtc: false,
+            infer_types: false,
+            json_compact: false,
+            json_stream: false,
+            note: Vec::new(),
+            map_values: Vec::new(),
+            na: None,
+            stamp: false,
+            lang: Lang::En,
+            config: None,
             verify: false,
             columns: Vec::new(),
+            completions: None,
             manpage: false,
+            compat_column: false,
+        }
+    }
+}
+
+impl From<&AppArgs> for ProcessOptions {
+    fn from(args: &AppArgs) -> Self {
+        Self {
+            header: args.header.clone(),
+            sep: args.sep.clone(),
+            sep_regex: args.sep_regex.clone(),
+            max_cols: args.max_cols,
+            quoted: args.quoted,
+            keep_quotes: args.keep_quotes,
+            expand_tabs: args.expand_tabs,
+            trim: args.trim,
+            trim_cells: args.trim_cells,
+            comment: args.comment.clone(),
+            keep_blank: args.keep_blank,
+            drop_empty_rows: args.drop_empty_rows,
+            drop_empty_cols: args.drop_empty_cols,
+            fill_cols: args.fill_cols,
+            fill_width: args.fill_width,
+            fill_across: args.fill_across,
+            hide_header: args.hide_header,
+            mb: args.mb,
+            filter: args.filter.clone(),
+            filter_not: args.filter_not.clone(),
+            r#where: args.r#where.clone(),
+            redact: args.redact.clone(),
+            redact_preset: args.redact_preset.clone(),
+            sortcol: args.sortcol,
+            sort_desc: args.sort_desc,
+            sort_ip: args.sort_ip,
+            numfmt: args.numfmt,
+            numfmt_out: args.numfmt_out,
+            reverse: args.reverse,
+            sample: args.sample,
+            shuffle: args.shuffle,
+            seed: args.seed,
+            gcol: args.gcol,
+            normalize_umlauts: args.normalize_umlauts,
+            lenient: args.lenient,
+            gcolval: args.gcolval,
+            gcount: args.gcount,
+            uniq_c: args.uniq_c,
+            freq: args.freq,
+            freq_bar: args.freq_bar,
+            stats: args.stats,
+            pivot: args.pivot.clone(),
+            nhl: args.nhl,
+            rh: args.rh,
+            in_format: if args.md_in {
+                Some("md".to_string())
+            } else if args.html_in {
+                Some("html".to_string())
+            } else if args.logfmt {
+                Some("logfmt".to_string())
+            } else {
+                args.in_format.clone()
+            },
+            preset: args.preset,
+            unbox: args.unbox,
+            columns: args.columns.clone(),
+            replace: args.replace.clone(),
+            transform: args.transform.clone(),
+            rename: args.rename.clone(),
+            insert: args.insert.clone(),
+            split: args.split.clone(),
+            datecol: args.datecol.clone(),
+            duration: args.duration.clone(),
+            fill: args.fill.clone(),
+            skip_bad_rows: args.skip_bad_rows,
+            strict: args.strict,
+            ragged: args.ragged,
+            parallel: args.parallel,
+            join_on: args.on.clone(),
+            join_type: args.join_type,
+            delta: args.delta.clone(),
+            precision: args.computed_precision(),
+            round: args
+                .precision
+                .iter()
+                .filter(|p| p.contains(':'))
+                .cloned()
+                .collect(),
+            humanize: args.humanize.clone(),
+            bar: args.bar.clone(),
+            bar_values: args.bar_values,
+            spark: args.spark.clone(),
+            color: args.color_enabled(),
+            lang: args.lang,
+        }
+    }
+}
+
+impl AppArgs {
+    /// Returns the byte that ends an input line: NUL with `-null`, otherwise newline.
+    pub fn line_terminator(&self) -> u8 {
+        if self.null { b'\0' } else { b'\n' }
+    }
+
+    /// Returns the decimal places of computed values: the last `-precision`
+    /// given without a column.
+    pub fn computed_precision(&self) -> Option<usize> {
+        self.precision.iter().rev().find_map(|p| p.parse().ok())
+    }
+
+    /// Returns whether output may be colored: only the ASCII table without `-strip-ansi`.
+    ///
+    /// `-color always` and `-color never` decide unconditionally, `auto` colors if
+    /// stdout is a terminal and the `NO_COLOR` environment variable is not set.
+    pub fn color_enabled(&self) -> bool {
+        let wanted = match self.color {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+                    && std::io::stdout().is_terminal()
+            }
+        };
+        cfg!(feature = "color") && wanted && self.output_format() == "ascii" && !self.strip_ansi
+    }
+
+    /// Returns the name of the selected output format.
+    ///
+    /// `-format NAME` takes precedence over the format flags (`-emit`, `-csv`,
+    /// `-json`, `-yaml`, `-html`, `-adoc`, `-rst`, `-jira`, `-sql`, `-screen-reader`);
+    /// without any of them the format is `ascii`.
+    /// `-json-compact` and `-json-stream` imply `-json`.
+    pub fn output_format(&self) -> &str {
+        if let Some(name) = &self.format {
+            name
+        } else if let Some(emit) = &self.emit {
+            emit
+        } else if self.csv {
+            "csv"
+        } else if self.json || self.json_compact || self.json_stream {
+            "json"
+        } else if self.yaml {
+            "yaml"
+        } else if self.html {
+            "html"
+        } else if self.adoc {
+            "adoc"
+        } else if self.rst {
+            "rst"
+        } else if self.jira {
+            "jira"
+        } else if self.screen_reader {
+            "screen-reader"
+        } else if self.sql.is_some() || self.sql_create {
+            "sql"
+        } else {
+            "ascii"
         }
     }
-}
\ No newline at end of file
+}
+
+impl From<&AppArgs> for FormatOptions {
+    fn from(args: &AppArgs) -> Self {
+        Self {
+            format: args.output_format().to_string(),
+            w: args.w,
+            colsep: args.colsep.clone(),
+            pad_inside: args.pad_inside,
+            pad_outside: args.pad_outside,
+            nf: args.nf,
+            nn: args.nn,
+            // Numbers are right-aligned in the style they are written in
+            numfmt: match args.numfmt_out {
+                Some(NumStyle::Plain) => None,
+                Some(NumStyle::De) => Some(NumFmt::De),
+                Some(NumStyle::En) => Some(NumFmt::En),
+                None => args.numfmt,
+            },
+            // A custom header line is always set off from the data
+            // column(1) draws no line under the -N names
+            ts: args.ts || (args.header.is_some() && !args.compat_column),
+            fs: args.fs,
+            cs: args.cs,
+            pp: args.pp,
+            num: args.num,
+            jtc: args.jtc,
+            infer_types: args.infer_types,
+            json_compact: args.json_compact,
+            json_stream: args.json_stream,
+            sql_table: args.sql.clone().unwrap_or_else(|| "data".to_string()),
+            sql_create: args.sql_create,
+            lang: args.lang,
+            link: args.link.clone(),
+            note: args.note.clone(),
+            map_values: args.map_values.clone(),
+            na: args.na.clone(),
+            collapse_groups: args.collapse_groups,
+            html_full: args.html_full,
+            caption: args.caption.clone(),
+            title: args.title.clone(),
+            html_id: args.html_id.clone(),
+            html_class: args.html_class.clone(),
+            keep_ansi: args.keep_ansi,
+            strip_ansi: args.strip_ansi,
+            keep_links: args.keep_links,
+            // Library option, the command line aligns numbers right automatically
+            align: Vec::new(),
+            align_headers: args.align_headers,
+            group_header: args.group_header.clone(),
+            highlight: args
+                .highlight
+                .iter()
+                .cloned()
+                .chain(
+                    args.diff
+                        .iter()
+                        .flat_map(|_| DIFF_HIGHLIGHTS.map(String::from)),
+                )
+                .collect(),
+            color: args.color_enabled(),
+            theme: args.theme.clone(),
+            group_column: args.gcol.and_then(|c| c.checked_sub(1)),
+            group_banner: args.gheader,
+            stamp: args.stamp,
+            source: args.file.clone(),
+            null_out: args.null_out,
+            parallel: args.parallel,
+            // Set by -watch from the previous refresh
+            min_widths: Vec::new(),
+            // Set from the columns recorded in -in rcol input
+            column_types: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use crate::cell::Cell;
+use crate::formatter::{Align, FormatOptions, format_to_string};
+use crate::processor::{ProcessOptions, TableData, process_rows};
+use std::fmt;
+
+/// Builds [`TableData`] from Rust values instead of text lines.
+///
+/// Rows are processed like command line input (column selection, sorting, grouping,
+/// filters and redaction from [`ProcessOptions`]), but nothing is split, so cells
+/// may contain blanks or separators. Column indices given to the builder methods
+/// are 0-based.
+///
+/// For one-off tables the builder also carries the [`FormatOptions`] and renders
+/// itself with [`render`](Self::render) or `to_string()`:
+///
+/// ```
+/// use rcol::builder::TableBuilder;
+/// use rcol::formatter::Align;
+///
+/// let text = TableBuilder::new()
+///     .headers(["Name", "Id"])
+///     .row(["web", "7"])
+///     .align(1, Align::Left)
+///     .pretty()
+///     .to_string();
+/// assert_eq!(text.lines().nth(1), Some("│ Name │ Id │"));
+/// assert_eq!(text.lines().nth(2), Some("│ web  │ 7  │"));
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use rcol::builder::TableBuilder;
+///
+/// let table = TableBuilder::new()
+///     .headers(["Team", "Name", "Age"])
+///     .row(["b", "Bob", "25"])
+///     .row(["a", "Alice", "30"])
+///     .sort_by(0)
+///     .group_by(0)
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(table.rows[0], vec!["a", "Alice", "30"]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TableBuilder {
+    headers: Vec<Cell>,
+    rows: Vec<Vec<Cell>>,
+    options: ProcessOptions,
+    format: FormatOptions,
+}
+
+impl TableBuilder {
+    /// Creates an empty builder with default processing options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the header cells.
+    pub fn headers<I, S>(mut self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.headers = headers.into_iter().map(|h| Cell::from(h.into())).collect();
+        self
+    }
+
+    /// Appends a data row.
+    pub fn row<I, S>(mut self, row: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.rows
+            .push(row.into_iter().map(|c| Cell::from(c.into())).collect());
+        self
+    }
+
+    /// Appends several data rows.
+    pub fn rows<R, I, S>(self, rows: R) -> Self
+    where
+        R: IntoIterator<Item = I>,
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        rows.into_iter().fold(self, |builder, row| builder.row(row))
+    }
+
+    /// Selects and orders the output columns (0-based input columns).
+    pub fn columns<I: IntoIterator<Item = usize>>(mut self, columns: I) -> Self {
+        self.options.columns = columns.into_iter().map(|c| (c + 1).to_string()).collect();
+        self
+    }
+
+    /// Sorts by the given output column (0-based).
+    pub fn sort_by(mut self, col: usize) -> Self {
+        self.options.sortcol = Some(col + 1);
+        self
+    }
+
+    /// Sorts descending instead of ascending.
+    pub fn descending(mut self) -> Self {
+        self.options.sort_desc = true;
+        self
+    }
+
+    /// Groups by the given output column (0-based).
+    pub fn group_by(mut self, col: usize) -> Self {
+        self.options.gcol = Some(col + 1);
+        self
+    }
+
+    /// Replaces all processing options, including those set by the other builder
+    /// methods so far.
+    pub fn options(mut self, options: ProcessOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Draws a border with box-drawing characters (`-pp`).
+    pub fn pretty(mut self) -> Self {
+        self.format.pp = true;
+        self
+    }
+
+    /// Draws a line between the header and the data (`-ts`).
+    pub fn title_separator(mut self) -> Self {
+        self.format.ts = true;
+        self
+    }
+
+    /// Draws vertical lines between the columns (`-cs`).
+    pub fn column_separators(mut self) -> Self {
+        self.format.cs = true;
+        self
+    }
+
+    /// Aligns the given output column (0-based) instead of aligning numbers
+    /// right and text left.
+    pub fn align(mut self, col: usize, align: Align) -> Self {
+        if self.format.align.len() <= col {
+            self.format.align.resize(col + 1, None);
+        }
+        self.format.align[col] = Some(align);
+        self
+    }
+
+    /// Replaces all format options, including those set by the other builder
+    /// methods so far.
+    pub fn format_options(mut self, format: FormatOptions) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Processes the rows and formats them with the builder's format options.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(String)` with the formatted table
+    /// - `Err(String)` if processing or formatting fails
+    pub fn render(&self) -> Result<String, String> {
+        let table = self.clone().build()?;
+        format_to_string(&table, &self.format).map_err(|e| e.to_string())
+    }
+
+    /// Processes the rows into table data.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(TableData)` ready for the formatter
+    /// - `Err(String)` if processing fails, e.g. a sort or group column is out of range
+    pub fn build(self) -> Result<TableData, String> {
+        process_rows(self.headers, self.rows, &self.options)
+    }
+}
+
+/// Renders the table like [`TableBuilder::render`].
+///
+/// Formatting fails with [`fmt::Error`] (so `to_string()` panics) if the rows
+/// can't be processed; use `render` to get the error message instead.
+impl fmt::Display for TableBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = self.render().map_err(|_| fmt::Error)?;
+        f.write_str(&text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_sorted_grouped() {
+        let table = TableBuilder::new()
+            .headers(["A", "B"])
+            .row(["y", "2"])
+            .row(["x", "1"])
+            .row(["x", "3"])
+            .sort_by(1)
+            .group_by(0)
+            .build()
+            .unwrap();
+
+        assert_eq!(table.headers, vec!["A", "B"]);
+        assert_eq!(
+            table.rows,
+            vec![
+                vec!["x", "1"],
+                vec![],
+                vec!["y", "2"],
+                vec![],
+                vec!["x", "3"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_keeps_cells_unsplit() {
+        let table = TableBuilder::new()
+            .headers(["Name", "Path"])
+            .rows([["my file", "/tmp/a b"]])
+            .columns([1, 0])
+            .build()
+            .unwrap();
+
+        assert_eq!(table.headers, vec!["Path", "Name"]);
+        assert_eq!(table.rows, vec![vec!["/tmp/a b", "my file"]]);
+        assert_eq!(table.original_column_indices, vec![1, 0]);
+    }
+
+    #[test]
+    fn test_build_with_options() {
+        let table = TableBuilder::new()
+            .headers(["Host", "Load"])
+            .row(["db1", "0.5"])
+            .row(["web1", "3.2"])
+            .options(ProcessOptions {
+                r#where: vec!["2>1".to_string()],
+                ..ProcessOptions::default()
+            })
+            .descending()
+            .build()
+            .unwrap();
+
+        assert_eq!(table.rows, vec![vec!["web1", "3.2"]]);
+    }
+
+    #[test]
+    fn test_build_sort_out_of_range() {
+        let result = TableBuilder::new().row(["a"]).sort_by(3).build();
+        assert!(result.is_err());
+        assert!(TableBuilder::new().row(["a"]).sort_by(3).render().is_err());
+    }
+
+    #[test]
+    fn test_render_styles() {
+        let builder = TableBuilder::new()
+            .headers(["Host", "Load"])
+            .row(["db", "0.5"])
+            .row(["web", "12"]);
+
+        assert_eq!(
+            builder
+                .clone()
+                .title_separator()
+                .column_separators()
+                .render()
+                .unwrap(),
+            " Host │ Load \n─────────────\n db   │  0.5 \n web  │   12 \n"
+        );
+        assert_eq!(
+            builder
+                .align(0, Align::Right)
+                .align(1, Align::Left)
+                .render()
+                .unwrap(),
+            " Host   Load \n   db   0.5  \n  web   12   \n"
+        );
+    }
+}
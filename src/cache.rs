@@ -0,0 +1,169 @@
+use crate::formatter::Stamp;
+use crate::intermediate::{self, Provenance, RcolDocument};
+use crate::processor::TableData;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Returns the directory of cached tables.
+///
+/// `$XDG_CACHE_HOME/rcol`, falling back to `~/.cache/rcol`.
+///
+/// # Returns
+///
+/// The path, or `None` if neither `XDG_CACHE_HOME` nor `HOME` is set
+pub fn default_cache_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join("rcol"))
+}
+
+/// Parses a cache lifetime given to `-cache` or an interval given to `-watch`:
+/// seconds, or a number with the unit `s`, `m`, `h` or `d`, e.g. `90`, `5m` or `1h`.
+///
+/// # Returns
+///
+/// - `Ok(Duration)` with the lifetime
+/// - `Err(String)` if the value is not a number with a known unit or is too long
+pub fn parse_ttl(spec: &str) -> Result<Duration, String> {
+    let invalid = || {
+        format!(
+            "Invalid duration '{}': expected e.g. 90, 30s, 5m, 2h or 1d",
+            spec
+        )
+    };
+    let (number, unit) = match spec.find(|c: char| !c.is_ascii_digit()) {
+        Some(pos) => spec.split_at(pos),
+        None => (spec, "s"),
+    };
+    let number: u64 = number.parse().map_err(|_| invalid())?;
+    let factor = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    number
+        .checked_mul(factor)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("Duration '{}' is too long", spec))
+}
+
+/// Processed tables stored on disk, keyed by the command and options that produced them.
+///
+/// Lets repeated invocations of a slow `-cmd` (e.g. cloud CLI listings) reuse the
+/// table of an earlier run within its lifetime (`-cache TTL`). Every table is one
+/// file in the `-emit rcol` format, named after a hash of its key; a table is
+/// expired once its file is older than the TTL.
+pub struct TableCache {
+    dir: PathBuf,
+}
+
+impl TableCache {
+    /// Creates a cache in `dir`, which is created when the first table is stored.
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Returns the table stored under `key` if it is younger than `ttl`.
+    ///
+    /// Missing, expired and unreadable entries are all a cache miss.
+    pub fn get(&self, key: &str, ttl: Duration) -> Option<TableData> {
+        let path = self.path(key);
+        let age = fs::metadata(&path).ok()?.modified().ok()?.elapsed().ok()?;
+        if age >= ttl {
+            return None;
+        }
+        let text = fs::read_to_string(&path).ok()?;
+        let lines: Vec<String> = text.lines().map(str::to_string).collect();
+        Some(intermediate::decode(&lines).ok()?.into_table())
+    }
+
+    /// Stores a table under `key`, replacing an older entry.
+    ///
+    /// The file is written next to its final name and renamed, so concurrent
+    /// invocations never read half a table.
+    pub fn put(&self, key: &str, data: &TableData) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let stamp = Stamp::new();
+        let provenance = Provenance {
+            version: stamp.version.to_string(),
+            command: stamp.command,
+            timestamp: stamp.timestamp,
+            source: key.lines().next().unwrap_or_default().to_string(),
+        };
+        let doc = RcolDocument::from_table(data, provenance);
+        let path = self.path(key);
+        let tmp = path.with_extension(format!("tmp{}", std::process::id()));
+        fs::write(&tmp, doc.encode()?)?;
+        fs::rename(&tmp, &path)
+    }
+
+    /// Returns the file of the entry stored under `key`.
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}.rcol", fnv1a(key)))
+    }
+}
+
+/// Hashes a cache key with 64-bit FNV-1a, which unlike `DefaultHasher` is stable
+/// across Rust versions, so cache file names stay valid after an upgrade.
+fn fnv1a(key: &str) -> u64 {
+    key.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ttl() {
+        assert_eq!(parse_ttl("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_ttl("5m"), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_ttl("1d"), Ok(Duration::from_secs(86400)));
+        assert!(parse_ttl("5w").is_err());
+        assert!(parse_ttl("m").is_err());
+        assert!(parse_ttl("99999999999999999d").is_err());
+    }
+
+    #[test]
+    fn test_table_cache() {
+        let dir = std::env::temp_dir().join(format!("rcol-cache-test-{}", std::process::id()));
+        let cache = TableCache::new(dir.clone());
+        let data = TableData {
+            headers: vec!["Name".into()],
+            rows: vec![vec!["web".into()]],
+            original_column_indices: vec![0],
+        };
+
+        assert!(
+            cache
+                .get("kubectl get pods", Duration::from_secs(60))
+                .is_none()
+        );
+        cache.put("kubectl get pods", &data).unwrap();
+        let cached = cache
+            .get("kubectl get pods", Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(cached.rows, data.rows);
+        assert!(
+            cache
+                .get("kubectl get nodes", Duration::from_secs(60))
+                .is_none()
+        );
+        assert!(cache.get("kubectl get pods", Duration::ZERO).is_none());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a("a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
@@ -0,0 +1,275 @@
+//! Cells of a [`TableData`](crate::processor::TableData) that share the text of
+//! their input line.
+//!
+//! Splitting a line doesn't copy its cells: the line is kept once, and every cell
+//! refers to its part of it. Cells are cheap to clone, so selecting a column
+//! twice, grouping or joining don't copy any text either.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::{Borrow, Cow};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, Range};
+use std::sync::{Arc, LazyLock};
+
+/// Text of all empty cells, so they don't allocate.
+static EMPTY: LazyLock<Arc<str>> = LazyLock::new(|| Arc::from(""));
+
+/// A table cell: a part of a shared text, usually an input line.
+///
+/// Derefs to `str` and compares, hashes and orders like its text.
+#[derive(Clone)]
+pub struct Cell {
+    text: Arc<str>,
+    start: usize,
+    end: usize,
+}
+
+impl Cell {
+    /// Creates a cell for a part of a shared line.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The shared line
+    /// * `part` - A subslice of `line`, e.g. from splitting it
+    ///
+    /// # Panics
+    ///
+    /// If `part` is not a subslice of `line`
+    pub fn part_of(line: &Arc<str>, part: &str) -> Self {
+        let range = subslice_range(line, part);
+        Self {
+            text: Arc::clone(line),
+            start: range.start,
+            end: range.end,
+        }
+    }
+
+    /// Narrows the cell to a part of its text, e.g. `cell.slice(cell.trim())`,
+    /// without copying it.
+    ///
+    /// # Panics
+    ///
+    /// If `part` is not a subslice of the cell's text
+    pub fn slice(&self, part: &str) -> Self {
+        let range = subslice_range(self.as_str(), part);
+        Self {
+            text: Arc::clone(&self.text),
+            start: self.start + range.start,
+            end: self.start + range.end,
+        }
+    }
+
+    /// Returns the text of the cell.
+    pub fn as_str(&self) -> &str {
+        &self.text[self.start..self.end]
+    }
+}
+
+/// Returns the byte range of `part` within `text`.
+fn subslice_range(text: &str, part: &str) -> Range<usize> {
+    let start = (part.as_ptr() as usize).wrapping_sub(text.as_ptr() as usize);
+    assert!(
+        start <= text.len() && part.len() <= text.len() - start,
+        "cell text is not a part of the line"
+    );
+    start..start + part.len()
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Self {
+            text: Arc::clone(&EMPTY),
+            start: 0,
+            end: 0,
+        }
+    }
+}
+
+impl Deref for Cell {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for Cell {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for Cell {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(text: &str) -> Self {
+        if text.is_empty() {
+            return Self::default();
+        }
+        Self {
+            text: Arc::from(text),
+            start: 0,
+            end: text.len(),
+        }
+    }
+}
+
+impl From<String> for Cell {
+    fn from(text: String) -> Self {
+        Self::from(text.as_str())
+    }
+}
+
+impl From<&String> for Cell {
+    fn from(text: &String) -> Self {
+        Self::from(text.as_str())
+    }
+}
+
+impl From<Cow<'_, str>> for Cell {
+    fn from(text: Cow<'_, str>) -> Self {
+        Self::from(&*text)
+    }
+}
+
+impl From<Cell> for String {
+    fn from(cell: Cell) -> Self {
+        cell.as_str().to_string()
+    }
+}
+
+impl PartialEq for Cell {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for Cell {}
+
+impl PartialEq<str> for Cell {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Cell {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for Cell {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<Cell> for str {
+    fn eq(&self, other: &Cell) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialEq<Cell> for &str {
+    fn eq(&self, other: &Cell) -> bool {
+        *self == other.as_str()
+    }
+}
+
+impl PartialEq<Cell> for String {
+    fn eq(&self, other: &Cell) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialOrd for Cell {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Cell {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Hash for Cell {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Debug for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl Serialize for Cell {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Cell {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Cell::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cells_share_the_line() {
+        let line: Arc<str> = Arc::from("a  bb c");
+        let cells: Vec<Cell> = line
+            .split(' ')
+            .filter(|part| !part.is_empty())
+            .map(|part| Cell::part_of(&line, part))
+            .collect();
+        assert_eq!(cells, vec!["a", "bb", "c"]);
+        assert_eq!(Arc::strong_count(&line), 4);
+
+        let padded = Cell::part_of(&line, &line[1..5]);
+        assert_eq!(padded, "  bb");
+        assert_eq!(padded.slice(padded.trim()), "bb");
+        assert_eq!(String::from(cells[1].clone()), "bb");
+    }
+
+    #[test]
+    fn test_owned_cells() {
+        assert_eq!(Cell::default(), "");
+        assert!(Cell::from(String::new()).is_empty());
+        let mut cells = vec![Cell::from("b"), Cell::from("a")];
+        cells.sort();
+        assert_eq!(cells, ["a", "b"]);
+        assert_eq!(
+            format!("{:?} {}", Cell::from("x"), Cell::from("y")),
+            "\"x\" y"
+        );
+        assert_eq!(serde_json::to_string(&Cell::from("q")).unwrap(), "\"q\"");
+        let cell: Cell = serde_json::from_str("\"q\"").unwrap();
+        assert_eq!(cell, "q");
+    }
+
+    #[test]
+    #[should_panic(expected = "not a part of the line")]
+    fn test_part_of_other_text() {
+        let line: Arc<str> = Arc::from("abc");
+        Cell::part_of(&line, "abc");
+    }
+}
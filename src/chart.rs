@@ -0,0 +1,196 @@
+//! Charts drawn into cells: bars (`-bar`) and sparklines (`-spark`).
+
+use crate::cell::Cell;
+
+/// Width of a `-bar` at its maximum value, in characters.
+pub const BAR_WIDTH: usize = 20;
+
+/// Block characters of a bar, from one to seven eighths of a full block.
+const EIGHTHS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// Block characters of a sparkline, from the lowest to the highest value.
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// A bar chart column given with `-bar N[:MAX]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BarSpec {
+    /// 0-based output column
+    pub column: usize,
+    /// Value drawn as a full bar, the largest value of the column if `None`
+    pub max: Option<f64>,
+}
+
+impl BarSpec {
+    /// Parses a bar specification like `3` or `3:100`.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - The specification as given on the command line (1-based column)
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(BarSpec)` if the specification is well-formed
+    /// - `Err(String)` otherwise
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid bar '{}': expected N or N:MAX", spec);
+        let (col, max) = match spec.split_once(':') {
+            Some((col, max)) => {
+                let max: f64 = max.trim().parse().map_err(|_| invalid())?;
+                if !(max.is_finite() && max > 0.0) {
+                    return Err(format!("Invalid bar '{}': MAX must be above 0", spec));
+                }
+                (col, Some(max))
+            }
+            None => (spec, None),
+        };
+        let col: usize = col.trim().parse().map_err(|_| invalid())?;
+        if col == 0 {
+            return Err("Column numbers must be 1-based".to_string());
+        }
+        Ok(Self {
+            column: col - 1,
+            max,
+        })
+    }
+
+    /// Replaces the numbers in the bar column with bars.
+    ///
+    /// Bars are scaled so that `max` (or the largest value) is [`BAR_WIDTH`]
+    /// characters wide, larger values are cut off there and negative values get
+    /// no bar. Cells that aren't numbers are kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `rows` - Data rows
+    /// * `with_values` - Put the number, aligned right, in front of the bar (`-bar-values`)
+    pub fn apply(&self, rows: &mut [Vec<Cell>], with_values: bool) {
+        let value = |row: &Vec<Cell>| {
+            row.get(self.column)
+                .and_then(|c| c.trim().parse::<f64>().ok())
+                .filter(|v| v.is_finite())
+        };
+        let max = self.max.unwrap_or_else(|| {
+            rows.iter()
+                .filter_map(value)
+                .fold(0.0, |max: f64, v| max.max(v))
+        });
+        let number_width = rows
+            .iter()
+            .filter(|row| value(row).is_some())
+            .map(|row| row[self.column].trim().len())
+            .max()
+            .unwrap_or(0);
+        for row in rows.iter_mut() {
+            let Some(v) = value(row) else {
+                continue;
+            };
+            let bar = bar(v, max, BAR_WIDTH);
+            row[self.column] = if with_values {
+                format!("{:>w$} {}", row[self.column].trim(), bar, w = number_width).into()
+            } else {
+                bar.into()
+            };
+        }
+    }
+}
+
+/// Draws a horizontal bar of block characters with a resolution of 1/8 character.
+///
+/// # Arguments
+///
+/// * `value` - The value to draw
+/// * `max` - The value drawn `width` characters wide
+/// * `width` - Maximum width of the bar
+///
+/// # Returns
+///
+/// The bar, empty for values up to 0 or a `max` that isn't above 0
+pub fn bar(value: f64, max: f64, width: usize) -> String {
+    if !(max > 0.0 && value > 0.0) {
+        return String::new();
+    }
+    let eighths = ((value / max).min(1.0) * (width * 8) as f64).round() as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    match eighths % 8 {
+        0 => {}
+        part => bar.push(EIGHTHS[part - 1]),
+    }
+    bar
+}
+
+/// Draws a comma-separated series of numbers as sparkline, e.g. `1,5,3,8` as `▁▅▃█`.
+///
+/// The lowest value of the series is drawn as the lowest block, the highest one
+/// as the full block, a series of equal values as middle blocks.
+///
+/// # Returns
+///
+/// The sparkline, or `None` if the cell is empty or a value isn't a number
+pub fn sparkline(cell: &str) -> Option<String> {
+    let values = cell
+        .split(',')
+        .map(|v| v.trim().parse::<f64>().ok().filter(|v| v.is_finite()))
+        .collect::<Option<Vec<f64>>>()?;
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let top = (SPARKS.len() - 1) as f64;
+    Some(
+        values
+            .iter()
+            .map(|v| {
+                let level = if max > min {
+                    ((v - min) / (max - min) * top).round() as usize
+                } else {
+                    SPARKS.len() / 2
+                };
+                SPARKS[level]
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bar() {
+        assert_eq!(bar(50.0, 100.0, 4), "██");
+        assert_eq!(bar(1.0, 4.0, 1), "▎");
+        assert_eq!(bar(200.0, 100.0, 2), "██");
+        assert_eq!(bar(-1.0, 100.0, 2), "");
+        assert_eq!(bar(1.0, 0.0, 2), "");
+    }
+
+    #[test]
+    fn test_bar_spec() {
+        assert_eq!(
+            BarSpec::parse("3:100").unwrap(),
+            BarSpec {
+                column: 2,
+                max: Some(100.0)
+            }
+        );
+        assert!(BarSpec::parse("0").is_err());
+        assert!(BarSpec::parse("3:0").is_err());
+        assert!(BarSpec::parse("x").is_err());
+
+        let mut rows: Vec<Vec<Cell>> = vec![
+            vec!["a".into(), "10".into()],
+            vec!["b".into(), "5".into()],
+            vec!["c".into(), "n/a".into()],
+        ];
+        BarSpec::parse("2").unwrap().apply(&mut rows, true);
+        assert_eq!(rows[0][1], format!("10 {}", "█".repeat(BAR_WIDTH)));
+        assert_eq!(rows[1][1], format!(" 5 {}", "█".repeat(BAR_WIDTH / 2)));
+        assert_eq!(rows[2][1], "n/a");
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline("1,5,3,8").as_deref(), Some("▁▅▃█"));
+        assert_eq!(sparkline("2, 2").as_deref(), Some("▅▅"));
+        assert_eq!(sparkline("1,x"), None);
+        assert_eq!(sparkline(""), None);
+    }
+}
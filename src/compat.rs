@@ -0,0 +1,327 @@
+//! Command line compatibility with util-linux `column(1)` (`--compat-column`).
+//!
+//! Scripts written for `column` keep working when rcol is called as `column`
+//! (e.g. through a symlink) or with `--compat-column`: [`column_args`] translates
+//! their options into rcol options. `column -t` becomes a headerless table with
+//! two spaces between the columns and no right-aligned numbers, `column` without
+//! `-t` a list laid out in as many columns as fit into the terminal.
+//!
+//! Supported are `-t`, `-s`, `-o`, `-N`, `-d`, `-J`, `-n` (accepted, JSON output
+//! is an array without a table name), `-x`, `-c`, `-L`, `-O` with column numbers
+//! and a single input file. Other options are rejected instead of being ignored.
+
+use std::ffi::OsString;
+use std::path::Path;
+
+/// Program name that switches to `column(1)` compatibility, e.g. through a symlink.
+const COLUMN_PROGRAM: &str = "column";
+
+/// Option that switches to `column(1)` compatibility.
+const COMPAT_FLAG: &str = "--compat-column";
+
+/// Width of the list layout if neither `-c` nor the `COLUMNS` environment variable set it.
+const DEFAULT_WIDTH: usize = 80;
+
+/// The options of a `column(1)` command line.
+#[derive(Debug, Default)]
+struct ColumnOptions {
+    table: bool,
+    separator: Option<String>,
+    output_separator: Option<String>,
+    names: Option<String>,
+    no_headings: bool,
+    json: bool,
+    fill_rows: bool,
+    width: Option<String>,
+    keep_empty: bool,
+    order: Option<String>,
+    files: Vec<OsString>,
+    help: bool,
+    version: bool,
+}
+
+/// Translates a command line in `column(1)` syntax into an rcol command line.
+///
+/// # Arguments
+///
+/// * `argv` - Command line including the program name
+///
+/// # Returns
+///
+/// - `None` if the program isn't called `column` and `--compat-column` is not given
+/// - `Some(Ok(argv))` with the rcol command line
+/// - `Some(Err(String))` if a `column` option is unsupported or misses its value
+pub fn column_args(argv: &[OsString]) -> Option<Result<Vec<OsString>, String>> {
+    let (program, rest) = argv.split_first()?;
+    let called_as_column = Path::new(program)
+        .file_stem()
+        .is_some_and(|stem| stem == COLUMN_PROGRAM);
+    if !called_as_column && !rest.iter().any(|arg| arg == COMPAT_FLAG) {
+        return None;
+    }
+    let rest: Vec<OsString> = rest
+        .iter()
+        .filter(|arg| *arg != COMPAT_FLAG)
+        .cloned()
+        .collect();
+    Some(parse_column_options(&rest).and_then(|opts| rcol_args(program, opts)))
+}
+
+/// Parses `column(1)` options, short ones may be combined like `-ts,`.
+fn parse_column_options(args: &[OsString]) -> Result<ColumnOptions, String> {
+    let mut opts = ColumnOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let Some(text) = arg.to_str().filter(|t| t.starts_with('-') && t.len() > 1) else {
+            opts.files.push(arg.clone());
+            continue;
+        };
+
+        if let Some(long) = text.strip_prefix("--") {
+            let (name, inline) = match long.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (long, None),
+            };
+            let mut value = || -> Result<String, String> {
+                match &inline {
+                    Some(value) => Ok(value.clone()),
+                    None => next_value(&mut args, text),
+                }
+            };
+            match name {
+                "table" => opts.table = true,
+                "separator" => opts.separator = Some(value()?),
+                "output-separator" => opts.output_separator = Some(value()?),
+                "table-columns" => opts.names = Some(value()?),
+                "table-noheadings" => opts.no_headings = true,
+                "json" => opts.json = true,
+                "table-name" => {
+                    value()?;
+                }
+                "fillrows" => opts.fill_rows = true,
+                "output-width" => opts.width = Some(value()?),
+                "keep-empty-lines" => opts.keep_empty = true,
+                "table-order" => opts.order = Some(value()?),
+                "help" => opts.help = true,
+                "version" => opts.version = true,
+                _ => return Err(unsupported(text)),
+            }
+            continue;
+        }
+
+        let flags = &text[1..];
+        for (i, flag) in flags.char_indices() {
+            let rest = &flags[i + flag.len_utf8()..];
+            let mut value = || -> Result<String, String> {
+                if rest.is_empty() {
+                    next_value(&mut args, &format!("-{}", flag))
+                } else {
+                    Ok(rest.to_string())
+                }
+            };
+            match flag {
+                't' => opts.table = true,
+                'd' => opts.no_headings = true,
+                'J' => opts.json = true,
+                'x' => opts.fill_rows = true,
+                'L' => opts.keep_empty = true,
+                'h' => opts.help = true,
+                'V' => opts.version = true,
+                's' | 'o' | 'N' | 'n' | 'c' | 'O' => {
+                    let value = value()?;
+                    match flag {
+                        's' => opts.separator = Some(value),
+                        'o' => opts.output_separator = Some(value),
+                        'N' => opts.names = Some(value),
+                        'c' => opts.width = Some(value),
+                        'O' => opts.order = Some(value),
+                        _ => {}
+                    }
+                    // The value is the rest of the argument
+                    break;
+                }
+                _ => return Err(unsupported(&format!("-{}", flag))),
+            }
+        }
+    }
+    Ok(opts)
+}
+
+/// Takes the value of `option` from the next argument.
+fn next_value<'a>(
+    args: &mut impl Iterator<Item = &'a OsString>,
+    option: &str,
+) -> Result<String, String> {
+    args.next()
+        .map(|value| value.to_string_lossy().into_owned())
+        .ok_or_else(|| format!("column option {} needs a value", option))
+}
+
+/// Error message of a `column(1)` option rcol doesn't support.
+fn unsupported(option: &str) -> String {
+    format!("column option {} is not supported by rcol", option)
+}
+
+/// Builds the rcol command line doing what the `column(1)` options ask for.
+fn rcol_args(program: &OsString, opts: ColumnOptions) -> Result<Vec<OsString>, String> {
+    let mut args: Vec<String> = Vec::new();
+    if opts.help {
+        args.push("--help".into());
+    } else if opts.version {
+        args.push("--version".into());
+    }
+
+    // column separates columns by two spaces or -o, without a margin, and
+    // doesn't align numbers to the right
+    args.extend([
+        COMPAT_FLAG.into(),
+        "--pad-inside=0".into(),
+        "--pad-outside=0".into(),
+        "--nn".into(),
+    ]);
+    match &opts.output_separator {
+        Some(sep) => args.extend(["--cs".into(), format!("--colsep={}", sep)]),
+        None => args.push("--colsep=  ".into()),
+    }
+
+    if opts.table || opts.json {
+        match &opts.separator {
+            Some(chars) => {
+                let class: String = chars
+                    .chars()
+                    .map(|c| regex::escape(&c.to_string()))
+                    .collect();
+                args.push(format!("--sep-regex=[{}]", class));
+            }
+            None => args.push("--mb".into()),
+        }
+        args.push("--nhl".into());
+        match &opts.names {
+            Some(names) => {
+                // The header is split like the data
+                let sep = opts
+                    .separator
+                    .as_deref()
+                    .and_then(|chars| chars.chars().next())
+                    .unwrap_or(' ');
+                args.push(format!("--header={}", names.replace(',', &sep.to_string())));
+                if opts.no_headings {
+                    args.push("--hide-header".into());
+                }
+            }
+            None => args.push("--hide-header".into()),
+        }
+        if opts.json {
+            args.push("--json".into());
+        }
+        if opts.keep_empty {
+            args.push("--keep-blank".into());
+        }
+        if let Some(order) = &opts.order {
+            for col in order.split(',') {
+                if col.parse::<usize>().is_err() {
+                    return Err(format!(
+                        "column option -O supports only column numbers, not '{}'",
+                        col
+                    ));
+                }
+                args.push(col.to_string());
+            }
+        }
+    } else {
+        let width = match &opts.width {
+            Some(width) => width
+                .parse::<usize>()
+                .map_err(|_| format!("invalid column width '{}'", width))?,
+            None => std::env::var("COLUMNS")
+                .ok()
+                .and_then(|c| c.parse().ok())
+                .unwrap_or(DEFAULT_WIDTH),
+        };
+        args.push(format!("--fill-width={}", width));
+        if opts.fill_rows {
+            args.push("--fill-across".into());
+        }
+    }
+
+    let mut files = opts.files.into_iter();
+    let file = files.next();
+    if files.next().is_some() {
+        return Err("rcol reads only one input file in column compatibility mode".to_string());
+    }
+
+    let mut argv: Vec<OsString> = vec![program.clone()];
+    if let Some(file) = file {
+        argv.push("--file".into());
+        argv.push(file);
+    }
+    // Column numbers of -O are positional, after all options
+    argv.extend(args.into_iter().map(OsString::from));
+    Ok(argv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translate(args: &[&str]) -> Result<Vec<String>, String> {
+        let argv: Vec<OsString> = args.iter().map(OsString::from).collect();
+        column_args(&argv)
+            .expect("column command line")
+            .map(|argv| {
+                argv.into_iter()
+                    .map(|arg| arg.into_string().unwrap())
+                    .collect()
+            })
+    }
+
+    #[test]
+    fn test_column_args_detection() {
+        let argv: Vec<OsString> = ["rcol", "-t"].iter().map(OsString::from).collect();
+        assert!(column_args(&argv).is_none());
+        assert!(translate(&["/usr/local/bin/column", "-t"]).is_ok());
+        assert!(translate(&["rcol", "--compat-column", "-t"]).is_ok());
+    }
+
+    #[test]
+    fn test_column_args_table() {
+        let args = translate(&["column", "-ts,", "-N", "a,b", "-o", "|", "data.csv"]).unwrap();
+        assert_eq!(&args[..3], ["column", "--file", "data.csv"]);
+        for expected in [
+            "--sep-regex=[,]",
+            "--header=a,b",
+            "--cs",
+            "--colsep=|",
+            "--nhl",
+        ] {
+            assert!(args.iter().any(|a| a == expected), "{:?}", args);
+        }
+        assert!(!args.iter().any(|a| a == "--hide-header"), "{:?}", args);
+
+        let args = translate(&["column", "--table", "--json", "--table-order=2,1"]).unwrap();
+        assert!(args.ends_with(&["--hide-header", "--json", "2", "1"].map(String::from)));
+        assert!(args.iter().any(|a| a == "--mb"));
+    }
+
+    #[test]
+    fn test_column_args_list() {
+        let args = translate(&["column", "-x", "-c", "40"]).unwrap();
+        assert!(args.ends_with(&["--fill-width=40", "--fill-across"].map(String::from)));
+    }
+
+    #[test]
+    fn test_column_args_errors() {
+        assert!(
+            translate(&["column", "-t", "-R", "1"])
+                .unwrap_err()
+                .contains("-R")
+        );
+        assert!(
+            translate(&["column", "-s"])
+                .unwrap_err()
+                .contains("needs a value")
+        );
+        assert!(translate(&["column", "-t", "-O", "name"]).is_err());
+        assert!(translate(&["column", "a.txt", "b.txt"]).is_err());
+    }
+}
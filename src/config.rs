@@ -0,0 +1,225 @@
+use crate::args::AppArgs;
+use crate::processor::Warning;
+use clap::parser::ValueSource;
+use clap::{Arg, ArgMatches, Command, CommandFactory, FromArgMatches};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Returns the path of the user's config file.
+///
+/// `$XDG_CONFIG_HOME/rcol/config.toml`, falling back to `~/.config/rcol/config.toml`.
+///
+/// # Returns
+///
+/// The path, or `None` if neither `XDG_CONFIG_HOME` nor `HOME` is set
+pub fn default_config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("rcol").join("config.toml"))
+}
+
+/// Reads and parses a TOML config file.
+///
+/// # Arguments
+///
+/// * `path` - Path of the config file
+///
+/// # Returns
+///
+/// - `Ok(toml::Table)` with the config values
+/// - `Err(String)` if the file can't be read or is not valid TOML
+pub fn load_config(path: &Path) -> Result<toml::Table, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read config file {}: {}", path.display(), e))?;
+    text.parse::<toml::Table>()
+        .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))
+}
+
+/// Turns config values into command line arguments.
+///
+/// Keys are long option names (`filter-not` or `filter_not`). Options that were
+/// given on the command line are skipped, so the command line always wins.
+/// `true` sets a flag, `false` leaves it unset, arrays repeat the option.
+///
+/// # Arguments
+///
+/// * `config` - Parsed config file
+/// * `cmd` - The clap command the options belong to
+/// * `matches` - Matches of the actual command line
+///
+/// # Returns
+///
+/// - `Ok(Vec<String>)` with arguments of the form `--name` / `--name=value`
+/// - `Err(String)` if a value has the wrong type (unknown keys are ignored, see [`unknown_keys`])
+pub fn config_args(
+    config: &toml::Table,
+    cmd: &Command,
+    matches: &ArgMatches,
+) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    for (key, value) in config {
+        let long = key.replace('_', "-");
+        // Keep working with config files written for other rcol versions
+        let Some(arg) = config_option(cmd, &long) else {
+            continue;
+        };
+        if matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine) {
+            continue;
+        }
+
+        let values = match value {
+            toml::Value::Array(items) => items.iter().collect(),
+            other => vec![other],
+        };
+        for value in values {
+            let text = match value {
+                toml::Value::Boolean(true) if !arg.get_action().takes_values() => {
+                    args.push(format!("--{}", long));
+                    continue;
+                }
+                toml::Value::Boolean(false) if !arg.get_action().takes_values() => continue,
+                _ if !arg.get_action().takes_values() => {
+                    return Err(format!("Config key '{}' expects true or false", key));
+                }
+                toml::Value::String(s) => s.clone(),
+                toml::Value::Integer(i) => i.to_string(),
+                toml::Value::Float(f) => f.to_string(),
+                _ => return Err(format!("Unsupported value for config key '{}'", key)),
+            };
+            args.push(format!("--{}={}", long, text));
+        }
+    }
+    Ok(args)
+}
+
+/// Returns a [`Warning::UnknownConfigKey`] for each config key that is no option.
+pub fn unknown_keys(config: &toml::Table, cmd: &Command) -> Vec<Warning> {
+    config
+        .keys()
+        .filter(|key| config_option(cmd, &key.replace('_', "-")).is_none())
+        .map(|key| Warning::UnknownConfigKey { key: key.clone() })
+        .collect()
+}
+
+/// Finds the option with the long name `long`; `-config` can't be set in the config file.
+fn config_option<'a>(cmd: &'a Command, long: &str) -> Option<&'a Arg> {
+    cmd.get_arguments()
+        .find(|a| a.get_long() == Some(long) && long != "config")
+}
+
+/// Parses the command line on top of the defaults from the config file.
+///
+/// The config file is `-config FILE` if given (it must exist), otherwise the
+/// [default config file](default_config_path) if it exists. Command line
+/// parse errors, `--help` and `--version` exit the process like `AppArgs::parse`.
+///
+/// # Arguments
+///
+/// * `argv` - Command line including the program name
+///
+/// # Returns
+///
+/// - `Ok((AppArgs, Vec<Warning>))` with config values applied wherever the command
+///   line doesn't set them, and the unknown keys of the config file
+/// - `Err(String)` if the config file is missing or invalid
+pub fn parse_args_with_config<I, T>(argv: I) -> Result<(AppArgs, Vec<Warning>), String>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let argv: Vec<OsString> = argv.into_iter().map(Into::into).collect();
+    let cmd = AppArgs::command();
+    let matches = cmd
+        .clone()
+        .try_get_matches_from(&argv)
+        .unwrap_or_else(|e| e.exit());
+
+    let path = match matches.get_one::<String>("config") {
+        Some(path) => Some(PathBuf::from(path)),
+        None => default_config_path().filter(|p| p.is_file()),
+    };
+    let Some(path) = path else {
+        let args = AppArgs::from_arg_matches(&matches).map_err(|e| e.to_string())?;
+        return Ok((args, Vec::new()));
+    };
+
+    let config = load_config(&path)?;
+    let extra = config_args(&config, &cmd, &matches)?;
+    let warnings = unknown_keys(&config, &cmd);
+    // Config values go right after the program name, before the command line
+    let (program, rest) = argv.split_at(argv.len().min(1));
+    let merged: Vec<OsString> = program
+        .iter()
+        .cloned()
+        .chain(extra.into_iter().map(OsString::from))
+        .chain(rest.iter().cloned())
+        .collect();
+    let matches = cmd
+        .try_get_matches_from(&merged)
+        .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?;
+    let args = AppArgs::from_arg_matches(&matches).map_err(|e| e.to_string())?;
+    Ok((args, warnings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args_for(config: &str, cli: &[&str]) -> Result<Vec<String>, String> {
+        let cmd = AppArgs::command();
+        let matches = cmd.clone().try_get_matches_from(cli).unwrap();
+        config_args(&config.parse().unwrap(), &cmd, &matches)
+    }
+
+    #[test]
+    fn test_config_args() {
+        let args = args_for(
+            "pp = true\nnum = false\ncolsep = \"|\"\nfilter_not = [\"a\", \"b\"]\nw = 2",
+            &["rcol"],
+        )
+        .unwrap();
+        assert_eq!(
+            args,
+            vec![
+                "--colsep=|",
+                "--filter-not=a",
+                "--filter-not=b",
+                "--pp",
+                "--w=2"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_config_args_command_line_wins() {
+        let args = args_for("colsep = \"|\"\npp = true", &["rcol", "-C", "#"]).unwrap();
+        assert_eq!(args, vec!["--pp"]);
+    }
+
+    #[test]
+    fn test_config_args_errors() {
+        assert!(args_for("pp = \"yes\"", &["rcol"]).is_err());
+        assert!(args_for("sortcol = [[1]]", &["rcol"]).is_err());
+    }
+
+    #[test]
+    fn test_config_args_unknown_keys_ignored() {
+        let config = "style = \"rounded\"\nconfig = \"x.toml\"\npp = true";
+        let args = args_for(config, &["rcol"]).unwrap();
+        assert_eq!(args, vec!["--pp"]);
+        let warnings = unknown_keys(&config.parse().unwrap(), &AppArgs::command());
+        assert_eq!(
+            warnings,
+            vec![
+                Warning::UnknownConfigKey {
+                    key: "config".to_string()
+                },
+                Warning::UnknownConfigKey {
+                    key: "style".to_string()
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,164 @@
+#[cfg(any(feature = "csv", feature = "polars"))]
+use crate::cell::Cell;
+use crate::processor::TableData;
+#[cfg(feature = "polars")]
+use polars::prelude::{Column, DataFrame, NamedFrom, Series};
+#[cfg(feature = "csv")]
+use std::io;
+
+/// Reads all records of a CSV reader into table data.
+///
+/// The header record becomes the table headers if the reader has headers
+/// (the `csv` default), otherwise the headers are empty like with `-nhl`.
+/// The table can then be shaped with [`crate::builder::TableBuilder`] or
+/// formatted directly.
+///
+/// # Errors
+///
+/// Returns the CSV error message if a record can't be read, e.g. on invalid
+/// UTF-8 or rows of different length with a non-flexible reader.
+#[cfg(feature = "csv")]
+impl<R: io::Read> TryFrom<&mut csv::Reader<R>> for TableData {
+    type Error = String;
+
+    fn try_from(reader: &mut csv::Reader<R>) -> Result<Self, String> {
+        let mut headers: Vec<Cell> = if reader.has_headers() {
+            let record = reader.headers().map_err(|e| e.to_string())?;
+            record.iter().map(Cell::from).collect()
+        } else {
+            Vec::new()
+        };
+        let rows = reader
+            .records()
+            .map(|record| {
+                record
+                    .map(|r| r.iter().map(Cell::from).collect::<Vec<_>>())
+                    .map_err(|e| e.to_string())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let width = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+        let width = width.max(headers.len());
+        headers.resize(width, Cell::default());
+        Ok(TableData {
+            headers,
+            rows,
+            original_column_indices: (0..width).collect(),
+        })
+    }
+}
+
+/// Converts table data into a polars `DataFrame`.
+///
+/// Every output column becomes a column named after its header (without the
+/// `-` right-alignment marker, `column_N` if the header is empty). Columns whose
+/// non-empty cells are all integers become `Int64`, all numbers `Float64`,
+/// anything else `String`. Empty and missing cells are null, separator rows of
+/// `-gcol` grouping are dropped.
+///
+/// # Errors
+///
+/// Returns the polars error message, e.g. for duplicate column names.
+#[cfg(feature = "polars")]
+impl TryFrom<TableData> for DataFrame {
+    type Error = String;
+
+    fn try_from(data: TableData) -> Result<Self, String> {
+        let rows: Vec<&Vec<Cell>> = data
+            .rows
+            .iter()
+            .filter(|row| !row.iter().all(|c| c.is_empty()))
+            .collect();
+        let width = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+        let width = width.max(data.headers.len());
+
+        let columns = (0..width)
+            .map(|i| {
+                let name = match data
+                    .headers
+                    .get(i)
+                    .map(|h| h.strip_prefix('-').unwrap_or(h))
+                {
+                    Some(h) if !h.is_empty() => h.to_string(),
+                    _ => format!("column_{}", i + 1),
+                };
+                let cells: Vec<Option<&str>> = rows
+                    .iter()
+                    .map(|row| row.get(i).map(Cell::as_str).filter(|c| !c.is_empty()))
+                    .collect();
+                Column::from(typed_series(&name, &cells))
+            })
+            .collect();
+        DataFrame::new(columns).map_err(|e| e.to_string())
+    }
+}
+
+/// Builds a series of the narrowest type (`Int64`, `Float64`, `String`) that fits all cells.
+#[cfg(feature = "polars")]
+fn typed_series(name: &str, cells: &[Option<&str>]) -> Series {
+    if let Some(ints) = parse_all::<i64>(cells) {
+        Series::new(name.into(), ints)
+    } else if let Some(floats) = parse_all::<f64>(cells) {
+        Series::new(name.into(), floats)
+    } else {
+        Series::new(name.into(), cells)
+    }
+}
+
+/// Parses all non-null cells, or returns `None` if any of them doesn't parse.
+#[cfg(feature = "polars")]
+fn parse_all<T: std::str::FromStr>(cells: &[Option<&str>]) -> Option<Vec<Option<T>>> {
+    cells
+        .iter()
+        .map(|cell| match cell {
+            Some(v) => v.parse().ok().map(Some),
+            None => Some(None),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "csv")]
+    fn test_from_csv_reader() {
+        let mut reader = csv::Reader::from_reader("Name,Path\nmy file,/tmp/a b\n".as_bytes());
+        let table = TableData::try_from(&mut reader).unwrap();
+        assert_eq!(table.headers, vec!["Name", "Path"]);
+        assert_eq!(table.rows, vec![vec!["my file", "/tmp/a b"]]);
+
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader("a,1\nb,2\n".as_bytes());
+        let table = TableData::try_from(&mut reader).unwrap();
+        assert_eq!(table.headers, vec!["", ""]);
+        assert_eq!(table.rows.len(), 2);
+
+        let mut reader = csv::Reader::from_reader("a,b\n1\n".as_bytes());
+        assert!(TableData::try_from(&mut reader).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "polars")]
+    fn test_into_dataframe() {
+        use polars::prelude::DataType;
+
+        let data = TableData {
+            headers: vec!["Host".into(), "-Load".into(), "Port".into()],
+            rows: vec![
+                vec!["db".into(), "0.5".into(), "5432".into()],
+                vec!["".into(), "".into(), "".into()],
+                vec!["web".into(), "3".into(), "".into()],
+            ],
+            original_column_indices: vec![0, 1, 2],
+        };
+        let df = DataFrame::try_from(data).unwrap();
+        assert_eq!(df.shape(), (2, 3));
+        assert_eq!(df.column("Host").unwrap().dtype(), &DataType::String);
+        assert_eq!(df.column("Load").unwrap().dtype(), &DataType::Float64);
+        assert_eq!(df.column("Port").unwrap().dtype(), &DataType::Int64);
+        assert_eq!(df.column("Port").unwrap().null_count(), 1);
+    }
+}
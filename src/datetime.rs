@@ -0,0 +1,175 @@
+//! Date and time columns (`-datecol`): parsed with a strftime format, reformatted
+//! and sorted chronologically.
+
+#[cfg(feature = "datetime")]
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
+
+/// A date column given with `-datecol N:FORMAT[→FORMAT]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateCol {
+    /// 0-based output column
+    pub column: usize,
+    /// strftime format the values are parsed with, e.g. `%d/%b/%Y:%H:%M:%S`
+    pub input: String,
+    /// strftime format the values are written with, the values are kept if `None`
+    pub output: Option<String>,
+}
+
+/// A parsed value, with the UTC offset if the input format has one.
+#[cfg(feature = "datetime")]
+enum Parsed {
+    Zoned(DateTime<FixedOffset>),
+    Naive(NaiveDateTime),
+}
+
+impl DateCol {
+    /// Parses a date column specification like `5:%d/%m/%Y` or
+    /// `5:%d/%b/%Y:%H:%M:%S→%Y-%m-%d %H:%M` (`->` works instead of `→`).
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - The specification as given on the command line (1-based column)
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(DateCol)` if the specification is well-formed
+    /// - `Err(String)` if it is not, or rcol is built without the `datetime` feature
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        if cfg!(not(feature = "datetime")) {
+            return Err("rcol was built without the 'datetime' feature".to_string());
+        }
+        let invalid = || {
+            format!(
+                "Invalid datecol '{}': expected N:FORMAT or N:FORMAT→FORMAT",
+                spec
+            )
+        };
+        let (column, formats) = spec.split_once(':').ok_or_else(invalid)?;
+        let column: usize = column.trim().parse().map_err(|_| invalid())?;
+        if column == 0 {
+            return Err("Column numbers must be 1-based".to_string());
+        }
+        let (input, output) = match formats.split_once('→').or_else(|| formats.split_once("->")) {
+            Some((input, output)) => (input, Some(output.to_string())),
+            None => (formats, None),
+        };
+        if input.is_empty() || output.as_deref() == Some("") {
+            return Err(invalid());
+        }
+        Ok(Self {
+            column: column - 1,
+            input: input.to_string(),
+            output,
+        })
+    }
+
+    /// Parses a cell with the input format.
+    ///
+    /// Formats without a time give midnight, formats without a date January 1st
+    /// 1970; values with a UTC offset are converted to UTC.
+    ///
+    /// # Returns
+    ///
+    /// The date and time for comparisons, or `None` if the cell doesn't match the format
+    #[cfg(feature = "datetime")]
+    pub fn timestamp(&self, cell: &str) -> Option<NaiveDateTime> {
+        Some(match self.parse_cell(cell)? {
+            Parsed::Zoned(value) => value.naive_utc(),
+            Parsed::Naive(value) => value,
+        })
+    }
+
+    /// Without the `datetime` feature no cell is a date.
+    #[cfg(not(feature = "datetime"))]
+    pub fn timestamp(&self, _cell: &str) -> Option<()> {
+        None
+    }
+
+    /// Reformats a cell from the input into the output format.
+    ///
+    /// # Returns
+    ///
+    /// The reformatted value, or `None` if there is no output format, the cell
+    /// doesn't match the input format or the output format needs something the
+    /// value lacks (e.g. `%z` for a value without UTC offset)
+    #[cfg(feature = "datetime")]
+    pub fn reformat(&self, cell: &str) -> Option<String> {
+        use std::fmt::Write;
+
+        let output = self.output.as_deref()?;
+        let mut out = String::new();
+        match self.parse_cell(cell)? {
+            Parsed::Zoned(value) => write!(out, "{}", value.format(output)),
+            Parsed::Naive(value) => write!(out, "{}", value.format(output)),
+        }
+        .ok()?;
+        Some(out)
+    }
+
+    /// Without the `datetime` feature no cell is reformatted.
+    #[cfg(not(feature = "datetime"))]
+    pub fn reformat(&self, _cell: &str) -> Option<String> {
+        None
+    }
+
+    #[cfg(feature = "datetime")]
+    fn parse_cell(&self, cell: &str) -> Option<Parsed> {
+        let cell = cell.trim();
+        let format = self.input.as_str();
+        if let Ok(value) = DateTime::parse_from_str(cell, format) {
+            return Some(Parsed::Zoned(value));
+        }
+        let value = NaiveDateTime::parse_from_str(cell, format)
+            .or_else(|_| {
+                NaiveDate::parse_from_str(cell, format).map(|date| date.and_time(NaiveTime::MIN))
+            })
+            .or_else(|_| {
+                NaiveTime::parse_from_str(cell, format)
+                    .map(|time| NaiveDate::default().and_time(time))
+            })
+            .ok()?;
+        Some(Parsed::Naive(value))
+    }
+}
+
+#[cfg(all(test, feature = "datetime"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_datecol_parse() {
+        let col = DateCol::parse("5:%d/%b/%Y:%H:%M:%S→%Y-%m-%d %H:%M").unwrap();
+        assert_eq!(col.column, 4);
+        assert_eq!(col.input, "%d/%b/%Y:%H:%M:%S");
+        assert_eq!(col.output.as_deref(), Some("%Y-%m-%d %H:%M"));
+
+        let col = DateCol::parse("1:%d.%m.%Y->%F").unwrap();
+        assert_eq!(col.output.as_deref(), Some("%F"));
+        assert_eq!(DateCol::parse("2:%F").unwrap().output, None);
+
+        for invalid in ["%F", "0:%F", "x:%F", "1:", "1:%F→"] {
+            assert!(DateCol::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_datecol_values() {
+        let col = DateCol::parse("1:%d/%b/%Y:%H:%M:%S %z→%Y-%m-%d %H:%M").unwrap();
+        assert_eq!(
+            col.reformat("10/Oct/2024:13:55:36 +0200").as_deref(),
+            Some("2024-10-10 13:55")
+        );
+        assert!(
+            col.timestamp("10/Oct/2024:13:55:36 +0200")
+                < col.timestamp("10/Oct/2024:12:00:00 +0000")
+        );
+        assert_eq!(col.reformat("yesterday"), None);
+
+        let col = DateCol::parse("1:%d.%m.%Y→%Y-%m-%d %z").unwrap();
+        assert!(col.timestamp("02.01.2024") > col.timestamp("31.12.2023"));
+        assert_eq!(col.reformat("02.01.2024"), None);
+
+        let col = DateCol::parse("1:%H:%M→%I:%M %p").unwrap();
+        assert_eq!(col.reformat("14:05").as_deref(), Some("02:05 PM"));
+    }
+}
@@ -0,0 +1,246 @@
+use crate::processor::TableData;
+use serde::de::value::{Error, MapDeserializer, SeqDeserializer};
+use serde::de::{self, DeserializeOwned, IntoDeserializer, Unexpected, Visitor};
+use std::str::FromStr;
+
+impl TableData {
+    /// Deserializes the data rows into user types.
+    ///
+    /// Headers are used as field names (without the `-` right-alignment marker),
+    /// cells are converted to the type of the field: numbers and booleans
+    /// (`true`/`false`, `yes`/`no`, `1`/`0`) are parsed, an empty cell is `None`
+    /// for `Option` fields. Tables without headers deserialize each row as a
+    /// sequence, e.g. into a tuple. Separator rows of `-gcol` grouping are skipped.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Vec<T>)` with one value per data row
+    /// - `Err(String)` naming the first row that doesn't fit `T`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rcol::processor::{ProcessOptions, process_input};
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Process {
+    ///     pid: u32,
+    ///     cmd: String,
+    /// }
+    ///
+    /// let lines = vec!["pid cmd".to_string(), "42 bash".to_string()];
+    /// let table = process_input(lines, &ProcessOptions::default()).unwrap();
+    /// let procs: Vec<Process> = table.deserialize_rows().unwrap();
+    /// assert_eq!(procs[0].pid, 42);
+    /// assert_eq!(procs[0].cmd, "bash");
+    /// ```
+    pub fn deserialize_rows<T: DeserializeOwned>(&self) -> Result<Vec<T>, String> {
+        let fields: Vec<&str> = self
+            .headers
+            .iter()
+            .map(|h| h.strip_prefix('-').unwrap_or(h))
+            .collect();
+        let named = fields.iter().any(|f| !f.is_empty());
+
+        self.rows
+            .iter()
+            .filter(|row| !row.iter().all(|c| c.is_empty()))
+            .enumerate()
+            .map(|(i, row)| {
+                let result = if named {
+                    let cells = fields.iter().copied().zip(row.iter().map(|c| Cell(c)));
+                    T::deserialize(MapDeserializer::<_, Error>::new(cells))
+                } else {
+                    T::deserialize(SeqDeserializer::<_, Error>::new(
+                        row.iter().map(|c| Cell(c)),
+                    ))
+                };
+                result.map_err(|e| format!("Row {}: {}", i + 1, e))
+            })
+            .collect()
+    }
+}
+
+/// A table cell that converts itself into the type requested by the visitor.
+struct Cell<'a>(&'a str);
+
+impl Cell<'_> {
+    fn parse<T: FromStr>(&self, exp: &dyn de::Expected) -> Result<T, Error> {
+        self.0
+            .trim()
+            .parse()
+            .map_err(|_| de::Error::invalid_value(Unexpected::Str(self.0), exp))
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Cell<'_> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+/// Forwards numeric types to `FromStr` parsing of the cell.
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                let value = self.parse(&visitor)?;
+                visitor.$visit(value)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for Cell<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_str(self.0)
+    }
+
+    deserialize_parsed! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.0.trim().to_lowercase().as_str() {
+            "true" | "yes" | "1" => visitor.visit_bool(true),
+            "false" | "no" | "0" => visitor.visit_bool(false),
+            _ => Err(de::Error::invalid_value(Unexpected::Str(self.0), &visitor)),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.0.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    serde::forward_to_deserialize_any! {
+        str string bytes byte_buf unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    fn table(headers: &[&str], rows: &[&[&str]]) -> TableData {
+        TableData {
+            headers: headers.iter().map(|&s| s.into()).collect(),
+            rows: rows
+                .iter()
+                .map(|r| r.iter().map(|&s| s.into()).collect())
+                .collect(),
+            original_column_indices: (0..headers.len()).collect(),
+        }
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum State {
+        Running,
+        Stopped,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "UPPERCASE")]
+    struct Pod {
+        name: String,
+        restarts: u32,
+        cpu: f64,
+        ready: bool,
+        state: State,
+        node: Option<String>,
+    }
+
+    #[test]
+    fn test_deserialize_rows() {
+        let data = table(
+            &["NAME", "-RESTARTS", "CPU", "READY", "STATE", "NODE"],
+            &[
+                &["web", "3", "0.25", "yes", "Running", "n1"],
+                &["", "", "", "", "", ""],
+                &["db", "0", "1", "false", "Stopped", ""],
+            ],
+        );
+
+        let pods: Vec<Pod> = data.deserialize_rows().unwrap();
+        assert_eq!(pods.len(), 2);
+        assert_eq!(
+            pods[0],
+            Pod {
+                name: "web".to_string(),
+                restarts: 3,
+                cpu: 0.25,
+                ready: true,
+                state: State::Running,
+                node: Some("n1".to_string()),
+            }
+        );
+        assert_eq!(pods[1].state, State::Stopped);
+        assert_eq!(pods[1].node, None);
+    }
+
+    #[test]
+    fn test_deserialize_rows_without_headers() {
+        let data = table(&["", ""], &[&["a", "1"], &["b", "2"]]);
+        let rows: Vec<(String, i64)> = data.deserialize_rows().unwrap();
+        assert_eq!(rows, vec![("a".to_string(), 1), ("b".to_string(), 2)]);
+    }
+
+    #[test]
+    fn test_deserialize_rows_error() {
+        let data = table(&["n"], &[&["1"], &["x"]]);
+        #[derive(Debug, Deserialize)]
+        #[allow(dead_code)]
+        struct Row {
+            n: u8,
+        }
+        let err = data.deserialize_rows::<Row>().unwrap_err();
+        assert!(
+            err.starts_with("Row 2: invalid value: string \"x\""),
+            "{}",
+            err
+        );
+    }
+}
@@ -0,0 +1,395 @@
+use crate::cell::Cell;
+use crate::i18n::Lang;
+use crate::numeric::format_decimal;
+use crate::processor::{NO_INPUT_COLUMN, TableData};
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+/// Reduces a table to the rows and cells that changed since a previous snapshot.
+///
+/// Rows are matched on the value of their first column, columns on their header
+/// name (or position when a table has no headers). Repeated keys are matched in
+/// order: the second row of a key with the second previous row of that key, and
+/// so on. The result keeps:
+/// - rows that are new, in full
+/// - rows with changed cells, showing the key and the changed cells only; numeric
+///   changes are annotated with the difference and percent change, e.g. `35 (+5, +16.7%)`
+/// - rows that disappeared, after the other rows, with their previous cells and the
+///   key marked `- KEY`
+///
+/// Unchanged rows are dropped, as are separator rows of `-gcol` grouping.
+///
+/// # Arguments
+///
+/// * `previous` - Table of the earlier run (see `-emit rcol`)
+/// * `current` - Processed table of this run
+/// * `precision` - Decimal places of the computed differences (from `-precision`)
+///
+/// # Returns
+///
+/// Table data with the same columns as `current`
+pub fn changes_since(
+    previous: &TableData,
+    current: &TableData,
+    precision: Option<usize>,
+) -> TableData {
+    let width = current.rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let columns: Vec<Option<usize>> = (0..width.max(current.headers.len()))
+        .map(|i| previous_column(previous, current, i))
+        .collect();
+
+    let key_col = columns.first().copied().flatten();
+    let mut previous_rows = rows_by_key(previous.rows.iter().enumerate().filter_map(|(i, row)| {
+        let key = row.get(key_col?)?;
+        (!is_separator(row)).then_some((i, key.as_str()))
+    }));
+
+    let mut rows = Vec::new();
+    let mut matched = vec![false; previous.rows.len()];
+    for row in current.rows.iter().filter(|row| !is_separator(row)) {
+        let Some(i) = previous_rows
+            .get_mut(row[0].as_str())
+            .and_then(VecDeque::pop_front)
+        else {
+            rows.push(row.clone());
+            continue;
+        };
+        matched[i] = true;
+        let old = &previous.rows[i];
+
+        let mut changed = false;
+        let mut out = vec![Cell::default(); row.len()];
+        out[0] = row[0].clone();
+        for (i, cell) in row.iter().enumerate().skip(1) {
+            let old_cell = columns[i]
+                .and_then(|j| old.get(j))
+                .map_or("", |c| c.as_str());
+            if cell != old_cell {
+                changed = true;
+                out[i] = describe_change(old_cell, cell, precision).into();
+            }
+        }
+        if changed {
+            rows.push(out);
+        }
+    }
+
+    // Previous rows left in the index are the removed ones
+    for (i, old) in previous.rows.iter().enumerate() {
+        let Some(key) = key_col.and_then(|col| old.get(col)) else {
+            continue;
+        };
+        if matched[i] || is_separator(old) {
+            continue;
+        }
+        let mut out: Vec<Cell> = columns
+            .iter()
+            .map(|col| col.and_then(|j| old.get(j)).cloned().unwrap_or_default())
+            .collect();
+        out[0] = format!("- {}", key).into();
+        rows.push(out);
+    }
+
+    TableData {
+        headers: current.headers.clone(),
+        rows,
+        original_column_indices: current.original_column_indices.clone(),
+    }
+}
+
+/// Header of the column marking the rows of [`diff_tables`].
+pub const DIFF_HEADER: &str = "diff";
+
+/// `-highlight` rules coloring a [`diff_tables`] result: changed cells yellow,
+/// added rows green and removed rows red.
+pub const DIFF_HIGHLIGHTS: [&str; 3] = ["→:yellow", "1=+:green:row", "1=-:red:row"];
+
+/// Compares two tables row by key and cell by cell (`-diff`).
+///
+/// Rows are matched on their key column, columns on their header name (or
+/// position when a table has no headers). Repeated keys are matched in order,
+/// like with [`changes_since`]. The result keeps only the rows that differ,
+/// marked in a new first column:
+/// - `+` rows only in `new`, in full
+/// - `-` rows only in `old`, in full, after the other rows
+/// - `~` rows with changed cells, written as `old→new`; the other cells are kept
+///
+/// Separator rows of `-gcol` grouping are dropped.
+///
+/// # Arguments
+///
+/// * `old` - Processed table of the other input
+/// * `new` - Processed table of this input
+/// * `key` - 0-based key column of `new`
+/// * `lang` - Language of the error message
+///
+/// # Returns
+///
+/// - `Ok(TableData)` with the marker column and the columns of `new`
+/// - `Err(String)` if the key column is beyond the columns of `new`
+pub fn diff_tables(
+    old: &TableData,
+    new: &TableData,
+    key: usize,
+    lang: Lang,
+) -> Result<TableData, String> {
+    let width = new
+        .rows
+        .iter()
+        .map(|r| r.len())
+        .max()
+        .unwrap_or(0)
+        .max(new.headers.len());
+    if key >= width && width > 0 {
+        return Err(lang.tr("exceeds-output-columns", &[&"key", &(key + 1), &width]));
+    }
+    let columns: Vec<Option<usize>> = (0..width).map(|i| previous_column(old, new, i)).collect();
+    let cell = |row: &[Cell], col: Option<usize>| -> Cell {
+        col.and_then(|j| row.get(j)).cloned().unwrap_or_default()
+    };
+    let old_key = columns.get(key).copied().flatten();
+    let mut old_rows = rows_by_key(
+        old.rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| !is_separator(row))
+            .map(|(i, row)| (i, cell(row, old_key))),
+    );
+
+    let mut rows = Vec::new();
+    let mut matched = vec![false; old.rows.len()];
+    for row in new.rows.iter().filter(|row| !is_separator(row)) {
+        let row_key = cell(row, Some(key));
+        let Some(i) = old_rows.get_mut(&row_key).and_then(VecDeque::pop_front) else {
+            let mut out = vec![Cell::from("+")];
+            out.extend(row.iter().cloned());
+            rows.push(out);
+            continue;
+        };
+        matched[i] = true;
+        let old_row = &old.rows[i];
+        let mut changed = false;
+        let mut out = vec![Cell::from("~")];
+        for (i, &col) in columns.iter().enumerate() {
+            let (old_cell, new_cell) = (cell(old_row, col), cell(row, Some(i)));
+            if old_cell == new_cell {
+                out.push(new_cell);
+            } else {
+                changed = true;
+                out.push(format!("{}→{}", old_cell, new_cell).into());
+            }
+        }
+        if changed {
+            rows.push(out);
+        }
+    }
+    for (i, row) in old.rows.iter().enumerate() {
+        if !matched[i] && !is_separator(row) {
+            let mut out = vec![Cell::from("-")];
+            out.extend(columns.iter().map(|&col| cell(row, col)));
+            rows.push(out);
+        }
+    }
+
+    let mut headers = Vec::new();
+    if !new.headers.is_empty() {
+        headers.push(Cell::from(DIFF_HEADER));
+        headers.extend(new.headers.iter().cloned());
+    }
+    let mut original_column_indices = vec![NO_INPUT_COLUMN];
+    original_column_indices.extend(new.original_column_indices.iter().copied());
+    Ok(TableData {
+        headers,
+        rows,
+        original_column_indices,
+    })
+}
+
+/// Checks whether a row is a separator row of `-gcol` grouping.
+fn is_separator(row: &[Cell]) -> bool {
+    row.iter().all(|c| c.is_empty())
+}
+
+/// Indexes rows by their key, keeping the rows of a repeated key in order.
+///
+/// # Arguments
+///
+/// * `keys` - Index and key of every row to match
+///
+/// # Returns
+///
+/// The indices of the rows of every key
+fn rows_by_key<K: Eq + Hash>(
+    keys: impl Iterator<Item = (usize, K)>,
+) -> HashMap<K, VecDeque<usize>> {
+    let mut rows: HashMap<K, VecDeque<usize>> = HashMap::new();
+    for (i, key) in keys {
+        rows.entry(key).or_default().push_back(i);
+    }
+    rows
+}
+
+/// Finds the column of `previous` corresponding to column `i` of `current`.
+fn previous_column(previous: &TableData, current: &TableData, i: usize) -> Option<usize> {
+    match current.headers.get(i) {
+        Some(h) if !h.is_empty() && !previous.headers.is_empty() => {
+            previous.headers.iter().position(|p| p == h)
+        }
+        _ => Some(i),
+    }
+}
+
+/// Formats a changed cell, annotating numeric changes with their difference.
+fn describe_change(old: &str, new: &str, precision: Option<usize>) -> String {
+    let (Ok(a), Ok(b)) = (old.parse::<f64>(), new.parse::<f64>()) else {
+        return new.to_string();
+    };
+    let diff = b - a;
+    let sign = if diff > 0.0 { "+" } else { "" };
+    let mut note = format!("{}{}", sign, format_decimal(diff, precision));
+    if a != 0.0 {
+        let percent = diff / a.abs() * 100.0;
+        note.push_str(&format!(
+            ", {}{}%",
+            sign,
+            format_decimal(percent, Some(precision.unwrap_or(1)))
+        ));
+    }
+    format!("{} ({})", new, note)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(headers: &[&str], rows: &[&[&str]]) -> TableData {
+        TableData {
+            headers: headers.iter().map(|&s| Cell::from(s)).collect(),
+            rows: rows
+                .iter()
+                .map(|r| r.iter().map(|&s| Cell::from(s)).collect())
+                .collect(),
+            original_column_indices: (0..headers.len()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_changes_since() {
+        let previous = table(
+            &["Host", "Status", "Load"],
+            &[
+                &["db", "up", "50"],
+                &["web", "up", "10"],
+                &["old", "up", "1"],
+            ],
+        );
+        let current = table(
+            &["Host", "Status", "Load"],
+            &[
+                &["db", "up", "44"],
+                &["web", "up", "10"],
+                &["api", "up", "3"],
+                &["", "", ""],
+            ],
+        );
+
+        let changes = changes_since(&previous, &current, None);
+        assert_eq!(
+            changes.rows,
+            vec![
+                vec!["db", "", "44 (-6, -12.0%)"],
+                vec!["api", "up", "3"],
+                vec!["- old", "up", "1"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_changes_since_matches_columns_by_header() {
+        let previous = table(&["Host", "Load", "Status"], &[&["db", "5", "up"]]);
+        let current = table(&["Host", "Status", "Load"], &[&["db", "down", "5"]]);
+
+        let changes = changes_since(&previous, &current, None);
+        assert_eq!(changes.rows, vec![vec!["db", "down", ""]]);
+    }
+
+    #[test]
+    fn test_changes_since_repeated_keys() {
+        let previous = table(
+            &["Team", "Name", "Salary"],
+            &[
+                &["dev", "Alice", "50000"],
+                &["dev", "Bob", "55000"],
+                &["ops", "Carol", "40000"],
+                &["ops", "Dave", "41000"],
+            ],
+        );
+        let current = table(
+            &["Team", "Name", "Salary"],
+            &[
+                &["dev", "Alice", "50000"],
+                &["dev", "Bob", "56000"],
+                &["ops", "Carol", "40000"],
+            ],
+        );
+
+        let changes = changes_since(&previous, &current, None);
+        assert_eq!(
+            changes.rows,
+            vec![
+                vec!["dev", "", "56000 (+1000, +1.8%)"],
+                vec!["- ops", "Dave", "41000"],
+            ]
+        );
+
+        let diff = diff_tables(&previous, &current, 0, Lang::En).unwrap();
+        assert_eq!(
+            diff.rows,
+            vec![
+                vec!["~", "dev", "Bob", "55000→56000"],
+                vec!["-", "ops", "Dave", "41000"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_tables() {
+        let old = table(
+            &["Pod", "Status", "Restarts"],
+            &[
+                &["api", "Running", "0"],
+                &["db", "Running", "2"],
+                &["job", "Completed", "0"],
+            ],
+        );
+        let new = table(
+            &["Pod", "Restarts", "Status"],
+            &[
+                &["api", "0", "Running"],
+                &["db", "3", "Running"],
+                &["", "", ""],
+                &["web", "0", "Pending"],
+            ],
+        );
+
+        let diff = diff_tables(&old, &new, 0, Lang::En).unwrap();
+        assert_eq!(diff.headers, ["diff", "Pod", "Restarts", "Status"]);
+        assert_eq!(
+            diff.rows,
+            vec![
+                vec!["~", "db", "2→3", "Running"],
+                vec!["+", "web", "0", "Pending"],
+                vec!["-", "job", "0", "Completed"],
+            ]
+        );
+        assert_eq!(diff.original_column_indices[0], NO_INPUT_COLUMN);
+        assert!(diff_tables(&old, &new, 3, Lang::En).is_err());
+    }
+
+    #[test]
+    fn test_describe_change() {
+        assert_eq!(describe_change("0", "5", None), "5 (+5)");
+        assert_eq!(describe_change("4", "5", Some(2)), "5 (+1.00, +25.00%)");
+        assert_eq!(describe_change("a", "b", None), "b");
+    }
+}
@@ -1,10 +1,74 @@
-use crate::args::AppArgs;
-use crate::processor::TableData;
+use crate::cell::Cell;
+use crate::i18n::Lang;
+use crate::intermediate::{ColumnType, Provenance, RcolDocument};
+use crate::numeric::{NumFmt, Number, RESET_COLOR, is_humanized, parse_localized, parse_number};
+use crate::parallel;
+use crate::processor::{NO_INPUT_COLUMN, TableData, WhereCondition};
+use crate::sql;
+use crate::transform::{ValueMap, map_values};
 use regex::Regex;
+#[cfg(feature = "yaml")]
 use serde_yaml::{Mapping, Value};
+use std::borrow::Cow;
+use std::fmt;
 use std::io::{self, Write};
+#[cfg(any(feature = "color", feature = "html"))]
+use std::sync::LazyLock;
+use std::time::{SystemTime, UNIX_EPOCH};
 use unicode_width::UnicodeWidthStr;
 
+/// ANSI escape sequences: CSI `\x1b[ ... [a-zA-Z]` and OSC `\x1b] ... (\x07|\x1b\\)`.
+///
+/// Compiled once, it is applied to every cell, header and column number.
+#[cfg(feature = "color")]
+static ANSI_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(\x1b\[[0-9;?]*[a-zA-Z])|(\x1b\].*?(\x07|\x1b\\))").unwrap());
+
+/// Strips ANSI escape sequences from a string.
+///
+/// # Arguments
+///
+/// * `s` - The string to strip
+///
+/// # Returns
+///
+/// The string with ANSI codes removed (unchanged without the `color` feature)
+fn strip_ansi(s: &str) -> Cow<'_, str> {
+    strip_ansi_codes(s, false)
+}
+
+/// Strips ANSI escape sequences from a string, optionally keeping OSC-8 hyperlinks.
+///
+/// # Arguments
+///
+/// * `s` - The string to strip
+/// * `keep_links` - Keep the OSC-8 sequences that open and close hyperlinks
+///   (e.g. from `ls --hyperlink`)
+///
+/// # Returns
+///
+/// The string with ANSI codes removed, borrowed if it had none
+#[cfg(feature = "color")]
+fn strip_ansi_codes(s: &str, keep_links: bool) -> Cow<'_, str> {
+    // Most cells have no escape sequence at all
+    if !s.contains('\x1b') {
+        return Cow::Borrowed(s);
+    }
+    ANSI_REGEX.replace_all(s, |caps: &regex::Captures| {
+        if keep_links && caps[0].starts_with("\x1b]8;") {
+            caps[0].to_string()
+        } else {
+            String::new()
+        }
+    })
+}
+
+/// Without the `color` feature ANSI escape codes are treated as ordinary text.
+#[cfg(not(feature = "color"))]
+fn strip_ansi_codes(s: &str, _keep_links: bool) -> Cow<'_, str> {
+    Cow::Borrowed(s)
+}
+
 /// Calculates the visible width of a string, accounting for Unicode and ANSI escape codes.
 ///
 /// Strips ANSI escape sequences (CSI and OSC codes) before calculating the display width
@@ -17,69 +81,1410 @@ use unicode_width::UnicodeWidthStr;
 /// # Returns
 ///
 /// The visible width in character cells (not bytes)
-/// Strips ANSI escape sequences from a string.
+pub(crate) fn visible_width(s: &str) -> usize {
+    // Printable ASCII takes one column per byte
+    if s.bytes().all(|b| (b' '..=b'~').contains(&b)) {
+        return s.len();
+    }
+    UnicodeWidthStr::width(strip_ansi(s).as_ref())
+}
+
+/// Resets the colors at the end of a cell that leaves a color open.
+///
+/// Keeps the padding and column separators after a colored cell (e.g. from
+/// `ls --color=always`) uncolored.
 ///
 /// # Arguments
 ///
-/// * `s` - The string to strip
+/// * `s` - The cell content
+///
+/// # Returns
+///
+/// The cell with `ESC[0m` appended if it contains escape codes and doesn't end
+/// with a reset
+fn close_ansi(s: &str) -> Cow<'_, str> {
+    if cfg!(feature = "color")
+        && s.contains("\x1b[")
+        && !s.ends_with("\x1b[0m")
+        && !s.ends_with("\x1b[m")
+    {
+        Cow::Owned(format!("{}\x1b[0m", s))
+    } else {
+        Cow::Borrowed(s)
+    }
+}
+
+/// Provenance information embedded into the output by `-stamp`.
+///
+/// Records the generating command line, the time of generation (UTC, RFC 3339)
+/// and the rcol version so archived reports can be traced back to their origin.
+pub(crate) struct Stamp {
+    pub(crate) command: String,
+    pub(crate) timestamp: String,
+    pub(crate) version: &'static str,
+}
+
+impl Stamp {
+    /// Captures the current command line and time.
+    pub(crate) fn new() -> Self {
+        let command = std::env::args()
+            .map(|a| {
+                if a.is_empty() || a.contains(char::is_whitespace) {
+                    format!("'{}'", a)
+                } else {
+                    a
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self {
+            command,
+            timestamp: format_utc_timestamp(secs),
+            version: env!("CARGO_PKG_VERSION"),
+        }
+    }
+
+    /// Renders the stamp as a single line of text (without comment markers).
+    fn line(&self) -> String {
+        format!(
+            "generated by rcol {} at {}: {}",
+            self.version, self.timestamp, self.command
+        )
+    }
+}
+
+/// Formats seconds since the Unix epoch as an RFC 3339 UTC timestamp.
+///
+/// # Arguments
+///
+/// * `secs` - Seconds since 1970-01-01T00:00:00Z
+///
+/// # Returns
+///
+/// A timestamp like `2024-05-01T12:00:00Z`
+fn format_utc_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+    let (hour, min, sec) = (rem / 3600, (rem % 3600) / 60, rem % 60);
+
+    // Civil-from-days (proleptic Gregorian calendar)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, hour, min, sec
+    )
+}
+
+/// Formats and outputs table data according to the specified format.
+///
+/// Writes to stdout, see [`format_to_writer`] for the details.
+///
+/// # Arguments
+///
+/// * `data` - Processed table data to format
+/// * `opts` - Format options specifying output format and layout
+///
+/// # Returns
+///
+/// - `Ok(())` if output succeeds
+/// - `Err(io::Error)` if writing to stdout fails, or the requested format was
+///   disabled at compile time (see the `csv`, `yaml` and `html` cargo features)
+pub fn format_output(data: TableData, opts: &FormatOptions) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut handle = stdout.lock();
+    format_to_writer(&data, opts, &mut handle)?;
+    handle.flush()
+}
+
+/// Formats table data into a `String` instead of printing it.
+///
+/// Useful for applications embedding rcol that want to capture a formatted table.
+///
+/// # Arguments
+///
+/// * `data` - Processed table data to format
+/// * `opts` - Format options specifying output format and layout
+///
+/// # Returns
+///
+/// - `Ok(String)` with the complete formatted output
+/// - `Err(io::Error)` if formatting fails, or the requested format was disabled
+///   at compile time
+pub fn format_to_string(data: &TableData, opts: &FormatOptions) -> io::Result<String> {
+    let mut buf = Vec::new();
+    format_to_writer(data, opts, &mut buf)?;
+    String::from_utf8(buf).map_err(io::Error::other)
+}
+
+/// Renders the table as aligned ASCII text with the default [`FormatOptions`]
+/// (no borders), e.g. for `println!("{table}")` while debugging.
+///
+/// # Examples
+///
+/// ```
+/// use rcol::builder::TableBuilder;
+///
+/// let table = TableBuilder::new()
+///     .headers(["Name", "Age"])
+///     .row(["Alice", "30"])
+///     .build()
+///     .unwrap();
+/// assert_eq!(table.to_string(), " Name    Age \n Alice    30 ");
+/// ```
+impl fmt::Display for TableData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = format_to_string(self, &FormatOptions::default()).map_err(|_| fmt::Error)?;
+        f.write_str(text.strip_suffix('\n').unwrap_or(&text))
+    }
+}
+
+/// Formats table data according to the specified format into any writer.
+///
+/// Dispatches through the built-in [`FormatterRegistry`], see
+/// [`format_with_registry`] for the details.
+///
+/// # Arguments
+///
+/// * `data` - Processed table data to format
+/// * `opts` - Format options specifying output format and layout
+/// * `out` - Destination of the formatted output
+///
+/// # Returns
+///
+/// - `Ok(())` if output succeeds
+/// - `Err(io::Error)` if writing fails, or the requested format was disabled at
+///   compile time (see the `csv`, `yaml` and `html` cargo features)
+pub fn format_to_writer(
+    data: &TableData,
+    opts: &FormatOptions,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    format_with_registry(data, opts, &FormatterRegistry::builtin(), out)
+}
+
+/// Horizontal alignment of a column in ASCII output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// Alignment of the header row in ASCII output (`-align-headers`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum HeaderAlign {
+    /// Left, right only with the `-` prefix
+    #[default]
+    Marker,
+    /// Like the data of the column: right above numeric or right-aligned columns
+    WithData,
+}
+
+/// Options controlling how [`TableData`] is written.
+///
+/// The output subset of the command line options; [`AppArgs`](crate::args::AppArgs)
+/// converts into it with `FormatOptions::from(&args)`.
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    /// Output format name: `ascii`, `csv`, `json`, `yaml`, `html`, `sql`, `screen-reader`,
+    /// `adoc`, `rst`, `jira`, `rcol`, or one
+    /// registered in a [`FormatterRegistry`]
+    pub format: String,
+    /// Padding width between columns
+    pub w: usize,
+    /// Column separator in non-pretty-print mode
+    pub colsep: String,
+    /// Spaces between a cell and its column boundaries (default `w`); with
+    /// `pad_outside` it enables the spacing that is stable across `cs` and `pp`
+    pub pad_inside: Option<usize>,
+    /// Spaces outside the first and last column without `pp` (default 1); with
+    /// `pad_inside` it enables the spacing that is stable across `cs` and `pp`
+    pub pad_outside: Option<usize>,
+    /// Don't align columns to a common width
+    pub nf: bool,
+    /// Don't right-align numbers
+    pub nn: bool,
+    /// Format of numbers with grouping separators or decimal commas, for right-alignment
+    pub numfmt: Option<NumFmt>,
+    /// Draw a line between header and data
+    pub ts: bool,
+    /// Draw a line before the last row
+    pub fs: bool,
+    /// Draw vertical lines between columns
+    pub cs: bool,
+    /// Draw a border with box-drawing characters
+    pub pp: bool,
+    /// Add a row with column numbers
+    pub num: bool,
+    /// Key JSON/YAML objects by the first column
+    pub jtc: bool,
+    /// Emit numbers, booleans and nulls instead of strings in JSON/YAML output
+    pub infer_types: bool,
+    /// Write JSON on a single line instead of pretty-printed
+    pub json_compact: bool,
+    /// Write one JSON value per row (NDJSON) instead of an array
+    pub json_stream: bool,
+    /// Language of generated labels (group summaries, screen reader labels)
+    pub lang: Lang,
+    /// Table name of SQL output
+    pub sql_table: String,
+    /// Write a `CREATE TABLE` statement before the SQL `INSERT`s
+    pub sql_create: bool,
+    /// HTML link rules `REGEX=URL`
+    pub link: Vec<String>,
+    /// Column footnotes `N:TEXT`
+    pub note: Vec<String>,
+    /// Display replacements `COLUMN:VALUE=TEXT,...` for ASCII and HTML output
+    pub map_values: Vec<String>,
+    /// Placeholder shown in empty cells of display formats; JSON and YAML write
+    /// empty and missing cells as `null`
+    pub na: Option<String>,
+    /// Render groups (separated by empty rows) as collapsible sections in HTML
+    pub collapse_groups: bool,
+    /// Write a standalone HTML document with embedded CSS
+    pub html_full: bool,
+    /// Table caption in HTML output (and title of the standalone document), also
+    /// the title of other formats without `title`
+    pub caption: Option<String>,
+    /// Title line above ASCII and AsciiDoc tables, also the HTML caption without
+    /// `caption`
+    pub title: Option<String>,
+    /// `id` attribute of the HTML table
+    pub html_id: Option<String>,
+    /// `class` attribute of the HTML table
+    pub html_class: Option<String>,
+    /// Keep ANSI color codes in every format
+    pub keep_ansi: bool,
+    /// Remove ANSI color codes in every format, including ASCII
+    pub strip_ansi: bool,
+    /// Keep OSC-8 hyperlinks in ASCII output when ANSI codes are stripped
+    pub keep_links: bool,
+    /// Alignment per output column in ASCII output; `None` (or a missing entry)
+    /// aligns numbers right and text left
+    pub align: Vec<Option<Align>>,
+    /// Alignment of the headers in ASCII output
+    pub align_headers: HeaderAlign,
+    /// Titles of column groups `TITLE=N:M,...` in a header row above the headers
+    /// of ASCII and HTML output
+    pub group_header: Option<String>,
+    /// Highlight rules `PATTERN:COLOR[:row]` for ASCII output
+    pub highlight: Vec<String>,
+    /// Colorize ASCII output (highlights and theme)
+    pub color: bool,
+    /// Theme of the colored ASCII table: `plain`, `bold`, `dark` or `light`
+    pub theme: String,
+    /// 0-based output column the table is grouped by, used to label groups
+    pub group_column: Option<usize>,
+    /// Start every group of `group_column` with a banner row instead of a blank
+    /// separator and drop the group column from the rows (ASCII output)
+    pub group_banner: bool,
+    /// Embed command line, timestamp and version
+    pub stamp: bool,
+    /// Input file name recorded in the `rcol` format (`stdin` if none)
+    pub source: Option<String>,
+    /// End lines with NUL instead of newline
+    pub null_out: bool,
+    /// Measure the columns on all cores (`parallel` feature)
+    pub parallel: bool,
+    /// Minimum width per column in ASCII output, e.g. the widths of the previous
+    /// `-watch` refresh so the columns don't jump
+    pub min_widths: Vec<usize>,
+    /// Type per output column recorded by `-emit rcol`; `None` (or a missing entry)
+    /// checks the cells whether the column is numeric
+    pub column_types: Vec<Option<ColumnType>>,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            format: "ascii".to_string(),
+            w: 1,
+            colsep: "│".to_string(),
+            pad_inside: None,
+            pad_outside: None,
+            nf: false,
+            nn: false,
+            numfmt: None,
+            ts: false,
+            fs: false,
+            cs: false,
+            pp: false,
+            num: false,
+            jtc: false,
+            infer_types: false,
+            json_compact: false,
+            json_stream: false,
+            lang: Lang::En,
+            sql_table: "data".to_string(),
+            sql_create: false,
+            link: Vec::new(),
+            note: Vec::new(),
+            map_values: Vec::new(),
+            na: None,
+            collapse_groups: false,
+            html_full: false,
+            caption: None,
+            title: None,
+            html_id: None,
+            html_class: None,
+            keep_ansi: false,
+            strip_ansi: false,
+            keep_links: false,
+            align: Vec::new(),
+            align_headers: HeaderAlign::Marker,
+            group_header: None,
+            highlight: Vec::new(),
+            color: false,
+            theme: "plain".to_string(),
+            group_column: None,
+            group_banner: false,
+            stamp: false,
+            source: None,
+            null_out: false,
+            parallel: false,
+            min_widths: Vec::new(),
+            column_types: Vec::new(),
+        }
+    }
+}
+
+impl FormatOptions {
+    /// Checks the options that don't depend on the table, so mistakes are found
+    /// before any input is read: the `-format` and `-theme` names and the syntax
+    /// of `-highlight`, `-map-values`, `-note`, `-group-header` and `-link`.
+    ///
+    /// The format must be one of [`FormatterRegistry::builtin`], see
+    /// [`validate_with_registry`](Self::validate_with_registry) for others.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if the options are valid
+    /// - `Err(io::Error)` naming the invalid option
+    pub fn validate(&self) -> io::Result<()> {
+        self.validate_with_registry(&FormatterRegistry::builtin())
+    }
+
+    /// Checks the options like [`validate`](Self::validate), with the formats of
+    /// `registry`.
+    pub fn validate_with_registry(&self, registry: &FormatterRegistry) -> io::Result<()> {
+        lookup_formatter(registry, &self.format)?;
+        Theme::from_options(self)?;
+        for spec in &self.highlight {
+            Highlight::parse(spec)?;
+        }
+        for spec in &self.map_values {
+            ValueMap::parse(spec).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        }
+        column_notes(self)?;
+        if let Some(spec) = &self.group_header {
+            parse_column_groups(spec)?;
+        }
+        #[cfg(feature = "html")]
+        link_rules(self)?;
+        Ok(())
+    }
+}
+
+/// An output format for table data.
+///
+/// Implement this to add an output format and register it in a
+/// [`FormatterRegistry`] under a name selectable with `-format NAME`.
+pub trait TableFormatter {
+    /// Writes `data` to `w` according to `opts`.
+    fn format(&self, data: &TableData, opts: &FormatOptions, w: &mut dyn Write) -> io::Result<()>;
+}
+
+/// Named collection of [`TableFormatter`]s the output is dispatched through.
+pub struct FormatterRegistry {
+    formatters: Vec<(String, Box<dyn TableFormatter>)>,
+}
+
+impl FormatterRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self {
+            formatters: Vec::new(),
+        }
+    }
+
+    /// Creates a registry with all built-in formats enabled at compile time:
+    /// `ascii`, `json`, `rcol`, `sql`, `screen-reader`, `adoc`, `rst`, `jira`, and (depending
+    /// on cargo features) `csv`, `yaml`, `html`.
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register("ascii", Box::new(AsciiFormatter));
+        registry.register("json", Box::new(JsonFormatter));
+        registry.register("rcol", Box::new(RcolFormatter));
+        registry.register("sql", Box::new(SqlFormatter));
+        registry.register("screen-reader", Box::new(ScreenReaderFormatter));
+        registry.register("adoc", Box::new(AsciiDocFormatter));
+        registry.register("rst", Box::new(RstFormatter));
+        registry.register("jira", Box::new(JiraFormatter));
+        #[cfg(feature = "csv")]
+        registry.register("csv", Box::new(CsvFormatter));
+        #[cfg(feature = "yaml")]
+        registry.register("yaml", Box::new(YamlFormatter));
+        #[cfg(feature = "html")]
+        registry.register("html", Box::new(HtmlFormatter));
+        registry
+    }
+
+    /// Registers a formatter under `name`, replacing any formatter of the same name.
+    pub fn register(&mut self, name: &str, formatter: Box<dyn TableFormatter>) {
+        match self.formatters.iter_mut().find(|(n, _)| n == name) {
+            Some(entry) => entry.1 = formatter,
+            None => self.formatters.push((name.to_string(), formatter)),
+        }
+    }
+
+    /// Looks up the formatter registered under `name`.
+    pub fn get(&self, name: &str) -> Option<&dyn TableFormatter> {
+        self.formatters
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, f)| f.as_ref())
+    }
+
+    /// Names of all registered formatters, in registration order.
+    pub fn names(&self) -> Vec<&str> {
+        self.formatters.iter().map(|(n, _)| n.as_str()).collect()
+    }
+}
+
+impl Default for FormatterRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+/// Formats table data with a formatter looked up in `registry`.
+///
+/// Applies the format independent steps (validating `-note` and adding footnote
+/// markers to the headers) before handing the table to the selected formatter.
+///
+/// # Arguments
+///
+/// * `data` - Processed table data to format
+/// * `opts` - Format options specifying output format and layout
+/// * `registry` - Formatters to choose from
+/// * `out` - Destination of the formatted output
+///
+/// # Returns
+///
+/// - `Ok(())` if output succeeds
+/// - `Err(io::Error)` if writing fails or no formatter is registered for the
+///   selected format
+pub fn format_with_registry(
+    data: &TableData,
+    opts: &FormatOptions,
+    registry: &FormatterRegistry,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let out: &mut dyn Write = out;
+    let formatter = lookup_formatter(registry, &opts.format)?;
+    let data = prepare(data, opts)?;
+    if opts.null_out {
+        return formatter.format(&data, opts, &mut SwapNewlines(out));
+    }
+    formatter.format(&data, opts, out)
+}
+
+/// Looks up the formatter of an output format.
+///
+/// # Returns
+///
+/// - `Ok(&dyn TableFormatter)` registered under `name`
+/// - `Err(io::Error)` if the format is unknown or its cargo feature is disabled
+fn lookup_formatter<'a>(
+    registry: &'a FormatterRegistry,
+    name: &str,
+) -> io::Result<&'a dyn TableFormatter> {
+    registry.get(name).ok_or_else(|| {
+        if matches!(name, "csv" | "yaml" | "html") {
+            unsupported_format(name)
+        } else {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Unknown output format '{}' (available: {})",
+                    name,
+                    registry.names().join(", ")
+                ),
+            )
+        }
+    })
+}
+
+/// Writer that swaps newlines and NULs on the way through (`-null-out`): lines
+/// end with NUL, and newlines in cells, turned into NULs before, come back.
+struct SwapNewlines<'a>(&'a mut dyn Write);
+
+impl Write for SwapNewlines<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let swapped: Vec<u8> = buf
+            .iter()
+            .map(|&byte| match byte {
+                b'\n' => b'\0',
+                b'\0' => b'\n',
+                other => other,
+            })
+            .collect();
+        self.0.write_all(&swapped)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// Prepares the cells of a table for the output format of `opts`: strips ANSI
+/// escapes, maps values, fills empty cells, highlights rows and marks the
+/// headers of columns with notes.
+///
+/// # Returns
+///
+/// - `Ok(Cow<TableData>)` with the table to format, borrowed if nothing changed
+/// - `Err(io::Error)` if an option is invalid
+fn prepare<'a>(data: &'a TableData, opts: &FormatOptions) -> io::Result<Cow<'a, TableData>> {
+    let name = opts.format.as_str();
+
+    // Footnote markers go into the displayed headers; JSON keeps its keys clean
+    let notes = column_notes(opts)?;
+    let num_cols = data.original_column_indices.len().max(data.headers.len());
+    if let Some(note) = notes.iter().find(|n| n.col >= num_cols) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            opts.lang.tr(
+                "exceeds-output-columns",
+                &[&"note", &(note.col + 1), &num_cols],
+            ),
+        ));
+    }
+    let mut data = Cow::Borrowed(data);
+
+    // Group separator rows get the cells of an empty row, unless the groups are
+    // formatted on their own
+    let splits_groups = match name {
+        "html" => opts.collapse_groups,
+        "ascii" => opts.group_banner && opts.group_column.is_some(),
+        _ => false,
+    };
+    if !splits_groups && data.rows.iter().any(|row| is_group_separator(row)) {
+        let table = data.to_mut();
+        let width = table
+            .rows
+            .iter()
+            .map(Vec::len)
+            .max()
+            .unwrap_or(0)
+            .max(table.headers.len());
+        for row in table.rows.iter_mut().filter(|row| row.is_empty()) {
+            row.resize(width, Cell::default());
+        }
+    }
+
+    if !keeps_ansi(opts) && has_ansi(&data) {
+        // HTML turns hyperlinks into <a href>, ASCII keeps them on request
+        let keep_links = name == "html" || (name == "ascii" && opts.keep_links);
+        let table = data.to_mut();
+        for cell in table
+            .headers
+            .iter_mut()
+            .chain(table.rows.iter_mut().flatten())
+        {
+            if let Cow::Owned(stripped) = strip_ansi_codes(cell, keep_links) {
+                *cell = stripped.into();
+            }
+        }
+    }
+
+    // Display formats show mapped values, structured formats keep the originals
+    if !opts.map_values.is_empty() && matches!(name, "ascii" | "html" | "screen-reader") {
+        let maps = opts
+            .map_values
+            .iter()
+            .map(|spec| ValueMap::parse(spec))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let table = data.to_mut();
+        map_values(&table.headers, &mut table.rows, &maps, opts.lang)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    }
+
+    if let Some(na) = &opts.na {
+        fill_empty_cells(data.to_mut(), name, na);
+    }
+
+    let highlights = opts
+        .highlight
+        .iter()
+        .map(|spec| Highlight::parse(spec))
+        .collect::<io::Result<Vec<_>>>()?;
+    if !highlights.is_empty() && opts.color && name == "ascii" {
+        highlight_rows(&mut data.to_mut().rows, &highlights);
+    }
+
+    if !notes.is_empty() && !matches!(name, "json" | "rcol" | "screen-reader") {
+        annotate_headers(&mut data.to_mut().headers, &notes);
+    }
+
+    // Newlines in cells and at the ends of lines swap places; JSON escapes both
+    if opts.null_out && !matches!(name, "json" | "yaml" | "rcol") {
+        let table = data.to_mut();
+        for cell in table
+            .headers
+            .iter_mut()
+            .chain(table.rows.iter_mut().flatten())
+        {
+            if cell.contains('\n') {
+                *cell = cell.replace('\n', "\0").into();
+            }
+        }
+    }
+    Ok(data)
+}
+
+/// Fills the empty cells of data rows for `-na`, including the missing cells of
+/// ragged rows.
+///
+/// Display formats show the placeholder, JSON and YAML get empty cells that
+/// [`json_cell`] writes as `null`; other formats keep the data as it is.
+/// Separator rows of `-gcol` grouping are left alone.
+fn fill_empty_cells(data: &mut TableData, name: &str, na: &str) {
+    let display = matches!(
+        name,
+        "ascii" | "html" | "screen-reader" | "adoc" | "rst" | "jira"
+    );
+    if !display && !matches!(name, "json" | "yaml") {
+        return;
+    }
+    let width = data.headers.len();
+    for row in data
+        .rows
+        .iter_mut()
+        .filter(|row| !row.iter().all(|c| c.is_empty()))
+    {
+        if row.len() < width {
+            row.resize(width, Cell::default());
+        }
+        if display {
+            for cell in row.iter_mut().filter(|c| c.is_empty()) {
+                *cell = na.into();
+            }
+        }
+    }
+}
+
+/// ANSI colors selectable by name in `-highlight` rules.
+const NAMED_COLORS: [(&str, &str); 9] = [
+    ("black", "\x1b[30m"),
+    ("red", "\x1b[31m"),
+    ("green", "\x1b[32m"),
+    ("yellow", "\x1b[33m"),
+    ("blue", "\x1b[34m"),
+    ("magenta", "\x1b[35m"),
+    ("cyan", "\x1b[36m"),
+    ("white", "\x1b[37m"),
+    ("bold", "\x1b[1m"),
+];
+
+/// What a `-highlight` rule matches.
+enum HighlightRule {
+    /// Regex matched against every cell
+    Pattern(Regex),
+    /// Condition like `3>90` on an output column
+    Condition(WhereCondition),
+}
+
+/// A `-highlight` rule coloring matching cells, or whole rows.
+struct Highlight {
+    rule: HighlightRule,
+    color: &'static str,
+    row: bool,
+}
+
+impl Highlight {
+    /// Parses a rule of the form `PATTERN:COLOR[:row]`.
+    ///
+    /// PATTERN is a condition in `-where` syntax (`3>90`, `2=~^prod`) on a 1-based
+    /// output column, or else a regex matched against every cell. With `:row` the
+    /// whole row is colored instead of the matching cells.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - The rule as given on the command line
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Highlight)` with the compiled rule
+    /// - `Err(io::Error)` if the color is unknown or the pattern invalid
+    fn parse(spec: &str) -> io::Result<Self> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+        let (rest, row) = match spec.strip_suffix(":row") {
+            Some(rest) => (rest, true),
+            None => (spec, false),
+        };
+        let (pattern, color) = rest
+            .rsplit_once(':')
+            .filter(|(p, _)| !p.is_empty())
+            .ok_or_else(|| {
+                invalid(format!(
+                    "Invalid --highlight '{}': expected PATTERN:COLOR[:row]",
+                    spec
+                ))
+            })?;
+        let color = NAMED_COLORS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(color))
+            .map(|(_, code)| *code)
+            .ok_or_else(|| {
+                let names: Vec<&str> = NAMED_COLORS.iter().map(|(name, _)| *name).collect();
+                invalid(format!(
+                    "Unknown highlight color '{}' (available: {})",
+                    color,
+                    names.join(", ")
+                ))
+            })?;
+
+        let starts_with_column = pattern.starts_with(|c: char| c.is_ascii_digit());
+        let rule = match WhereCondition::parse(pattern) {
+            Ok(condition) if starts_with_column => HighlightRule::Condition(condition),
+            _ => HighlightRule::Pattern(
+                Regex::new(pattern)
+                    .map_err(|e| invalid(format!("Invalid highlight regex: {}", e)))?,
+            ),
+        };
+        Ok(Self { rule, color, row })
+    }
+
+    /// Returns the indices of the cells the rule matches in a row (without colors).
+    fn matching_cells(&self, row: &[Cell]) -> Vec<usize> {
+        match &self.rule {
+            HighlightRule::Pattern(re) => {
+                (0..row.len()).filter(|&i| re.is_match(&row[i])).collect()
+            }
+            HighlightRule::Condition(c) if c.matches(row) => vec![c.column()],
+            HighlightRule::Condition(_) => Vec::new(),
+        }
+    }
+}
+
+/// Colors the cells matched by the highlight rules; the first matching rule wins.
+///
+/// # Arguments
+///
+/// * `rows` - Data rows, separator rows of `-gcol` grouping are left alone
+/// * `rules` - Rules from [`Highlight::parse`]
+fn highlight_rows(rows: &mut [Vec<Cell>], rules: &[Highlight]) {
+    for row in rows.iter_mut() {
+        if row.iter().all(|c| c.is_empty()) {
+            continue;
+        }
+        let plain: Vec<Cell> = row.iter().map(|c| strip_ansi(c).into()).collect();
+        let mut colors: Vec<Option<&str>> = vec![None; row.len()];
+        for rule in rules {
+            let cells = rule.matching_cells(&plain);
+            if rule.row && !cells.is_empty() {
+                colors.iter_mut().for_each(|c| *c = c.or(Some(rule.color)));
+            } else {
+                for i in cells.into_iter().filter(|&i| i < row.len()) {
+                    colors[i] = colors[i].or(Some(rule.color));
+                }
+            }
+        }
+        for (cell, color) in row.iter_mut().zip(colors) {
+            if let Some(color) = color.filter(|_| !cell.is_empty()) {
+                *cell = format!("{}{}{}", color, cell, RESET_COLOR).into();
+            }
+        }
+    }
+}
+
+/// Decides whether ANSI escape codes stay in the output of the selected format.
+///
+/// `-strip-ansi` and `-keep-ansi` apply to every format. By default only the
+/// terminal table (`ascii`) and the `rcol` format, which feeds another rcol, keep them.
+fn keeps_ansi(opts: &FormatOptions) -> bool {
+    if opts.strip_ansi {
+        false
+    } else {
+        opts.keep_ansi || matches!(opts.format.as_str(), "ascii" | "rcol")
+    }
+}
+
+/// Checks whether any header or cell contains an escape character.
+fn has_ansi(data: &TableData) -> bool {
+    data.headers
+        .iter()
+        .chain(data.rows.iter().flatten())
+        .any(|cell| cell.contains('\x1b'))
+}
+
+/// Built-in aligned text table (`ascii`).
+struct AsciiFormatter;
+
+impl TableFormatter for AsciiFormatter {
+    fn format(&self, data: &TableData, opts: &FormatOptions, w: &mut dyn Write) -> io::Result<()> {
+        format_ascii(data, opts, w)
+    }
+}
+
+/// Built-in JSON output (`json`).
+struct JsonFormatter;
+
+impl TableFormatter for JsonFormatter {
+    fn format(&self, data: &TableData, opts: &FormatOptions, w: &mut dyn Write) -> io::Result<()> {
+        format_json(data, opts, w)
+    }
+}
+
+/// Built-in rcol intermediate format (`rcol`).
+struct RcolFormatter;
+
+impl TableFormatter for RcolFormatter {
+    fn format(&self, data: &TableData, opts: &FormatOptions, w: &mut dyn Write) -> io::Result<()> {
+        format_rcol(data, opts, w)
+    }
+}
+
+/// Built-in SQL statements output (`sql`).
+struct SqlFormatter;
+
+impl TableFormatter for SqlFormatter {
+    fn format(&self, data: &TableData, opts: &FormatOptions, w: &mut dyn Write) -> io::Result<()> {
+        format_sql(data, opts, w)
+    }
+}
+
+/// Built-in linear output for screen readers (`screen-reader`).
+struct ScreenReaderFormatter;
+
+impl TableFormatter for ScreenReaderFormatter {
+    fn format(&self, data: &TableData, opts: &FormatOptions, w: &mut dyn Write) -> io::Result<()> {
+        format_screen_reader(data, opts, w)
+    }
+}
+
+/// Built-in AsciiDoc table output (`adoc`).
+struct AsciiDocFormatter;
+
+impl TableFormatter for AsciiDocFormatter {
+    fn format(&self, data: &TableData, opts: &FormatOptions, w: &mut dyn Write) -> io::Result<()> {
+        format_adoc(data, opts, w)
+    }
+}
+
+/// Built-in reStructuredText grid table output (`rst`).
+struct RstFormatter;
+
+impl TableFormatter for RstFormatter {
+    fn format(&self, data: &TableData, opts: &FormatOptions, w: &mut dyn Write) -> io::Result<()> {
+        format_rst(data, opts, w)
+    }
+}
+
+/// Built-in Jira/Confluence wiki markup output (`jira`).
+struct JiraFormatter;
+
+impl TableFormatter for JiraFormatter {
+    fn format(&self, data: &TableData, opts: &FormatOptions, w: &mut dyn Write) -> io::Result<()> {
+        format_jira(data, opts, w)
+    }
+}
+
+/// Built-in CSV output (`csv`).
+#[cfg(feature = "csv")]
+struct CsvFormatter;
+
+#[cfg(feature = "csv")]
+impl TableFormatter for CsvFormatter {
+    fn format(&self, data: &TableData, opts: &FormatOptions, w: &mut dyn Write) -> io::Result<()> {
+        format_csv(data, opts, w)
+    }
+}
+
+/// Built-in YAML output (`yaml`).
+#[cfg(feature = "yaml")]
+struct YamlFormatter;
+
+#[cfg(feature = "yaml")]
+impl TableFormatter for YamlFormatter {
+    fn format(&self, data: &TableData, opts: &FormatOptions, w: &mut dyn Write) -> io::Result<()> {
+        format_yaml(data, opts, w)
+    }
+}
+
+/// Built-in HTML table output (`html`).
+#[cfg(feature = "html")]
+struct HtmlFormatter;
+
+#[cfg(feature = "html")]
+impl TableFormatter for HtmlFormatter {
+    fn format(&self, data: &TableData, opts: &FormatOptions, w: &mut dyn Write) -> io::Result<()> {
+        format_html(data, opts, w)
+    }
+}
+
+/// A footnote attached to an output column with `-note N:TEXT`.
+struct Note {
+    /// 0-based output column index
+    col: usize,
+    text: String,
+}
+
+/// Parses the `-note` options.
+///
+/// # Arguments
+///
+/// * `opts` - Format options containing the notes
 ///
 /// # Returns
 ///
-/// A new String with ANSI codes removed
-fn strip_ansi(s: &str) -> String {
-    // Regex to strip ANSI escape codes
-    // CSI: \x1b\[ ... [a-zA-Z]
-    // OSC: \x1b\] ... (\x07|\x1b\\)
-    let ansi_regex = Regex::new(r"(\x1b\[[0-9;?]*[a-zA-Z])|(\x1b\].*?(\x07|\x1b\\))").unwrap();
-    ansi_regex.replace_all(s, "").to_string()
+/// - `Ok(Vec<Note>)` in the order given (note `i` gets marker `[i+1]`)
+/// - `Err(io::Error)` if a note is not of the form `N:TEXT` with a 1-based column
+fn column_notes(opts: &FormatOptions) -> io::Result<Vec<Note>> {
+    opts.note
+        .iter()
+        .map(|spec| {
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Invalid note (expected COLUMN:TEXT): {}", spec),
+                )
+            };
+            let (col, text) = spec.split_once(':').ok_or_else(invalid)?;
+            let col: usize = col.trim().parse().map_err(|_| invalid())?;
+            if col == 0 {
+                return Err(invalid());
+            }
+            Ok(Note {
+                col: col - 1,
+                text: text.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Returns the footnote marker for the note at position `i`, e.g. `[1]`.
+fn note_marker(i: usize) -> String {
+    format!("[{}]", i + 1)
+}
+
+/// Appends the footnote markers to the annotated headers.
+fn annotate_headers(headers: &mut [Cell], notes: &[Note]) {
+    for (i, note) in notes.iter().enumerate() {
+        if let Some(h) = headers.get_mut(note.col) {
+            *h = format!("{}{}", h, note_marker(i)).into();
+        }
+    }
+}
+
+/// Writes the notes below a table, one per line, each line starting with `prefix`.
+fn write_notes(out: &mut dyn Write, notes: &[Note], prefix: &str) -> io::Result<()> {
+    for (i, note) in notes.iter().enumerate() {
+        writeln!(out, "{}{} {}", prefix, note_marker(i), note.text)?;
+    }
+    Ok(())
+}
+
+/// Builds the error returned for an output format whose cargo feature is disabled.
+fn unsupported_format(feature: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("rcol was built without the '{}' feature", feature),
+    )
+}
+
+/// Formats table data as SQL statements.
+///
+/// Writes `INSERT INTO` statements for the table `-sql` (preceded by `CREATE TABLE`
+/// with `-sql-create`), see [`sql::write_sql`]. Stamp and notes become `--` comments.
+///
+/// # Arguments
+///
+/// * `data` - Table data to format
+/// * `opts` - Format options (checks `-sql`, `-sql-create` and `-stamp`)
+/// * `out` - Destination of the output
+///
+/// # Returns
+///
+/// - `Ok(())` if output succeeds
+/// - `Err(io::Error)` if writing fails
+fn format_sql(data: &TableData, opts: &FormatOptions, out: &mut dyn Write) -> io::Result<()> {
+    if opts.stamp {
+        writeln!(out, "-- {}", Stamp::new().line())?;
+    }
+    sql::write_sql(data, &opts.sql_table, opts.sql_create, out)?;
+    write_notes(out, &column_notes(opts)?, "-- ")
+}
+
+/// Formats table data for screen readers and braille displays (`-screen-reader`).
+///
+/// Every data row becomes a block of `column: value` lines, blocks are separated
+/// by a blank line. There is no alignment padding, box drawing or color, so
+/// nothing but the content is read out. Columns without header are called
+/// `column N`, empty cells `empty`. Separator rows of `-gcol` grouping are skipped
+/// and notes are written after the rows as `note on column: text`. The labels are
+/// in the `-lang` language.
+///
+/// # Arguments
+///
+/// * `data` - Table data to format
+/// * `opts` - Format options (checks `-stamp`, `-note` and `-lang`)
+/// * `out` - Destination of the output
+///
+/// # Returns
+///
+/// - `Ok(())` if output succeeds
+/// - `Err(io::Error)` if writing fails
+fn format_screen_reader(
+    data: &TableData,
+    opts: &FormatOptions,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    let width = data.rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let names: Vec<String> = (0..width.max(data.headers.len()))
+        .map(|i| {
+            match data
+                .headers
+                .get(i)
+                .map(|h| h.strip_prefix('-').unwrap_or(h))
+            {
+                Some(h) if !h.is_empty() => h.to_string(),
+                _ => opts.lang.tr("column", &[&(i + 1)]),
+            }
+        })
+        .collect();
+    let empty = opts.lang.tr("empty", &[]);
+
+    let mut blocks = Vec::new();
+    if opts.stamp {
+        blocks.push(vec![Stamp::new().line()]);
+    }
+    for row in data
+        .rows
+        .iter()
+        .filter(|row| !row.iter().all(|c| c.is_empty()))
+    {
+        blocks.push(
+            names
+                .iter()
+                .zip(row.iter().map(Cell::as_str).chain(std::iter::repeat("")))
+                .map(|(name, val)| {
+                    let val = if val.is_empty() { empty.as_str() } else { val };
+                    format!("{}: {}", name, val)
+                })
+                .collect(),
+        );
+    }
+    let notes = column_notes(opts)?;
+    if !notes.is_empty() {
+        blocks.push(
+            notes
+                .iter()
+                .map(|n| format!("{}: {}", opts.lang.tr("note-on", &[&names[n.col]]), n.text))
+                .collect(),
+        );
+    }
+
+    for (i, block) in blocks.iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        for line in block {
+            writeln!(out, "{}", line)?;
+        }
+    }
+    Ok(())
+}
+
+/// Prepares table data for the documentation formats (`-adoc`, `-rst`).
+///
+/// Strips the `-` right-alignment markers from the headers, drops separator rows
+/// of `-gcol` grouping and escapes every cell with `escape`.
+///
+/// # Returns
+///
+/// The prepared table and whether each column is aligned right: by `align`, else
+/// by the header marker, else if all its non-empty cells are numbers (unless `-nn`)
+fn document_table(
+    data: &TableData,
+    opts: &FormatOptions,
+    escape: fn(&str) -> String,
+) -> (TableData, Vec<bool>) {
+    let rows: Vec<Vec<Cell>> = data
+        .rows
+        .iter()
+        .filter(|row| !row.iter().all(|c| c.is_empty()))
+        .map(|row| row.iter().map(|c| escape(c).into()).collect())
+        .collect();
+    let width = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    let width = width.max(data.headers.len());
+    let headers: Vec<Cell> = data
+        .headers
+        .iter()
+        .map(|h| escape(h.strip_prefix('-').unwrap_or(h)).into())
+        .collect();
+
+    let right = (0..width)
+        .map(|i| match opts.align.get(i) {
+            Some(Some(align)) => *align == Align::Right,
+            _ if data.headers.get(i).is_some_and(|h| h.starts_with('-')) => true,
+            _ => !opts.nn && numeric_column(&rows, i, opts),
+        })
+        .collect();
+    let table = TableData {
+        headers,
+        rows,
+        original_column_indices: data.original_column_indices.clone(),
+    };
+    (table, right)
+}
+
+/// Checks whether column `i` has non-empty cells and all of them are numbers
+/// (`-na` placeholders count as empty), unless its type was recorded.
+fn numeric_column(rows: &[Vec<Cell>], i: usize, opts: &FormatOptions) -> bool {
+    if let Some(Some(column_type)) = opts.column_types.get(i) {
+        return *column_type == ColumnType::Number;
+    }
+    let mut cells = rows
+        .iter()
+        .filter_map(|r| r.get(i))
+        .filter(|c| !c.is_empty() && opts.na.as_deref() != Some(c.as_str()))
+        .peekable();
+    cells.peek().is_some() && cells.all(|c| is_number(c, opts.numfmt))
+}
+
+/// Checks whether a cell is a number, in the `-numfmt` format if one is given,
+/// or a `-humanize` value like `1.2M`.
+pub(crate) fn is_number(val: &str, fmt: Option<NumFmt>) -> bool {
+    let val = strip_ansi(val);
+    parse_localized(&val, fmt).is_some() || is_humanized(&val)
+}
+
+/// Pads `val` to `width` on the side given by `right`.
+fn pad_cell(val: &str, width: usize, right: bool) -> String {
+    let pad = " ".repeat(width.saturating_sub(visible_width(val)));
+    if right {
+        format!("{}{}", pad, val)
+    } else {
+        format!("{}{}", val, pad)
+    }
+}
+
+/// Formats table data as an AsciiDoc table (`-adoc`).
+///
+/// Writes a `|===` block with the column alignment in the `cols` attribute and
+/// the headers as header row. Cells are padded to the column widths of the ASCII
+/// table so the source stays readable; `|` in cells is escaped. Stamp and notes
+/// are written as comment lines and a paragraph after the table.
+///
+/// # Arguments
+///
+/// * `data` - Table data to format
+/// * `opts` - Format options (checks `-nn`, `-stamp` and `-note`)
+/// * `out` - Destination of the output
+///
+/// # Returns
+///
+/// - `Ok(())` if output succeeds
+/// - `Err(io::Error)` if writing fails
+fn format_adoc(data: &TableData, opts: &FormatOptions, out: &mut dyn Write) -> io::Result<()> {
+    let (table, right) = document_table(data, opts, |c| c.replace('|', "\\|"));
+    let widths = calculate_widths(&table, opts).widths;
+    let row_line = |row: &[Cell]| {
+        widths
+            .iter()
+            .zip(&right)
+            .enumerate()
+            .map(|(i, (w, r))| {
+                let val = row.get(i).map_or("", Cell::as_str);
+                format!("| {}", pad_cell(val, *w, *r))
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    if opts.stamp {
+        writeln!(out, "// {}", Stamp::new().line())?;
+    }
+    if let Some(title) = opts.title.as_deref().or(opts.caption.as_deref()) {
+        writeln!(out, ".{}", title)?;
+    }
+    let cols: Vec<&str> = right.iter().map(|r| if *r { ">" } else { "<" }).collect();
+    if table.headers.is_empty() {
+        writeln!(out, "[cols=\"{}\"]", cols.join(","))?;
+    } else {
+        writeln!(out, "[cols=\"{}\",options=\"header\"]", cols.join(","))?;
+    }
+    writeln!(out, "|===")?;
+    if !table.headers.is_empty() {
+        writeln!(out, "{}", row_line(&table.headers))?;
+        writeln!(out)?;
+    }
+    for row in &table.rows {
+        writeln!(out, "{}", row_line(row))?;
+    }
+    writeln!(out, "|===")?;
+
+    let notes = column_notes(opts)?;
+    if !notes.is_empty() {
+        writeln!(out)?;
+        write_notes(out, &notes, "")?;
+    }
+    Ok(())
+}
+
+/// Formats table data as a reStructuredText grid table (`-rst`).
+///
+/// Every row is framed by `+---+` lines, the header row is set off with `+===+`.
+/// Columns have the widths of the ASCII table and keep its alignment. Stamp and
+/// notes are written as a comment before and a paragraph after the table.
+///
+/// # Arguments
+///
+/// * `data` - Table data to format
+/// * `opts` - Format options (checks `-nn`, `-stamp` and `-note`)
+/// * `out` - Destination of the output
+///
+/// # Returns
+///
+/// - `Ok(())` if output succeeds
+/// - `Err(io::Error)` if writing fails
+fn format_rst(data: &TableData, opts: &FormatOptions, out: &mut dyn Write) -> io::Result<()> {
+    let (table, right) = document_table(data, opts, str::to_string);
+    // Grid tables can't have empty columns
+    let widths: Vec<usize> = calculate_widths(&table, opts)
+        .widths
+        .into_iter()
+        .map(|w| w.max(1))
+        .collect();
+    let border = |c: &str| {
+        let parts: Vec<String> = widths.iter().map(|w| c.repeat(w + 2)).collect();
+        format!("+{}+", parts.join("+"))
+    };
+    let row_line = |row: &[Cell]| {
+        let cells: Vec<String> = widths
+            .iter()
+            .zip(&right)
+            .enumerate()
+            .map(|(i, (w, r))| pad_cell(row.get(i).map_or("", Cell::as_str), *w, *r))
+            .collect();
+        format!("| {} |", cells.join(" | "))
+    };
+
+    if opts.stamp {
+        writeln!(out, ".. {}", Stamp::new().line())?;
+        writeln!(out)?;
+    }
+    writeln!(out, "{}", border("-"))?;
+    if !table.headers.is_empty() {
+        writeln!(out, "{}", row_line(&table.headers))?;
+        // The header separator can't be the last line of the table
+        let header_sep = if table.rows.is_empty() { "-" } else { "=" };
+        writeln!(out, "{}", border(header_sep))?;
+    }
+    for row in &table.rows {
+        writeln!(out, "{}", row_line(row))?;
+        writeln!(out, "{}", border("-"))?;
+    }
+
+    let notes = column_notes(opts)?;
+    if !notes.is_empty() {
+        writeln!(out)?;
+        write_notes(out, &notes, "")?;
+    }
+    Ok(())
 }
 
-/// Calculates the visible width of a string, accounting for Unicode and ANSI escape codes.
+/// Formats table data as Jira/Confluence wiki markup (`-jira`).
 ///
-/// Strips ANSI escape sequences (CSI and OSC codes) before calculating the display width
-/// using Unicode width rules. This ensures proper alignment in terminal output.
+/// Headers become a `||Header||Header||` row, data rows `|cell|cell|` rows. `|` in
+/// cells is escaped and empty cells hold a space, which the wiki renderer needs
+/// to keep the cell. Stamp and notes are written as paragraphs around the table.
 ///
 /// # Arguments
 ///
-/// * `s` - The string to measure
+/// * `data` - Table data to format
+/// * `opts` - Format options (checks `-stamp` and `-note`)
+/// * `out` - Destination of the output
 ///
 /// # Returns
 ///
-/// The visible width in character cells (not bytes)
-fn visible_width(s: &str) -> usize {
-    let stripped = strip_ansi(s);
-    UnicodeWidthStr::width(stripped.as_str())
+/// - `Ok(())` if output succeeds
+/// - `Err(io::Error)` if writing fails
+fn format_jira(data: &TableData, opts: &FormatOptions, out: &mut dyn Write) -> io::Result<()> {
+    let (table, right) = document_table(data, opts, |c| c.replace('|', "\\|"));
+    let row_line = |row: &[Cell], sep: &str| {
+        let cells: Vec<&str> = (0..right.len())
+            .map(|i| match row.get(i).map(Cell::as_str) {
+                Some("") | None => " ",
+                Some(val) => val,
+            })
+            .collect();
+        format!("{}{}{}", sep, cells.join(sep), sep)
+    };
+
+    if opts.stamp {
+        writeln!(out, "{}", Stamp::new().line())?;
+        writeln!(out)?;
+    }
+    if !table.headers.is_empty() {
+        writeln!(out, "{}", row_line(&table.headers, "||"))?;
+    }
+    for row in &table.rows {
+        writeln!(out, "{}", row_line(row, "|"))?;
+    }
+
+    let notes = column_notes(opts)?;
+    if !notes.is_empty() {
+        writeln!(out)?;
+        write_notes(out, &notes, "")?;
+    }
+    Ok(())
 }
 
-/// Formats and outputs table data according to the specified format.
+/// Writes table data in the self-describing rcol intermediate format.
 ///
-/// Routes to the appropriate formatter based on output format flags:
-/// - CSV (`-csv`)
-/// - JSON (`-json`)
-/// - HTML (`-html`)
-/// - ASCII table (default)
+/// The output is a single line of JSON meant to be read by another rcol
+/// invocation with `-in rcol`.
 ///
 /// # Arguments
 ///
-/// * `data` - Processed table data to format
-/// * `args` - Application arguments specifying output format and options
+/// * `data` - Table data to serialize
+/// * `opts` - Format options (used for the provenance's source)
+/// * `out` - Destination of the output
 ///
 /// # Returns
 ///
 /// - `Ok(())` if output succeeds
-/// - `Err(io::Error)` if writing to stdout fails
-pub fn format_output(data: TableData, args: &AppArgs) -> io::Result<()> {
-    if args.csv {
-        format_csv(&data, args)
-    } else if args.json {
-        format_json(&data, args)
-    } else if args.yaml {
-        format_yaml(&data, args)
-    } else if args.html {
-        format_html(&data, args)
-    } else {
-        format_ascii(&data, args)
-    }
+/// - `Err(io::Error)` if serializing or writing fails
+fn format_rcol(data: &TableData, opts: &FormatOptions, out: &mut dyn Write) -> io::Result<()> {
+    let stamp = Stamp::new();
+    let provenance = Provenance {
+        version: stamp.version.to_string(),
+        command: stamp.command,
+        timestamp: stamp.timestamp,
+        source: opts.source.clone().unwrap_or_else(|| "stdin".to_string()),
+    };
+    let doc = RcolDocument::from_table(data, provenance);
+
+    writeln!(out, "{}", doc.encode()?)?;
+    Ok(())
 }
 
 /// Formats table data as CSV output.
@@ -90,24 +1495,32 @@ pub fn format_output(data: TableData, args: &AppArgs) -> io::Result<()> {
 /// # Arguments
 ///
 /// * `data` - Table data to format
-/// * `_args` - Application arguments (currently unused for CSV formatting)
+/// * `opts` - Format options (checks `-stamp` flag)
+/// * `out` - Destination of the output
 ///
 /// # Returns
 ///
 /// - `Ok(())` if output succeeds
 /// - `Err(io::Error)` if writing fails
-fn format_csv(data: &TableData, _args: &AppArgs) -> io::Result<()> {
-    let mut wtr = csv::Writer::from_writer(io::stdout());
+#[cfg(feature = "csv")]
+fn format_csv(data: &TableData, opts: &FormatOptions, out: &mut dyn Write) -> io::Result<()> {
+    if opts.stamp {
+        writeln!(out, "# {}", Stamp::new().line())?;
+    }
+
+    let mut wtr = csv::Writer::from_writer(&mut *out);
 
     if !data.headers.is_empty() {
-        wtr.write_record(&data.headers)?;
+        wtr.write_record(data.headers.iter().map(Cell::as_str))?;
     }
 
     for row in &data.rows {
-        wtr.write_record(row)?;
+        wtr.write_record(row.iter().map(Cell::as_str))?;
     }
 
     wtr.flush()?;
+    drop(wtr);
+    write_notes(out, &column_notes(opts)?, "# ")?;
     Ok(())
 }
 
@@ -120,18 +1533,21 @@ fn format_csv(data: &TableData, _args: &AppArgs) -> io::Result<()> {
 /// # Arguments
 ///
 /// * `data` - Table data to format
-/// * `args` - Application arguments (checks `-jtc` flag)
+/// * `opts` - Format options (checks `-jtc`, `-infer-types` and `-stamp` flags)
+/// * `out` - Destination of the output
 ///
 /// # Returns
 ///
 /// - `Ok(())` if output succeeds
 /// - `Err(io::Error)` if writing fails
-fn format_yaml(data: &TableData, args: &AppArgs) -> io::Result<()> {
-    let stdout = io::stdout();
-    let mut handle = stdout.lock();
+#[cfg(feature = "yaml")]
+fn format_yaml(data: &TableData, opts: &FormatOptions, out: &mut dyn Write) -> io::Result<()> {
+    if opts.stamp {
+        writeln!(out, "# {}", Stamp::new().line())?;
+    }
 
     if !data.headers.is_empty() {
-        if args.jtc {
+        if opts.jtc {
             let mut map = Mapping::new();
             for row in &data.rows {
                 if let Some(key) = row.first() {
@@ -139,18 +1555,18 @@ fn format_yaml(data: &TableData, args: &AppArgs) -> io::Result<()> {
                     for (i, val) in row.iter().enumerate().skip(1) {
                         if i < data.headers.len() {
                             obj.insert(
-                                Value::String(strip_ansi(&data.headers[i])),
-                                Value::String(strip_ansi(val)),
+                                Value::String(data.headers[i].to_string()),
+                                yaml_cell(val, opts),
                             );
                         }
                     }
-                    map.insert(Value::String(strip_ansi(key)), Value::Mapping(obj));
+                    map.insert(Value::String(key.to_string()), Value::Mapping(obj));
                 }
             }
             write!(
-                handle,
+                out,
                 "{}",
-                serde_yaml::to_string(&map).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+                serde_yaml::to_string(&map).map_err(io::Error::other)?
             )?;
         } else {
             let mut arr = Vec::new();
@@ -159,143 +1575,575 @@ fn format_yaml(data: &TableData, args: &AppArgs) -> io::Result<()> {
                 for (i, val) in row.iter().enumerate() {
                     if i < data.headers.len() {
                         obj.insert(
-                            Value::String(strip_ansi(&data.headers[i])),
-                            Value::String(strip_ansi(val)),
+                            Value::String(data.headers[i].to_string()),
+                            yaml_cell(val, opts),
                         );
                     }
                 }
                 arr.push(Value::Mapping(obj));
             }
             write!(
-                handle,
+                out,
                 "{}",
-                serde_yaml::to_string(&arr).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+                serde_yaml::to_string(&arr).map_err(io::Error::other)?
             )?;
         }
     } else {
-        // Strip ANSI from raw rows if no headers
-        let stripped_rows: Vec<Vec<String>> = data
+        let rows: Vec<Vec<Value>> = data
             .rows
             .iter()
-            .map(|row| row.iter().map(|s| strip_ansi(s)).collect())
+            .map(|row| row.iter().map(|val| yaml_cell(val, opts)).collect())
             .collect();
         write!(
-            handle,
+            out,
             "{}",
-            serde_yaml::to_string(&stripped_rows)
-                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+            serde_yaml::to_string(&rows).map_err(io::Error::other)?
         )?;
     }
 
-    writeln!(handle)?;
+    writeln!(out)?;
+    write_notes(out, &column_notes(opts)?, "# ")?;
     Ok(())
 }
 
+/// Converts a cell to a YAML value, typed like [`json_cell`] with `-infer-types`.
+#[cfg(feature = "yaml")]
+fn yaml_cell(val: &str, opts: &FormatOptions) -> Value {
+    serde_yaml::to_value(json_cell(val, opts)).unwrap_or_else(|_| Value::String(val.to_string()))
+}
+
+/// Converts a cell to a JSON value.
+///
+/// Without `-infer-types` every cell is a string, or `null` if it is empty and
+/// `-na` is given. With it, empty cells become `null`, `true`/`false` booleans and
+/// decimal numbers numbers. Numbers with leading zeros (e.g. `007`) stay strings,
+/// so IDs and codes keep their text.
+fn json_cell(val: &str, opts: &FormatOptions) -> serde_json::Value {
+    if val.is_empty() && opts.na.is_some() {
+        return serde_json::Value::Null;
+    }
+    if !opts.infer_types {
+        return serde_json::Value::String(val.to_string());
+    }
+    match val {
+        "" => return serde_json::Value::Null,
+        "true" => return serde_json::Value::Bool(true),
+        "false" => return serde_json::Value::Bool(false),
+        _ => {}
+    }
+    match parse_number(val) {
+        Some(Number::Int(n)) => serde_json::Value::from(n),
+        Some(Number::Float(n)) => serde_json::Value::from(n),
+        None => serde_json::Value::String(val.to_string()),
+    }
+}
+
 /// Formats table data as JSON output.
 ///
 /// Supports two output modes:
 /// - Standard: Array of objects, where each object represents a row with header keys
 /// - Title column mode (`-jtc`): Object keyed by first column, with nested objects for remaining columns
 ///
+/// With `-stamp` or `-note` the table is wrapped as
+/// `{"meta": {...}, "notes": [...], "data": ...}`. `-json-compact` writes it on a
+/// single line, `-json-stream` writes one row per line instead (see [`format_json_stream`]).
+///
 /// # Arguments
 ///
 /// * `data` - Table data to format
-/// * `args` - Application arguments (checks `-jtc` flag)
+/// * `opts` - Format options (checks `-jtc`, `-infer-types`, `-json-compact`, `-json-stream`
+///   and `-stamp` flags)
+/// * `out` - Destination of the output
 ///
 /// # Returns
 ///
 /// - `Ok(())` if output succeeds
 /// - `Err(io::Error)` if writing fails
-fn format_json(data: &TableData, args: &AppArgs) -> io::Result<()> {
-    let stdout = io::stdout();
-    let mut handle = stdout.lock();
-
-    if !data.headers.is_empty() {
-        if args.jtc {
+fn format_json(data: &TableData, opts: &FormatOptions, out: &mut dyn Write) -> io::Result<()> {
+    if opts.json_stream {
+        return format_json_stream(data, opts, out);
+    }
+    let value = if !data.headers.is_empty() {
+        if opts.jtc {
             let mut map = serde_json::Map::new();
             for row in &data.rows {
                 if let Some(key) = row.first() {
-                    let mut obj = serde_json::Map::new();
-                    for (i, val) in row.iter().enumerate().skip(1) {
-                        if i < data.headers.len() {
-                            obj.insert(
-                                strip_ansi(&data.headers[i]),
-                                serde_json::Value::String(strip_ansi(val)),
-                            );
-                        }
-                    }
-                    map.insert(strip_ansi(key), serde_json::Value::Object(obj));
+                    let obj = json_object(&data.headers, row, 1, opts);
+                    map.insert(key.to_string(), serde_json::Value::Object(obj));
                 }
             }
-            serde_json::to_writer_pretty(&mut handle, &map)?;
+            serde_json::Value::Object(map)
         } else {
-            let mut arr = Vec::new();
-            for row in &data.rows {
-                let mut obj = serde_json::Map::new();
-                for (i, val) in row.iter().enumerate() {
-                    if i < data.headers.len() {
-                        obj.insert(
-                            strip_ansi(&data.headers[i]),
-                            serde_json::Value::String(strip_ansi(val)),
-                        );
-                    }
-                }
-                arr.push(obj);
-            }
-            serde_json::to_writer_pretty(&mut handle, &arr)?;
+            data.rows
+                .iter()
+                .map(|row| serde_json::Value::Object(json_object(&data.headers, row, 0, opts)))
+                .collect()
         }
     } else {
-        // Strip ANSI from raw rows if no headers
-        let stripped_rows: Vec<Vec<String>> = data
-            .rows
+        data.rows
             .iter()
-            .map(|row| row.iter().map(|s| strip_ansi(s)).collect())
-            .collect();
-        serde_json::to_writer_pretty(&mut handle, &stripped_rows)?;
+            .map(|row| {
+                row.iter()
+                    .map(|val| json_cell(val, opts))
+                    .collect::<serde_json::Value>()
+            })
+            .collect()
+    };
+
+    let notes = column_notes(opts)?;
+    let value = if opts.stamp || !notes.is_empty() {
+        // Wrap the table so provenance and notes can travel as separate keys
+        let mut wrapped = serde_json::Map::new();
+        if opts.stamp {
+            let stamp = Stamp::new();
+            wrapped.insert(
+                "meta".to_string(),
+                serde_json::json!({
+                    "command": stamp.command,
+                    "timestamp": stamp.timestamp,
+                    "version": stamp.version,
+                }),
+            );
+        }
+        if !notes.is_empty() {
+            let notes: Vec<serde_json::Value> = notes
+                .iter()
+                .map(|n| {
+                    serde_json::json!({
+                        "column": n.col + 1,
+                        "header": data.headers.get(n.col),
+                        "note": n.text,
+                    })
+                })
+                .collect();
+            wrapped.insert("notes".to_string(), serde_json::Value::Array(notes));
+        }
+        wrapped.insert("data".to_string(), value);
+        serde_json::Value::Object(wrapped)
+    } else {
+        value
+    };
+
+    if opts.json_compact {
+        serde_json::to_writer(&mut *out, &value)?;
+    } else {
+        serde_json::to_writer_pretty(&mut *out, &value)?;
     }
+    writeln!(out)?;
+    Ok(())
+}
 
-    writeln!(handle)?;
+/// Writes one compact JSON value per data row (NDJSON, `-json-stream`).
+///
+/// Rows are objects keyed by the headers (with `-jtc` an object with the first
+/// column as its only key), or arrays if the table has no headers. Every row is
+/// written as soon as it is serialized instead of collecting an array first.
+/// Separator rows of `-gcol` grouping are skipped.
+///
+/// # Returns
+///
+/// - `Ok(())` if output succeeds
+/// - `Err(io::Error)` if writing fails, or `-stamp`/`-note` are set, which need a
+///   wrapping document
+fn format_json_stream(
+    data: &TableData,
+    opts: &FormatOptions,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    if opts.stamp || !opts.note.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--json-stream writes bare rows and can't be combined with --stamp or --note",
+        ));
+    }
+    for row in data
+        .rows
+        .iter()
+        .filter(|row| !row.iter().all(|c| c.is_empty()))
+    {
+        let value = if data.headers.is_empty() {
+            row.iter().map(|val| json_cell(val, opts)).collect()
+        } else if opts.jtc {
+            let Some(key) = row.first() else { continue };
+            let obj = json_object(&data.headers, row, 1, opts);
+            serde_json::json!({ key.as_str(): obj })
+        } else {
+            serde_json::Value::Object(json_object(&data.headers, row, 0, opts))
+        };
+        serde_json::to_writer(&mut *out, &value)?;
+        writeln!(out)?;
+    }
     Ok(())
 }
 
+/// Builds the JSON object of a row, keyed by the headers, starting at column `skip`.
+fn json_object(
+    headers: &[Cell],
+    row: &[Cell],
+    skip: usize,
+    opts: &FormatOptions,
+) -> serde_json::Map<String, serde_json::Value> {
+    headers
+        .iter()
+        .zip(row)
+        .skip(skip)
+        .map(|(header, val)| (header.to_string(), json_cell(val, opts)))
+        .collect()
+}
+
+/// Matches plain http(s) URLs, which are always linked in HTML output.
+#[cfg(feature = "html")]
+const URL_PATTERN: &str = r#"\bhttps?://[^\s<>"']*[^\s<>"'.,;:!?)]"#;
+
+/// Matches an OSC-8 hyperlink `ESC]8;params;URL ST text ESC]8;; ST`, capturing
+/// the URL and the text.
+#[cfg(feature = "html")]
+static OSC8_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\x1b\]8;[^;\x07\x1b]*;([^\x07\x1b]*)(?:\x07|\x1b\\)(.*?)\x1b\]8;;(?:\x07|\x1b\\)")
+        .unwrap()
+});
+
+/// Matches a `{N}` placeholder of a link rule URL.
+#[cfg(feature = "html")]
+static PLACEHOLDER_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\{(\d+)\}").unwrap());
+
+/// A rule turning regex matches into hyperlinks in HTML output.
+#[cfg(feature = "html")]
+struct LinkRule {
+    regex: Regex,
+    /// URL template, `{0}` is replaced by the match and `{1}`.. by its groups
+    url: String,
+}
+
+/// Builds the link rules for HTML output: plain URLs first, then every `-link`.
+///
+/// # Arguments
+///
+/// * `opts` - Format options containing the `-link` rules
+///
+/// # Returns
+///
+/// - `Ok(Vec<LinkRule>)` with all compiled rules
+/// - `Err(io::Error)` if a rule is not of the form `REGEX=URL` or the regex is invalid
+#[cfg(feature = "html")]
+fn link_rules(opts: &FormatOptions) -> io::Result<Vec<LinkRule>> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+    let mut rules = vec![LinkRule {
+        regex: Regex::new(URL_PATTERN).unwrap(),
+        url: "{0}".to_string(),
+    }];
+    for spec in &opts.link {
+        // URLs contain ':' and often '=', so the regex ends at the first '='
+        let (pattern, url) = spec
+            .split_once('=')
+            .filter(|(p, u)| !p.is_empty() && !u.is_empty())
+            .ok_or_else(|| invalid(format!("Invalid --link '{}': expected REGEX=URL", spec)))?;
+        let regex =
+            Regex::new(pattern).map_err(|e| invalid(format!("Invalid link regex: {}", e)))?;
+        rules.push(LinkRule {
+            regex,
+            url: url.to_string(),
+        });
+    }
+    Ok(rules)
+}
+
+/// Escapes text for HTML element content and double quoted attributes.
+#[cfg(feature = "html")]
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Turns the hyperlinks of a cell into `<a href>` elements.
+///
+/// OSC-8 hyperlinks of the input (e.g. from `ls --hyperlink`) keep their URL,
+/// the remaining text is linked by the link rules (see [`link_rules_matches`]).
+///
+/// # Arguments
+///
+/// * `cell` - Cell value
+/// * `rules` - Link rules from [`link_rules`]
+///
+/// # Returns
+///
+/// The cell value with hyperlinks inserted
+#[cfg(feature = "html")]
+fn linkify(cell: &str, rules: &[LinkRule]) -> String {
+    let mut out = String::with_capacity(cell.len());
+    let mut pos = 0;
+    for caps in OSC8_REGEX.captures_iter(cell) {
+        let m = caps.get(0).unwrap();
+        out.push_str(&link_rules_matches(&cell[pos..m.start()], rules));
+        if caps[1].is_empty() {
+            out.push_str(&escape_html(&caps[2]));
+        } else {
+            out.push_str(&format!(
+                "<a href=\"{}\">{}</a>",
+                escape_html(&caps[1]),
+                escape_html(&caps[2])
+            ));
+        }
+        pos = m.end();
+    }
+    out.push_str(&link_rules_matches(&cell[pos..], rules));
+    out
+}
+
+/// Wraps all link rule matches of a text in `<a href>` elements and escapes the text.
+///
+/// Matches are taken leftmost first; where matches of several rules overlap, the
+/// earlier rule wins and the overlapping ones are left as text.
+///
+/// # Arguments
+///
+/// * `cell` - Cell text
+/// * `rules` - Link rules from [`link_rules`]
+///
+/// # Returns
+///
+/// The text with hyperlinks inserted
+#[cfg(feature = "html")]
+fn link_rules_matches(cell: &str, rules: &[LinkRule]) -> String {
+    let mut matches: Vec<(usize, usize, usize, String)> = Vec::new();
+    for (rule_idx, rule) in rules.iter().enumerate() {
+        for caps in rule.regex.captures_iter(cell) {
+            let m = caps.get(0).unwrap();
+            if m.is_empty() {
+                continue;
+            }
+            let url = PLACEHOLDER_REGEX.replace_all(&rule.url, |c: &regex::Captures| {
+                c[1].parse::<usize>()
+                    .ok()
+                    .and_then(|i| caps.get(i))
+                    .map_or("", |g| g.as_str())
+                    .to_string()
+            });
+            matches.push((m.start(), rule_idx, m.end(), url.to_string()));
+        }
+    }
+    if matches.is_empty() {
+        return escape_html(cell);
+    }
+    matches.sort();
+
+    let mut out = String::with_capacity(cell.len());
+    let mut pos = 0;
+    for (start, _, end, url) in matches {
+        if start < pos {
+            continue;
+        }
+        out.push_str(&escape_html(&cell[pos..start]));
+        out.push_str(&format!(
+            "<a href=\"{}\">{}</a>",
+            escape_html(&url),
+            escape_html(&cell[start..end])
+        ));
+        pos = end;
+    }
+    out.push_str(&escape_html(&cell[pos..]));
+    out
+}
+
 /// Formats table data as HTML table output.
 ///
 /// Generates a complete HTML table with proper thead/tbody structure.
-/// Headers are output in `<th>` tags, data rows in `<td>` tags.
+/// Headers are output in `<th>` tags, data rows in `<td>` tags. URLs and matches
+/// of `-link` rules in data cells become hyperlinks. With `-collapse-groups` every
+/// group of a grouped table becomes its own table inside a collapsible `<details>`
+/// element, summarized by its group value and row count. All cell content is
+/// HTML-escaped; `-html-full` wraps the table in a standalone document with
+/// embedded CSS.
 ///
 /// # Arguments
 ///
 /// * `data` - Table data to format
-/// * `_args` - Application arguments (currently unused for HTML formatting)
+/// * `opts` - Format options (checks `-stamp`, `-link`, `-collapse-groups`, `-html-full`,
+///   `-caption`, `-html-id` and `-html-class`)
+/// * `out` - Destination of the output
 ///
 /// # Returns
 ///
 /// - `Ok(())` if output succeeds
 /// - `Err(io::Error)` if writing fails
-fn format_html(data: &TableData, _args: &AppArgs) -> io::Result<()> {
-    println!("<table>");
-    if !data.headers.is_empty() {
-        println!("  <thead>");
-        println!("    <tr>");
-        for h in &data.headers {
-            println!("      <th>{}</th>", h);
+#[cfg(feature = "html")]
+fn format_html(data: &TableData, opts: &FormatOptions, out: &mut dyn Write) -> io::Result<()> {
+    if opts.html_full {
+        let title = html_caption(opts).unwrap_or("rcol");
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, "<html>")?;
+        writeln!(out, "<head>")?;
+        writeln!(out, "<meta charset=\"utf-8\">")?;
+        writeln!(out, "<title>{}</title>", escape_html(title))?;
+        writeln!(out, "<style>\n{}</style>", HTML_STYLE)?;
+        writeln!(out, "</head>")?;
+        writeln!(out, "<body>")?;
+    }
+    if opts.stamp {
+        // "--" is not allowed inside an HTML comment
+        let mut line = Stamp::new().line();
+        while line.contains("--") {
+            line = line.replace("--", "- -");
         }
-        println!("    </tr>");
-        println!("  </thead>");
+        writeln!(out, "<!-- {} -->", line)?;
     }
-    println!("  <tbody>");
-    for row in &data.rows {
-        println!("    <tr>");
-        for val in row {
-            println!("      <td>{}</td>", val);
+    let rules = link_rules(opts)?;
+    let groups: Vec<&[Vec<Cell>]> = data
+        .rows
+        .split(|row| is_group_separator(row))
+        .filter(|group| !group.is_empty())
+        .collect();
+    if opts.collapse_groups && groups.len() > 1 {
+        if let Some(caption) = html_caption(opts) {
+            writeln!(out, "<p class=\"caption\">{}</p>", escape_html(caption))?;
+        }
+        for (i, group) in groups.iter().enumerate() {
+            let label = opts
+                .group_column
+                .and_then(|col| group[0].get(col))
+                .filter(|v| !v.is_empty())
+                .map(Cell::to_string)
+                .unwrap_or_else(|| opts.lang.tr("group", &[&(i + 1)]));
+            let count = if group.len() == 1 { "one-row" } else { "rows" };
+            writeln!(out, "<details class=\"group\">")?;
+            writeln!(
+                out,
+                "  <summary>{} ({})</summary>",
+                escape_html(&label),
+                opts.lang.tr(count, &[&group.len()])
+            )?;
+            write_html_table(out, &data.headers, group, opts, &rules, Some(i), "  ")?;
+            writeln!(out, "</details>")?;
         }
-        println!("    </tr>");
+    } else {
+        write_html_table(out, &data.headers, &data.rows, opts, &rules, None, "")?;
+    }
+    for (i, note) in column_notes(opts)?.iter().enumerate() {
+        writeln!(
+            out,
+            "<p class=\"note\">{} {}</p>",
+            note_marker(i),
+            escape_html(&note.text)
+        )?;
+    }
+    if opts.html_full {
+        writeln!(out, "</body>")?;
+        writeln!(out, "</html>")?;
     }
-    println!("  </tbody>");
-    println!("</table>");
     Ok(())
 }
 
+/// Caption of HTML tables: `-caption`, else `-title`.
+#[cfg(feature = "html")]
+fn html_caption(opts: &FormatOptions) -> Option<&str> {
+    opts.caption.as_deref().or(opts.title.as_deref())
+}
+
+/// Style sheet embedded into standalone HTML documents (`-html-full`).
+#[cfg(feature = "html")]
+const HTML_STYLE: &str = "\
+body { font-family: sans-serif; }
+table { border-collapse: collapse; margin: 0.5em 0; }
+caption { font-weight: bold; text-align: left; padding: 0.3em 0; }
+th, td { border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: left; }
+th { background: #f0f0f0; }
+td.num { text-align: right; }
+tbody tr:nth-child(even) { background: #fafafa; }
+details.group summary { cursor: pointer; font-weight: bold; }
+p.note { font-size: smaller; }
+";
+
+/// Writes one `<table>` element, every line prefixed with `indent`.
+///
+/// The table gets the `-html-id` and `-html-class` attributes; tables of collapsed
+/// groups (`group` is their index) get the id with a `-N` suffix and no caption.
+/// Numeric cells are marked with `class="num"` unless `-nn` is set.
+#[cfg(feature = "html")]
+fn write_html_table(
+    out: &mut dyn Write,
+    headers: &[Cell],
+    rows: &[Vec<Cell>],
+    opts: &FormatOptions,
+    rules: &[LinkRule],
+    group: Option<usize>,
+    indent: &str,
+) -> io::Result<()> {
+    let mut attrs = String::new();
+    if let Some(id) = &opts.html_id {
+        let id = match group {
+            Some(i) => format!("{}-{}", id, i + 1),
+            None => id.clone(),
+        };
+        attrs.push_str(&format!(" id=\"{}\"", escape_html(&id)));
+    }
+    if let Some(class) = &opts.html_class {
+        attrs.push_str(&format!(" class=\"{}\"", escape_html(class)));
+    }
+    writeln!(out, "{}<table{}>", indent, attrs)?;
+    if let Some(caption) = html_caption(opts).filter(|_| group.is_none()) {
+        writeln!(
+            out,
+            "{}  <caption>{}</caption>",
+            indent,
+            escape_html(caption)
+        )?;
+    }
+    if !headers.is_empty() {
+        writeln!(out, "{}  <thead>", indent)?;
+        let groups = column_groups(opts, headers.len())?;
+        if !groups.is_empty() {
+            writeln!(out, "{}    <tr>", indent)?;
+            let mut col = 0;
+            for group in &groups {
+                for _ in col..group.first {
+                    writeln!(out, "{}      <th></th>", indent)?;
+                }
+                writeln!(
+                    out,
+                    "{}      <th colspan=\"{}\">{}</th>",
+                    indent,
+                    group.last - group.first + 1,
+                    escape_html(&group.title)
+                )?;
+                col = group.last + 1;
+            }
+            for _ in col..headers.len() {
+                writeln!(out, "{}      <th></th>", indent)?;
+            }
+            writeln!(out, "{}    </tr>", indent)?;
+        }
+        writeln!(out, "{}    <tr>", indent)?;
+        for h in headers {
+            writeln!(out, "{}      <th>{}</th>", indent, escape_html(h))?;
+        }
+        writeln!(out, "{}    </tr>", indent)?;
+        writeln!(out, "{}  </thead>", indent)?;
+    }
+    writeln!(out, "{}  <tbody>", indent)?;
+    for row in rows {
+        writeln!(out, "{}    <tr>", indent)?;
+        for val in row {
+            let class = if !opts.nn && is_number(val, opts.numfmt) {
+                " class=\"num\""
+            } else {
+                ""
+            };
+            writeln!(
+                out,
+                "{}      <td{}>{}</td>",
+                indent,
+                class,
+                linkify(val, rules)
+            )?;
+        }
+        writeln!(out, "{}    </tr>", indent)?;
+    }
+    writeln!(out, "{}  </tbody>", indent)?;
+    writeln!(out, "{}</table>", indent)
+}
+
 /// Unicode box-drawing characters for table formatting.
 ///
 /// Contains all the characters needed to draw table borders and separators
@@ -368,7 +2216,7 @@ impl BoxChars {
 /// # Arguments
 ///
 /// * `data` - Table data to format
-/// * `args` - Application arguments controlling formatting options
+/// * `opts` - Format options controlling the layout
 ///
 /// # Returns
 ///
@@ -382,69 +2230,394 @@ impl BoxChars {
 /// - Left-aligns text values
 /// - Headers starting with '-' are right-aligned
 /// - Draws Unicode box characters for pretty printing when `-pp` is enabled
+///
+/// Styling of a colored ASCII table, selected with `-theme`.
+#[derive(Debug, Clone, Copy, Default)]
+struct Theme {
+    /// SGR sequence of the header cells
+    header: Option<&'static str>,
+    /// Background SGR sequence of every second data row
+    zebra: Option<&'static str>,
+}
+
+/// Themes selectable with `-theme`.
+const THEMES: [(&str, Theme); 4] = [
+    (
+        "plain",
+        Theme {
+            header: None,
+            zebra: None,
+        },
+    ),
+    (
+        "bold",
+        Theme {
+            header: Some("\x1b[1m"),
+            zebra: None,
+        },
+    ),
+    (
+        "dark",
+        Theme {
+            header: Some("\x1b[1;36m"),
+            zebra: Some("\x1b[48;5;236m"),
+        },
+    ),
+    (
+        "light",
+        Theme {
+            header: Some("\x1b[1;34m"),
+            zebra: Some("\x1b[48;5;254m"),
+        },
+    ),
+];
+
+impl Theme {
+    /// Looks up the theme of the options; without `color` every theme is plain.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Theme)` with the styles to apply
+    /// - `Err(io::Error)` if the theme name is unknown
+    fn from_options(opts: &FormatOptions) -> io::Result<Self> {
+        let theme = THEMES
+            .iter()
+            .find(|(name, _)| *name == opts.theme)
+            .map(|(_, theme)| *theme)
+            .ok_or_else(|| {
+                let names: Vec<&str> = THEMES.iter().map(|(name, _)| *name).collect();
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Unknown theme '{}' (available: {})",
+                        opts.theme,
+                        names.join(", ")
+                    ),
+                )
+            })?;
+        Ok(if opts.color { theme } else { Theme::default() })
+    }
+
+    /// Puts a whole line on the zebra background, restoring it after every
+    /// color reset inside the line.
+    fn stripe(&self, line: &str) -> String {
+        match self.zebra {
+            Some(bg) => format!(
+                "{}{}{}",
+                bg,
+                line.replace(RESET_COLOR, &format!("{}{}", RESET_COLOR, bg)),
+                RESET_COLOR
+            ),
+            None => line.to_string(),
+        }
+    }
+}
+
 /// Context for rendering the table.
 struct RenderContext<'a> {
     widths: &'a [usize],
-    args: &'a AppArgs,
+    opts: &'a FormatOptions,
     chars: BoxChars,
     col_sep: &'a str,
+    /// Spaces between a cell and its column boundaries
     padding: String,
+    /// Column boundary without `-cs` or `-pp`
+    gap: String,
+    /// Space outside the first and last column without `-pp`
+    margin: String,
     draw_borders: bool,
     draw_cs: bool,
     draw_ts: bool,
     draw_fs: bool,
+    theme: Theme,
+    /// Banner label of the group starting at each row (`-gheader`)
+    banners: Vec<Option<String>>,
+    /// Title line above the table (`-title`, `-caption`)
+    title: Option<&'a str>,
+}
+
+impl RenderContext<'_> {
+    /// Starts a line with the left border (`-pp`) or margin.
+    fn line_start(&self, line: &mut String) {
+        if self.draw_borders {
+            line.push(self.chars.v);
+        } else {
+            line.push_str(&self.margin);
+        }
+    }
+
+    /// Ends a line with the right border (`-pp`) or margin.
+    fn line_end(&self, line: &mut String) {
+        self.line_start(line);
+    }
+
+    /// Returns the corners of the `-pp` border line below the title, or at the
+    /// top of a table without title.
+    fn top_corners(&self) -> (char, char) {
+        if self.title.is_some() {
+            (self.chars.lm, self.chars.rm)
+        } else {
+            (self.chars.tl, self.chars.tr)
+        }
+    }
+
+    /// Returns the visible width of a table line, borders or margins included.
+    fn table_width(&self) -> usize {
+        let mut width =
+            self.widths.iter().sum::<usize>() + self.widths.len() * 2 * self.padding.len();
+        if self.draw_borders {
+            width += 2 + self.widths.len().saturating_sub(1);
+        } else {
+            width += 2 * self.margin.len();
+            let gap = if self.draw_cs {
+                visible_width(self.col_sep)
+            } else {
+                self.gap.len()
+            };
+            width += self.widths.len().saturating_sub(1) * gap;
+        }
+        width
+    }
+
+    /// Appends the boundary between two columns.
+    fn column_gap(&self, line: &mut String) {
+        if self.draw_borders {
+            line.push(self.chars.v);
+        } else if self.draw_cs {
+            line.push_str(self.col_sep);
+        } else {
+            line.push_str(&self.gap);
+        }
+    }
 }
 
 /// Formats table data as an ASCII/Unicode table with borders and alignment.
-fn format_ascii(data: &TableData, args: &AppArgs) -> io::Result<()> {
-    let widths = calculate_widths(data, args);
-    let padding = " ".repeat(args.w);
-    let col_sep = &args.colsep;
+///
+/// Without `-pad-inside` and `-pad-outside` every cell is padded with `-w` spaces
+/// on both sides and plain columns are separated by another `-w` spaces. Either
+/// option switches to a spacing that keeps the columns in place when `-cs` or
+/// `-pp` are toggled: cells are padded with `-pad-inside` spaces (default `-w`),
+/// the column boundary is always as wide as `-colsep` and the table is indented
+/// by `-pad-outside` spaces (default 1, the width of the `-pp` border).
+fn format_ascii(data: &TableData, opts: &FormatOptions, out: &mut dyn Write) -> io::Result<()> {
+    write_ascii(data, &mut std::iter::empty(), opts, out)
+}
+
+/// Formats a table whose rows come in parts as one ASCII table, e.g. rows read
+/// back from disk with `-low-mem`, holding one part in memory at a time.
+///
+/// The headers and the first rows come with `first`, the following parts have
+/// rows only. The columns must have been measured before: `opts.min_widths` holds
+/// the [`column_widths`] of all parts. With `-align-headers with-data` the
+/// alignment of the headers follows the rows of `first`.
+///
+/// # Arguments
+///
+/// * `first` - Headers and the first rows
+/// * `rest` - The rows of the following parts
+/// * `opts` - Format options
+/// * `out` - Destination of the output
+///
+/// # Returns
+///
+/// - `Ok(())` if output succeeds
+/// - `Err(io::Error)` if a part can't be read or writing fails
+pub fn format_ascii_parts(
+    first: &TableData,
+    rest: impl Iterator<Item = io::Result<Vec<Vec<Cell>>>>,
+    opts: &FormatOptions,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let opts = &FormatOptions {
+        format: "ascii".to_string(),
+        ..opts.clone()
+    };
+    let out: &mut dyn Write = out;
+    let data = prepare(first, opts)?;
+    let mut rest = rest.map(|rows| {
+        let part = TableData {
+            headers: first.headers.clone(),
+            rows: rows?,
+            original_column_indices: first.original_column_indices.clone(),
+        };
+        Ok(prepare(&part, opts)?.into_owned().rows)
+    });
+    if opts.null_out {
+        write_ascii(&data, &mut rest, opts, &mut SwapNewlines(out))
+    } else {
+        write_ascii(&data, &mut rest, opts, out)
+    }
+}
+
+/// Formats the table of [`format_ascii`], followed by more rows in parts.
+fn write_ascii(
+    data: &TableData,
+    rest: &mut dyn Iterator<Item = io::Result<Vec<Vec<Cell>>>>,
+    opts: &FormatOptions,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    let banner_table;
+    let (data, banners) = match opts.group_column.filter(|_| opts.group_banner) {
+        Some(col) => {
+            let (table, banners) = group_banners(data, col, opts);
+            banner_table = table;
+            (&banner_table, banners)
+        }
+        None => (data, Vec::new()),
+    };
+    let Measured { mut widths, cells } = calculate_widths(data, opts);
+    let groups = if data.headers.is_empty() {
+        Vec::new()
+    } else {
+        column_groups(opts, widths.len())?
+    };
+    let unified = opts.pad_inside.is_some() || opts.pad_outside.is_some();
+    let padding = " ".repeat(opts.pad_inside.unwrap_or(opts.w));
+    let (gap, margin) = if unified {
+        (
+            " ".repeat(visible_width(&opts.colsep)),
+            " ".repeat(opts.pad_outside.unwrap_or(1)),
+        )
+    } else {
+        (padding.clone(), String::new())
+    };
+    let col_sep = &opts.colsep;
     let chars = BoxChars::unicode();
 
-    let draw_borders = args.pp;
-    let draw_ts = args.ts || args.header.is_some();
-    let draw_fs = args.fs;
-    let draw_cs = args.cs || args.pp;
+    let draw_borders = opts.pp;
+    let draw_ts = opts.ts;
+    let draw_fs = opts.fs;
+    let draw_cs = opts.cs || opts.pp;
+
+    // A group title wider than its columns widens the last of them
+    let gap_width = if draw_borders {
+        1
+    } else if draw_cs {
+        visible_width(col_sep)
+    } else {
+        gap.len()
+    };
+    for group in &groups {
+        let span = span_width(&widths[group.first..=group.last], padding.len(), gap_width);
+        let title = visible_width(&group.title);
+        if title > span {
+            widths[group.last] += title - span;
+        }
+    }
+    // So does a title wider than the table inside the border
+    let title = opts.title.as_deref().or(opts.caption.as_deref());
+    if let (Some(title), true, Some(last)) = (title, draw_borders, widths.len().checked_sub(1)) {
+        let inner = span_width(&widths, padding.len(), gap_width) + 2 * padding.len();
+        let needed = visible_width(title) + 2;
+        if needed > inner {
+            widths[last] += needed - inner;
+        }
+    }
 
     let ctx = RenderContext {
         widths: &widths,
-        args,
+        opts,
         chars,
         col_sep,
         padding,
+        gap,
+        margin,
         draw_borders,
         draw_cs,
         draw_ts,
         draw_fs,
+        theme: Theme::from_options(opts)?,
+        banners,
+        title,
     };
 
+    if opts.stamp {
+        writeln!(out, "# {}", Stamp::new().line())?;
+    }
+    if let Some(title) = title {
+        print_title(out, &ctx, title)?;
+    }
+
     // Print Column Numbers
-    if args.num {
-        print_column_numbers(data, &ctx);
+    if opts.num {
+        print_column_numbers(out, data, &ctx)?;
     } else {
         // No numbers, check if we need top border for header or data
-        if draw_borders {
-            print_separator(&ctx, ctx.chars.tl, ctx.chars.tr, ctx.chars.tm, ctx.chars.h);
+        let (left, right) = ctx.top_corners();
+        if draw_borders && !groups.is_empty() {
+            let chars = &ctx.chars;
+            print_group_separator(out, &ctx, &groups, left, right, chars.h, chars.tm)?;
+        } else if draw_borders {
+            print_separator(out, &ctx, left, right, ctx.chars.tm, ctx.chars.h)?;
         }
     }
 
     // Print Header
+    if !groups.is_empty() {
+        print_group_header(out, &groups, gap_width, &ctx)?;
+    }
     if !data.headers.is_empty() {
-        print_header(data, &ctx);
+        print_header(out, data, &ctx)?;
     }
 
     // Print Rows
-    print_data_rows(data, &ctx);
+    let mut printed = Printed::default();
+    let mut rest = rest.peekable();
+    let last = rest.peek().is_none();
+    print_data_rows(out, &data.rows, &cells, &ctx, &mut printed, last)?;
+    while let Some(rows) = rest.next() {
+        let rows = rows?;
+        let cells = measure_rows(&rows, opts.parallel).cells;
+        let last = rest.peek().is_none();
+        print_data_rows(out, &rows, &cells, &ctx, &mut printed, last)?;
+    }
 
     // Bottom Border
     if draw_borders {
-        print_separator(&ctx, ctx.chars.bl, ctx.chars.br, ctx.chars.bm, ctx.chars.h);
+        print_separator(
+            out,
+            &ctx,
+            ctx.chars.bl,
+            ctx.chars.br,
+            ctx.chars.bm,
+            ctx.chars.h,
+        )?;
     }
 
+    write_notes(out, &column_notes(opts)?, "")?;
     Ok(())
 }
 
+/// Returns the width of every column of `data` in ASCII output (without padding).
+///
+/// Feed them back as [`FormatOptions::min_widths`] to keep the columns of
+/// following tables at least as wide. The cells are measured as they are
+/// printed, e.g. with `-na` placeholders and `-map` values.
+pub fn column_widths(data: &TableData, opts: &FormatOptions) -> Vec<usize> {
+    let opts = &FormatOptions {
+        format: "ascii".to_string(),
+        ..opts.clone()
+    };
+    // Invalid options are reported when the table is formatted
+    match prepare(data, opts) {
+        Ok(data) => calculate_widths(&data, opts).widths,
+        Err(_) => calculate_widths(data, opts).widths,
+    }
+}
+
+/// Rows measured at a time, on one core each with `-parallel`.
+const MEASURE_CHUNK: usize = 4096;
+
+/// The cells of a table measured once for ASCII output, for both the widths of
+/// the columns and the padding of every cell.
+#[derive(Default)]
+struct Measured {
+    /// Width of every column
+    widths: Vec<usize>,
+    /// Visible width of every cell of the data rows, row after row
+    cells: Vec<usize>,
+}
+
 /// Calculates the width of each column based on data content and headers.
 ///
 /// Also handles adjusting widths for the column numbering row if `-num` is specified.
@@ -452,54 +2625,80 @@ fn format_ascii(data: &TableData, args: &AppArgs) -> io::Result<()> {
 /// # Arguments
 ///
 /// * `data` - The table data containing headers and rows
-/// * `args` - Application arguments
+/// * `opts` - Format options
 ///
 /// # Returns
 ///
-/// A vector of column widths
-fn calculate_widths(data: &TableData, args: &AppArgs) -> Vec<usize> {
-    let mut widths = Vec::new();
-    let mut num_cols = 0;
+/// The widths of the columns and of the cells of the data rows
+fn calculate_widths(data: &TableData, opts: &FormatOptions) -> Measured {
+    let mut measured = measure_rows(&data.rows, opts.parallel);
+    let headers: Vec<usize> = data.headers.iter().map(|h| visible_width(h)).collect();
+    widen(&mut measured.widths, &headers);
 
-    if !data.headers.is_empty() {
-        num_cols = data.headers.len();
-        // Initial width based on headers
-        for h in &data.headers {
-            widths.push(visible_width(h));
+    if opts.num {
+        // Adjust for column numbers if needed
+        for (i, width) in measured.widths.iter_mut().enumerate() {
+            let num_str = column_number(data, i);
+            let num_w = visible_width(&num_str);
+            if num_w > *width {
+                *width = num_w;
+            }
         }
     }
+    widen(&mut measured.widths, &opts.min_widths);
+    measured
+}
 
-    // Update widths based on max content length in rows
-    for row in &data.rows {
-        if row.len() > num_cols {
-            num_cols = row.len();
-            widths.resize(num_cols, 0);
-        }
-        for (i, val) in row.iter().enumerate() {
-            if i < widths.len() {
-                let w = visible_width(val);
-                if w > widths[i] {
-                    widths[i] = w;
-                }
+/// Measures every cell of data rows, with `-parallel` in chunks on all cores.
+///
+/// # Arguments
+///
+/// * `rows` - Data rows
+/// * `parallel` - Measure on all cores
+///
+/// # Returns
+///
+/// The widths of the columns and of the cells, without headers
+fn measure_rows(rows: &[Vec<Cell>], parallel: bool) -> Measured {
+    let chunks: Vec<&[Vec<Cell>]> = if parallel {
+        rows.chunks(MEASURE_CHUNK).collect()
+    } else {
+        vec![rows]
+    };
+    let parts = parallel::map(chunks, parallel, |rows| {
+        let mut part = Measured {
+            widths: Vec::new(),
+            cells: Vec::with_capacity(rows.iter().map(Vec::len).sum()),
+        };
+        for row in rows {
+            if row.len() > part.widths.len() {
+                part.widths.resize(row.len(), 0);
+            }
+            for (width, cell) in part.widths.iter_mut().zip(row) {
+                let cell = visible_width(cell);
+                *width = (*width).max(cell);
+                part.cells.push(cell);
             }
         }
+        part
+    });
+    let mut parts = parts.into_iter();
+    let mut measured = parts.next().unwrap_or_default();
+    for part in parts {
+        widen(&mut measured.widths, &part.widths);
+        measured.cells.extend(part.cells);
     }
+    measured
+}
 
-    if args.num {
-        // Adjust for column numbers if needed
-        for i in 0..widths.len() {
-            let num_str = if i < data.original_column_indices.len() {
-                (data.original_column_indices[i] + 1).to_string()
-            } else {
-                (i + 1).to_string()
-            };
-            let num_w = visible_width(&num_str);
-            if num_w > widths[i] {
-                widths[i] = num_w;
-            }
-        }
+/// Widens the columns to the widths of `other`, adding the columns it has more.
+fn widen(widths: &mut Vec<usize>, other: &[usize]) {
+    if other.len() > widths.len() {
+        widths.resize(other.len(), 0);
+    }
+    for (width, other) in widths.iter_mut().zip(other) {
+        *width = (*width).max(*other);
     }
-    widths
 }
 
 /// Prints a horizontal separator line.
@@ -508,16 +2707,27 @@ fn calculate_widths(data: &TableData, args: &AppArgs) -> Vec<usize> {
 ///
 /// # Arguments
 ///
+/// * `out` - Destination of the output
 /// * `ctx` - Render context
 /// * `left` - Character for the left edge
 /// * `right` - Character for the right edge
 /// * `cross` - Character for column intersections
 /// * `horiz` - Character for the horizontal line
-fn print_separator(ctx: &RenderContext, left: char, right: char, cross: char, horiz: char) {
+fn print_separator(
+    out: &mut dyn Write,
+    ctx: &RenderContext,
+    left: char,
+    right: char,
+    cross: char,
+    horiz: char,
+) -> io::Result<()> {
     let mut line = String::new();
 
+    let fill = |n: usize| horiz.to_string().repeat(n);
     if ctx.draw_borders {
         line.push(left);
+    } else {
+        line.push_str(&fill(ctx.margin.len()));
     }
     for (i, w) in ctx.widths.iter().enumerate() {
         if i > 0 {
@@ -525,20 +2735,158 @@ fn print_separator(ctx: &RenderContext, left: char, right: char, cross: char, ho
                 line.push(cross);
             } else {
                 // Fill space between columns with horizontal line if no vertical separator
-                for _ in 0..ctx.args.w {
-                    line.push(horiz);
-                }
+                line.push_str(&fill(ctx.gap.len()));
             }
         }
-        let total_w = w + 2 * ctx.args.w;
-        for _ in 0..total_w {
-            line.push(horiz);
-        }
+        line.push_str(&fill(w + 2 * ctx.padding.len()));
     }
     if ctx.draw_borders {
         line.push(right);
+    } else {
+        line.push_str(&fill(ctx.margin.len()));
+    }
+    writeln!(out, "{}", line)?;
+    Ok(())
+}
+
+/// Checks whether a row separates two groups of `-gcol`.
+///
+/// Separator rows have no cells at all, unlike data rows whose cells are all empty.
+fn is_group_separator(row: &[Cell]) -> bool {
+    row.is_empty()
+}
+
+/// Replaces the group separator rows by banners for `-gheader`.
+///
+/// The group column is removed from the headers and rows, its value in the first
+/// row of a group becomes the label of the group (`Group N` if it is empty).
+///
+/// # Arguments
+///
+/// * `data` - Table data grouped by `-gcol`
+/// * `col` - 0-based output column of the group values
+/// * `opts` - Format options, for the language of the fallback label
+///
+/// # Returns
+///
+/// The table without separator rows and group column, and the banner label of
+/// the group starting at each of its rows
+fn group_banners(
+    data: &TableData,
+    col: usize,
+    opts: &FormatOptions,
+) -> (TableData, Vec<Option<String>>) {
+    let without_col = |cells: &[Cell]| -> Vec<Cell> {
+        cells
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != col)
+            .map(|(_, c)| c.clone())
+            .collect()
+    };
+    let mut rows = Vec::new();
+    let mut banners = Vec::new();
+    let groups = data
+        .rows
+        .split(|row| is_group_separator(row))
+        .filter(|group| !group.is_empty());
+    for (i, group) in groups.enumerate() {
+        let label = group[0]
+            .get(col)
+            .filter(|v| !v.is_empty())
+            .map(Cell::to_string)
+            .unwrap_or_else(|| opts.lang.tr("group", &[&(i + 1)]));
+        for (j, row) in group.iter().enumerate() {
+            rows.push(without_col(row));
+            banners.push((j == 0).then(|| label.clone()));
+        }
+    }
+    let table = TableData {
+        headers: if data.headers.is_empty() {
+            Vec::new()
+        } else {
+            without_col(&data.headers)
+        },
+        rows,
+        original_column_indices: data
+            .original_column_indices
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != col)
+            .map(|(_, c)| *c)
+            .collect(),
+    };
+    (table, banners)
+}
+
+/// Prints the title line centered above the table (`-title`), with `-pp` in a
+/// box of its own on top of the table.
+///
+/// # Arguments
+///
+/// * `out` - Destination of the output
+/// * `ctx` - Render context
+/// * `title` - Text of the title
+fn print_title(out: &mut dyn Write, ctx: &RenderContext, title: &str) -> io::Result<()> {
+    let mut width = ctx.table_width();
+    let h = ctx.chars.h.to_string();
+    if ctx.draw_borders {
+        width = width.saturating_sub(2);
+        writeln!(out, "{}{}{}", ctx.chars.tl, h.repeat(width), ctx.chars.tr)?;
+    }
+    let fill = width.saturating_sub(visible_width(title));
+    let content = match ctx.theme.header {
+        Some(style) => format!("{}{}{}", style, title, RESET_COLOR),
+        None => title.to_string(),
+    };
+    let line = format!(
+        "{}{}{}",
+        " ".repeat(fill / 2),
+        content,
+        " ".repeat(fill - fill / 2)
+    );
+    if ctx.draw_borders {
+        writeln!(out, "{}{}{}", ctx.chars.v, line, ctx.chars.v)
+    } else {
+        writeln!(out, "{}", line)
+    }
+}
+
+/// Prints the banner row starting a group (`-gheader`), e.g. `── Sales ─────`,
+/// as wide as the table.
+///
+/// # Arguments
+///
+/// * `out` - Destination of the output
+/// * `ctx` - Render context
+/// * `label` - Group value shown in the banner
+fn print_banner(out: &mut dyn Write, ctx: &RenderContext, label: &str) -> io::Result<()> {
+    let h = ctx.chars.h;
+    let (left, right) = if ctx.draw_borders {
+        (ctx.chars.lm, ctx.chars.rm)
+    } else {
+        (h, h)
+    };
+    let width = ctx.table_width();
+    let mut line = format!("{}{} {} ", left, h, label);
+    let fill = width.saturating_sub(visible_width(&line) + 1);
+    line.push_str(&h.to_string().repeat(fill));
+    line.push(right);
+    match ctx.theme.header {
+        Some(style) => writeln!(out, "{}{}{}", style, line, RESET_COLOR)?,
+        None => writeln!(out, "{}", line)?,
+    }
+    Ok(())
+}
+
+/// Number shown above output column `i` with `-num`: the input column it was
+/// read from, nothing for `-insert` columns.
+fn column_number(data: &TableData, i: usize) -> String {
+    match data.original_column_indices.get(i) {
+        Some(&NO_INPUT_COLUMN) => String::new(),
+        Some(&idx) => (idx + 1).to_string(),
+        None => (i + 1).to_string(),
     }
-    println!("{}", line);
 }
 
 /// Prints the row containing column numbers.
@@ -548,32 +2896,26 @@ fn print_separator(ctx: &RenderContext, left: char, right: char, cross: char, ho
 ///
 /// # Arguments
 ///
+/// * `out` - Destination of the output
 /// * `data` - Table data
 /// * `ctx` - Render context
-fn print_column_numbers(data: &TableData, ctx: &RenderContext) {
+fn print_column_numbers(
+    out: &mut dyn Write,
+    data: &TableData,
+    ctx: &RenderContext,
+) -> io::Result<()> {
     if ctx.draw_borders {
-        print_separator(ctx, ctx.chars.tl, ctx.chars.tr, ctx.chars.tm, ctx.chars.h);
+        let (left, right) = ctx.top_corners();
+        print_separator(out, ctx, left, right, ctx.chars.tm, ctx.chars.h)?;
     }
 
     let mut line = String::new();
-    if ctx.draw_borders {
-        line.push(ctx.chars.v);
-    }
+    ctx.line_start(&mut line);
     for (i, w) in ctx.widths.iter().enumerate() {
         if i > 0 {
-            if ctx.draw_borders {
-                line.push(ctx.chars.v);
-            } else if ctx.draw_cs {
-                line.push_str(ctx.col_sep);
-            } else {
-                line.push_str(&ctx.padding);
-            }
+            ctx.column_gap(&mut line);
         }
-        let num_str = if i < data.original_column_indices.len() {
-            (data.original_column_indices[i] + 1).to_string()
-        } else {
-            (i + 1).to_string()
-        };
+        let num_str = column_number(data, i);
         // Calculate width for alignment
         let num_w = visible_width(&num_str);
         line.push_str(&ctx.padding);
@@ -583,53 +2925,233 @@ fn print_column_numbers(data: &TableData, ctx: &RenderContext) {
         }
         line.push_str(&ctx.padding);
     }
-    if ctx.draw_borders {
-        line.push(ctx.chars.v);
-    }
-    println!("{}", line);
+    ctx.line_end(&mut line);
+    writeln!(out, "{}", line)?;
 
     if ctx.draw_borders || ctx.draw_ts {
         if ctx.draw_borders {
-            print_separator(ctx, ctx.chars.lm, ctx.chars.rm, ctx.chars.c, ctx.chars.h);
+            print_separator(
+                out,
+                ctx,
+                ctx.chars.lm,
+                ctx.chars.rm,
+                ctx.chars.c,
+                ctx.chars.h,
+            )?;
         } else {
-            print_separator(ctx, ctx.chars.h, ctx.chars.h, ctx.chars.h, ctx.chars.h);
+            print_separator(out, ctx, ctx.chars.h, ctx.chars.h, ctx.chars.h, ctx.chars.h)?;
         }
     }
+    Ok(())
 }
 
-/// Prints the header row.
+/// Titles above a range of columns (`-group-header`).
+#[derive(Debug, Clone, PartialEq)]
+struct ColumnGroup {
+    title: String,
+    /// 0-based first output column of the group
+    first: usize,
+    /// 0-based last output column of the group
+    last: usize,
+}
+
+/// Parses the `-group-header` specification `TITLE=N:M,TITLE=N,...` of the options.
+///
+/// # Arguments
+///
+/// * `opts` - Format options
+/// * `count` - Number of output columns
+///
+/// # Returns
+///
+/// - `Ok(Vec)` with the groups ordered by their first column, empty without `-group-header`
+/// - `Err(io::Error)` if a group is malformed, overlaps another or is beyond the columns
+fn column_groups(opts: &FormatOptions, count: usize) -> io::Result<Vec<ColumnGroup>> {
+    let Some(spec) = &opts.group_header else {
+        return Ok(Vec::new());
+    };
+    let groups = parse_column_groups(spec)?;
+    if let Some(last) = groups.iter().map(|group| group.last + 1).max()
+        && last > count
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            opts.lang
+                .tr("exceeds-output-columns", &[&"group-header", &last, &count]),
+        ));
+    }
+    Ok(groups)
+}
+
+/// Parses the `-group-header` groups, see [`column_groups`].
+///
+/// # Returns
+///
+/// - `Ok(Vec)` with the groups ordered by their first column
+/// - `Err(io::Error)` if a group is malformed or overlaps another
+fn parse_column_groups(spec: &str) -> io::Result<Vec<ColumnGroup>> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+    let mut groups = Vec::new();
+    for part in spec.split(',') {
+        let malformed = || {
+            invalid(format!(
+                "Invalid --group-header '{}': expected TITLE=N:M,...",
+                part
+            ))
+        };
+        let (title, range) = part.rsplit_once('=').ok_or_else(malformed)?;
+        let (first, last) = range.split_once(':').unwrap_or((range, range));
+        let first: usize = first.trim().parse().map_err(|_| malformed())?;
+        let last: usize = last.trim().parse().map_err(|_| malformed())?;
+        if first == 0 || last < first {
+            return Err(malformed());
+        }
+        groups.push(ColumnGroup {
+            title: title.to_string(),
+            first: first - 1,
+            last: last - 1,
+        });
+    }
+    groups.sort_by_key(|group| group.first);
+    if let Some(pair) = groups.windows(2).find(|pair| pair[1].first <= pair[0].last) {
+        return Err(invalid(format!(
+            "--group-header '{}' overlaps '{}'",
+            pair[1].title, pair[0].title
+        )));
+    }
+    Ok(groups)
+}
+
+/// Width of adjacent columns as one cell, including their padding and the
+/// column boundaries between them.
+fn span_width(widths: &[usize], padding: usize, gap: usize) -> usize {
+    widths.iter().map(|w| w + 2 * padding).sum::<usize>() + widths.len().saturating_sub(1) * gap
+        - 2 * padding
+}
+
+/// Prints the row with the titles of the column groups above the header row.
 ///
-/// Handles alignment of header text (right-aligned if starting with `-`).
+/// Titles are centered over their columns; column boundaries are only drawn
+/// between groups and columns without group.
 ///
 /// # Arguments
 ///
-/// * `data` - Table data
+/// * `out` - Destination of the output
+/// * `groups` - Column groups from [`column_groups`]
+/// * `gap_width` - Width of a column boundary
 /// * `ctx` - Render context
-fn print_header(data: &TableData, ctx: &RenderContext) {
+fn print_group_header(
+    out: &mut dyn Write,
+    groups: &[ColumnGroup],
+    gap_width: usize,
+    ctx: &RenderContext,
+) -> io::Result<()> {
     let mut line = String::new();
+    ctx.line_start(&mut line);
+    let mut col = 0;
+    while col < ctx.widths.len() {
+        if col > 0 {
+            ctx.column_gap(&mut line);
+        }
+        let (title, last) = match groups.iter().find(|group| group.first == col) {
+            Some(group) => (group.title.as_str(), group.last),
+            None => ("", col),
+        };
+        let width = span_width(&ctx.widths[col..=last], ctx.padding.len(), gap_width);
+        let fill = width.saturating_sub(visible_width(title));
+        let mut content = title.to_string();
+        if let Some(style) = ctx.theme.header.filter(|_| !title.is_empty()) {
+            content = format!("{}{}{}", style, content, RESET_COLOR);
+        }
+        line.push_str(&ctx.padding);
+        line.push_str(&" ".repeat(fill / 2));
+        line.push_str(&content);
+        line.push_str(&" ".repeat(fill - fill / 2));
+        line.push_str(&ctx.padding);
+        col = last + 1;
+    }
+    ctx.line_end(&mut line);
+    writeln!(out, "{}", line)?;
+
     if ctx.draw_borders {
-        line.push(ctx.chars.v);
+        let chars = &ctx.chars;
+        print_group_separator(out, ctx, groups, chars.lm, chars.rm, chars.tm, chars.c)?;
+    }
+    Ok(())
+}
+
+/// Prints a `-pp` border line that crosses column boundaries differently within
+/// and between column groups.
+///
+/// # Arguments
+///
+/// * `out` - Destination of the output
+/// * `ctx` - Render context
+/// * `groups` - Column groups from [`column_groups`]
+/// * `left` - Character for the left edge
+/// * `right` - Character for the right edge
+/// * `within` - Character for boundaries within a group
+/// * `between` - Character for the other boundaries
+fn print_group_separator(
+    out: &mut dyn Write,
+    ctx: &RenderContext,
+    groups: &[ColumnGroup],
+    left: char,
+    right: char,
+    within: char,
+    between: char,
+) -> io::Result<()> {
+    let mut line = String::from(left);
+    for (i, w) in ctx.widths.iter().enumerate() {
+        if i > 0 {
+            let joined = groups.iter().any(|g| g.first < i && i <= g.last);
+            line.push(if joined { within } else { between });
+        }
+        line.push_str(&ctx.chars.h.to_string().repeat(w + 2 * ctx.padding.len()));
     }
+    line.push(right);
+    writeln!(out, "{}", line)
+}
+
+/// Prints the header row.
+///
+/// Handles alignment of header text: right-aligned if starting with `-`, with
+/// `-align-headers with-data` also above numeric columns (unless `-nn`).
+///
+/// # Arguments
+///
+/// * `out` - Destination of the output
+/// * `data` - Table data
+/// * `ctx` - Render context
+fn print_header(out: &mut dyn Write, data: &TableData, ctx: &RenderContext) -> io::Result<()> {
+    let mut line = String::new();
+    ctx.line_start(&mut line);
 
     for (i, h) in data.headers.iter().enumerate() {
         if i > 0 {
-            if ctx.draw_borders {
-                line.push(ctx.chars.v);
-            } else if ctx.draw_cs {
-                line.push_str(ctx.col_sep);
-            } else {
-                line.push_str(&ctx.padding);
-            }
+            ctx.column_gap(&mut line);
         }
 
         // Check for right alignment marker
-        let align_right = h.starts_with('-');
-        let content = if align_right { &h[1..] } else { h };
-        let content_w = visible_width(content);
+        let marked = h.starts_with('-');
+        let mut content = close_ansi(if marked { &h[1..] } else { h });
+        let align_right = match ctx.opts.align.get(i) {
+            Some(Some(align)) => *align == Align::Right,
+            _ if marked => true,
+            _ => {
+                ctx.opts.align_headers == HeaderAlign::WithData
+                    && !ctx.opts.nn
+                    && numeric_column(&data.rows, i, ctx.opts)
+            }
+        };
+        let content_w = visible_width(&content);
+        if let Some(style) = ctx.theme.header.filter(|_| !content.is_empty()) {
+            content = Cow::Owned(format!("{}{}{}", style, content, RESET_COLOR));
+        }
 
         let w = ctx.widths[i];
-        if ctx.args.nf {
-            line.push_str(content);
+        if ctx.opts.nf {
+            line.push_str(&content);
         } else {
             // Apply padding for alignment
             line.push_str(&ctx.padding);
@@ -637,26 +3159,40 @@ fn print_header(data: &TableData, ctx: &RenderContext) {
             let pad = " ".repeat(pad_len);
             if align_right {
                 line.push_str(&pad);
-                line.push_str(content);
+                line.push_str(&content);
             } else {
-                line.push_str(content);
+                line.push_str(&content);
                 line.push_str(&pad);
             }
             line.push_str(&ctx.padding);
         }
     }
-    if ctx.draw_borders {
-        line.push(ctx.chars.v);
-    }
-    println!("{}", line);
+    ctx.line_end(&mut line);
+    writeln!(out, "{}", line)?;
 
     if ctx.draw_ts {
         if ctx.draw_borders {
-            print_separator(ctx, ctx.chars.lm, ctx.chars.rm, ctx.chars.c, ctx.chars.h);
+            print_separator(
+                out,
+                ctx,
+                ctx.chars.lm,
+                ctx.chars.rm,
+                ctx.chars.c,
+                ctx.chars.h,
+            )?;
         } else {
-            print_separator(ctx, ctx.chars.h, ctx.chars.h, ctx.chars.h, ctx.chars.h);
+            print_separator(out, ctx, ctx.chars.h, ctx.chars.h, ctx.chars.h, ctx.chars.h)?;
         }
     }
+    Ok(())
+}
+
+/// Rows of a table printed so far, when its rows are printed in parts.
+#[derive(Default)]
+struct Printed {
+    rows: usize,
+    /// Rows without group separator rows, for the stripes
+    data_rows: usize,
 }
 
 /// Prints the data rows.
@@ -666,63 +3202,651 @@ fn print_header(data: &TableData, ctx: &RenderContext) {
 ///
 /// # Arguments
 ///
-/// * `data` - Table data
+/// * `out` - Destination of the output
+/// * `rows` - Data rows, the whole table or a part of it
+/// * `cells` - Visible width of every cell of `rows`, row after row
 /// * `ctx` - Render context
-fn print_data_rows(data: &TableData, ctx: &RenderContext) {
-    for (row_idx, row) in data.rows.iter().enumerate() {
-        if ctx.draw_fs && row_idx > 0 && row_idx == data.rows.len() - 1 {
+/// * `printed` - Rows printed before, counts the printed rows
+/// * `last` - The rows end the table
+fn print_data_rows(
+    out: &mut dyn Write,
+    rows: &[Vec<Cell>],
+    cells: &[usize],
+    ctx: &RenderContext,
+    printed: &mut Printed,
+    last: bool,
+) -> io::Result<()> {
+    let mut cells = cells;
+    for (i, row) in rows.iter().enumerate() {
+        let (row_cells, next) = cells.split_at(row.len());
+        cells = next;
+        let row_idx = printed.rows + i;
+        if ctx.draw_fs && last && row_idx > 0 && i == rows.len() - 1 {
             if ctx.draw_borders {
-                print_separator(ctx, ctx.chars.lm, ctx.chars.rm, ctx.chars.c, ctx.chars.h);
+                print_separator(
+                    out,
+                    ctx,
+                    ctx.chars.lm,
+                    ctx.chars.rm,
+                    ctx.chars.c,
+                    ctx.chars.h,
+                )?;
             } else {
-                print_separator(ctx, ctx.chars.h, ctx.chars.h, ctx.chars.h, ctx.chars.h);
+                print_separator(out, ctx, ctx.chars.h, ctx.chars.h, ctx.chars.h, ctx.chars.h)?;
             }
         }
 
-        let mut line = String::new();
-        if ctx.draw_borders {
-            line.push(ctx.chars.v);
+        if let Some(Some(label)) = ctx.banners.get(row_idx) {
+            print_banner(out, ctx, label)?;
         }
 
-        for (i, val) in row.iter().enumerate() {
+        let mut line = String::new();
+        ctx.line_start(&mut line);
+
+        for ((i, val), &val_w) in row.iter().enumerate().zip(row_cells) {
+            let val = close_ansi(val);
             if i > 0 {
-                if ctx.draw_borders {
-                    line.push(ctx.chars.v);
-                } else if ctx.draw_cs {
-                    line.push_str(ctx.col_sep);
-                } else {
-                    line.push_str(&ctx.padding);
-                }
+                ctx.column_gap(&mut line);
             }
 
-            let w = if i < ctx.widths.len() {
-                ctx.widths[i]
-            } else {
-                visible_width(val)
-            };
+            let w = ctx.widths.get(i).copied().unwrap_or(val_w);
 
-            if ctx.args.nf {
-                line.push_str(val);
+            if ctx.opts.nf {
+                line.push_str(&val);
             } else {
                 line.push_str(&ctx.padding);
-                // Check if value is numeric for default right-alignment
-                let is_num = !ctx.args.nn && val.parse::<f64>().is_ok();
-                let val_w = visible_width(val);
+                // Without an explicit alignment numeric values are aligned right
+                let align_right = match ctx.opts.align.get(i) {
+                    Some(Some(align)) => *align == Align::Right,
+                    _ => !ctx.opts.nn && is_number(&val, ctx.opts.numfmt),
+                };
                 let pad_len = w.saturating_sub(val_w);
                 let pad = " ".repeat(pad_len);
 
-                if is_num {
+                if align_right {
                     line.push_str(&pad);
-                    line.push_str(val);
+                    line.push_str(&val);
                 } else {
-                    line.push_str(val);
+                    line.push_str(&val);
                     line.push_str(&pad);
                 }
                 line.push_str(&ctx.padding);
             }
         }
-        if ctx.draw_borders {
-            line.push(ctx.chars.v);
+        ctx.line_end(&mut line);
+        // Every second data row is striped, group separator rows don't count
+        if !row.iter().all(|c| c.is_empty()) {
+            printed.data_rows += 1;
+            if printed.data_rows.is_multiple_of(2) {
+                line = ctx.theme.stripe(&line);
+            }
+        }
+        writeln!(out, "{}", line)?;
+    }
+    printed.rows += rows.len();
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(clippy::field_reassign_with_default)]
+mod tests {
+    use super::*;
+
+    fn sample() -> TableData {
+        TableData {
+            headers: vec!["Name".into(), "Age".into()],
+            rows: vec![
+                vec!["Alice".into(), "30".into()],
+                vec!["Bob".into(), "5".into()],
+            ],
+            original_column_indices: vec![0, 1],
+        }
+    }
+
+    #[test]
+    fn test_format_to_string_ascii() {
+        let out = format_to_string(&sample(), &FormatOptions::default()).unwrap();
+        assert_eq!(out, " Name    Age \n Alice    30 \n Bob       5 \n");
+    }
+
+    #[test]
+    fn test_align_headers_with_data() {
+        let mut opts = FormatOptions::default();
+        opts.align_headers = HeaderAlign::WithData;
+        let out = format_to_string(&sample(), &opts).unwrap();
+        assert_eq!(out, " Name    Age \n Alice    30 \n Bob       5 \n");
+
+        let mut data = sample();
+        data.headers[1] = "Years".into();
+        let out = format_to_string(&data, &opts).unwrap();
+        assert_eq!(out, " Name    Years \n Alice      30 \n Bob         5 \n");
+
+        opts.nn = true;
+        let out = format_to_string(&data, &opts).unwrap();
+        assert_eq!(out, " Name    Years \n Alice   30    \n Bob     5     \n");
+    }
+
+    #[test]
+    fn test_pad_inside_outside() {
+        let mut opts = FormatOptions::default();
+        opts.pad_inside = Some(2);
+        opts.w = 3;
+        let plain = format_to_string(&sample(), &opts).unwrap();
+        assert_eq!(
+            plain,
+            "   Name      Age   \n   Alice      30   \n   Bob         5   \n"
+        );
+        opts.cs = true;
+        let cs = format_to_string(&sample(), &opts).unwrap();
+        opts.pp = true;
+        let pp = format_to_string(&sample(), &opts).unwrap();
+
+        // Toggling -cs and -pp doesn't move the columns
+        let column_of = |out: &str, text: &str| {
+            let line = out.lines().find(|l| l.contains(text)).unwrap();
+            line[..line.find(text).unwrap()].chars().count()
+        };
+        for text in ["Name", "Alice", "30", "5"] {
+            assert_eq!(column_of(&plain, text), column_of(&cs, text), "{}", text);
+            assert_eq!(column_of(&plain, text), column_of(&pp, text), "{}", text);
+        }
+
+        opts.pp = false;
+        opts.cs = false;
+        opts.pad_outside = Some(0);
+        let out = format_to_string(&sample(), &opts).unwrap();
+        assert_eq!(out.lines().next().unwrap(), "  Name      Age  ");
+    }
+
+    #[test]
+    fn test_format_to_string_pretty() {
+        let mut opts = FormatOptions::default();
+        opts.pp = true;
+        let out = format_to_string(&sample(), &opts).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], "┌───────┬─────┐");
+        assert_eq!(lines[1], "│ Name  │ Age │");
+        assert_eq!(lines[4], "└───────┴─────┘");
+    }
+
+    struct UpperFormatter;
+
+    impl TableFormatter for UpperFormatter {
+        fn format(
+            &self,
+            data: &TableData,
+            _opts: &FormatOptions,
+            w: &mut dyn Write,
+        ) -> io::Result<()> {
+            for row in &data.rows {
+                writeln!(w, "{}", row.join(",").to_uppercase())?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_custom_formatter_registry() {
+        let mut registry = FormatterRegistry::builtin();
+        registry.register("upper", Box::new(UpperFormatter));
+        assert!(registry.names().contains(&"ascii"));
+        assert!(registry.names().contains(&"upper"));
+
+        let mut opts = FormatOptions::default();
+        opts.format = "upper".to_string();
+        let mut buf = Vec::new();
+        format_with_registry(&sample(), &opts, &registry, &mut buf).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), "ALICE,30\nBOB,5\n");
+    }
+
+    #[test]
+    fn test_unknown_format() {
+        let mut opts = FormatOptions::default();
+        opts.format = "nope".to_string();
+        let err = format_to_string(&sample(), &opts).unwrap_err();
+        assert!(err.to_string().contains("Unknown output format 'nope'"));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(FormatOptions::default().validate().is_ok());
+        let invalid: [fn(&mut FormatOptions); 5] = [
+            |opts| opts.format = "nope".to_string(),
+            |opts| opts.highlight = vec!["foo".to_string()],
+            |opts| opts.map_values = vec!["1".to_string()],
+            |opts| opts.note = vec!["x".to_string()],
+            |opts| opts.group_header = Some("a=1:2,b=2".to_string()),
+        ];
+        for set in invalid {
+            let mut opts = FormatOptions::default();
+            set(&mut opts);
+            let err = opts.validate().unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "html")]
+    fn test_linkify() {
+        let mut opts = FormatOptions::default();
+        opts.link = vec![
+            r"JIRA-(\d+)=https://jira/browse/{0}?id={1}".to_string(),
+            r"\b(\d{2}):(\d{2})\b=https://clock/{1}/{2}".to_string(),
+        ];
+        let rules = link_rules(&opts).unwrap();
+
+        assert_eq!(
+            linkify("see JIRA-42.", &rules),
+            "see <a href=\"https://jira/browse/JIRA-42?id=42\">JIRA-42</a>."
+        );
+        assert_eq!(
+            linkify("https://example.com/a?b=1&c=2, ok", &rules),
+            "<a href=\"https://example.com/a?b=1&amp;c=2\">https://example.com/a?b=1&amp;c=2</a>, ok"
+        );
+        // The URL rule wins over the overlapping JIRA match inside it
+        assert_eq!(
+            linkify("http://x/JIRA-1", &rules),
+            "<a href=\"http://x/JIRA-1\">http://x/JIRA-1</a>"
+        );
+        // A ':' in the regex
+        assert_eq!(
+            linkify("at 12:30", &rules),
+            "at <a href=\"https://clock/12/30\">12:30</a>"
+        );
+        assert_eq!(linkify("plain", &rules), "plain");
+        assert_eq!(linkify("a<b & c", &rules), "a&lt;b &amp; c");
+    }
+
+    #[test]
+    #[cfg(feature = "html")]
+    fn test_html_options() {
+        let data = TableData {
+            headers: vec!["<Name>".into(), "Size".into()],
+            rows: vec![vec!["a & b".into(), "42".into()]],
+            original_column_indices: vec![0, 1],
+        };
+        let mut opts = FormatOptions::default();
+        opts.format = "html".to_string();
+        opts.html_full = true;
+        opts.caption = Some("Files".to_string());
+        opts.html_id = Some("files".to_string());
+        opts.html_class = Some("wide".to_string());
+        let html = format_to_string(&data, &opts).unwrap();
+
+        assert!(html.starts_with("<!DOCTYPE html>\n<html>\n"));
+        assert!(html.contains("<title>Files</title>"));
+        assert!(html.contains("td.num { text-align: right; }"));
+        assert!(html.contains("<table id=\"files\" class=\"wide\">\n  <caption>Files</caption>"));
+        assert!(html.contains("<th>&lt;Name&gt;</th>"));
+        assert!(html.contains("<td>a &amp; b</td>"));
+        assert!(html.contains("<td class=\"num\">42</td>"));
+        assert!(html.ends_with("</body>\n</html>\n"));
+    }
+
+    #[test]
+    #[cfg(feature = "html")]
+    fn test_linkify_osc8() {
+        let rules = link_rules(&FormatOptions::default()).unwrap();
+        let cell = "\x1b]8;;file://host/tmp/a.txt\x1b\\a.txt\x1b]8;;\x1b\\ see https://x.io";
+        assert_eq!(
+            linkify(cell, &rules),
+            "<a href=\"file://host/tmp/a.txt\">a.txt</a> see <a href=\"https://x.io\">https://x.io</a>"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "html")]
+    fn test_link_rules_invalid() {
+        let mut opts = FormatOptions::default();
+        opts.link = vec!["no-url".to_string()];
+        assert!(link_rules(&opts).is_err());
+        opts.link = vec!["(=https://x".to_string()];
+        assert!(link_rules(&opts).is_err());
+        opts.link = vec![r"OPS-\d+:https://x".to_string()];
+        assert!(link_rules(&opts).is_err());
+    }
+
+    #[test]
+    fn test_screen_reader() {
+        let data = TableData {
+            headers: vec!["Host".into(), "-Load".into(), "".into()],
+            rows: vec![
+                vec!["db".into(), "0.5".into(), "x".into()],
+                vec!["".into(), "".into(), "".into()],
+                vec!["web".into()],
+            ],
+            original_column_indices: vec![0, 1, 2],
+        };
+        let mut opts = FormatOptions::default();
+        opts.format = "screen-reader".to_string();
+        opts.note = vec!["2:per core".to_string()];
+        assert_eq!(
+            format_to_string(&data, &opts).unwrap(),
+            "Host: db\nLoad: 0.5\ncolumn 3: x\n\n\
+             Host: web\nLoad: empty\ncolumn 3: empty\n\n\
+             note on Load: per core\n"
+        );
+
+        opts.lang = Lang::De;
+        assert_eq!(
+            format_to_string(&data, &opts).unwrap(),
+            "Host: db\nLoad: 0.5\nSpalte 3: x\n\n\
+             Host: web\nLoad: leer\nSpalte 3: leer\n\n\
+             Anmerkung zu Load: per core\n"
+        );
+    }
+
+    #[test]
+    fn test_adoc_and_rst() {
+        let data = TableData {
+            headers: vec!["Name".into(), "-Pipe".into(), "Age".into()],
+            rows: vec![
+                vec!["Alice".into(), "a|b".into(), "30".into()],
+                vec!["".into(), "".into(), "".into()],
+                vec!["Bob".into(), "".into(), "5".into()],
+            ],
+            original_column_indices: vec![0, 1, 2],
+        };
+        let mut opts = FormatOptions::default();
+        opts.format = "adoc".to_string();
+        assert_eq!(
+            format_to_string(&data, &opts).unwrap(),
+            "[cols=\"<,>,>\",options=\"header\"]\n\
+             |===\n\
+             | Name  | Pipe | Age\n\
+             \n\
+             | Alice | a\\|b |  30\n\
+             | Bob   |      |   5\n\
+             |===\n"
+        );
+
+        opts.format = "rst".to_string();
+        assert_eq!(
+            format_to_string(&data, &opts).unwrap(),
+            "+-------+------+-----+\n\
+             | Name  | Pipe | Age |\n\
+             +=======+======+=====+\n\
+             | Alice |  a|b |  30 |\n\
+             +-------+------+-----+\n\
+             | Bob   |      |   5 |\n\
+             +-------+------+-----+\n"
+        );
+    }
+
+    #[test]
+    fn test_jira() {
+        let data = TableData {
+            headers: vec!["Name".into(), "-Note".into()],
+            rows: vec![
+                vec!["Alice".into(), "a|b".into()],
+                vec!["".into(), "".into()],
+                vec!["Bob".into()],
+            ],
+            original_column_indices: vec![0, 1],
+        };
+        let mut opts = FormatOptions::default();
+        opts.format = "jira".to_string();
+        assert_eq!(
+            format_to_string(&data, &opts).unwrap(),
+            "||Name||Note||\n|Alice|a\\|b|\n|Bob| |\n"
+        );
+    }
+
+    #[test]
+    fn test_json_infer_types() {
+        let data = TableData {
+            headers: vec![
+                "id".into(),
+                "n".into(),
+                "x".into(),
+                "ok".into(),
+                "note".into(),
+            ],
+            rows: vec![vec![
+                "007".into(),
+                "-3".into(),
+                "2.5".into(),
+                "true".into(),
+                "".into(),
+            ]],
+            original_column_indices: vec![0, 1, 2, 3, 4],
+        };
+        let mut opts = FormatOptions::default();
+        opts.format = "json".to_string();
+        opts.infer_types = true;
+        let value: serde_json::Value =
+            serde_json::from_str(&format_to_string(&data, &opts).unwrap()).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!([{"id": "007", "n": -3, "x": 2.5, "ok": true, "note": null}])
+        );
+
+        opts.infer_types = false;
+        let value: serde_json::Value =
+            serde_json::from_str(&format_to_string(&data, &opts).unwrap()).unwrap();
+        assert_eq!(value[0]["n"], "-3");
+    }
+
+    #[test]
+    fn test_format_to_writer_json() {
+        let mut opts = FormatOptions::default();
+        opts.format = "json".to_string();
+        let mut buf = Vec::new();
+        format_to_writer(&sample(), &opts, &mut buf).unwrap();
+
+        let value: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(value[1]["Name"], "Bob");
+        assert_eq!(value[1]["Age"], "5");
+    }
+
+    #[test]
+    #[cfg(feature = "color")]
+    fn test_ansi_colors() {
+        let data = TableData {
+            headers: vec!["Name".into(), "Size".into()],
+            rows: vec![
+                vec!["\x1b[01;34mbin".into(), "\x1b[32m7\x1b[0m".into()],
+                vec!["lib".into(), "12".into()],
+            ],
+            original_column_indices: vec![0, 1],
+        };
+
+        let mut opts = FormatOptions::default();
+        let out = format_to_string(&data, &opts).unwrap();
+        // The open color is reset before the padding, aligned by visible width
+        assert_eq!(
+            out.lines().nth(1).unwrap(),
+            " \x1b[01;34mbin\x1b[0m       \x1b[32m7\x1b[0m "
+        );
+        assert_eq!(out.lines().nth(2).unwrap(), " lib      12 ");
+
+        opts.strip_ansi = true;
+        let out = format_to_string(&data, &opts).unwrap();
+        assert_eq!(out.lines().nth(1).unwrap(), " bin       7 ");
+
+        opts.strip_ansi = false;
+        opts.format = "json".to_string();
+        let out = format_to_string(&data, &opts).unwrap();
+        assert!(!out.contains("\\u001b"));
+        opts.keep_ansi = true;
+        let out = format_to_string(&data, &opts).unwrap();
+        assert!(out.contains("\\u001b[32m7\\u001b[0m"));
+    }
+
+    #[test]
+    fn test_highlight_rows() {
+        let rules: Vec<Highlight> = ["ERROR:red", "2>90:yellow:row", "^ok$:bold"]
+            .iter()
+            .map(|spec| Highlight::parse(spec).unwrap())
+            .collect();
+        let mut rows: Vec<Vec<Cell>> = vec![
+            vec!["ERROR disk".into(), "95".into()],
+            vec!["".into(), "".into()],
+            vec!["ok".into(), "91".into()],
+            vec!["fine".into(), "10".into()],
+        ];
+        highlight_rows(&mut rows, &rules);
+
+        assert_eq!(rows[0][0], "\x1b[31mERROR disk\x1b[0m");
+        assert_eq!(rows[0][1], "\x1b[33m95\x1b[0m");
+        assert_eq!(rows[1], vec!["", ""]);
+        assert_eq!(rows[2][0], "\x1b[33mok\x1b[0m");
+        assert_eq!(rows[3], vec!["fine", "10"]);
+    }
+
+    #[test]
+    fn test_group_header() {
+        let data = TableData {
+            headers: ["name", "id", "city", "country"].map(Cell::from).to_vec(),
+            rows: vec![["alice", "1", "Berlin", "DE"].map(Cell::from).to_vec()],
+            original_column_indices: vec![0, 1, 2, 3],
+        };
+        let mut opts = FormatOptions {
+            group_header: Some("Location=3:4,Identification=1:2".to_string()),
+            pp: true,
+            ..FormatOptions::default()
+        };
+        let groups = column_groups(&opts, 4).unwrap();
+        assert_eq!(groups[0].title, "Identification");
+        assert_eq!((groups[1].first, groups[1].last), (2, 3));
+
+        let out = format_to_string(&data, &opts).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "┌────────────────┬──────────────────┐");
+        assert_eq!(lines[1], "│ Identification │     Location     │");
+        assert_eq!(lines[2], "├───────┬────────┼────────┬─────────┤");
+        assert_eq!(lines[3], "│ name  │ id     │ city   │ country │");
+
+        for invalid in ["A=1:5", "A=2:1", "A=0", "A", "A=1:2,B=2:3"] {
+            opts.group_header = Some(invalid.to_string());
+            assert!(column_groups(&opts, 4).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_title() {
+        let data = TableData {
+            headers: ["name", "id"].map(Cell::from).to_vec(),
+            rows: vec![["alice", "1"].map(Cell::from).to_vec()],
+            original_column_indices: vec![0, 1],
+        };
+        let mut opts = FormatOptions {
+            title: Some("Users of cluster A".to_string()),
+            pp: true,
+            ..FormatOptions::default()
+        };
+        let out = format_to_string(&data, &opts).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "┌────────────────────┐");
+        assert_eq!(lines[1], "│ Users of cluster A │");
+        assert_eq!(lines[2], "├───────┬────────────┤");
+        assert_eq!(lines[3], "│ name  │ id         │");
+
+        opts.pp = false;
+        opts.title = None;
+        opts.caption = Some("Users".to_string());
+        let out = format_to_string(&data, &opts).unwrap();
+        assert_eq!(out.lines().next().unwrap(), "   Users    ");
+    }
+
+    #[test]
+    fn test_highlight_parse_errors() {
+        assert!(Highlight::parse("ERROR").is_err());
+        assert!(Highlight::parse("ERROR:pink").is_err());
+        assert!(Highlight::parse("(:red").is_err());
+        // Not a condition: matched as a regex
+        assert!(matches!(
+            Highlight::parse("a=b:red").unwrap().rule,
+            HighlightRule::Pattern(_)
+        ));
+    }
+
+    #[test]
+    fn test_format_ascii_parts() {
+        let mut data = sample();
+        data.rows[1][1] = Cell::default();
+        data.rows.push(vec!["Carol".into(), "41".into()]);
+        let mut opts = FormatOptions::default();
+        opts.pp = true;
+        opts.fs = true;
+        opts.na = Some("-".to_string());
+        let whole = format_to_string(&data, &opts).unwrap();
+
+        // The rows in three parts, measured before
+        opts.min_widths = column_widths(&data, &opts);
+        let mut first = data.clone();
+        let mut rest = first.rows.split_off(1);
+        let last = rest.split_off(1);
+        let mut out = Vec::new();
+        format_ascii_parts(&first, [Ok(rest), Ok(last)].into_iter(), &opts, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), whole);
+    }
+
+    #[test]
+    fn test_measure_rows() {
+        let rows: Vec<Vec<Cell>> = [&["ab", "日本"][..], &["x", "", "long"], &[]]
+            .iter()
+            .map(|row| row.iter().map(|&c| Cell::from(c)).collect())
+            .collect();
+        for parallel in [false, true] {
+            let measured = measure_rows(&rows, parallel);
+            assert_eq!(measured.widths, [2, 4, 4]);
+            assert_eq!(measured.cells, [2, 4, 1, 0, 4]);
         }
-        println!("{}", line);
+        assert_eq!(visible_width("a b~"), 4);
+        assert_eq!(visible_width("größe"), 5);
+        #[cfg(feature = "color")]
+        assert_eq!(visible_width("\x1b[1mx\x1b[0m"), 1);
+    }
+
+    #[test]
+    fn test_min_widths() {
+        let data = TableData {
+            headers: vec!["A".into(), "B".into()],
+            rows: vec![vec!["x".into(), "yy".into()]],
+            original_column_indices: vec![0, 1],
+        };
+        let mut opts = FormatOptions::default();
+        assert_eq!(column_widths(&data, &opts), vec![1, 2]);
+        opts.min_widths = vec![3];
+        assert_eq!(column_widths(&data, &opts), vec![3, 2]);
+        assert_eq!(
+            format_to_string(&data, &opts).unwrap(),
+            " A     B  \n x     yy \n"
+        );
+    }
+
+    #[test]
+    fn test_group_banners() {
+        let data = TableData {
+            headers: vec!["Dept".into(), "Name".into()],
+            rows: vec![
+                vec!["IT".into(), "Carol".into()],
+                Vec::new(),
+                vec!["Sales".into(), "Alice".into()],
+                vec![String::new().into(), "Bob".into()],
+            ],
+            original_column_indices: vec![0, 1],
+        };
+        let mut opts = FormatOptions::default();
+        opts.group_column = Some(0);
+        opts.group_banner = true;
+        assert_eq!(
+            format_to_string(&data, &opts).unwrap(),
+            " Name  \n── IT ─\n Carol \n── Sales ─\n Alice \n Bob   \n"
+        );
+        opts.pp = true;
+        assert_eq!(
+            format_to_string(&data, &opts).unwrap(),
+            "┌───────┐\n│ Name  │\n├─ IT ──┤\n│ Carol │\n├─ Sales ┤\n│ Alice │\n│ Bob   │\n└───────┘\n"
+        );
+    }
+
+    #[test]
+    fn test_format_utc_timestamp() {
+        assert_eq!(format_utc_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_utc_timestamp(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_utc_timestamp(1_714_565_045), "2024-05-01T12:04:05Z");
     }
 }
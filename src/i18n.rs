@@ -0,0 +1,237 @@
+use std::fmt::Display;
+
+/// Language of the texts rcol generates itself: labels, warnings, error prefixes
+/// and the errors about the table.
+///
+/// Table content is never translated, only what rcol adds around it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Lang {
+    /// English
+    #[default]
+    En,
+    /// German
+    De,
+}
+
+impl Lang {
+    /// Returns the catalog text of `key` with `{0}`, `{1}`, ... replaced by `args`.
+    ///
+    /// # Panics
+    ///
+    /// If `key` is not in the catalog, which is a bug in rcol.
+    pub fn tr(self, key: &str, args: &[&dyn Display]) -> String {
+        let (_, en, de) = CATALOG
+            .iter()
+            .find(|(k, _, _)| *k == key)
+            .unwrap_or_else(|| panic!("missing translation '{}'", key));
+        let mut text = match self {
+            Lang::En => en,
+            Lang::De => de,
+        }
+        .to_string();
+        for (i, arg) in args.iter().enumerate() {
+            text = text.replace(&format!("{{{}}}", i), &arg.to_string());
+        }
+        text
+    }
+}
+
+/// Texts generated by rcol: key, English, German.
+const CATALOG: &[(&str, &str, &str)] = &[
+    // Labels in the output
+    ("group", "Group {0}", "Gruppe {0}"),
+    ("one-row", "{0} row", "{0} Zeile"),
+    ("rows", "{0} rows", "{0} Zeilen"),
+    ("column", "column {0}", "Spalte {0}"),
+    ("empty", "empty", "leer"),
+    ("note-on", "note on {0}", "Anmerkung zu {0}"),
+    ("watch-title", "Every {0}: {1}", "Alle {0}: {1}"),
+    ("freq-value", "value", "Wert"),
+    ("freq-count", "count", "Anzahl"),
+    ("freq-percent", "percent", "Prozent"),
+    ("stats-column", "column", "Spalte"),
+    ("stats-count", "count", "Anzahl"),
+    ("stats-min", "min", "Min"),
+    ("stats-max", "max", "Max"),
+    ("stats-mean", "mean", "Mittelwert"),
+    ("stats-median", "median", "Median"),
+    ("stats-stddev", "stddev", "Standardabweichung"),
+    // Warnings
+    ("warning", "Warning: {0}", "Warnung: {0}"),
+    (
+        "skipped-rows",
+        "--skip-bad-rows dropped {0} of {1} rows",
+        "--skip-bad-rows hat {0} von {1} Zeilen verworfen",
+    ),
+    ("ignoring-it", "{0}, ignoring it", "{0}, wird ignoriert"),
+    (
+        "dropped-line",
+        "line {0} has {1} columns, expected {2}, dropping it",
+        "Zeile {0} hat {1} Spalten statt {2}, wird verworfen",
+    ),
+    (
+        "dropped-row",
+        "row {0} has {1} columns, expected {2}, dropping it",
+        "Datenzeile {0} hat {1} Spalten statt {2}, wird verworfen",
+    ),
+    (
+        "using-column",
+        "{0}, using column {1}",
+        "{0}, verwende Spalte {1}",
+    ),
+    (
+        "retrying",
+        "{0}, retrying in {1} ms ({2}/{3})",
+        "{0}, neuer Versuch in {1} ms ({2}/{3})",
+    ),
+    (
+        "unknown-config-key",
+        "unknown config key '{0}', ignoring it",
+        "unbekannter Konfigurationsschlüssel '{0}', wird ignoriert",
+    ),
+    (
+        "watch-stale",
+        "refresh failed, showing the table from {1} s ago: {0}",
+        "Aktualisierung fehlgeschlagen, zeige die Tabelle von vor {1} s: {0}",
+    ),
+    (
+        "cache-failed",
+        "cannot cache the table: {0}",
+        "Tabelle kann nicht zwischengespeichert werden: {0}",
+    ),
+    // Errors about the table
+    (
+        "exceeds-output-columns",
+        "--{0} {1} exceeds the number of output columns ({2})",
+        "--{0} {1} übersteigt die Zahl der Ausgabespalten ({2})",
+    ),
+    (
+        "exceeds-input-columns",
+        "Column {0} exceeds the number of input columns ({1})",
+        "Spalte {0} übersteigt die Zahl der Eingabespalten ({1})",
+    ),
+    (
+        "option-exceeds-input-columns",
+        "--{0} {1} exceeds the number of input columns ({2})",
+        "--{0} {1} übersteigt die Zahl der Eingabespalten ({2})",
+    ),
+    (
+        "column-not-1-based",
+        "--{0} {1}: column numbers must be 1-based",
+        "--{0} {1}: Spaltennummern beginnen bei 1",
+    ),
+    (
+        "no-column-named",
+        "{0}: no column named '{1}'",
+        "{0}: keine Spalte namens '{1}'",
+    ),
+    (
+        "on-exceeds-input",
+        "--on {0} exceeds the number of columns of the input ({1})",
+        "--on {0} übersteigt die Zahl der Spalten der Eingabe ({1})",
+    ),
+    (
+        "on-exceeds-joined",
+        "--on {0} exceeds the number of columns of the joined table ({1})",
+        "--on {0} übersteigt die Zahl der Spalten der verbundenen Tabelle ({1})",
+    ),
+    (
+        "line-columns",
+        "Line {0} has {1} columns, expected {2}",
+        "Zeile {0} hat {1} Spalten statt {2}",
+    ),
+    (
+        "row-columns",
+        "Row {0} has {1} columns, expected {2}",
+        "Datenzeile {0} hat {1} Spalten statt {2}",
+    ),
+    // Error prefixes
+    (
+        "error-options",
+        "Invalid options: {0}",
+        "Ungültige Optionen: {0}",
+    ),
+    (
+        "error-reading-input",
+        "Error reading input: {0}",
+        "Fehler beim Lesen der Eingabe: {0}",
+    ),
+    (
+        "error-processing-input",
+        "Error processing input: {0}",
+        "Fehler beim Verarbeiten der Eingabe: {0}",
+    ),
+    (
+        "error-formatting-output",
+        "Error formatting output: {0}",
+        "Fehler beim Formatieren der Ausgabe: {0}",
+    ),
+    (
+        "error-since",
+        "Error reading --since snapshot: {0}",
+        "Fehler beim Lesen des --since-Schnappschusses: {0}",
+    ),
+    (
+        "error-sqlite",
+        "Error writing --sqlite database: {0}",
+        "Fehler beim Schreiben der --sqlite-Datenbank: {0}",
+    ),
+    (
+        "error-writing-file",
+        "Error writing {0}: {1}",
+        "Fehler beim Schreiben von {0}: {1}",
+    ),
+    (
+        "error-watch",
+        "--watch needs --file or --cmd, standard input can't be read again",
+        "--watch braucht --file oder --cmd, die Standardeingabe kann nicht erneut gelesen werden",
+    ),
+    (
+        "error-completions",
+        "Error writing completions: {0}",
+        "Fehler beim Schreiben der Vervollständigungen: {0}",
+    ),
+    (
+        "error-manpage",
+        "Error writing man page: {0}",
+        "Fehler beim Schreiben der Manpage: {0}",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tr() {
+        assert_eq!(Lang::En.tr("group", &[&3]), "Group 3");
+        assert_eq!(Lang::De.tr("rows", &[&12]), "12 Zeilen");
+        assert_eq!(
+            Lang::De.tr("using-column", &[&"--sortcol 9", &2]),
+            "--sortcol 9, verwende Spalte 2"
+        );
+    }
+
+    #[test]
+    fn test_catalog_complete() {
+        for (key, en, de) in CATALOG {
+            assert!(!en.is_empty() && !de.is_empty(), "{}", key);
+            for i in 0..4 {
+                let placeholder = format!("{{{}}}", i);
+                assert_eq!(
+                    en.contains(&placeholder),
+                    de.contains(&placeholder),
+                    "{}",
+                    key
+                );
+            }
+            assert_eq!(
+                CATALOG.iter().filter(|(k, _, _)| k == key).count(),
+                1,
+                "{}",
+                key
+            );
+        }
+    }
+}
@@ -1,16 +1,20 @@
-use crate::args::AppArgs;
+use crate::processor::Warning;
 use std::fs::File;
 use std::io::{self, BufRead, BufReader, IsTerminal};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::thread;
+use std::time::Duration;
 
 /// Reads input lines from a file and/or stdin based on application arguments.
 ///
-/// If a file is specified via `args.file`, reads all lines from that file.
+/// If a file is specified, reads all lines from that file.
 /// Additionally reads from stdin if it's not a terminal (piped input) or if no file
 /// was specified. This allows combining file and piped input when both are provided.
 ///
 /// # Arguments
 ///
-/// * `args` - Application arguments containing the optional file path
+/// * `file` - Optional path of the input file (`-file`)
+/// * `terminator` - Byte ending a line, `\n` or `\0` (`-null`)
 ///
 /// # Returns
 ///
@@ -22,31 +26,372 @@ use std::io::{self, BufRead, BufReader, IsTerminal};
 /// - File only: `rcol -file=data.txt`
 /// - Stdin only: `cat data.txt | rcol`
 /// - Both: `cat extra.txt | rcol -file=data.txt` (combines both sources)
-pub fn read_input(args: &AppArgs) -> io::Result<Vec<String>> {
-    let mut lines = Vec::new();
+pub fn read_input(file: Option<&str>, terminator: u8) -> io::Result<Vec<String>> {
+    input_lines(file, terminator)?.collect()
+}
 
-    // Read from file if specified
-    if let Some(filename) = &args.file {
-        let file = File::open(filename)?;
-        let reader = BufReader::new(file);
-        for line in reader.lines() {
-           lines.push(line?.trim().to_string());
+/// Reads all lines of a file, never stdin (e.g. the table of `-join`).
+///
+/// # Returns
+///
+/// - `Ok(Vec<String>)` containing all lines of the file
+/// - `Err(io::Error)` if the file can't be opened or read
+pub fn read_file(path: &str, terminator: u8) -> io::Result<Vec<String>> {
+    Records::new(BufReader::new(File::open(path)?), terminator).collect()
+}
+
+/// The input of `-file` (and piped stdin) held in memory, to be split into
+/// lines without a `String` per line.
+///
+/// With the `mmap` feature the file is memory-mapped, so the operating system
+/// pages it in on demand; without it, it is read into one buffer. Lines of stdin
+/// are read like in [`read_input`].
+pub struct FileInput {
+    data: FileData,
+    stdin: Vec<String>,
+    terminator: u8,
+}
+
+/// The content of the file of a [`FileInput`].
+enum FileData {
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+    Read(Vec<u8>),
+}
+
+impl FileInput {
+    /// Opens the file and reads stdin if it's not a terminal.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Path of the input file (`-file`)
+    /// * `terminator` - Byte ending a line, `\n` or `\0` (`-null`)
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(FileInput)` with the file mapped or read
+    /// - `Err(io::Error)` if the file can't be opened or stdin can't be read
+    pub fn open(path: &str, terminator: u8) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let data = Self::map(file)?;
+        let stdin = io::stdin();
+        let stdin = if stdin.is_terminal() {
+            Vec::new()
+        } else {
+            Records::new(stdin.lock(), terminator).collect::<io::Result<_>>()?
+        };
+        Ok(Self {
+            data,
+            stdin,
+            terminator,
+        })
+    }
+
+    #[cfg(feature = "mmap")]
+    fn map(file: File) -> io::Result<FileData> {
+        // Mapping an empty file fails on some platforms, and pipes can't be mapped
+        if !file.metadata()?.is_file() || file.metadata()?.len() == 0 {
+            return Self::read(file);
+        }
+        // SAFETY: the map is only read; like every tool reading a file, rcol sees
+        // garbage if another process truncates or rewrites it meanwhile
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        Ok(FileData::Mapped(map))
+    }
+
+    #[cfg(not(feature = "mmap"))]
+    fn map(file: File) -> io::Result<FileData> {
+        Self::read(file)
+    }
+
+    fn read(mut file: File) -> io::Result<FileData> {
+        let mut buf = Vec::new();
+        io::Read::read_to_end(&mut file, &mut buf)?;
+        Ok(FileData::Read(buf))
+    }
+
+    /// Returns the lines of the file followed by the lines of stdin.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Vec<&str>)` with the lines, borrowed from the file
+    /// - `Err(io::Error)` if the file isn't valid UTF-8
+    #[cfg_attr(not(feature = "mmap"), allow(clippy::infallible_destructuring_match))]
+    pub fn lines(&self) -> io::Result<Vec<&str>> {
+        let bytes: &[u8] = match &self.data {
+            #[cfg(feature = "mmap")]
+            FileData::Mapped(map) => map,
+            FileData::Read(buf) => buf,
+        };
+        let mut lines = split_records(bytes, self.terminator)?;
+        lines.extend(self.stdin.iter().map(String::as_str));
+        Ok(lines)
+    }
+}
+
+/// Splits a buffer into lines like [`Records`], borrowing them from the buffer.
+///
+/// # Arguments
+///
+/// * `bytes` - The text
+/// * `terminator` - Byte ending a line, `\n` or `\0` (`-null`)
+///
+/// # Returns
+///
+/// - `Ok(Vec<&str>)` with the lines without their terminators
+/// - `Err(io::Error)` if the text isn't valid UTF-8
+pub fn split_records(bytes: &[u8], terminator: u8) -> io::Result<Vec<&str>> {
+    let text = std::str::from_utf8(bytes).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "stream did not contain valid UTF-8",
+        )
+    })?;
+    let terminator = char::from(terminator);
+    let mut lines: Vec<&str> = text.split_terminator(terminator).collect();
+    if terminator == '\n' {
+        // Like Records, a `\r` is removed only before a terminator
+        let last = lines.len().wrapping_sub(usize::from(!text.ends_with('\n')));
+        for (i, line) in lines.iter_mut().enumerate() {
+            if i != last {
+                *line = line.strip_suffix('\r').unwrap_or(line);
+            }
         }
     }
+    Ok(lines)
+}
 
-    // Read from stdin if it's not a terminal (piped input) or if no file was specified
-    // If no file specified and it IS a terminal, we still read (interactive mode like cat)
-    // But if file IS specified and stdin IS a terminal, we probably skip stdin to avoid hanging?
-    // Requirement: "if there is also data from STDIN, this is added together"
-    // This usually implies piped data.
+/// Returns the input lines of a file and/or stdin one at a time.
+///
+/// Reads the same sources as [`read_input`], but lazily, so callers can process
+/// lines while the input is still being written (e.g. `-json-stream`).
+///
+/// # Arguments
+///
+/// * `file` - Optional path of the input file (`-file`)
+/// * `terminator` - Byte ending a line, `\n` or `\0` (`-null`)
+///
+/// # Returns
+///
+/// - `Ok(iterator)` yielding the lines, or the error of a failed read
+/// - `Err(io::Error)` if the file can't be opened
+pub fn input_lines(
+    file: Option<&str>,
+    terminator: u8,
+) -> io::Result<impl Iterator<Item = io::Result<String>>> {
+    // Read from file if specified
+    let file_lines = match file {
+        Some(filename) => Some(Records::new(
+            BufReader::new(File::open(filename)?),
+            terminator,
+        )),
+        None => None,
+    };
 
+    // Piped stdin is added to the file; a terminal is only read without a file,
+    // interactively like cat
     let stdin = io::stdin();
-    if !stdin.is_terminal() || args.file.is_none() {
-        let reader = stdin.lock();
-        for line in reader.lines() {
-           lines.push(line?.trim().to_string());
+    let stdin_lines = if !stdin.is_terminal() || file.is_none() {
+        Some(Records::new(stdin.lock(), terminator))
+    } else {
+        None
+    };
+
+    Ok(file_lines
+        .into_iter()
+        .flatten()
+        .chain(stdin_lines.into_iter().flatten()))
+}
+
+/// Runs a shell command and reads its standard output as input lines (`-cmd`).
+///
+/// The command runs with `sh -c` (`cmd /C` on Windows), so pipes and quoting work
+/// like in the shell. Its stderr is passed through.
+///
+/// # Arguments
+///
+/// * `cmd` - The command line to run, e.g. `kubectl get pods -A`
+/// * `terminator` - Byte ending a line, `\n` or `\0` (`-null`)
+///
+/// # Returns
+///
+/// - `Ok(Vec<String>)` containing all output lines
+/// - `Err(io::Error)` if the command can't be started, its output can't be read,
+///   or it exits with a non-zero status
+pub fn run_command(cmd: &str, terminator: u8) -> io::Result<Vec<String>> {
+    command_lines(cmd, terminator)?.collect()
+}
+
+/// Runs a shell command like [`run_command`], retrying it when it fails (`-retries`, `-backoff`).
+///
+/// The first retry waits `backoff`, every further one twice as long as the one
+/// before. Each failure is passed to `warn` before retrying.
+///
+/// # Arguments
+///
+/// * `cmd` - The command line to run
+/// * `retries` - How often to retry a failed command
+/// * `backoff` - Wait before the first retry
+/// * `terminator` - Byte ending a line, `\n` or `\0` (`-null`)
+/// * `warn` - Called with a [`Warning::Retrying`] for each failed attempt
+///
+/// # Returns
+///
+/// - `Ok(Vec<String>)` containing the output lines of the first successful run
+/// - `Err(io::Error)` of the last attempt if all attempts fail
+pub fn run_command_with_retries(
+    cmd: &str,
+    retries: u32,
+    backoff: Duration,
+    terminator: u8,
+    mut warn: impl FnMut(Warning),
+) -> io::Result<Vec<String>> {
+    let mut wait = backoff;
+    let mut attempt = 0;
+    loop {
+        match run_command(cmd, terminator) {
+            Err(e) if attempt < retries => {
+                attempt += 1;
+                warn(Warning::Retrying {
+                    error: e.to_string(),
+                    wait,
+                    attempt,
+                    retries,
+                });
+                thread::sleep(wait);
+                wait *= 2;
+            }
+            result => return result,
         }
     }
+}
 
-    Ok(lines)
+/// Starts a shell command and returns its output lines one at a time.
+///
+/// Like [`run_command`], but lines can be processed while the command is still
+/// running (e.g. `-json-stream`). A non-zero exit status is reported as the last item.
+///
+/// # Arguments
+///
+/// * `cmd` - The command line to run
+/// * `terminator` - Byte ending a line, `\n` or `\0` (`-null`)
+///
+/// # Returns
+///
+/// - `Ok(CommandLines)` yielding the output lines
+/// - `Err(io::Error)` if the command can't be started
+pub fn command_lines(cmd: &str, terminator: u8) -> io::Result<CommandLines> {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.arg("/C");
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c");
+        command
+    };
+    let mut child = command
+        .arg(cmd)
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("cannot run '{}': {}", cmd, e)))?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    Ok(CommandLines {
+        cmd: cmd.to_string(),
+        child: Some(child),
+        lines: Records::new(BufReader::new(stdout), terminator),
+    })
+}
+
+/// Output lines of a command started with [`command_lines`].
+pub struct CommandLines {
+    cmd: String,
+    child: Option<Child>,
+    lines: Records<BufReader<ChildStdout>>,
+}
+
+impl Iterator for CommandLines {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(line) = self.lines.next() {
+            return Some(line);
+        }
+        // End of output: report a failed command once
+        let status = match self.child.take()?.wait() {
+            Ok(status) => status,
+            Err(e) => return Some(Err(e)),
+        };
+        if status.success() {
+            None
+        } else {
+            Some(Err(io::Error::other(format!(
+                "command '{}' failed with {}",
+                self.cmd, status
+            ))))
+        }
+    }
+}
+
+/// Lines of a reader ending with a terminator byte, like [`BufRead::lines`] for
+/// `\n` (a `\r` before it is removed as well), or NUL-terminated records of
+/// `find -print0` for `\0` (`-null`).
+pub struct Records<R> {
+    reader: R,
+    terminator: u8,
+}
+
+impl<R: BufRead> Records<R> {
+    /// Reads the lines of `reader` ending with `terminator`.
+    pub fn new(reader: R, terminator: u8) -> Self {
+        Self { reader, terminator }
+    }
+}
+
+impl<R: BufRead> Iterator for Records<R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buf = Vec::new();
+        match self.reader.read_until(self.terminator, &mut buf) {
+            Ok(0) => None,
+            Ok(_) => {
+                if buf.last() == Some(&self.terminator) {
+                    buf.pop();
+                    if self.terminator == b'\n' && buf.last() == Some(&b'\r') {
+                        buf.pop();
+                    }
+                }
+                Some(String::from_utf8(buf).map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "stream did not contain valid UTF-8",
+                    )
+                }))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_records() {
+        fn split(text: &str, terminator: u8) -> Vec<&str> {
+            split_records(text.as_bytes(), terminator).unwrap()
+        }
+        assert_eq!(split("a b\r\nc\n\nd\r", b'\n'), ["a b", "c", "", "d\r"]);
+        assert_eq!(split("a\n", b'\n'), ["a"]);
+        assert!(split("", b'\n').is_empty());
+        assert_eq!(split("x\ny\0z\0", b'\0'), ["x\ny", "z"]);
+
+        let records: Vec<String> = Records::new("a b\r\nc\n\nd\r".as_bytes(), b'\n')
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(records, split("a b\r\nc\n\nd\r", b'\n'));
+        assert!(split_records(&[b'a', 0xff], b'\n').is_err());
+    }
 }
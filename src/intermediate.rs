@@ -0,0 +1,308 @@
+use crate::cell::Cell;
+use crate::formatter::{FormatOptions, is_number, visible_width};
+use crate::processor::{NO_INPUT_COLUMN, TableData};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Version of the intermediate format written by `-emit rcol`.
+///
+/// Version 1 documents are still read; they may lack the column types and widths.
+pub const FORMAT_VERSION: u32 = 2;
+
+/// Type of a column, inferred from its non-empty cells.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnType {
+    /// All non-empty cells are numbers; aligned right
+    Number,
+    String,
+}
+
+/// Where a table in the intermediate format came from.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Provenance {
+    /// rcol version that wrote the document
+    pub version: String,
+    /// Command line of the writing rcol invocation
+    pub command: String,
+    /// Time of writing (UTC, RFC 3339)
+    pub timestamp: String,
+    /// Input file name, or `stdin`
+    pub source: String,
+}
+
+/// Self-describing serialization of [`TableData`] used to chain rcol invocations.
+///
+/// Written as a single line of JSON by `-emit rcol` and read back by `-in rcol`, so a
+/// pipeline like `rcol -emit rcol 3 1 | rcol -in rcol -pp` doesn't have to re-split the
+/// text. Besides the cells it records the inferred column types and widths, and the
+/// original input column of every column so `-num` keeps showing the right numbers.
+/// The reading invocation aligns the columns by their recorded type and keeps them
+/// at least as wide as recorded, see [`RecordedColumns`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RcolDocument {
+    /// Format version, see [`FORMAT_VERSION`]
+    pub rcol: u32,
+    pub headers: Vec<Cell>,
+    /// Type per column
+    #[serde(default)]
+    pub types: Vec<ColumnType>,
+    /// Visible width per column (max over header and cells)
+    #[serde(default)]
+    pub widths: Vec<usize>,
+    pub original_column_indices: Vec<usize>,
+    pub rows: Vec<Vec<Cell>>,
+    pub provenance: Provenance,
+}
+
+impl RcolDocument {
+    /// Builds a document from processed table data.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Table data to serialize
+    /// * `provenance` - Origin information to embed
+    pub fn from_table(data: &TableData, provenance: Provenance) -> Self {
+        let num_cols = data
+            .rows
+            .iter()
+            .map(|r| r.len())
+            .chain(std::iter::once(data.headers.len()))
+            .max()
+            .unwrap_or(0);
+
+        let mut types = Vec::with_capacity(num_cols);
+        let mut widths = Vec::with_capacity(num_cols);
+        for i in 0..num_cols {
+            let cells = data.rows.iter().filter_map(|r| r.get(i));
+            let mut non_empty = cells.clone().filter(|c| !c.is_empty()).peekable();
+            let is_number = non_empty.peek().is_some() && non_empty.all(|c| is_number(c, None));
+            types.push(if is_number {
+                ColumnType::Number
+            } else {
+                ColumnType::String
+            });
+
+            let width = data
+                .headers
+                .get(i)
+                .into_iter()
+                .chain(cells)
+                .map(|c| visible_width(c))
+                .max()
+                .unwrap_or(0);
+            widths.push(width);
+        }
+
+        Self {
+            rcol: FORMAT_VERSION,
+            headers: data.headers.clone(),
+            types,
+            widths,
+            original_column_indices: data.original_column_indices.clone(),
+            rows: data.rows.clone(),
+            provenance,
+        }
+    }
+
+    /// Serializes the document as a single line of JSON.
+    pub fn encode(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    /// Converts the document back into table data, dropping types, widths and
+    /// provenance (see [`RecordedColumns`] to keep them).
+    pub fn into_table(self) -> TableData {
+        TableData {
+            headers: self.headers,
+            rows: self.rows,
+            original_column_indices: self.original_column_indices,
+        }
+    }
+}
+
+/// Column types and widths of an `-in rcol` document, by original input column.
+///
+/// The reading invocation can select and reorder the columns of the document, so
+/// its output columns are matched by their original input column. Columns that
+/// don't come from the document, e.g. from `-insert`, are measured as usual.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecordedColumns(HashMap<usize, (Option<ColumnType>, usize)>);
+
+impl RecordedColumns {
+    /// Collects the recorded columns of a document.
+    pub fn new(doc: &RcolDocument) -> Self {
+        let mut columns = HashMap::new();
+        for (i, &original) in doc.original_column_indices.iter().enumerate() {
+            if original != NO_INPUT_COLUMN {
+                let recorded = (
+                    doc.types.get(i).copied(),
+                    doc.widths.get(i).copied().unwrap_or(0),
+                );
+                columns.entry(original).or_insert(recorded);
+            }
+        }
+        Self(columns)
+    }
+
+    /// Sets the column types and minimum widths of the output columns that come
+    /// from the document.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The processed table
+    /// * `opts` - Format options to update; widths already set are kept if wider
+    pub fn apply(&self, data: &TableData, opts: &mut FormatOptions) {
+        if self.0.is_empty() {
+            return;
+        }
+        for (i, original) in data.original_column_indices.iter().enumerate() {
+            let Some(&(column_type, width)) = self.0.get(original) else {
+                continue;
+            };
+            if opts.column_types.len() <= i {
+                opts.column_types.resize(i + 1, None);
+            }
+            opts.column_types[i] = column_type;
+            if opts.min_widths.len() <= i {
+                opts.min_widths.resize(i + 1, 0);
+            }
+            opts.min_widths[i] = opts.min_widths[i].max(width);
+        }
+    }
+}
+
+/// Reads a table saved with `-emit rcol` from a file.
+///
+/// # Arguments
+///
+/// * `path` - Path of the saved table
+///
+/// # Returns
+///
+/// - `Ok(TableData)` with the saved table
+/// - `Err(String)` if the file can't be read or is not a valid document
+pub fn load_snapshot(path: &str) -> Result<TableData, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    let lines: Vec<String> = text.lines().map(|l| l.to_string()).collect();
+    Ok(decode(&lines)?.into_table())
+}
+
+/// Parses input lines written by `-emit rcol`.
+///
+/// # Arguments
+///
+/// * `lines` - Input lines (the document may be wrapped over several lines)
+///
+/// # Returns
+///
+/// - `Ok(RcolDocument)` with the decoded table
+/// - `Err(String)` if the input is not a valid document of a supported version
+pub fn decode(lines: &[String]) -> Result<RcolDocument, String> {
+    let text = lines.join("\n");
+    if text.trim().is_empty() {
+        return Err("Invalid rcol input: empty document".to_string());
+    }
+    let doc: RcolDocument =
+        serde_json::from_str(&text).map_err(|e| format!("Invalid rcol input: {}", e))?;
+    if !(1..=FORMAT_VERSION).contains(&doc.rcol) {
+        return Err(format!(
+            "Unsupported rcol input version {} (expected {})",
+            doc.rcol, FORMAT_VERSION
+        ));
+    }
+    Ok(doc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provenance() -> Provenance {
+        Provenance {
+            version: "0.1.0".to_string(),
+            command: "rcol --emit rcol".to_string(),
+            timestamp: "2024-05-01T12:00:00Z".to_string(),
+            source: "stdin".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let data = TableData {
+            headers: vec!["Name".into(), "Size".into()],
+            rows: vec![
+                vec!["a b".into(), "10".into()],
+                vec!["ü".into(), "2.5".into()],
+            ],
+            original_column_indices: vec![4, 0],
+        };
+
+        let doc = RcolDocument::from_table(&data, provenance());
+        assert_eq!(doc.types, vec![ColumnType::String, ColumnType::Number]);
+        assert_eq!(doc.widths, vec![4, 4]);
+
+        let decoded = decode(&[doc.encode().unwrap()]).unwrap();
+        assert_eq!(decoded, doc);
+        assert_eq!(decoded.rows, data.rows);
+        assert_eq!(decoded.original_column_indices, vec![4, 0]);
+
+        let table = decoded.into_table();
+        assert_eq!(table.headers, data.headers);
+        assert_eq!(table.rows, data.rows);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert!(decode(&[]).is_err());
+        assert!(decode(&["not json".to_string()]).is_err());
+
+        let mut doc = RcolDocument::from_table(
+            &TableData {
+                headers: Vec::new(),
+                rows: Vec::new(),
+                original_column_indices: Vec::new(),
+            },
+            provenance(),
+        );
+        doc.rcol = 99;
+        let err = decode(&[doc.encode().unwrap()]).unwrap_err();
+        assert!(err.contains("version 99"));
+
+        // Version 1 documents may lack the column types and widths
+        let old = r#"{"rcol":1,"headers":["a"],"original_column_indices":[0],"rows":[["1"]],"provenance":{"version":"","command":"","timestamp":"","source":"stdin"}}"#;
+        let old = decode(&[old.to_string()]).unwrap();
+        assert_eq!(old.rows, vec![vec!["1"]]);
+        assert!(old.types.is_empty());
+    }
+
+    #[test]
+    fn test_recorded_columns() {
+        let data = TableData {
+            headers: vec!["id".into(), "host".into()],
+            rows: vec![
+                vec!["a1".into(), "web".into()],
+                vec!["22".into(), "db".into()],
+            ],
+            original_column_indices: vec![3, 0],
+        };
+        let recorded = RecordedColumns::new(&RcolDocument::from_table(&data, provenance()));
+
+        // The reading invocation swapped the columns, kept one row and inserted one
+        let read = TableData {
+            headers: vec!["host".into(), "id".into(), "new".into()],
+            rows: vec![vec!["db".into(), "22".into(), "x".into()]],
+            original_column_indices: vec![0, 3, NO_INPUT_COLUMN],
+        };
+        let mut opts = FormatOptions {
+            min_widths: vec![6],
+            ..FormatOptions::default()
+        };
+        recorded.apply(&read, &mut opts);
+        assert_eq!(
+            opts.column_types,
+            vec![Some(ColumnType::String), Some(ColumnType::String)]
+        );
+        assert_eq!(opts.min_widths, vec![6, 2]);
+    }
+}
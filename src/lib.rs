@@ -2,7 +2,36 @@
 //!
 //! Column formatting library for the rcol CLI tool.
 
+#[cfg(feature = "cli")]
 pub mod args;
+pub mod builder;
+pub mod cache;
+pub mod cell;
+pub mod chart;
+#[cfg(feature = "cli")]
+pub mod compat;
+#[cfg(feature = "cli")]
+pub mod config;
+#[cfg(any(feature = "csv", feature = "polars"))]
+pub mod convert;
+pub mod datetime;
+pub mod de;
+pub mod diff;
 pub mod formatter;
+pub mod i18n;
 pub mod input;
+pub mod intermediate;
+pub mod logfmt;
+pub mod markup;
+pub mod numeric;
+mod parallel;
+pub mod partition;
+pub mod pivot;
+pub mod preset;
 pub mod processor;
+pub mod sample;
+pub mod spill;
+pub mod sql;
+#[cfg(feature = "test-util")]
+pub mod testing;
+pub mod transform;
@@ -0,0 +1,140 @@
+//! Structured log lines in logfmt (`-in logfmt`): `key=value key2="quoted value"`.
+
+use crate::markup::MarkupTable;
+use std::collections::HashMap;
+
+/// Parses logfmt lines into a table with one column per key.
+///
+/// The header is the union of the keys in the order they first appear, keys
+/// missing from a line give empty cells (see `-na`). A key without `=` has an
+/// empty value. Blank lines are skipped.
+///
+/// # Arguments
+///
+/// * `lines` - Input lines, one log record each
+///
+/// # Returns
+///
+/// The headers and rows of the table
+pub fn parse_logfmt(lines: &[String]) -> MarkupTable {
+    let mut headers: Vec<String> = Vec::new();
+    let mut columns: HashMap<String, usize> = HashMap::new();
+    let mut records = Vec::new();
+    for line in lines.iter().filter(|line| !line.trim().is_empty()) {
+        let record: Vec<(usize, String)> = pairs(line)
+            .into_iter()
+            .map(|(key, value)| {
+                let col = *columns.entry(key.clone()).or_insert_with(|| {
+                    headers.push(key);
+                    headers.len() - 1
+                });
+                (col, value)
+            })
+            .collect();
+        records.push(record);
+    }
+
+    let rows = records
+        .into_iter()
+        .map(|record| {
+            let mut row = vec![String::new(); headers.len()];
+            for (col, value) in record {
+                row[col] = value;
+            }
+            row
+        })
+        .collect();
+    (headers, rows)
+}
+
+/// Splits a line into its key/value pairs.
+///
+/// Values in double quotes may contain blanks, `\"` and `\\` are a quote and a
+/// backslash and `\n` and `\t` a newline and a tab in them.
+fn pairs(line: &str) -> Vec<(String, String)> {
+    let mut pairs = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let mut key = String::new();
+        while let Some(c) = chars.next_if(|&c| c != '=' && !c.is_whitespace()) {
+            key.push(c);
+        }
+        if key.is_empty() && chars.peek().is_none() {
+            return pairs;
+        }
+
+        let mut value = String::new();
+        if chars.next_if_eq(&'=').is_some() {
+            if chars.next_if_eq(&'"').is_some() {
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some('n') => value.push('\n'),
+                            Some('t') => value.push('\t'),
+                            Some(c) => value.push(c),
+                            None => value.push('\\'),
+                        },
+                        c => value.push(c),
+                    }
+                }
+            } else {
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                    value.push(c);
+                }
+            }
+        }
+        // A stray `=` without a key is skipped
+        if !key.is_empty() {
+            pairs.push((key, value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pairs() {
+        assert_eq!(
+            pairs(r#"level=info msg="user \"bob\" logged in" took=12ms debug"#),
+            [
+                ("level".to_string(), "info".to_string()),
+                ("msg".to_string(), r#"user "bob" logged in"#.to_string()),
+                ("took".to_string(), "12ms".to_string()),
+                ("debug".to_string(), String::new()),
+            ]
+        );
+        assert_eq!(
+            pairs(r#"  a= b="x\ty" =c "#),
+            [
+                ("a".to_string(), String::new()),
+                ("b".to_string(), "x\ty".to_string()),
+            ]
+        );
+        assert!(pairs("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_logfmt() {
+        let lines = [
+            "level=info msg=started",
+            "",
+            "level=error msg=\"disk full\" path=/var",
+            "path=/tmp",
+        ]
+        .map(String::from);
+        let (headers, rows) = parse_logfmt(&lines);
+        assert_eq!(headers, ["level", "msg", "path"]);
+        assert_eq!(
+            rows,
+            [
+                vec!["info", "started", ""],
+                vec!["error", "disk full", "/var"],
+                vec!["", "", "/tmp"],
+            ]
+        );
+    }
+}
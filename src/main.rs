@@ -1,130 +1,696 @@
-mod args;
-mod formatter;
-mod input;
-mod processor;
-
-use args::AppArgs;
-use clap::Parser;
-use formatter::format_output;
-use input::read_input;
-use processor::process_input;
+use clap::{CommandFactory, Parser};
+use clap_complete::generate;
+use clap_mangen::Man;
+use rcol::args::AppArgs;
+use rcol::cell::Cell;
+use rcol::cache::{TableCache, default_cache_dir};
+use rcol::compat::column_args;
+use rcol::config::parse_args_with_config;
+use rcol::diff::{changes_since, diff_tables};
+use rcol::formatter::{
+    FormatOptions, column_widths, format_ascii_parts, format_output, format_to_string,
+    format_to_writer,
+};
+use rcol::input::{
+    FileInput, command_lines, input_lines, read_file, read_input, run_command,
+    run_command_with_retries,
+};
+use rcol::intermediate::load_snapshot;
+use rcol::partition::{chunk_files, chunks, split_by};
+use rcol::processor::{Pipeline, ProcessOptions, TableData, Warning, detect_separator};
+use rcol::spill::Spill;
+use rcol::sql::{query_table, write_sqlite};
+use roff::{Roff, bold, italic, roman};
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
 use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
 
-/// Print comprehensive man page for rcol
-fn print_manpage() {
-    let version = env!("CARGO_PKG_VERSION");
-    let author = env!("CARGO_PKG_AUTHORS");
-
-    let mantext = r##"
-    rcol(1)                          General Commands Manual                         rcol(1)
-
-    NAME
-           rcol - Rust Column Formatter: Format and shape unformatted ASCII text into columns
-
-    SYNOPSIS
-           rcol [OPTIONS] [COLUMNS...]
-
-    DESCRIPTION
-           rcol formats unformatted ASCII text columns into neatly aligned columns. It can read input
-           from standard input or a specified file, process the data (sorting, grouping,
-           filtering), and output in various formats including plain text, CSV, JSON, or HTML.
-
-    OPTIONS
-           -f, --file FILENAME           Read input from FILENAME instead of standard input
-           -H, --header LINE            Define a custom header line for the output
-           -s, --sep SEPARATOR          Define the input separator (default: whitespace)
-           -m, --mb                     Treat multiple consecutive separators as a single delimiter
-           -w, --width WIDTH            Set padding width between columns (default: 1)
-           -C, --colsep SEPARATOR       Define column separation string (default: '│')
-           -F, --filter REGEX           Process only lines matching the given regular expression
-           -S, --sortcol N              Sort output by column N (1-based index)
-           -g, --gcol N                 Group output by column N
-           -gcolval                     Keep repeated group values instead of replacing with empty strings
-           --nf                         No Format: Do not align columns to a common width
-           --nn                         No Numerical: Disable automatic right-alignment of numerical values
-           --nhl                        No Headline: Treat first line as data, not a header
-           --ts                         Title Separator: Draw line between header and data
-           --fs                         Footer Separator: Draw line before last row of data
-           --cs                         Column Separator: Draw vertical line between columns
-           -p, --pp                     Pretty Print: Draw border around table with Unicode box characters
-           --rh                         Remove Header: Discard first line of input
-           -n, --num                    Numbering: Add row with column numbers at top
-           --csv                        Output as CSV format
-           --json                       Output as JSON format
-           --yaml                       Output as YAML format
-           --html                       Output as HTML format
-           --jtc                        JSON Title Column: Use first column as key for JSON objects
-           -v, --verify                 Print parameter verification info
-           -M, --manpage                Output comprehensive man page
-           COLUMNS                      Specify which columns to output (1-based indices)
-
-    EXAMPLES
-           # Format input from stdin with default settings
-           cat data.txt | rcol
-
-           # Format specific columns with custom separator and pretty print
-           rcol -s ',' -p 1 3 5 < data.csv
-
-           # Group by column 2 and sort by column 1
-           rcol -g 2 -S 1 data.txt
-
-           # Convert to JSON with first column as keys
-           rcol --json --jtc data.txt
-
-    SEE ALSO
-           column(1), fmt(1)
-
-    VERSION
-           {version}
-
-    AUTHOR
-           {author}
-    "##;
-    println!("{}", mantext
-        .replace("{version}", version)
-        .replace("{author}", author)
-    );
+/// Number of input lines processed and written at once by `--json-stream`
+const STREAM_CHUNK: usize = 1000;
+
+/// Number of input lines processed, and rows printed, at once by `--low-mem`
+const LOW_MEM_CHUNK: usize = 100_000;
+
+/// Exit status for output that can't be written and other failures
+const EXIT_FAILURE: i32 = 1;
+
+/// Exit status for invalid arguments, as clap uses it for unknown options
+const EXIT_USAGE: i32 = 2;
+
+/// Exit status for input that can't be read
+const EXIT_INPUT: i32 = 3;
+
+/// Exit status for input that can't be processed
+const EXIT_PROCESSING: i32 = 4;
+
+/// Exit status for output without data rows with `--exit-nonempty`
+const EXIT_EMPTY: i32 = 5;
+
+/// Exit statuses for the EXIT STATUS section of the man page
+const MANPAGE_EXIT_STATUS: &[(i32, &str)] = &[
+    (0, "Success, also if no row matched the filters (see --exit-nonempty)."),
+    (
+        EXIT_FAILURE,
+        "The output can't be written, e.g. a closed pipe or a failed --sqlite export.",
+    ),
+    (
+        EXIT_USAGE,
+        "Invalid arguments: unknown options, a bad config file or an invalid column, filter or other specification.",
+    ),
+    (
+        EXIT_INPUT,
+        "The input can't be read: a missing --file, a failing --cmd or an unreadable --since snapshot.",
+    ),
+    (
+        EXIT_PROCESSING,
+        "The input can't be processed, e.g. --sortcol beyond its columns or a ragged row with --strict.",
+    ),
+    (EXIT_EMPTY, "The output has no data rows and --exit-nonempty is given."),
+];
+
+/// Example invocations for the EXAMPLES section of the man page
+const MANPAGE_EXAMPLES: &[(&str, &str)] = &[
+    ("Format input from stdin with default settings", "cat data.txt | rcol"),
+    (
+        "Format specific columns with custom separator and pretty print",
+        "rcol -s ',' -p 1 3 5 < data.csv",
+    ),
+    ("Group by column 2 and sort by column 1", "rcol -g 2 -S 1 data.txt"),
+    ("Convert to JSON with first column as keys", "rcol --json --jtc data.txt"),
+];
+
+/// Writes the man page in roff format to stdout, e.g. `rcol --manpage > rcol.1`.
+///
+/// NAME, SYNOPSIS, DESCRIPTION and OPTIONS are generated from the clap definition
+/// of [`AppArgs`], so the man page always lists the options rcol actually accepts.
+fn print_manpage() -> io::Result<()> {
+    let man = Man::new(AppArgs::command());
+    let mut out = io::stdout().lock();
+    man.render_title(&mut out)?;
+    man.render_name_section(&mut out)?;
+    man.render_synopsis_section(&mut out)?;
+    man.render_description_section(&mut out)?;
+    man.render_options_section(&mut out)?;
+
+    let mut roff = Roff::new();
+    roff.control("SH", ["EXAMPLES"]);
+    for (description, command) in MANPAGE_EXAMPLES {
+        roff.text([roman(format!("# {}", description))])
+            .control("RS", [] as [&str; 0])
+            .text([bold(*command)])
+            .control("RE", [] as [&str; 0]);
+    }
+    roff.control("SH", ["CONFIGURATION"])
+        .text([
+            roman("Default options are read from "),
+            italic("$XDG_CONFIG_HOME/rcol/config.toml"),
+            roman(" (usually "),
+            italic("~/.config/rcol/config.toml"),
+            roman(") if it exists, or from the file given with "),
+            bold("--config"),
+            roman(". Keys are long option names, options given on the command line override them:"),
+        ])
+        .control("RS", [] as [&str; 0])
+        .control("nf", [] as [&str; 0])
+        .text([roman("pp = true\ncolsep = \"|\"\nfilter_not = [\"^#\", \"^$\"]")])
+        .control("fi", [] as [&str; 0])
+        .control("RE", [] as [&str; 0])
+        .control("SH", ["EXIT STATUS"]);
+    for (status, meaning) in MANPAGE_EXIT_STATUS {
+        roff.control("TP", [] as [&str; 0])
+            .text([bold(status.to_string())])
+            .text([roman(*meaning)]);
+    }
+    roff.control("SH", ["SEE ALSO"])
+        .text([bold("column"), roman("(1), "), bold("fmt"), roman("(1)")]);
+    roff.to_writer(&mut out)?;
+
+    man.render_version_section(&mut out)?;
+    man.render_authors_section(&mut out)
+}
+
+/// Why rcol failed, with the message to print. Each class has its own exit status.
+enum Failure {
+    /// Invalid arguments or specifications
+    Usage(String),
+    /// The input can't be read
+    Input(String),
+    /// The input can't be processed
+    Processing(String),
+    /// The output can't be written
+    Output(String),
+}
+
+impl Failure {
+    fn message(&self) -> &str {
+        match self {
+            Failure::Usage(message)
+            | Failure::Input(message)
+            | Failure::Processing(message)
+            | Failure::Output(message) => message,
+        }
+    }
+
+    /// Prints the message on stderr and exits with the status of the failure class.
+    fn exit(self) -> ! {
+        let status = match self {
+            Failure::Usage(_) => EXIT_USAGE,
+            Failure::Input(_) => EXIT_INPUT,
+            Failure::Processing(_) => EXIT_PROCESSING,
+            Failure::Output(_) => EXIT_FAILURE,
+        };
+        eprintln!("{}", self.message());
+        process::exit(status)
+    }
+}
+
+/// Processes and writes the input chunk by chunk while it is read (`--json-stream`).
+///
+/// Only used when the rows don't depend on each other, see [`Pipeline::streams`].
+///
+/// # Returns
+///
+/// - `Ok(usize)` with the number of data rows when all input was written
+/// - `Err(Failure)` if reading, processing or writing fails
+fn stream_output(args: &AppArgs, pipeline: Pipeline) -> Result<usize, Failure> {
+    let opts = FormatOptions::from(args);
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let mut data_rows = 0;
+    process_chunks(args, pipeline, STREAM_CHUNK, |data| {
+        data_rows += data.data_rows();
+        format_to_writer(&data, &opts, &mut out)
+            .and_then(|_| out.flush())
+            .map_err(|e| Failure::Output(args.lang.tr("error-formatting-output", &[&e])))
+    })?;
+    Ok(data_rows)
+}
+
+/// Prints the input as one table in two passes with bounded memory (`--low-mem`).
+///
+/// The first pass processes the input chunk by chunk, measures the columns and
+/// spills the rows to a temporary file; with `--sortcol` every chunk is sorted.
+/// The second pass reads the rows back, merging the sorted chunks, and prints
+/// them. Only used for ASCII output when the rows can be merged, see
+/// [`Pipeline::merges`].
+///
+/// # Returns
+///
+/// - `Ok(usize)` with the number of data rows when the table was written
+/// - `Err(Failure)` if reading, processing, spilling or writing fails
+fn low_mem_output(args: &AppArgs, pipeline: Pipeline) -> Result<usize, Failure> {
+    let mut opts = FormatOptions::from(args);
+    let output = |e: io::Error| Failure::Output(args.lang.tr("error-formatting-output", &[&e]));
+    let mut spill = Spill::new();
+    let mut table: Option<TableData> = None;
+    let mut data_rows = 0;
+    let pipeline = process_chunks(args, pipeline, LOW_MEM_CHUNK, |data| {
+        data_rows += data.data_rows();
+        for (i, width) in column_widths(&data, &opts).into_iter().enumerate() {
+            match opts.min_widths.get_mut(i) {
+                Some(max) => *max = (*max).max(width),
+                None => opts.min_widths.push(width),
+            }
+        }
+        let TableData {
+            headers,
+            rows,
+            original_column_indices,
+        } = data;
+        table.get_or_insert(TableData {
+            headers,
+            rows: Vec::new(),
+            original_column_indices,
+        });
+        spill.push(rows).map_err(output)
+    })?;
+
+    let order = |a: &[Cell], b: &[Cell]| pipeline.compare_rows(a, b);
+    let mut rows = spill
+        .into_rows(args.sortcol.map(|_| order))
+        .map_err(output)?;
+    let mut part = move || {
+        rows.by_ref()
+            .take(LOW_MEM_CHUNK)
+            .collect::<io::Result<Vec<_>>>()
+    };
+    // The first chunk is processed also if the input is empty
+    let Some(mut first) = table else {
+        return Ok(0);
+    };
+    first.rows = part().map_err(output)?;
+    let rest = std::iter::from_fn(|| match part() {
+        Ok(rows) if rows.is_empty() => None,
+        result => Some(result),
+    });
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    format_ascii_parts(&first, rest, &opts, &mut out)
+        .and_then(|_| out.flush())
+        .map_err(output)?;
+    Ok(data_rows)
+}
+
+/// Reads the input chunk by chunk and processes every chunk on its own.
+///
+/// The header line of the input is put in front of every chunk after the first.
+///
+/// # Arguments
+///
+/// * `size` - Number of lines per chunk
+/// * `each` - Takes every processed chunk
+///
+/// # Returns
+///
+/// - `Ok(Pipeline)` with the pipeline, once all input was processed
+/// - `Err(Failure)` if reading or processing fails, or the first failure of `each`
+fn process_chunks(
+    args: &AppArgs,
+    mut pipeline: Pipeline,
+    size: usize,
+    mut each: impl FnMut(TableData) -> Result<(), Failure>,
+) -> Result<Pipeline, Failure> {
+    let read_error = |e: io::Error| Failure::Input(args.lang.tr("error-reading-input", &[&e]));
+    let mut lines: Box<dyn Iterator<Item = io::Result<String>>> = match &args.cmd {
+        Some(cmd) => Box::new(command_lines(cmd, args.line_terminator()).map_err(read_error)?),
+        None => Box::new(
+            input_lines(args.file.as_deref(), args.line_terminator()).map_err(read_error)?,
+        ),
+    };
+    let mut header: Option<String> = None;
+    let mut first = true;
+    loop {
+        let mut chunk: Vec<String> = header.iter().cloned().collect();
+        let prefix = chunk.len();
+        for line in lines.by_ref().take(size) {
+            chunk.push(line.map_err(read_error)?);
+        }
+        if chunk.len() == prefix && !first {
+            return Ok(pipeline);
+        }
+        let first_line = if first {
+            pipeline.first_data_line(&chunk)
+        } else {
+            None
+        };
+        let data = pipeline
+            .process(chunk)
+            .map_err(|e| Failure::Processing(args.lang.tr("error-processing-input", &[&e])))?;
+        print_warnings(args, &pipeline);
+        each(data)?;
+        if first {
+            header = pipeline.chunk_header(first_line);
+            first = false;
+        }
+    }
+}
+
+/// Reads the input (`--file`, stdin or `--cmd`) and processes it into a table.
+///
+/// # Returns
+///
+/// - `Ok(TableData)` with the processed table
+/// - `Err(Failure)` if reading or processing fails
+fn read_table(args: &AppArgs, pipeline: &Pipeline) -> Result<TableData, Failure> {
+    let input = |e: io::Error| Failure::Input(args.lang.tr("error-reading-input", &[&e]));
+    let processing = |e: String| Failure::Processing(args.lang.tr("error-processing-input", &[&e]));
+    let data = match (&args.cmd, &args.file) {
+        (Some(cmd), _) => {
+            let backoff = Duration::from_millis(args.backoff);
+            let terminator = args.line_terminator();
+            let warn = |warning| print_warning(args, &warning);
+            let lines = run_command_with_retries(cmd, args.retries, backoff, terminator, warn)
+                .map_err(input)?;
+            pipeline.process(lines).map_err(processing)?
+        }
+        (None, Some(file)) => {
+            // Lines borrowed from the mapped file, no String per line
+            let file = FileInput::open(file, args.line_terminator()).map_err(input)?;
+            let lines = file.lines().map_err(input)?;
+            pipeline.process_borrowed(&lines).map_err(processing)?
+        }
+        (None, None) => {
+            let lines = read_input(None, args.line_terminator()).map_err(input)?;
+            pipeline.process(lines).map_err(processing)?
+        }
+    };
+    print_warnings(args, pipeline);
+    Ok(data)
+}
+
+/// Compares the processed input with the table in `path`, processed the same way (`--diff`).
+fn diff_with(
+    args: &AppArgs,
+    pipeline: &Pipeline,
+    path: &str,
+    data: &TableData,
+) -> Result<TableData, Failure> {
+    let Some(key) = args.key.checked_sub(1) else {
+        let message = args.lang.tr("column-not-1-based", &[&"key", &0]);
+        return Err(Failure::Usage(args.lang.tr("error-options", &[&message])));
+    };
+    let processing = |e: String| Failure::Processing(args.lang.tr("error-processing-input", &[&e]));
+    let lines = read_file(path, args.line_terminator())
+        .map_err(|e| Failure::Input(args.lang.tr("error-reading-input", &[&e])))?;
+    let other = pipeline.process(lines).map_err(processing)?;
+    print_warnings(args, pipeline);
+    diff_tables(&other, data, key, args.lang).map_err(processing)
+}
+
+/// Writes the table split by `--split-by` or `--chunk` instead of printing it in one piece.
+///
+/// With `--split-out` or `--chunk-out` every table goes into a file of its own,
+/// missing directories are created and the files are never colored. Chunks
+/// without `--chunk-out` are printed one after the other.
+fn write_parts(args: &AppArgs, data: &TableData) -> Result<(), Failure> {
+    let usage = |e: &dyn std::fmt::Display| Failure::Usage(args.lang.tr("error-options", &[e]));
+    let tables = match (args.split_by, args.chunk) {
+        (Some(column), _) => {
+            let column = column
+                .checked_sub(1)
+                .ok_or_else(|| usage(&args.lang.tr("column-not-1-based", &[&"split-by", &0])))?;
+            let template = args.split_out.as_deref().unwrap_or_default();
+            split_by(data, column, template, args.lang).map_err(|e| usage(&e))?
+        }
+        (None, Some(0)) => return Err(usage(&"--chunk must be at least 1")),
+        (None, Some(size)) => match &args.chunk_out {
+            Some(template) => chunk_files(data, size, template).map_err(|e| usage(&e))?,
+            None => {
+                let opts = FormatOptions::from(args);
+                let mut out = io::stdout().lock();
+                return chunks(data, size)
+                    .iter()
+                    .try_for_each(|table| format_to_writer(table, &opts, &mut out))
+                    .and_then(|_| out.flush())
+                    .map_err(|e| Failure::Output(args.lang.tr("error-formatting-output", &[&e])));
+            }
+        },
+        (None, None) => Vec::new(),
+    };
+    let opts = FormatOptions {
+        color: false,
+        ..FormatOptions::from(args)
+    };
+    for (path, table) in tables {
+        let write = || -> io::Result<()> {
+            let path = Path::new(&path);
+            if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                fs::create_dir_all(dir)?;
+            }
+            let mut out = BufWriter::new(File::create(path)?);
+            format_to_writer(&table, &opts, &mut out)?;
+            out.flush()
+        };
+        write().map_err(|e| Failure::Output(args.lang.tr("error-writing-file", &[&path, &e])))?;
+    }
+    Ok(())
+}
+
+/// Prints the warnings of the processed input on stderr, unless `--quiet`.
+fn print_warnings(args: &AppArgs, pipeline: &Pipeline) {
+    for warning in pipeline.take_warnings() {
+        print_warning(args, &warning);
+    }
+}
+
+/// Prints a warning on stderr in the `--lang` language, unless `--quiet`.
+fn print_warning(args: &AppArgs, warning: &Warning) {
+    if !args.quiet {
+        let message = warning.message(args.lang);
+        eprintln!("{}", args.lang.tr("warning", &[&message]));
+    }
+}
+
+/// Reads and processes the table of a `--cmd`, reusing a table cached within `--cache TTL`.
+///
+/// Tables are keyed by the command and the processing options, so any option
+/// changing the table also changes the key. A table that can't be cached only
+/// causes a warning.
+fn read_cached_table(
+    args: &AppArgs,
+    pipeline: &Pipeline,
+    cmd: &str,
+    ttl: Duration,
+) -> Result<TableData, Failure> {
+    let Some(dir) = default_cache_dir() else {
+        return read_table(args, pipeline);
+    };
+    let cache = TableCache::new(dir);
+    let key = format!("{}\n{:?}", cmd, ProcessOptions::from(args));
+    if !args.no_cache
+        && let Some(data) = cache.get(&key, ttl)
+    {
+        return Ok(data);
+    }
+    let data = read_table(args, pipeline)?;
+    if let Err(e) = cache.put(&key, &data)
+        && !args.quiet
+    {
+        let message = args.lang.tr("cache-failed", &[&e]);
+        eprintln!("{}", args.lang.tr("warning", &[&message]));
+    }
+    Ok(data)
+}
+
+/// Re-reads and redraws the table every `--watch` interval until rcol is interrupted.
+///
+/// Columns only grow between refreshes, so they don't jump. When a refresh fails,
+/// the last table stays on the screen under a banner with the error.
+///
+/// # Returns
+///
+/// `Err(Failure)` if the input is stdin, which can't be read again, or if the
+/// table can't be formatted or written
+fn watch(args: &AppArgs, pipeline: &Pipeline, interval: Duration) -> Result<(), Failure> {
+    let source = match (&args.cmd, &args.file) {
+        (Some(cmd), _) => cmd,
+        (None, Some(file)) => file,
+        (None, None) => return Err(Failure::Usage(args.lang.tr("error-watch", &[]))),
+    };
+    // Re-running a command without a pause would keep a CPU busy
+    let interval = interval.max(Duration::from_secs(1));
+    let every = format!("{}s", interval.as_secs());
+    let title = args.lang.tr("watch-title", &[&every, source]);
+    let format_error =
+        |e: io::Error| Failure::Output(args.lang.tr("error-formatting-output", &[&e]));
+    let mut opts = FormatOptions::from(args);
+    let mut last: Option<(String, Instant)> = None;
+    loop {
+        let table = match (&args.cmd, args.cache) {
+            (Some(cmd), Some(ttl)) => read_cached_table(args, pipeline, cmd, ttl),
+            _ => read_table(args, pipeline),
+        };
+        let body = match table {
+            Ok(data) => {
+                opts.min_widths = column_widths(&data, &opts);
+                let text = format_to_string(&data, &opts).map_err(format_error)?;
+                last = Some((text.clone(), Instant::now()));
+                text
+            }
+            Err(e) => match &last {
+                Some((text, at)) => {
+                    let age = at.elapsed().as_secs();
+                    let message = e.message();
+                    format!(
+                        "{}\n\n{}",
+                        args.lang.tr("watch-stale", &[&message, &age]),
+                        text
+                    )
+                }
+                None => format!("{}\n", e.message()),
+            },
+        };
+
+        // Clear the screen and draw from the top left corner in one write
+        let mut out = io::stdout().lock();
+        write!(out, "\x1b[H\x1b[2J{}\n\n{}", title, body)
+            .and_then(|_| out.flush())
+            .map_err(format_error)?;
+        drop(out);
+        thread::sleep(interval);
+    }
+}
+
+/// Exits with [`EXIT_EMPTY`] if the output had no data rows and `--exit-nonempty` is given.
+fn exit_if_empty(args: &AppArgs, data_rows: usize) {
+    if args.exit_nonempty && data_rows == 0 {
+        process::exit(EXIT_EMPTY);
+    }
 }
 
 /// Main entry point for the rcol application.
 ///
 /// Parses command-line arguments, reads input from file or stdin, processes the data
 /// according to the specified options, and formats the output in the requested format.
-/// Exits with status code 1 on any error.
+/// Exits with a status per failure class: 2 for invalid arguments, 3 if the input
+/// can't be read, 4 if it can't be processed, 5 for output without data rows with
+/// `--exit-nonempty` and 1 if the output can't be written.
 fn main() {
-    let args = AppArgs::parse();
+    let argv: Vec<OsString> = std::env::args_os().collect();
+    let args = match column_args(&argv) {
+        // Scripts written for column(1) don't expect the defaults of the config file
+        Some(Ok(argv)) => Ok((AppArgs::parse_from(argv), Vec::new())),
+        Some(Err(e)) => Err(e),
+        None => parse_args_with_config(argv),
+    };
+    let (args, warnings) = match args {
+        Ok(parsed) => parsed,
+        Err(e) => Failure::Usage(format!("Error: {}", e)).exit(),
+    };
+    for warning in &warnings {
+        print_warning(&args, warning);
+    }
 
     if args.verify {
         println!("Args: {:?}", args);
+        if args.sep == "auto" {
+            let lines = match &args.cmd {
+                Some(cmd) => run_command(cmd, args.line_terminator()),
+                None => read_input(args.file.as_deref(), args.line_terminator()),
+            };
+            match lines {
+                Ok(lines) => println!("Separator: {}", detect_separator(&lines).0),
+                Err(e) => eprintln!("{}", args.lang.tr("error-reading-input", &[&e])),
+            }
+        }
         return;
     }
 
     if args.manpage {
-        print_manpage();
+        if let Err(e) = print_manpage() {
+            Failure::Output(args.lang.tr("error-manpage", &[&e])).exit();
+        }
         return;
     }
 
-    // Read input
-    let lines = match read_input(&args) {
-        Ok(l) => l,
-        Err(e) => {
-            eprintln!("Error reading input: {}", e);
-            process::exit(1);
+    if let Some(shell) = args.completions {
+        // Rendered first, clap_complete panics if stdout fails
+        let mut script = Vec::new();
+        generate(shell, &mut AppArgs::command(), "rcol", &mut script);
+        // A reader like `head` may close the pipe before the end
+        if let Err(e) = io::stdout().write_all(&script)
+            && e.kind() != io::ErrorKind::BrokenPipe
+        {
+            Failure::Output(args.lang.tr("error-completions", &[&e])).exit();
         }
-    };
+        return;
+    }
 
-    // Process input
-    let processed_data = match process_input(lines, &args) {
-        Ok(data) => data,
-        Err(e) => {
-            eprintln!("Error processing input: {}", e);
-            process::exit(1);
+    let mut pipeline = Pipeline::new(ProcessOptions::from(&args));
+    // Mistakes on the command line are usage errors, found before any input is read
+    if let Err(e) = pipeline.validate() {
+        Failure::Usage(args.lang.tr("error-options", &[&e])).exit();
+    }
+    if let Err(e) = FormatOptions::from(&args).validate() {
+        Failure::Usage(args.lang.tr("error-options", &[&e])).exit();
+    }
+    if let Some(file) = &args.join {
+        match read_file(file, args.line_terminator()) {
+            Ok(lines) => pipeline.join(lines),
+            Err(e) => Failure::Input(args.lang.tr("error-reading-input", &[&e])).exit(),
+        };
+    }
+    // A retried command can't take back rows that were already written
+    let retried = args.cmd.is_some() && args.retries > 0;
+    let streaming = args.json_stream
+        && args.sqlite.is_none()
+        && args.since.is_none()
+        && args.diff.is_none()
+        && args.query.is_none()
+        && args.split_by.is_none()
+        && args.chunk.is_none()
+        && args.cache.is_none();
+    if streaming && !retried && pipeline.streams() {
+        match stream_output(&args, pipeline) {
+            Ok(data_rows) => exit_if_empty(&args, data_rows),
+            Err(failure) => failure.exit(),
+        }
+        return;
+    }
+
+    if args.low_mem {
+        let ascii = FormatOptions::from(&args).format == "ascii";
+        if !ascii || retried || !pipeline.merges() {
+            let message = "--low-mem needs ASCII output, no --retries and no options that \
+                           need all rows at once, e.g. --gcol, --reverse or --freq";
+            Failure::Usage(args.lang.tr("error-options", &[&message])).exit();
+        }
+        match low_mem_output(&args, pipeline) {
+            Ok(data_rows) => exit_if_empty(&args, data_rows),
+            Err(failure) => failure.exit(),
+        }
+        return;
+    }
+
+    if let Some(interval) = args.watch {
+        if let Err(failure) = watch(&args, &pipeline, interval) {
+            failure.exit();
         }
+        return;
+    }
+
+    // Read and process input
+    let table = match (&args.cmd, args.cache) {
+        (Some(cmd), Some(ttl)) => read_cached_table(&args, &pipeline, cmd, ttl),
+        _ => read_table(&args, &pipeline),
+    };
+    let processed_data = match table {
+        Ok(data) => data,
+        Err(failure) => failure.exit(),
+    };
+
+    // Compare with a previous run
+    let processed_data = match &args.since {
+        Some(path) => match load_snapshot(path) {
+            Ok(previous) => changes_since(&previous, &processed_data, args.computed_precision()),
+            Err(e) => Failure::Input(args.lang.tr("error-since", &[&e])).exit(),
+        },
+        None => processed_data,
+    };
+    let processed_data = match &args.diff {
+        Some(path) => match diff_with(&args, &pipeline, path, &processed_data) {
+            Ok(data) => data,
+            Err(failure) => failure.exit(),
+        },
+        None => processed_data,
     };
+    let processed_data = match &args.query {
+        Some(query) => match query_table(&processed_data, query) {
+            Ok(data) => data,
+            Err(e) => Failure::Processing(args.lang.tr("error-processing-input", &[&e])).exit(),
+        },
+        None => processed_data,
+    };
+
+    let data_rows = processed_data.data_rows();
+
+    // Export into a database instead of printing the table
+    if let Some(path) = &args.sqlite {
+        if let Err(e) = write_sqlite(&processed_data, Path::new(path), &args.table) {
+            Failure::Output(args.lang.tr("error-sqlite", &[&e])).exit();
+        }
+        exit_if_empty(&args, data_rows);
+        return;
+    }
+
+    // Write a file per group or chunk instead of printing one table
+    if args.split_by.is_some() || args.chunk.is_some() {
+        if let Err(failure) = write_parts(&args, &processed_data) {
+            failure.exit();
+        }
+        exit_if_empty(&args, data_rows);
+        return;
+    }
 
     // Format output
-    if let Err(e) = format_output(processed_data, &args) {
-        eprintln!("Error formatting output: {}", e);
-        process::exit(1);
+    let mut opts = FormatOptions::from(&args);
+    pipeline.recorded_columns().apply(&processed_data, &mut opts);
+    if let Err(e) = format_output(processed_data, &opts) {
+        Failure::Output(args.lang.tr("error-formatting-output", &[&e])).exit();
     }
+    exit_if_empty(&args, data_rows);
 }
\ No newline at end of file
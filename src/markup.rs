@@ -0,0 +1,268 @@
+use regex::Regex;
+
+/// Header cells and data rows of a table read from markup.
+pub type MarkupTable = (Vec<String>, Vec<Vec<String>>);
+
+/// Parses the first Markdown table of the input (`-in md`).
+///
+/// A table is a header row followed by a delimiter row (`|---|:---:|`) and the
+/// data rows up to the first line that is not a table row. Leading and trailing
+/// pipes are optional, `\|` is a pipe inside a cell. The alignment given in the
+/// delimiter row is not kept, numbers are aligned right in the output anyway.
+///
+/// # Arguments
+///
+/// * `lines` - Input lines, other text around the table is ignored
+///
+/// # Returns
+///
+/// - `Ok(MarkupTable)` with the headers and rows of the table
+/// - `Err(String)` if the input contains no Markdown table
+pub fn parse_markdown(lines: &[String]) -> Result<MarkupTable, String> {
+    let start = lines
+        .windows(2)
+        .position(|pair| pair[0].contains('|') && is_delimiter_row(&pair[1]))
+        .ok_or("No Markdown table found in the input")?;
+
+    let headers = markdown_cells(&lines[start]);
+    let rows = lines[start + 2..]
+        .iter()
+        .take_while(|line| line.contains('|'))
+        .map(|line| markdown_cells(line))
+        .collect();
+    Ok((headers, rows))
+}
+
+/// Checks for the delimiter row under a Markdown table header, e.g. `|---|:--:|`.
+fn is_delimiter_row(line: &str) -> bool {
+    let cells = markdown_cells(line);
+    line.contains('-')
+        && !cells.is_empty()
+        && cells.iter().all(|c| {
+            let dashes = c.trim_start_matches(':').trim_end_matches(':');
+            !dashes.is_empty() && dashes.chars().all(|ch| ch == '-')
+        })
+}
+
+/// Splits a Markdown table row into trimmed cells at unescaped pipes.
+fn markdown_cells(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = match line.strip_suffix('|') {
+        Some(rest) if !rest.ends_with('\\') => rest,
+        _ => line,
+    };
+
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cell.push('|');
+                chars.next();
+            }
+            '|' => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+/// Parses the first HTML table of the input (`-in html`).
+///
+/// Rows are the `<tr>` elements, cells their `<th>` and `<td>` elements (closing
+/// tags may be omitted). The first row is the header if it consists of `<th>`
+/// cells only. Tags inside cells are removed, entities decoded and white space
+/// collapsed. Only the first `<table>` is read.
+///
+/// # Arguments
+///
+/// * `lines` - Input lines, e.g. a whole web page
+///
+/// # Returns
+///
+/// - `Ok(MarkupTable)` with the headers (empty without header row) and rows
+/// - `Err(String)` if the input contains no HTML table
+pub fn parse_html(lines: &[String]) -> Result<MarkupTable, String> {
+    let text = lines.join("\n");
+    let table_start = Regex::new(r"(?i)<table\b[^>]*>").unwrap();
+    let table_end = Regex::new(r"(?i)</table\s*>").unwrap();
+    let row_start = Regex::new(r"(?i)<tr\b[^>]*>").unwrap();
+    let row_end = Regex::new(r"(?i)</tr\s*>").unwrap();
+    let cell_start = Regex::new(r"(?i)<(th|td)\b[^>]*>").unwrap();
+
+    let start = table_start
+        .find(&text)
+        .ok_or("No HTML table found in the input")?
+        .end();
+    let table = &text[start..];
+    let table = &table[..table_end.find(table).map_or(table.len(), |m| m.start())];
+
+    let mut headers = Vec::new();
+    let mut rows = Vec::new();
+    let row_starts: Vec<usize> = row_start.find_iter(table).map(|m| m.end()).collect();
+    for (i, &from) in row_starts.iter().enumerate() {
+        let to = row_starts.get(i + 1).copied().unwrap_or(table.len());
+        let row = &table[from..to];
+        let row = &row[..row_end.find(row).map_or(row.len(), |m| m.start())];
+
+        let opens: Vec<_> = cell_start.captures_iter(row).collect();
+        let mut all_th = !opens.is_empty();
+        let mut cells = Vec::new();
+        for (j, caps) in opens.iter().enumerate() {
+            let open = caps.get(0).unwrap();
+            all_th &= caps[1].eq_ignore_ascii_case("th");
+            let end = opens
+                .get(j + 1)
+                .map_or(row.len(), |next| next.get(0).unwrap().start());
+            cells.push(html_text(&row[open.end()..end]));
+        }
+        if i == 0 && all_th {
+            headers = cells;
+        } else if !cells.is_empty() {
+            rows.push(cells);
+        }
+    }
+    Ok((headers, rows))
+}
+
+/// Converts the HTML of a cell into plain text.
+fn html_text(html: &str) -> String {
+    let tag = Regex::new(r"<[^>]*>").unwrap();
+    let text = tag.replace_all(html, " ");
+    let entity = Regex::new(r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);").unwrap();
+    let text = entity.replace_all(&text, |caps: &regex::Captures| {
+        let name = &caps[1];
+        let decoded = match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ if name.starts_with("#x") || name.starts_with("#X") => {
+                u32::from_str_radix(&name[2..], 16)
+                    .ok()
+                    .and_then(char::from_u32)
+            }
+            _ if name.starts_with('#') => name[1..].parse().ok().and_then(char::from_u32),
+            _ => None,
+        };
+        decoded.map_or_else(|| caps[0].to_string(), String::from)
+    });
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Characters that separate the cells of a box-drawn table row (`-unbox`).
+const VERTICAL_RULES: [char; 6] = ['│', '┃', '║', '┆', '┊', '|'];
+
+/// Checks whether a line is a border or separator line of a box-drawn table,
+/// e.g. `┌──┬──┐`, `├──┼──┤`, `+----+----+` or `|====|`.
+pub fn is_rule_line(line: &str) -> bool {
+    let line = line.trim();
+    line.chars()
+        .any(|c| matches!(c, '-' | '=' | '─' | '━' | '═'))
+        && line.chars().all(|c| {
+            matches!(c, '-' | '=' | '+' | ':' | ' ') || ('\u{2500}'..='\u{257f}').contains(&c)
+        })
+}
+
+/// Splits a row of a box-drawn table at its vertical rules (`-unbox`).
+///
+/// Cells are trimmed, the empty cells outside a left and right border are dropped.
+///
+/// # Returns
+///
+/// The cells, or `None` if the line contains no vertical rule
+pub fn split_boxed(line: &str) -> Option<Vec<String>> {
+    let line = line.trim();
+    if !line.contains(VERTICAL_RULES) {
+        return None;
+    }
+    let line = line.strip_prefix(VERTICAL_RULES).unwrap_or(line);
+    let line = line.strip_suffix(VERTICAL_RULES).unwrap_or(line);
+    Some(
+        line.split(VERTICAL_RULES)
+            .map(|cell| cell.trim().to_string())
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_parse_markdown() {
+        let input = lines(
+            "Some text\n\
+             \n\
+             | Name | Size | Note |\n\
+             |:-----|-----:|------|\n\
+             | a    |   10 | x \\| y |\n\
+             | b    |    2 |\n\
+             \n\
+             More text | with a pipe",
+        );
+        let (headers, rows) = parse_markdown(&input).unwrap();
+        assert_eq!(headers, vec!["Name", "Size", "Note"]);
+        assert_eq!(rows, vec![vec!["a", "10", "x | y"], vec!["b", "2"]]);
+
+        let (headers, _) = parse_markdown(&lines("a | b\n--- | ---\n1 | 2")).unwrap();
+        assert_eq!(headers, vec!["a", "b"]);
+
+        assert!(parse_markdown(&lines("a | b\n1 | 2")).is_err());
+    }
+
+    #[test]
+    fn test_box_drawn_lines() {
+        assert!(is_rule_line("┌───────┬─────┐"));
+        assert!(is_rule_line(" ──────────── "));
+        assert!(is_rule_line("+------+-----+"));
+        assert!(!is_rule_line("│ Name  │ Age │"));
+        assert!(!is_rule_line("-5"));
+        assert!(!is_rule_line(""));
+
+        assert_eq!(
+            split_boxed("│ Alice │  30 │"),
+            Some(vec!["Alice".to_string(), "30".to_string()])
+        );
+        assert_eq!(
+            split_boxed(" Bob   |   5 "),
+            Some(vec!["Bob".to_string(), "5".to_string()])
+        );
+        assert_eq!(split_boxed("no rules"), None);
+    }
+
+    #[test]
+    fn test_parse_html() {
+        let input = lines(
+            "<html><body><p>Pods</p>\n\
+             <table class=\"t\">\n\
+             <thead><tr><th>Name</th><th>Status</th></tr></thead>\n\
+             <tbody>\n\
+             <tr><td><a href=\"x\">web</a></td><td>Running &amp; ready</td></tr>\n\
+             <tr><td>db<td>Failed&#33;\n\
+             </tbody></table>\n\
+             <table><tr><td>other</td></tr></table>",
+        );
+        let (headers, rows) = parse_html(&input).unwrap();
+        assert_eq!(headers, vec!["Name", "Status"]);
+        assert_eq!(
+            rows,
+            vec![vec!["web", "Running & ready"], vec!["db", "Failed!"]]
+        );
+
+        let (headers, rows) = parse_html(&lines("<table><tr><td>1</td></tr></table>")).unwrap();
+        assert!(headers.is_empty());
+        assert_eq!(rows, vec![vec!["1"]]);
+
+        assert!(parse_html(&lines("<p>no table</p>")).is_err());
+    }
+}
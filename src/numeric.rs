@@ -0,0 +1,674 @@
+use crate::cell::Cell;
+
+/// Number of decimal places used for computed values when no `-precision` is given.
+///
+/// Large enough to keep meaningful digits, small enough to hide binary floating
+/// point noise such as the `4` in `0.30000000000000004`.
+pub const DEFAULT_PRECISION: usize = 10;
+
+/// Formats a computed number (sum, mean, delta, ...) for output.
+///
+/// All formatters use this helper so computed values look identical in every output
+/// format and on every platform:
+/// - With a precision, exactly that many decimal places are printed (`1.50` for 2)
+/// - Without one, the value is rounded to [`DEFAULT_PRECISION`] places and trailing
+///   zeros are dropped (`0.1 + 0.2` prints as `0.3`, `2.0` as `2`)
+/// - Negative zero is printed as `0`
+///
+/// # Arguments
+///
+/// * `value` - The number to format
+/// * `precision` - Fixed number of decimal places (from `-precision`), if any
+///
+/// # Returns
+///
+/// The formatted number
+pub fn format_decimal(value: f64, precision: Option<usize>) -> String {
+    if !value.is_finite() {
+        return value.to_string();
+    }
+
+    let places = precision.unwrap_or(DEFAULT_PRECISION);
+    let mut out = format!("{:.*}", places, value);
+
+    if precision.is_none() && out.contains('.') {
+        let trimmed = out.trim_end_matches('0').trim_end_matches('.').len();
+        out.truncate(trimmed);
+    }
+
+    // "-0", "-0.00", ... after rounding
+    if out.starts_with('-') && out[1..].chars().all(|c| c == '0' || c == '.') {
+        out.remove(0);
+    }
+    out
+}
+
+/// A cell recognized as a number by type inference (`-infer-types`, `-sql`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Number {
+    Int(i64),
+    Float(f64),
+}
+
+/// Parses a cell as a decimal number for typed output.
+///
+/// Stricter than `f64` parsing: the value must start and end with a digit (no
+/// `inf`, `NaN`, `+1` or `1.`), and numbers with leading zeros such as `007`
+/// are not numbers, so IDs and codes keep their text.
+///
+/// # Returns
+///
+/// `Some(Number::Int)` for integers fitting `i64`, `Some(Number::Float)` for other
+/// decimal numbers, `None` otherwise
+pub fn parse_number(val: &str) -> Option<Number> {
+    let digits = val.strip_prefix('-').unwrap_or(val);
+    let leading_zero = digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.");
+    let numeric = digits.starts_with(|c: char| c.is_ascii_digit())
+        && digits.ends_with(|c: char| c.is_ascii_digit());
+    if !numeric || leading_zero {
+        return None;
+    }
+    if let Ok(n) = val.parse::<i64>() {
+        return Some(Number::Int(n));
+    }
+    val.parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite())
+        .map(Number::Float)
+}
+
+/// How numbers with grouping separators or a decimal comma are read (`-numfmt`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum NumFmt {
+    /// `1.234,56`: `.` groups digits, `,` is the decimal separator
+    De,
+    /// `1,234.56`: `,` groups digits, `.` is the decimal separator
+    En,
+    /// Decided per value: the last of `.` and `,` is the decimal separator; a
+    /// single `,` followed by three digits and repeated `.` group digits
+    Auto,
+}
+
+/// How numbers are written with `-numfmt-out`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum NumStyle {
+    /// `1234.56`
+    Plain,
+    /// `1.234,56`
+    De,
+    /// `1,234.56`
+    En,
+}
+
+impl NumFmt {
+    /// Grouping and decimal separator of `val` in this format.
+    fn separators(self, val: &str) -> (char, char) {
+        match self {
+            NumFmt::De => ('.', ','),
+            NumFmt::En => (',', '.'),
+            NumFmt::Auto => match (val.rfind('.'), val.rfind(',')) {
+                (Some(dot), Some(comma)) if dot > comma => (',', '.'),
+                (Some(_), Some(_)) => ('.', ','),
+                (Some(_), None) if val.matches('.').count() > 1 => ('.', ','),
+                (None, Some(comma))
+                    if val.matches(',').count() == 1 && val.len() - comma - 1 != 3 =>
+                {
+                    ('.', ',')
+                }
+                _ => (',', '.'),
+            },
+        }
+    }
+}
+
+/// Converts a number with grouping separators into plain notation, e.g. `1.234,5`
+/// with [`NumFmt::De`] into `1234.5`.
+///
+/// Groups after the first one must have three digits, so `1.5` is no German number.
+///
+/// # Returns
+///
+/// The number as `-1234.5`, or `None` if `val` isn't a number in this format
+fn plain_number(val: &str, group: Option<char>, decimal: char) -> Option<String> {
+    let is_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let (sign, digits) = match val.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", val),
+    };
+    let (int, frac) = match digits.split_once(decimal) {
+        Some((int, frac)) => (int, Some(frac)),
+        None => (digits, None),
+    };
+    if frac.is_some_and(|frac| !is_digits(frac)) {
+        return None;
+    }
+    let mut plain = sign.to_string();
+    match group.filter(|g| int.contains(*g)) {
+        Some(group) => {
+            let mut groups = int.split(group);
+            let first = groups.next()?;
+            if !is_digits(first) || first.len() > 3 {
+                return None;
+            }
+            plain.push_str(first);
+            for g in groups {
+                if g.len() != 3 || !is_digits(g) {
+                    return None;
+                }
+                plain.push_str(g);
+            }
+        }
+        None if is_digits(int) => plain.push_str(int),
+        None => return None,
+    }
+    if let Some(frac) = frac {
+        plain.push('.');
+        plain.push_str(frac);
+    }
+    Some(plain)
+}
+
+/// Parses a cell as number for sorting and alignment.
+///
+/// With a [`NumFmt`] grouping separators and decimal commas are understood,
+/// values it doesn't accept (e.g. computed `1.5` with [`NumFmt::De`]) are still
+/// tried as plain number.
+///
+/// # Arguments
+///
+/// * `val` - The cell
+/// * `fmt` - Format of the numbers (`-numfmt`), plain numbers only if `None`
+///
+/// # Returns
+///
+/// The value, or `None` if the cell isn't a number
+pub fn parse_localized(val: &str, fmt: Option<NumFmt>) -> Option<f64> {
+    fmt.and_then(|fmt| {
+        let (group, decimal) = fmt.separators(val);
+        plain_number(val, Some(group), decimal)
+    })
+    .and_then(|plain| plain.parse::<f64>().ok())
+    .or_else(|| val.parse::<f64>().ok())
+}
+
+/// Writes a number cell in another style, keeping its decimal places, e.g.
+/// `1234.5` as `1,234.5` with [`NumStyle::En`].
+///
+/// # Arguments
+///
+/// * `val` - The cell
+/// * `fmt` - Format of the numbers (`-numfmt`), plain numbers only if `None`
+/// * `style` - Style to write the number in (`-numfmt-out`)
+///
+/// # Returns
+///
+/// The reformatted number, or `None` if the cell isn't a decimal number or has
+/// leading zeros like an ID (`007`)
+pub fn restyle_number(val: &str, fmt: Option<NumFmt>, style: NumStyle) -> Option<String> {
+    let plain = match fmt {
+        Some(fmt) => {
+            let (group, decimal) = fmt.separators(val);
+            plain_number(val, Some(group), decimal)
+        }
+        None => None,
+    }
+    .or_else(|| plain_number(val, None, '.'))?;
+    parse_number(&plain)?;
+
+    let (group, decimal) = match style {
+        NumStyle::Plain => return Some(plain),
+        NumStyle::De => ('.', ','),
+        NumStyle::En => (',', '.'),
+    };
+    let (sign, digits) = match plain.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", plain.as_str()),
+    };
+    let (int, frac) = digits.split_once('.').unwrap_or((digits, ""));
+    let mut out = sign.to_string();
+    for (i, c) in int.chars().enumerate() {
+        if i > 0 && (int.len() - i) % 3 == 0 {
+            out.push(group);
+        }
+        out.push(c);
+    }
+    if !frac.is_empty() {
+        out.push(decimal);
+        out.push_str(frac);
+    }
+    Some(out)
+}
+
+/// Unit prefixes of `-humanize`, from thousands up.
+const UNIT_PREFIXES: [&str; 6] = ["k", "M", "G", "T", "P", "E"];
+
+/// Writes a large value with a unit prefix like `numfmt --to=si`, e.g.
+/// `1234567` as `1.2M`.
+///
+/// Values below 10 of a unit get one decimal place, larger ones none. Values
+/// below 1000 (1024) are written as they are.
+///
+/// # Arguments
+///
+/// * `value` - The value to write
+/// * `iec` - Use powers of 1024 and the prefixes `Ki`, `Mi`, ... (e.g. for bytes)
+///
+/// # Returns
+///
+/// The value with unit prefix
+pub fn humanize(value: f64, iec: bool) -> String {
+    let base = if iec { 1024.0 } else { 1000.0 };
+    let mut scaled = value;
+    let mut unit = None;
+    while scaled.abs() >= base && unit.is_none_or(|u| u + 1 < UNIT_PREFIXES.len()) {
+        scaled /= base;
+        unit = Some(unit.map_or(0, |u| u + 1));
+    }
+    let Some(mut unit) = unit else {
+        return format_decimal(value, None);
+    };
+    // 999.96k is rounded to 1.0M, not 1000k
+    if format!("{:.0}", scaled.abs()) == format!("{}", base) && unit + 1 < UNIT_PREFIXES.len() {
+        scaled /= base;
+        unit += 1;
+    }
+    let places = usize::from(scaled.abs() < 9.95);
+    let prefix = match (iec, UNIT_PREFIXES[unit]) {
+        (true, "k") => "Ki".to_string(),
+        (true, prefix) => format!("{}i", prefix),
+        (false, prefix) => prefix.to_string(),
+    };
+    format!("{}{}", format_decimal(scaled, Some(places)), prefix)
+}
+
+/// Checks whether a cell is a value written by [`humanize`], e.g. `1.2M` or `3Gi`.
+pub fn is_humanized(val: &str) -> bool {
+    let val = match val.strip_suffix("Ki") {
+        Some(number) => return parse_number(number).is_some(),
+        None => val.strip_suffix('i').unwrap_or(val),
+    };
+    UNIT_PREFIXES.iter().any(|unit| {
+        val.strip_suffix(unit)
+            .is_some_and(|number| parse_number(number).is_some())
+    })
+}
+
+/// ANSI color of positive values in signed computed columns.
+pub const POSITIVE_COLOR: &str = "\x1b[32m";
+/// ANSI color of negative values in signed computed columns.
+pub const NEGATIVE_COLOR: &str = "\x1b[31m";
+/// ANSI sequence resetting the color.
+pub const RESET_COLOR: &str = "\x1b[0m";
+
+/// A computed column given with `-delta A-B[:percent]`.
+///
+/// Holds the difference `A - B` of two output columns, or with `:percent` the
+/// change from `B` to `A` in percent of `B`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delta {
+    /// 0-based output column of the minuend
+    pub minuend: usize,
+    /// 0-based output column of the subtrahend
+    pub subtrahend: usize,
+    /// Percent change instead of the difference
+    pub percent: bool,
+}
+
+impl Delta {
+    /// Parses a delta specification like `3-2` or `3-2:percent`.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - The specification as given on the command line (1-based columns)
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Delta)` if the specification is well-formed
+    /// - `Err(String)` otherwise
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid delta '{}': expected A-B or A-B:percent", spec);
+        let (cols, mode) = match spec.split_once(':') {
+            Some((cols, "percent")) => (cols, true),
+            Some(_) => return Err(invalid()),
+            None => (spec, false),
+        };
+        let (a, b) = cols.split_once('-').ok_or_else(invalid)?;
+        let a: usize = a.trim().parse().map_err(|_| invalid())?;
+        let b: usize = b.trim().parse().map_err(|_| invalid())?;
+        if a == 0 || b == 0 {
+            return Err("Column numbers must be 1-based".to_string());
+        }
+        Ok(Self {
+            minuend: a - 1,
+            subtrahend: b - 1,
+            percent: mode,
+        })
+    }
+
+    /// Computes the delta of one row.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - Cells of the row
+    /// * `precision` - Fixed number of decimal places (from `-precision`), if any
+    ///
+    /// # Returns
+    ///
+    /// The formatted delta with a `+` sign for positive values, or an empty string if
+    /// a cell is not a number (or the base of a percent change is zero)
+    pub fn compute(&self, row: &[Cell], precision: Option<usize>) -> String {
+        let Some((a, b)) = self.operands(row) else {
+            return String::new();
+        };
+        let delta = if self.percent {
+            if b == 0.0 {
+                return String::new();
+            }
+            (a - b) / b.abs() * 100.0
+        } else {
+            a - b
+        };
+        let text = format_decimal(delta, precision);
+        if delta > 0.0 && text.bytes().any(|c| (b'1'..=b'9').contains(&c)) {
+            format!("+{}", text)
+        } else {
+            text
+        }
+    }
+
+    /// Returns both operand cells of a row as numbers, or `None` if one isn't a number.
+    pub fn operands(&self, row: &[Cell]) -> Option<(f64, f64)> {
+        let value = |i: usize| row.get(i).and_then(|c| c.trim().parse::<f64>().ok());
+        Some((value(self.minuend)?, value(self.subtrahend)?))
+    }
+
+    /// Returns the header of the computed column, e.g. `New-Old` or `New-Old %`.
+    pub fn header(&self, headers: &[Cell]) -> String {
+        let name = |i: usize| match headers.get(i) {
+            Some(h) if !h.is_empty() => h.to_string(),
+            _ => (i + 1).to_string(),
+        };
+        let header = format!("{}-{}", name(self.minuend), name(self.subtrahend));
+        if self.percent { header + " %" } else { header }
+    }
+}
+
+/// Wraps a signed number in green (positive) or red (negative) ANSI color.
+///
+/// Zero and non-numeric values are returned unchanged.
+pub fn color_signed(value: &str) -> String {
+    match value.parse::<f64>() {
+        Ok(v) if v > 0.0 => format!("{}{}{}", POSITIVE_COLOR, value, RESET_COLOR),
+        Ok(v) if v < 0.0 => format!("{}{}{}", NEGATIVE_COLOR, value, RESET_COLOR),
+        _ => value.to_string(),
+    }
+}
+
+/// Parses a duration like `5m30s`, `2h`, `1.5d` (kubectl AGE) or `01:02:03`,
+/// `1-02:03:04` (ps TIME, `[[DAYS-]HOURS:]MINUTES:SECONDS`).
+///
+/// The units are `ms`, `s`, `m`, `h`, `d`, `w` and `y` (365 days). A number without
+/// unit is not a duration.
+///
+/// # Returns
+///
+/// The duration in seconds, or `None` if the value is no duration
+pub fn parse_duration(value: &str) -> Option<f64> {
+    let value = value.trim();
+    if value.contains(':') {
+        let (days, clock) = match value.split_once('-') {
+            Some((days, clock)) => (days.parse::<u64>().ok()? as f64, clock),
+            None => (0.0, value),
+        };
+        let parts: Vec<&str> = clock.split(':').collect();
+        if !(2..=3).contains(&parts.len()) {
+            return None;
+        }
+        let last = parts.len() - 1;
+        let mut values = Vec::new();
+        for (i, part) in parts.iter().enumerate() {
+            // Only the seconds may have a fraction
+            let valid = |c: char| c.is_ascii_digit() || (i == last && c == '.');
+            if part.is_empty() || !part.chars().all(valid) {
+                return None;
+            }
+            values.push(part.parse::<f64>().ok()?);
+        }
+        let (hours, minutes, seconds) = match values[..] {
+            [h, m, s] => (h, m, s),
+            [m, s] => (0.0, m, s),
+            _ => return None,
+        };
+        return Some(days * 86_400.0 + hours * 3600.0 + minutes * 60.0 + seconds);
+    }
+
+    if value.is_empty() {
+        return None;
+    }
+    let mut seconds = 0.0;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let unit_end = rest[number_end..]
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .map_or(rest.len(), |i| number_end + i);
+        let number: f64 = rest[..number_end].parse().ok()?;
+        let unit = match &rest[number_end..unit_end] {
+            "ms" => 0.001,
+            "s" => 1.0,
+            "m" => 60.0,
+            "h" => 3600.0,
+            "d" => 86_400.0,
+            "w" => 604_800.0,
+            "y" => 31_536_000.0,
+            _ => return None,
+        };
+        seconds += number * unit;
+        rest = &rest[unit_end..];
+    }
+    Some(seconds)
+}
+
+/// Formats a duration uniformly like `1d02h03m04s`: the largest unit without and
+/// the others with two digits, units that are zero at the end are left out.
+///
+/// # Arguments
+///
+/// * `seconds` - The duration, rounded to whole seconds
+///
+/// # Returns
+///
+/// The formatted duration, `0s` for durations below half a second
+pub fn format_duration(seconds: f64) -> String {
+    let mut rest = seconds.max(0.0).round() as u64;
+    if rest == 0 {
+        return "0s".to_string();
+    }
+    let mut parts = Vec::new();
+    for (size, unit) in [(86_400, 'd'), (3600, 'h'), (60, 'm'), (1, 's')] {
+        let amount = rest / size;
+        rest %= size;
+        if !parts.is_empty() || amount > 0 {
+            parts.push((amount, unit));
+        }
+    }
+    while parts.last().is_some_and(|(amount, _)| *amount == 0) {
+        parts.pop();
+    }
+    parts
+        .iter()
+        .enumerate()
+        .map(|(i, (amount, unit))| {
+            if i == 0 {
+                format!("{}{}", amount, unit)
+            } else {
+                format!("{:02}{}", amount, unit)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_decimal_default() {
+        assert_eq!(format_decimal(0.1 + 0.2, None), "0.3");
+        assert_eq!(format_decimal(2.0, None), "2");
+        assert_eq!(format_decimal(-1.25, None), "-1.25");
+        assert_eq!(format_decimal(1e-12, None), "0");
+    }
+
+    #[test]
+    fn test_format_decimal_fixed_precision() {
+        assert_eq!(format_decimal(1.5, Some(2)), "1.50");
+        assert_eq!(format_decimal(2.0 / 3.0, Some(3)), "0.667");
+        assert_eq!(format_decimal(42.0, Some(0)), "42");
+    }
+
+    #[test]
+    fn test_format_decimal_negative_zero() {
+        assert_eq!(format_decimal(-0.0, None), "0");
+        assert_eq!(format_decimal(-0.0001, Some(2)), "0.00");
+    }
+
+    #[test]
+    fn test_delta_parse() {
+        assert_eq!(
+            Delta::parse("3-2").unwrap(),
+            Delta {
+                minuend: 2,
+                subtrahend: 1,
+                percent: false
+            }
+        );
+        assert!(Delta::parse("3-2:percent").unwrap().percent);
+        assert!(Delta::parse("3").is_err());
+        assert!(Delta::parse("3-x").is_err());
+        assert!(Delta::parse("0-1").is_err());
+        assert!(Delta::parse("3-2:ratio").is_err());
+    }
+
+    #[test]
+    fn test_delta_compute() {
+        let row = ["db", "80", "100", "x"].map(Cell::from);
+        let delta = Delta::parse("3-2").unwrap();
+        assert_eq!(delta.compute(&row, None), "+20");
+        assert_eq!(Delta::parse("2-3").unwrap().compute(&row, None), "-20");
+        assert_eq!(
+            Delta::parse("3-2:percent").unwrap().compute(&row, Some(1)),
+            "+25.0"
+        );
+        assert_eq!(Delta::parse("2-2").unwrap().compute(&row, None), "0");
+        assert_eq!(Delta::parse("4-2").unwrap().compute(&row, None), "");
+
+        let headers = ["Host", "Old", "New"].map(Cell::from);
+        assert_eq!(delta.header(&headers), "New-Old");
+        assert_eq!(Delta::parse("3-2:percent").unwrap().header(&[]), "3-2 %");
+    }
+
+    #[test]
+    fn test_color_signed() {
+        assert_eq!(color_signed("+2"), "\x1b[32m+2\x1b[0m");
+        assert_eq!(color_signed("-2"), "\x1b[31m-2\x1b[0m");
+        assert_eq!(color_signed("0"), "0");
+        assert_eq!(color_signed(""), "");
+    }
+
+    #[test]
+    fn test_format_decimal_non_finite() {
+        assert_eq!(format_decimal(f64::NAN, Some(2)), "NaN");
+        assert_eq!(format_decimal(f64::INFINITY, None), "inf");
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("5m30s"), Some(330.0));
+        assert_eq!(parse_duration("2h"), Some(7200.0));
+        assert_eq!(parse_duration("1.5d"), Some(129_600.0));
+        assert_eq!(parse_duration("250ms"), Some(0.25));
+        assert_eq!(parse_duration("00:01:23"), Some(83.0));
+        assert_eq!(parse_duration("12:30"), Some(750.0));
+        assert_eq!(parse_duration("1-02:03:04"), Some(93_784.0));
+        for invalid in ["", "42", "5x", "m5", "1:2:3:4", "a:b", "1-2"] {
+            assert_eq!(parse_duration(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(3720.0), "1h02m");
+        assert_eq!(format_duration(3723.0), "1h02m03s");
+        assert_eq!(format_duration(45.0), "45s");
+        assert_eq!(format_duration(90_000.0), "1d01h");
+        assert_eq!(format_duration(0.2), "0s");
+    }
+
+    #[test]
+    fn test_parse_localized() {
+        let de = Some(NumFmt::De);
+        assert_eq!(parse_localized("1.234,56", de), Some(1234.56));
+        assert_eq!(parse_localized("-1.234.567", de), Some(-1234567.0));
+        assert_eq!(parse_localized("1,5", de), Some(1.5));
+        assert_eq!(parse_localized("1.5", de), Some(1.5));
+        assert_eq!(parse_localized("12.34.5", de), None);
+        assert_eq!(parse_localized("1,234.56", Some(NumFmt::En)), Some(1234.56));
+        assert_eq!(parse_localized("1,234.56", None), None);
+
+        let auto = Some(NumFmt::Auto);
+        for (val, expected) in [
+            ("1.234,56", 1234.56),
+            ("1,234.56", 1234.56),
+            ("1,234", 1234.0),
+            ("1,5", 1.5),
+            ("1.5", 1.5),
+            ("1.234.567", 1234567.0),
+        ] {
+            assert_eq!(parse_localized(val, auto), Some(expected), "{}", val);
+        }
+        assert_eq!(parse_localized("1,2,3", auto), None);
+    }
+
+    #[test]
+    fn test_restyle_number() {
+        assert_eq!(
+            restyle_number("1234567.50", None, NumStyle::En).as_deref(),
+            Some("1,234,567.50")
+        );
+        assert_eq!(
+            restyle_number("-1,234.5", Some(NumFmt::En), NumStyle::De).as_deref(),
+            Some("-1.234,5")
+        );
+        assert_eq!(
+            restyle_number("1.234,5", Some(NumFmt::De), NumStyle::Plain).as_deref(),
+            Some("1234.5")
+        );
+        assert_eq!(
+            restyle_number("123", None, NumStyle::De).as_deref(),
+            Some("123")
+        );
+        assert_eq!(restyle_number("007", None, NumStyle::En), None);
+        assert_eq!(restyle_number("1e5", None, NumStyle::En), None);
+        assert_eq!(restyle_number("n/a", None, NumStyle::En), None);
+    }
+
+    #[test]
+    fn test_humanize() {
+        assert_eq!(humanize(1234567.0, false), "1.2M");
+        assert_eq!(humanize(45_600_000_000.0, false), "46G");
+        assert_eq!(humanize(999_960.0, false), "1.0M");
+        assert_eq!(humanize(-2048.0, true), "-2.0Ki");
+        assert_eq!(humanize(999.0, false), "999");
+        assert_eq!(humanize(12.5, true), "12.5");
+        assert!(is_humanized("1.2M"));
+        assert!(is_humanized("46Gi"));
+        assert!(is_humanized("1.5Ki"));
+        assert!(!is_humanized("5m"));
+        assert!(!is_humanized("M"));
+    }
+}
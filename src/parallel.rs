@@ -0,0 +1,73 @@
+//! Data parallelism for large inputs (`-parallel`), on rayon with the `parallel`
+//! feature.
+//!
+//! Without the feature or without `-parallel` everything runs on the calling
+//! thread. Results are in the order of the items either way.
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Maps the items, on all cores if `parallel` is set.
+#[cfg(feature = "parallel")]
+pub(crate) fn map<T, U, F>(items: Vec<T>, parallel: bool, f: F) -> Vec<U>
+where
+    T: Send,
+    U: Send,
+    F: Fn(T) -> U + Sync + Send,
+{
+    if parallel {
+        items.into_par_iter().map(f).collect()
+    } else {
+        items.into_iter().map(f).collect()
+    }
+}
+
+/// Without the `parallel` feature the items are mapped one after the other.
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn map<T, U, F>(items: Vec<T>, _parallel: bool, f: F) -> Vec<U>
+where
+    F: Fn(T) -> U,
+{
+    items.into_iter().map(f).collect()
+}
+
+/// Changes every item in place, on all cores if `parallel` is set.
+#[cfg(feature = "parallel")]
+pub(crate) fn for_each<T, F>(items: &mut [T], parallel: bool, f: F)
+where
+    T: Send,
+    F: Fn(&mut T) + Sync + Send,
+{
+    if parallel {
+        items.par_iter_mut().for_each(f);
+    } else {
+        items.iter_mut().for_each(f);
+    }
+}
+
+/// Without the `parallel` feature the items are changed one after the other.
+#[cfg(not(feature = "parallel"))]
+pub(crate) fn for_each<T, F>(items: &mut [T], _parallel: bool, f: F)
+where
+    F: Fn(&mut T),
+{
+    items.iter_mut().for_each(f);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parallel_keeps_order() {
+        let items: Vec<usize> = (0..10_000).collect();
+        for parallel in [false, true] {
+            let doubled = map(items.clone(), parallel, |i| i * 2);
+            assert!(doubled.iter().enumerate().all(|(i, &d)| d == i * 2));
+
+            let mut squared = items.clone();
+            for_each(&mut squared, parallel, |i| *i *= *i);
+            assert_eq!(squared[99], 99 * 99);
+        }
+    }
+}
@@ -0,0 +1,227 @@
+//! Output split into several tables: one per group value (`-split-by`) or per
+//! number of rows (`-chunk`).
+
+use crate::cell::Cell;
+use crate::i18n::Lang;
+use crate::processor::TableData;
+use std::collections::HashMap;
+
+/// Placeholder of the group value in `-split-out` paths.
+pub const VALUE_PLACEHOLDER: &str = "{value}";
+
+/// Placeholder of the chunk number in `-chunk-out` paths.
+pub const NUMBER_PLACEHOLDER: &str = "{n}";
+
+/// Splits a table into one table per value of a column, for one file each.
+///
+/// Every table keeps the headers and holds the rows with one value, in their
+/// input order; the tables are ordered by the first occurrence of their value.
+/// Values that give the same file name (e.g. `a/b` and `a_b`) share a table.
+/// Separator rows of `-gcol` grouping are dropped.
+///
+/// # Arguments
+///
+/// * `data` - The processed table
+/// * `column` - 0-based output column whose values group the rows
+/// * `template` - Path of the files, `{value}` is replaced by the file name of the value
+/// * `lang` - Language of the error messages
+///
+/// # Returns
+///
+/// - `Ok(Vec<(String, TableData)>)` with the path and table of each group
+/// - `Err(String)` if the column doesn't exist or the template has no `{value}`
+pub fn split_by(
+    data: &TableData,
+    column: usize,
+    template: &str,
+    lang: Lang,
+) -> Result<Vec<(String, TableData)>, String> {
+    if !template.contains(VALUE_PLACEHOLDER) {
+        return Err(format!(
+            "Invalid --split-out '{}': expected a path with {}",
+            template, VALUE_PLACEHOLDER
+        ));
+    }
+    let columns = data
+        .rows
+        .iter()
+        .map(|row| row.len())
+        .chain([data.headers.len()])
+        .max()
+        .unwrap_or(0);
+    if column >= columns {
+        return Err(lang.tr(
+            "exceeds-output-columns",
+            &[&"split-by", &(column + 1), &columns],
+        ));
+    }
+
+    let mut tables: Vec<(String, TableData)> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for row in data
+        .rows
+        .iter()
+        .filter(|row| row.iter().any(|c| !c.is_empty()))
+    {
+        let value = row.get(column).map_or("", Cell::as_str);
+        let path = template.replace(VALUE_PLACEHOLDER, &file_name(value));
+        let pos = *positions.entry(path.clone()).or_insert_with(|| {
+            let table = TableData {
+                headers: data.headers.clone(),
+                rows: Vec::new(),
+                original_column_indices: data.original_column_indices.clone(),
+            };
+            tables.push((path, table));
+            tables.len() - 1
+        });
+        tables[pos].1.rows.push(row.clone());
+    }
+    Ok(tables)
+}
+
+/// Splits a table into tables of at most `size` data rows, each with the headers.
+///
+/// Separator rows of `-gcol` grouping are dropped. A table without rows gives
+/// one table with the headers only, so there is always some output.
+///
+/// # Arguments
+///
+/// * `data` - The processed table
+/// * `size` - Maximum number of rows per table, at least 1
+///
+/// # Returns
+///
+/// The tables in row order
+pub fn chunks(data: &TableData, size: usize) -> Vec<TableData> {
+    let rows: Vec<&Vec<Cell>> = data
+        .rows
+        .iter()
+        .filter(|row| row.iter().any(|c| !c.is_empty()))
+        .collect();
+    let table = |rows: &[&Vec<Cell>]| TableData {
+        headers: data.headers.clone(),
+        rows: rows.iter().map(|row| row.to_vec()).collect(),
+        original_column_indices: data.original_column_indices.clone(),
+    };
+    if rows.is_empty() {
+        return vec![table(&[])];
+    }
+    rows.chunks(size.max(1)).map(table).collect()
+}
+
+/// Splits a table into tables of at most `size` data rows for one file each,
+/// see [`chunks`].
+///
+/// # Arguments
+///
+/// * `data` - The processed table
+/// * `size` - Maximum number of rows per file, at least 1
+/// * `template` - Path of the files, `{n}` is replaced by the 1-based chunk number
+///
+/// # Returns
+///
+/// - `Ok(Vec<(String, TableData)>)` with the path and table of each chunk
+/// - `Err(String)` if the template has no `{n}`
+pub fn chunk_files(
+    data: &TableData,
+    size: usize,
+    template: &str,
+) -> Result<Vec<(String, TableData)>, String> {
+    if !template.contains(NUMBER_PLACEHOLDER) {
+        return Err(format!(
+            "Invalid --chunk-out '{}': expected a path with {}",
+            template, NUMBER_PLACEHOLDER
+        ));
+    }
+    Ok(chunks(data, size)
+        .into_iter()
+        .enumerate()
+        .map(|(i, table)| {
+            let path = template.replace(NUMBER_PLACEHOLDER, &(i + 1).to_string());
+            (path, table)
+        })
+        .collect())
+}
+
+/// Turns a cell value into a file name: path separators and control characters
+/// become `_`, as do empty values and the names `.` and `..`.
+fn file_name(value: &str) -> String {
+    let name: String = value
+        .trim()
+        .chars()
+        .map(|c| {
+            if c == '/' || c == '\\' || c.is_control() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    match name.as_str() {
+        "" | "." | ".." => "_".to_string(),
+        _ => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(file_name("web-1"), "web-1");
+        assert_eq!(file_name(" a/b\\c "), "a_b_c");
+        assert_eq!(file_name(""), "_");
+        assert_eq!(file_name(".."), "_");
+    }
+
+    #[test]
+    fn test_split_by() {
+        let data = TableData {
+            headers: ["ns", "pod"].map(Cell::from).to_vec(),
+            rows: [["dev", "a"], ["prod", "b"], ["", ""], ["dev", "c"]]
+                .iter()
+                .map(|row| row.map(Cell::from).to_vec())
+                .collect(),
+            original_column_indices: vec![0, 1],
+        };
+        let tables = split_by(&data, 0, "out/{value}.txt", Lang::En).unwrap();
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables[0].0, "out/dev.txt");
+        assert_eq!(tables[0].1.headers, ["ns", "pod"]);
+        assert_eq!(tables[0].1.rows, [["dev", "a"], ["dev", "c"]]);
+        assert_eq!(tables[1].0, "out/prod.txt");
+        assert_eq!(tables[1].1.rows, [["prod", "b"]]);
+
+        assert!(split_by(&data, 2, "{value}", Lang::En).is_err());
+        assert!(split_by(&data, 0, "out.txt", Lang::En).is_err());
+    }
+
+    #[test]
+    fn test_chunks() {
+        let data = TableData {
+            headers: vec!["n".into()],
+            rows: ["1", "2", "", "3", "4", "5"]
+                .iter()
+                .map(|&n| vec![Cell::from(n)])
+                .collect(),
+            original_column_indices: vec![0],
+        };
+        let tables = chunks(&data, 2);
+        assert_eq!(tables.len(), 3);
+        assert!(tables.iter().all(|table| table.headers == ["n"]));
+        assert_eq!(tables[1].rows, [["3"], ["4"]]);
+        assert_eq!(tables[2].rows, [["5"]]);
+
+        let files = chunk_files(&data, 5, "part{n}.csv").unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].0, "part1.csv");
+        assert!(chunk_files(&data, 5, "part.csv").is_err());
+
+        let empty = TableData {
+            rows: Vec::new(),
+            ..data
+        };
+        assert_eq!(chunks(&empty, 2).len(), 1);
+    }
+}
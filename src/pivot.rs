@@ -0,0 +1,266 @@
+//! Cross tabulation (`-pivot`): the distinct values of one column become columns.
+
+use crate::cell::Cell;
+use crate::numeric::{NumFmt, format_decimal, parse_localized};
+use crate::processor::TableData;
+use std::collections::HashMap;
+
+/// How the values falling into one cell of a pivot table are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    /// Number of rows, or of non-empty values with a value column
+    Count,
+    Sum,
+    Min,
+    Max,
+    /// Arithmetic mean
+    Avg,
+    /// First value in input order, also for text
+    First,
+}
+
+impl Aggregate {
+    /// Parses the name of an aggregate function (`agg=...`).
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "count" => Aggregate::Count,
+            "sum" => Aggregate::Sum,
+            "min" => Aggregate::Min,
+            "max" => Aggregate::Max,
+            "avg" | "mean" => Aggregate::Avg,
+            "first" => Aggregate::First,
+            _ => return None,
+        })
+    }
+}
+
+/// A cross tabulation given with `-pivot 'rows=N cols=M value=V agg=FUNC'`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pivot {
+    /// 0-based output column whose values label the rows
+    pub rows: usize,
+    /// 0-based output column whose values become the columns
+    pub cols: usize,
+    /// 0-based output column with the aggregated values, rows are counted if `None`
+    pub value: Option<usize>,
+    pub agg: Aggregate,
+}
+
+impl Pivot {
+    /// Parses a pivot specification like `rows=1 cols=2 value=3 agg=sum`.
+    ///
+    /// The keys are separated by blanks or commas. `value` and `agg` are
+    /// optional: without a value the rows are counted, with one they are summed
+    /// unless `agg` says otherwise (`count`, `sum`, `min`, `max`, `avg`, `first`).
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - The specification as given on the command line (1-based columns)
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Pivot)` if the specification is well-formed
+    /// - `Err(String)` if it is not
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let invalid = || {
+            format!(
+                "Invalid pivot '{}': expected rows=N cols=M [value=V] [agg=count|sum|min|max|avg|first]",
+                spec
+            )
+        };
+        let column = |value: &str| -> Result<usize, String> {
+            match value.trim().parse::<usize>() {
+                Ok(0) => Err("Column numbers must be 1-based".to_string()),
+                Ok(col) => Ok(col - 1),
+                Err(_) => Err(invalid()),
+            }
+        };
+
+        let (mut rows, mut cols, mut value, mut agg) = (None, None, None, None);
+        for part in spec.split([' ', ',']).filter(|part| !part.is_empty()) {
+            let (key, arg) = part.split_once('=').ok_or_else(invalid)?;
+            match key {
+                "rows" => rows = Some(column(arg)?),
+                "cols" => cols = Some(column(arg)?),
+                "value" => value = Some(column(arg)?),
+                "agg" => agg = Some(Aggregate::parse(arg).ok_or_else(invalid)?),
+                _ => return Err(invalid()),
+            }
+        }
+        let (Some(rows), Some(cols)) = (rows, cols) else {
+            return Err(invalid());
+        };
+        let default = if value.is_some() {
+            Aggregate::Sum
+        } else {
+            Aggregate::Count
+        };
+        Ok(Self {
+            rows,
+            cols,
+            value,
+            agg: agg.unwrap_or(default),
+        })
+    }
+
+    /// Returns the largest output column the pivot refers to.
+    pub fn max_column(&self) -> usize {
+        self.rows.max(self.cols).max(self.value.unwrap_or(0))
+    }
+
+    /// Builds the cross table of the rows.
+    ///
+    /// Row and column labels appear in the order of their first occurrence.
+    /// Cells without any row stay empty, as do cells of numeric aggregates
+    /// without a number; `count` counts rows, or non-empty values with a value
+    /// column.
+    ///
+    /// # Arguments
+    ///
+    /// * `headers` - Headers of the table, the header of the row column labels the
+    ///   row labels
+    /// * `rows` - Data rows, separator rows are skipped
+    /// * `numfmt` - Format of the numbers (`-numfmt`)
+    /// * `precision` - Decimal places of computed values (`-precision`)
+    ///
+    /// # Returns
+    ///
+    /// The cross table; it always has headers, the column labels
+    pub fn table(
+        &self,
+        headers: &[Cell],
+        rows: &[Vec<Cell>],
+        numfmt: Option<NumFmt>,
+        precision: Option<usize>,
+    ) -> TableData {
+        fn cell(row: &[Cell], col: usize) -> &str {
+            row.get(col).map_or("", Cell::as_str)
+        }
+        let mut row_labels: Vec<&str> = Vec::new();
+        let mut col_labels: Vec<&str> = Vec::new();
+        let mut row_pos: HashMap<&str, usize> = HashMap::new();
+        let mut col_pos: HashMap<&str, usize> = HashMap::new();
+        let mut values: HashMap<(usize, usize), Vec<&str>> = HashMap::new();
+        for row in rows.iter().filter(|row| row.iter().any(|c| !c.is_empty())) {
+            let (label, column) = (cell(row, self.rows), cell(row, self.cols));
+            let r = *row_pos.entry(label).or_insert_with(|| {
+                row_labels.push(label);
+                row_labels.len() - 1
+            });
+            let c = *col_pos.entry(column).or_insert_with(|| {
+                col_labels.push(column);
+                col_labels.len() - 1
+            });
+            let value = self.value.map_or("", |col| cell(row, col));
+            values.entry((r, c)).or_default().push(value);
+        }
+
+        let rows = row_labels
+            .iter()
+            .enumerate()
+            .map(|(r, label)| {
+                let mut out = vec![Cell::from(*label)];
+                out.extend((0..col_labels.len()).map(|c| match values.get(&(r, c)) {
+                    Some(cells) => self.aggregate(cells, numfmt, precision).into(),
+                    None => Cell::default(),
+                }));
+                out
+            })
+            .collect();
+        let mut headers = vec![headers.get(self.rows).cloned().unwrap_or_default()];
+        headers.extend(col_labels.iter().map(|&label| Cell::from(label)));
+        TableData {
+            original_column_indices: (0..headers.len()).collect(),
+            headers,
+            rows,
+        }
+    }
+
+    /// Combines the values of one cell.
+    fn aggregate(
+        &self,
+        cells: &[&str],
+        numfmt: Option<NumFmt>,
+        precision: Option<usize>,
+    ) -> String {
+        let numbers = || {
+            cells
+                .iter()
+                .filter_map(|c| parse_localized(c.trim(), numfmt))
+        };
+        let number =
+            |value: Option<f64>| value.map_or(String::new(), |v| format_decimal(v, precision));
+        match self.agg {
+            Aggregate::Count if self.value.is_none() => cells.len().to_string(),
+            Aggregate::Count => cells.iter().filter(|c| !c.is_empty()).count().to_string(),
+            Aggregate::Sum => number(numbers().reduce(|a, b| a + b)),
+            Aggregate::Min => number(numbers().reduce(f64::min)),
+            Aggregate::Max => number(numbers().reduce(f64::max)),
+            Aggregate::Avg => {
+                let (sum, n) = numbers().fold((0.0, 0), |(sum, n), v| (sum + v, n + 1));
+                number((n > 0).then(|| sum / n as f64))
+            }
+            Aggregate::First => cells.first().map_or(String::new(), |c| c.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pivot_parse() {
+        let pivot = Pivot::parse("rows=1 cols=2 value=3 agg=avg").unwrap();
+        assert_eq!(pivot.rows, 0);
+        assert_eq!(pivot.cols, 1);
+        assert_eq!(pivot.value, Some(2));
+        assert_eq!(pivot.agg, Aggregate::Avg);
+        assert_eq!(
+            Pivot::parse("rows=2,cols=1,value=3").unwrap().agg,
+            Aggregate::Sum
+        );
+        assert_eq!(Pivot::parse("cols=1 rows=2").unwrap().agg, Aggregate::Count);
+
+        for invalid in [
+            "rows=1",
+            "rows=0 cols=1",
+            "rows=1 cols=x",
+            "rows=1 cols=2 agg=median",
+            "1 2",
+        ] {
+            assert!(Pivot::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_pivot_table() {
+        let headers = ["region", "year", "sales"].map(Cell::from);
+        let rows: Vec<Vec<Cell>> = [
+            ["north", "2023", "10"],
+            ["south", "2023", "5"],
+            ["north", "2024", "7"],
+            ["", "", ""],
+            ["north", "2023", "2.5"],
+        ]
+        .iter()
+        .map(|row| row.map(Cell::from).to_vec())
+        .collect();
+
+        let table = Pivot::parse("rows=1 cols=2 value=3")
+            .unwrap()
+            .table(&headers, &rows, None, None);
+        assert_eq!(table.headers, ["region", "2023", "2024"]);
+        assert_eq!(table.rows, [["north", "12.5", "7"], ["south", "5", ""]]);
+
+        let table = Pivot::parse("rows=2 cols=1")
+            .unwrap()
+            .table(&headers, &rows, None, None);
+        assert_eq!(table.headers, ["year", "north", "south"]);
+        assert_eq!(table.rows, [["2023", "2", "1"], ["2024", "1", ""]]);
+
+        let pivot = Pivot::parse("rows=1 cols=2 value=3 agg=max").unwrap();
+        let table = pivot.table(&headers, &rows, None, Some(2));
+        assert_eq!(table.rows[0], ["north", "10.00", "7.00"]);
+    }
+}
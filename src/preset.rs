@@ -0,0 +1,142 @@
+//! Input presets (`-preset`): splitting and headers of well-known log formats.
+
+use crate::markup::MarkupTable;
+
+/// Headers of the combined log format, the common log format ends after `size`.
+pub const ACCESS_LOG_HEADERS: [&str; 9] = [
+    "host",
+    "ident",
+    "user",
+    "time",
+    "request",
+    "status",
+    "size",
+    "referer",
+    "user_agent",
+];
+
+/// A well-known input format with its own splitting and headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Preset {
+    /// Apache/nginx access log in common or combined log format
+    AccessLog,
+}
+
+impl Preset {
+    /// Splits the input lines into a table with the headers of the preset.
+    ///
+    /// Blank lines are skipped. Fields beyond the known ones (e.g. a forwarded-for
+    /// address appended by nginx) become columns without header.
+    ///
+    /// # Arguments
+    ///
+    /// * `lines` - Input lines, one log record each
+    ///
+    /// # Returns
+    ///
+    /// The headers and rows of the table
+    pub fn parse(self, lines: &[String]) -> MarkupTable {
+        match self {
+            Preset::AccessLog => {
+                let headers = ACCESS_LOG_HEADERS.map(String::from).to_vec();
+                let rows = lines
+                    .iter()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(|line| access_log_fields(line))
+                    .collect();
+                (headers, rows)
+            }
+        }
+    }
+}
+
+/// Splits an access log line at blanks, keeping a `[timestamp]` and `"quoted"`
+/// fields like the request and the user agent in one cell.
+///
+/// Brackets and quotes are removed, `\"` and `\\` in quoted fields are a quote
+/// and a backslash; other escapes are kept as logged.
+fn access_log_fields(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let Some(first) = chars.next() else {
+            return fields;
+        };
+        let mut field = String::new();
+        match first {
+            '[' => {
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    field.push(c);
+                }
+            }
+            '"' => {
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next_if(|&c| c == '"' || c == '\\') {
+                            Some(c) => field.push(c),
+                            None => field.push('\\'),
+                        },
+                        c => field.push(c),
+                    }
+                }
+            }
+            c => {
+                field.push(c);
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                    field.push(c);
+                }
+            }
+        }
+        fields.push(field);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_access_log_fields() {
+        let line = r#"203.0.113.7 - frank [10/Oct/2024:13:55:36 -0700] "GET /a b.gif HTTP/1.1" 200 2326 "http://example.com/" "Mozilla/5.0 (X11; \"Linux\")""#;
+        assert_eq!(
+            access_log_fields(line),
+            [
+                "203.0.113.7",
+                "-",
+                "frank",
+                "10/Oct/2024:13:55:36 -0700",
+                "GET /a b.gif HTTP/1.1",
+                "200",
+                "2326",
+                "http://example.com/",
+                r#"Mozilla/5.0 (X11; "Linux")"#,
+            ]
+        );
+        assert_eq!(
+            access_log_fields(r#"h "\x16\x03" -"#),
+            ["h", r"\x16\x03", "-"]
+        );
+        assert!(access_log_fields("  ").is_empty());
+    }
+
+    #[test]
+    fn test_preset_parse() {
+        let lines = [
+            r#"::1 - - [10/Oct/2024:13:55:36 +0000] "GET / HTTP/1.1" 304 -"#,
+            "",
+        ]
+        .map(String::from);
+        let (headers, rows) = Preset::AccessLog.parse(&lines);
+        assert_eq!(headers.len(), 9);
+        assert_eq!(headers[4], "request");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0][4], "GET / HTTP/1.1");
+        assert_eq!(rows[0].len(), 7);
+    }
+}
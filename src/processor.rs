@@ -1,391 +1,3651 @@
-use crate::args::AppArgs;
+use crate::cell::Cell;
+use crate::chart::{BarSpec, sparkline};
+use crate::datetime::DateCol;
+use crate::i18n::Lang;
+use crate::intermediate::{self, RecordedColumns};
+use crate::logfmt;
+use crate::markup;
+use crate::numeric::{
+    Delta, NumFmt, NumStyle, Number, color_signed, format_decimal, format_duration, humanize,
+    parse_duration, parse_localized, parse_number, restyle_number,
+};
+use crate::parallel;
+use crate::pivot::Pivot;
+use crate::preset::Preset;
+use crate::sample::{Rng, sample_rows};
+use crate::transform::{
+    CaseOp, Insert, Rename, Replace, Split, Transform, parse_case_ops, parse_renames,
+    redact_fields, redact_line, redact_table, redaction_patterns, rename_headers,
+};
 use regex::Regex;
-use std::cmp::Ordering;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::cmp::{Ordering, Reverse};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::time::Duration;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// A problem with the input that rcol worked around instead of failing.
+///
+/// Collected while a [`Pipeline`] processes the input, see [`Pipeline::take_warnings`].
+/// With `-strict` the first warning fails the processing instead.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Warning {
+    /// A row with `found` instead of `expected` cells was dropped (`-ragged drop`);
+    /// `line` is its input line if the rows were split from text lines, `row` its
+    /// 1-based number among the rows
+    DroppedRow {
+        line: Option<usize>,
+        row: usize,
+        found: usize,
+        expected: usize,
+    },
+    /// `-skip-bad-rows` dropped `dropped` of `total` rows
+    SkippedRows { dropped: usize, total: usize },
+    /// An out-of-range column option was ignored (`-lenient`)
+    IgnoredColumn { problem: String },
+    /// An out-of-range column option was clamped to the 1-based `column` (`-lenient`)
+    ClampedColumn { problem: String, column: usize },
+    /// The `-cmd` failed with `error` and runs again after `wait` (`-retries`)
+    Retrying {
+        error: String,
+        wait: Duration,
+        attempt: u32,
+        retries: u32,
+    },
+    /// The config file has a `key` that is no rcol option
+    UnknownConfigKey { key: String },
+}
+
+impl Warning {
+    /// Returns the warning as text in `lang`, without the `Warning:` prefix.
+    pub fn message(&self, lang: Lang) -> String {
+        match self {
+            Warning::DroppedRow {
+                line: Some(line),
+                found,
+                expected,
+                ..
+            } => lang.tr("dropped-line", &[line, found, expected]),
+            Warning::DroppedRow {
+                line: None,
+                row,
+                found,
+                expected,
+            } => lang.tr("dropped-row", &[row, found, expected]),
+            Warning::SkippedRows { dropped, total } => lang.tr("skipped-rows", &[dropped, total]),
+            Warning::IgnoredColumn { problem } => lang.tr("ignoring-it", &[problem]),
+            Warning::ClampedColumn { problem, column } => {
+                lang.tr("using-column", &[problem, column])
+            }
+            Warning::Retrying {
+                error,
+                wait,
+                attempt,
+                retries,
+            } => lang.tr("retrying", &[error, &wait.as_millis(), attempt, retries]),
+            Warning::UnknownConfigKey { key } => lang.tr("unknown-config-key", &[key]),
+        }
+    }
+}
 
 /// Represents processed tabular data with headers and rows.
 ///
 /// Contains the table structure after processing, including selected and reordered columns.
 /// The `original_column_indices` field tracks which original columns were selected,
-/// which is useful for column numbering display.
-#[derive(Debug)]
+/// which is useful for column numbering display. Columns that aren't read from the
+/// input (`-insert`) have the index [`NO_INPUT_COLUMN`].
+#[derive(Debug, Clone)]
 pub struct TableData {
-    pub headers: Vec<String>,
-    pub rows: Vec<Vec<String>>,
+    pub headers: Vec<Cell>,
+    pub rows: Vec<Vec<Cell>>,
     pub original_column_indices: Vec<usize>,
 }
 
-/// Processes input lines according to application arguments to produce table data.
-///
-/// Executes the complete data processing pipeline:
-/// 1. Filters lines based on regex pattern (if specified)
-/// 2. Splits lines into columns using the specified separator
-/// 3. Handles header extraction or application
-/// 4. Selects and reorders columns based on column specifications
-/// 5. Sorts rows by specified column (if requested)
-/// 6. Groups rows by specified column with optional value hiding (if requested)
-///
-/// # Arguments
-///
-/// * `lines` - Raw input lines to process
-/// * `args` - Application arguments specifying how to process the data
-///
-/// # Returns
-///
-/// - `Ok(TableData)` containing the processed table structure
-/// - `Err(String)` if processing fails (invalid regex, column specs, etc.)
-///
-/// # Processing Details
-///
-/// - **Filtering**: Lines not matching the filter regex are excluded
-/// - **Headers**: Determined by `-header`, `-nhl`, or first line default
-/// - **Column Selection**: Supports ranges (1:3) and individual columns (1 2 5)
-/// - **Sorting**: Numeric sort if values are numbers, otherwise lexicographic
-/// - **Grouping**: Inserts separator rows between groups, hides repeated values unless `-gcolval`
-pub fn process_input(lines: Vec<String>, args: &AppArgs) -> Result<TableData, String> {
-    let mut rows: Vec<Vec<String>> = Vec::new();
-    let mut headers: Vec<String> = Vec::new();
-
-    // 1. Filter lines
-    let filter_regex = if let Some(pattern) = &args.filter {
-        Some(Regex::new(pattern).map_err(|e| format!("Invalid filter regex: {}", e))?)
-    } else {
-        None
-    };
+/// Original column index of a column that isn't read from the input, e.g. of `-insert`.
+pub const NO_INPUT_COLUMN: usize = usize::MAX;
+
+impl TableData {
+    /// Counts the data rows, without the separator rows between groups.
+    pub fn data_rows(&self) -> usize {
+        self.rows.iter().filter(|row| !is_separator(row)).count()
+    }
+}
+
+/// Which rows a `-join` keeps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum JoinType {
+    /// Only rows with a matching row in both tables
+    #[default]
+    Inner,
+    /// All rows of the input, with empty cells where the joined table has no match
+    Left,
+    /// All rows of the joined table, with empty cells where the input has no match
+    Right,
+}
+
+/// What happens to rows with more or fewer cells than the header (`-ragged`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Ragged {
+    /// Fill missing cells with empty cells (or `-fill` defaults), extra cells become additional columns
+    #[default]
+    Pad,
+    /// Like `pad`, but extra cells are removed
+    Truncate,
+    /// Like `pad`, but extra cells are joined into the last column with the separator
+    MergeLast,
+    /// Drop the row with a warning on stderr
+    Drop,
+}
+
+/// Which white space is removed from the input lines before they are split (`-trim`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Trim {
+    /// Keep the lines as they are, e.g. the indentation of tree output
+    None,
+    /// Remove leading white space
+    Left,
+    /// Remove trailing white space
+    Right,
+    /// Remove leading and trailing white space
+    #[default]
+    Both,
+}
+
+impl Trim {
+    /// Removes the white space selected by `self` from `line`.
+    pub fn apply(self, line: &str) -> &str {
+        match self {
+            Trim::None => line,
+            Trim::Left => line.trim_start(),
+            Trim::Right => line.trim_end(),
+            Trim::Both => line.trim(),
+        }
+    }
+}
+
+/// Options controlling how input is turned into [`TableData`].
+///
+/// The processing subset of the command line options; [`AppArgs`](crate::args::AppArgs)
+/// converts into it with `ProcessOptions::from(&args)`. Column numbers are 1-based
+/// like on the command line.
+#[derive(Debug, Clone)]
+pub struct ProcessOptions {
+    /// Custom header line, split with `sep`
+    pub header: Option<String>,
+    /// Input separator, `auto` detects it from the input
+    pub sep: String,
+    /// Input separator regex, takes precedence over `sep`
+    pub sep_regex: Option<String>,
+    /// Treat consecutive whitespace as a single separator
+    pub mb: bool,
+    /// Split lines into at most this many cells, the last one keeps the rest of the line
+    pub max_cols: Option<usize>,
+    /// Don't split at separators within single or double quotes
+    pub quoted: bool,
+    /// Keep the quotes in cells of `quoted` input
+    pub keep_quotes: bool,
+    /// Expand tabs to spaces with tab stops every this many columns before splitting
+    pub expand_tabs: Option<usize>,
+    /// White space removed from the lines before splitting
+    pub trim: Trim,
+    /// Remove leading and trailing white space from every cell
+    pub trim_cells: bool,
+    /// Skip lines starting with this marker
+    pub comment: Option<String>,
+    /// Turn blank lines between data rows into separator rows instead of dropping them
+    pub keep_blank: bool,
+    /// Drop rows whose selected cells are all empty, e.g. from lines of separators only
+    pub drop_empty_rows: bool,
+    /// Drop output columns without any non-empty data cell
+    pub drop_empty_cols: bool,
+    /// Lay out the lines as items of a list in this many columns, like `column` without `-t`
+    pub fill_cols: Option<usize>,
+    /// Lay out the lines as items of a list in as many columns as fit into this
+    /// width (with two spaces between columns), unless `fill_cols` is set
+    pub fill_width: Option<usize>,
+    /// Fill the `fill_cols` columns across, then down, instead of down, then across
+    pub fill_across: bool,
+    /// Output no header row, the table consists of data rows only
+    pub hide_header: bool,
+    /// Keep only lines matching any of these regexes
+    pub filter: Vec<String>,
+    /// Drop lines matching any of these regexes
+    pub filter_not: Vec<String>,
+    /// Column conditions like `3>100` that every row must satisfy
+    pub r#where: Vec<String>,
+    /// Regexes whose matches are replaced with `***`
+    pub redact: Vec<String>,
+    /// Names of built-in redaction pattern sets
+    pub redact_preset: Vec<String>,
+    /// Output column to sort by
+    pub sortcol: Option<usize>,
+    /// Sort descending
+    pub sort_desc: bool,
+    /// Sort by IP address instead of text
+    pub sort_ip: bool,
+    /// Format of numbers with grouping separators or decimal commas, for sorting
+    pub numfmt: Option<NumFmt>,
+    /// Style the number cells are rewritten in
+    pub numfmt_out: Option<NumStyle>,
+    /// Reverse the row order
+    pub reverse: bool,
+    /// Keep a uniform random sample of this many data rows
+    pub sample: Option<usize>,
+    /// Put the data rows into random order
+    pub shuffle: bool,
+    /// Seed of `sample` and `shuffle`, for the same rows on every run
+    pub seed: Option<u64>,
+    /// Split, filter and replace the rows on all cores (`parallel` feature)
+    pub parallel: bool,
+    /// Output column to group by
+    pub gcol: Option<usize>,
+    /// Compare umlauts as their transliterations when sorting and grouping
+    pub normalize_umlauts: bool,
+    /// Clamp out-of-range `sortcol`/`gcol` instead of failing
+    pub lenient: bool,
+    /// Keep repeated values in the group column
+    pub gcolval: bool,
+    /// Append a `count` column with the number of rows of each group
+    pub gcount: bool,
+    /// Collapse identical adjacent rows into one and append their number as `count` column
+    pub uniq_c: bool,
+    /// Replace the table with the frequencies of the values of this output column
+    pub freq: Option<usize>,
+    /// Width of the bar column of `freq`, no bar column if `None`
+    pub freq_bar: Option<usize>,
+    /// Replace the table with statistics of its numeric columns
+    pub stats: bool,
+    /// Replace the table with a cross tabulation `rows=N cols=M [value=V] [agg=FUNC]`
+    pub pivot: Option<String>,
+    /// The first line is data, not a header
+    pub nhl: bool,
+    /// Discard the first line
+    pub rh: bool,
+    /// Input format, `text` (default), `rcol`, `md`, `html` or `logfmt`
+    pub in_format: Option<String>,
+    /// Split the lines like a well-known log format and name the columns
+    pub preset: Option<Preset>,
+    /// Read a box-drawn table: drop border and separator lines, split at vertical rules
+    pub unbox: bool,
+    /// Column specifications like `3`, `1:4` or `5:2`
+    pub columns: Vec<String>,
+    /// New headers `COLUMN=NAME,...` for output columns given by number or header name
+    pub rename: Vec<String>,
+    /// Constant columns `N=HEADER:VALUE` inserted at output column N
+    pub insert: Vec<String>,
+    /// Input columns `N:DELIMITER` split into one column per piece before the columns are selected
+    pub split: Vec<String>,
+    /// Substitutions `N:s/REGEX/REPLACEMENT/[gi]` in output column N
+    pub replace: Vec<String>,
+    /// String operations like `1:upper,2:title` on output columns (`upper`, `lower`, `title`, `trim`)
+    pub transform: Vec<String>,
+    /// Date columns `N:FORMAT[→FORMAT]`, sorted chronologically and reformatted
+    pub datecol: Vec<String>,
+    /// Duration columns `N:seconds` or `N:hms` rewritten as seconds or like `1h02m`
+    pub duration: Vec<String>,
+    /// Defaults `N:TEXT` for cells missing in input column N of ragged rows
+    pub fill: Vec<String>,
+    /// Drop malformed rows (wrong number of cells, non-numeric `delta` operands)
+    /// and report their count on stderr
+    pub skip_bad_rows: bool,
+    /// Fail on rows with a different number of cells and on columns beyond the input
+    pub strict: bool,
+    /// What happens to rows with a different number of cells than the header
+    pub ragged: Ragged,
+    /// Key columns `N[:M]` of a `-join`, N of the input and M (default N) of the
+    /// joined table; column 1 if not set
+    pub join_on: Option<String>,
+    /// Which rows a `-join` keeps
+    pub join_type: JoinType,
+    /// Computed columns like `3-2` or `3-2:percent`, appended after the selected columns
+    pub delta: Vec<String>,
+    /// Decimal places of computed values
+    pub precision: Option<usize>,
+    /// Decimal places `N:PLACES` the numbers of output column N are rounded to
+    pub round: Vec<String>,
+    /// Output columns `N[:iec]` whose numbers are written with unit prefixes like `1.2G`
+    pub humanize: Vec<String>,
+    /// Bar charts `N[:MAX]` replacing the numbers of output column N
+    pub bar: Vec<String>,
+    /// Keep the numbers in front of the `bar` charts
+    pub bar_values: bool,
+    /// Output columns whose comma-separated series are drawn as sparklines
+    pub spark: Vec<usize>,
+    /// Color the signs of computed columns with ANSI codes
+    pub color: bool,
+    /// Language of warnings
+    pub lang: Lang,
+}
+
+impl Default for ProcessOptions {
+    fn default() -> Self {
+        Self {
+            header: None,
+            sep: " ".to_string(),
+            sep_regex: None,
+            max_cols: None,
+            quoted: false,
+            keep_quotes: false,
+            expand_tabs: None,
+            trim: Trim::Both,
+            trim_cells: false,
+            comment: None,
+            keep_blank: false,
+            drop_empty_rows: false,
+            drop_empty_cols: false,
+            fill_cols: None,
+            fill_width: None,
+            fill_across: false,
+            hide_header: false,
+            mb: false,
+            filter: Vec::new(),
+            filter_not: Vec::new(),
+            r#where: Vec::new(),
+            redact: Vec::new(),
+            redact_preset: Vec::new(),
+            sortcol: None,
+            sort_desc: false,
+            sort_ip: false,
+            numfmt: None,
+            numfmt_out: None,
+            reverse: false,
+            sample: None,
+            shuffle: false,
+            seed: None,
+            parallel: false,
+            gcol: None,
+            normalize_umlauts: false,
+            lenient: false,
+            gcolval: false,
+            gcount: false,
+            uniq_c: false,
+            freq: None,
+            freq_bar: None,
+            stats: false,
+            pivot: None,
+            nhl: false,
+            rh: false,
+            in_format: None,
+            preset: None,
+            unbox: false,
+            columns: Vec::new(),
+            rename: Vec::new(),
+            insert: Vec::new(),
+            split: Vec::new(),
+            replace: Vec::new(),
+            transform: Vec::new(),
+            datecol: Vec::new(),
+            duration: Vec::new(),
+            fill: Vec::new(),
+            skip_bad_rows: false,
+            strict: false,
+            ragged: Ragged::Pad,
+            join_on: None,
+            join_type: JoinType::Inner,
+            delta: Vec::new(),
+            precision: None,
+            round: Vec::new(),
+            humanize: Vec::new(),
+            bar: Vec::new(),
+            bar_values: false,
+            spark: Vec::new(),
+            color: false,
+            lang: Lang::En,
+        }
+    }
+}
+
+/// Processes input lines according to application arguments to produce table data.
+///
+/// Executes the complete data processing pipeline:
+/// 1. Filters lines based on regex patterns (if specified)
+/// 2. Splits lines into columns using the specified separator and applies `-where` conditions
+/// 3. Handles header extraction or application and redacts cells (if requested)
+/// 4. Selects and reorders columns based on column specifications (filling missing cells
+///    with their `-fill` default), applies `-replace` substitutions, `-transform`
+///    string operations and `-duration` normalization and appends `-delta` columns; with `-skip-bad-rows` malformed rows are dropped first
+/// 5. Sorts rows by specified column, ascending or descending, and reverses them (if requested)
+/// 6. Groups rows by specified column with optional value hiding (if requested)
+///
+/// # Arguments
+///
+/// * `lines` - Raw input lines to process
+/// * `opts` - Options specifying how to process the data
+///
+/// # Returns
+///
+/// - `Ok(TableData)` containing the processed table structure
+/// - `Err(String)` if processing fails (invalid regex, column specs, out-of-range
+///   `-sortcol`/`-gcol` without `-lenient`, any [`Warning`] with `-strict`, etc.)
+///
+/// Warnings are dropped, process the input with a [`Pipeline`] to get them.
+///
+/// # Processing Details
+///
+/// - **Filtering**: Lines not matching any `-filter` regex, or matching any `-filter-not`
+///   regex, are excluded. The header line is never filtered
+/// - **Conditions**: Rows must satisfy every `-where` condition, evaluated on input columns
+/// - **Transforms**: Custom [`Transform`]s of a [`Pipeline`] run on the input columns
+/// - **Redaction**: Matches of `-redact` / `-redact-preset` patterns are replaced with `***`,
+///   on whole input lines and the `-header` text before they are split
+/// - **Headers**: Determined by `-header`, `-nhl`, or first line default
+/// - **Joining**: The columns of a [`Pipeline::join`]ed table follow the input columns
+///   of the rows with the same `-on` key, before the columns are split and selected
+/// - **Splitting**: `-split` replaces input columns with one column per piece of their
+///   cells before the columns are selected, so the pieces can be selected and sorted
+/// - **Column Selection**: Supports ranges (1:3) and individual columns (1 2 5).
+///   `-drop-empty-rows` drops rows whose selected cells are all empty, `-drop-empty-cols`
+///   finally drops the columns whose data cells are all empty
+/// - **Renaming**: `-rename` replaces the headers of selected columns, found by output
+///   column number or by their header
+/// - **Inserted columns**: `-insert` adds constant columns after renaming, all later
+///   column numbers (`-replace`, `-sortcol`, ...) count them
+/// - **Deltas**: Computed after column selection, so `-delta` and `-sortcol` can refer to them
+/// - **Malformed rows**: With `-skip-bad-rows`, rows with a different number of cells than
+///   the header (or, without header, than most rows) are dropped unless `-fill` covers the
+///   missing cells, as are rows whose `-delta` operands are not numbers. The number of
+///   dropped rows is reported on stderr. With `-strict` such rows, and selected columns
+///   beyond the input, are errors naming the input line. `-ragged` truncates extra
+///   cells, merges them into the last column or drops such rows with a warning
+/// - **Sampling**: `-sample` keeps a random sample of the selected rows and `-shuffle`
+///   puts them into random order, both before sorting
+/// - **Sorting**: Numeric sort if values are numbers (with `-numfmt` also like `1.234,56`)
+///   or durations (`5m30s`, `01:02:03`), chronological for `-datecol` columns, by address
+///   with `-sort-ip`, otherwise lexicographic on the collation key. `-precision N:PLACES`,
+///   `-humanize` and `-numfmt-out` rewrite the numbers after sorting
+/// - **Counting**: `-uniq-c` collapses identical adjacent rows before sorting and appends
+///   their number as `count` column
+/// - **Grouping**: Inserts separator rows without cells between groups, hides repeated
+///   values unless `-gcolval`; group changes are detected on the collation key. `-gcount`
+///   appends the number of rows of the group in a `count` column
+pub fn process_input(lines: Vec<String>, opts: &ProcessOptions) -> Result<TableData, String> {
+    Pipeline::new(opts.clone()).process(lines)
+}
+
+/// Processes a table that is already split into cells.
+///
+/// Runs the same pipeline as [`process_input`] on pre-split data: rows are filtered
+/// (`filter` patterns are matched against the cells joined with a blank), redacted,
+/// and columns are selected, sorted and grouped. `header` is split with `sep`;
+/// `nhl`, `rh` and `in_format` don't apply.
+///
+/// # Arguments
+///
+/// * `headers` - Header cells (may be empty)
+/// * `rows` - Data rows
+/// * `opts` - Options specifying how to process the data
+///
+/// # Returns
+///
+/// - `Ok(TableData)` containing the processed table structure
+/// - `Err(String)` if processing fails, see [`process_input`]
+pub fn process_rows(
+    headers: Vec<Cell>,
+    rows: Vec<Vec<Cell>>,
+    opts: &ProcessOptions,
+) -> Result<TableData, String> {
+    Pipeline::new(opts.clone()).process_rows(headers, rows)
+}
+
+/// Processing options together with custom [`Transform`]s.
+///
+/// Lets embedding applications hook their own cell or row mutations (e.g. lookups
+/// against internal systems) into the processing without forking the processor.
+/// Transforms run in the order they were added, after filtering and before
+/// redaction, column selection, sorting and grouping.
+///
+/// # Examples
+///
+/// ```
+/// use rcol::cell::Cell;
+/// use rcol::processor::{Pipeline, ProcessOptions};
+/// use rcol::transform::Transform;
+///
+/// struct Upper;
+///
+/// impl Transform for Upper {
+///     fn transform_cell(&self, _col: usize, cell: &mut Cell) {
+///         *cell = cell.to_uppercase().into();
+///     }
+/// }
+///
+/// let mut pipeline = Pipeline::new(ProcessOptions::default());
+/// pipeline.add_transform(Box::new(Upper));
+/// let table = pipeline.process(vec!["Name".into(), "alice".into()]).unwrap();
+/// assert_eq!(table.rows[0], vec!["ALICE"]);
+/// ```
+pub struct Pipeline {
+    options: ProcessOptions,
+    transforms: Vec<Box<dyn Transform>>,
+    /// Lines of the table joined to the input (`-join`)
+    join: Option<Vec<String>>,
+    warnings: RefCell<Vec<Warning>>,
+    /// Column types and widths of the last `-in rcol` input
+    recorded: RefCell<RecordedColumns>,
+}
+
+impl Pipeline {
+    /// Creates a pipeline without transforms.
+    pub fn new(options: ProcessOptions) -> Self {
+        Self {
+            options,
+            transforms: Vec::new(),
+            join: None,
+            warnings: RefCell::new(Vec::new()),
+            recorded: RefCell::default(),
+        }
+    }
+
+    /// Returns the column types and widths recorded in the `-in rcol` input
+    /// processed last, to be applied to the format options of its table.
+    pub fn recorded_columns(&self) -> RecordedColumns {
+        self.recorded.borrow().clone()
+    }
+
+    /// Returns the warnings of the input processed so far and forgets them.
+    ///
+    /// Processing doesn't print warnings, the caller decides whether to show them
+    /// (rcol prints them on stderr unless `-quiet`).
+    pub fn take_warnings(&self) -> Vec<Warning> {
+        self.warnings.take()
+    }
+
+    /// Checks the options without any input: the syntax of the column and other
+    /// specifications, the regexes and the numeric limits.
+    ///
+    /// Errors found here are mistakes on the command line. [`Pipeline::process`]
+    /// reports them as well, together with errors that depend on the input, such
+    /// as a `-sortcol` beyond the columns of the table.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if the options can be used
+    /// - `Err(String)` describing the first invalid option
+    pub fn validate(&self) -> Result<(), String> {
+        let opts = &self.options;
+        check_limits(opts)?;
+        RowFilter::new(opts)?;
+        separator_regex::<&str>(opts, &[])?;
+        redaction_patterns(opts)?;
+        Specs::parse(opts).map(|_| ())
+    }
+
+    /// Keeps the warnings of a processed table, or with `-strict` fails with the first.
+    fn report(&self, warnings: Vec<Warning>) -> Result<(), String> {
+        match warnings.first() {
+            Some(warning) if self.options.strict => {
+                Err(format!("{} (--strict)", warning.message(self.options.lang)))
+            }
+            _ => {
+                self.warnings.borrow_mut().extend(warnings);
+                Ok(())
+            }
+        }
+    }
+
+    /// Joins the rows of another table to the input, matched by the key columns of
+    /// `-on` (see [`JoinType`]).
+    ///
+    /// The lines are split like the input; `-filter` and `-where` don't apply to
+    /// them. The columns of the joined table, without its key column, follow the
+    /// input columns and can be selected by their numbers after them.
+    pub fn join(&mut self, lines: Vec<String>) -> &mut Self {
+        self.join = Some(lines);
+        self
+    }
+
+    /// Appends a transform to the pipeline.
+    pub fn add_transform(&mut self, transform: Box<dyn Transform>) -> &mut Self {
+        self.transforms.push(transform);
+        self
+    }
+
+    /// Returns whether rows can be processed independently of each other.
+    ///
+    /// This is the case unless the rows are sorted, grouped, reversed, sampled or
+    /// shuffled, the separator is detected from the input (`-sep auto`), the rows
+    /// come in `-in rcol`, `md`, `html` or `logfmt` format, a `-preset` or
+    /// box-drawn (`-unbox`), or `-skip-bad-rows`, `-fill-cols`, `-uniq-c`, `-freq`,
+    /// `-stats`, `-pivot`, `-bar`, `-split`, `-drop-empty-cols` or a [`join`](Pipeline::join)
+    /// needs to see all rows. Input of such a pipeline can be processed in chunks
+    /// as it arrives, see [`Pipeline::chunk_header`].
+    pub fn streams(&self) -> bool {
+        self.options.sortcol.is_none() && self.independent_rows()
+    }
+
+    /// Returns whether the input can be processed in chunks whose rows are merged
+    /// afterwards (`-low-mem`).
+    ///
+    /// Like [`Pipeline::streams`], but the rows may be sorted: every chunk is
+    /// sorted on its own and the chunks are merged with [`Pipeline::compare_rows`].
+    /// That needs the values of the sort column as they were sorted, so nothing
+    /// may rewrite them after sorting (`-datecol`, `-round`, `-humanize`,
+    /// `-numfmt-out`, `-spark` or `-color` on computed columns).
+    pub fn merges(&self) -> bool {
+        let opts = &self.options;
+        let rewritten = !opts.datecol.is_empty()
+            || !opts.round.is_empty()
+            || !opts.humanize.is_empty()
+            || opts.numfmt_out.is_some()
+            || !opts.spark.is_empty()
+            || (opts.color && !opts.delta.is_empty());
+        self.independent_rows() && (opts.sortcol.is_none() || !rewritten)
+    }
+
+    /// Returns whether a row of the output depends on other rows than its own,
+    /// apart from sorting; see [`Pipeline::streams`].
+    fn independent_rows(&self) -> bool {
+        let opts = &self.options;
+        opts.gcol.is_none()
+            && !opts.reverse
+            && opts.sample.is_none()
+            && !opts.shuffle
+            && !opts.skip_bad_rows
+            && !opts.uniq_c
+            && opts.freq.is_none()
+            && !opts.stats
+            && opts.pivot.is_none()
+            && opts.bar.is_empty()
+            && opts.split.is_empty()
+            && !opts.drop_empty_cols
+            && !opts.unbox
+            && !lays_out_list(opts)
+            && opts.sep != "auto"
+            && opts.in_format.as_deref().is_none_or(|f| f == "text")
+            && opts.preset.is_none()
+            && self.join.is_none()
+    }
+
+    /// Compares two processed rows in the order of `-sortcol`, `-sort-desc` and
+    /// `-sort-ip`, e.g. to merge chunks that were sorted one by one.
+    ///
+    /// Rows compare equal without `-sortcol`. A sort column beyond the row is
+    /// clamped to its last cell like with `-lenient`; without it processing
+    /// failed already.
+    pub fn compare_rows(&self, a: &[Cell], b: &[Cell]) -> Ordering {
+        let opts = &self.options;
+        let Some(col) = opts.sortcol else {
+            return Ordering::Equal;
+        };
+        fn cell(row: &[Cell], col: usize) -> &str {
+            let idx = col.saturating_sub(1).min(row.len().saturating_sub(1));
+            row.get(idx).map_or("", Cell::as_str)
+        }
+        let (a, b) = (cell(a, col), cell(b, col));
+        if opts.sort_ip {
+            return compare_parsed(&ip_sort_key(a), &ip_sort_key(b), opts.sort_desc);
+        }
+        let ord = compare_cells(a, b, opts);
+        if opts.sort_desc { ord.reverse() } else { ord }
+    }
+
+    /// Returns the first line of `lines` that is neither blank nor a `-comment`
+    /// line, i.e. the header line of text input that has one.
+    pub fn first_data_line(&self, lines: &[String]) -> Option<String> {
+        lines
+            .iter()
+            .find(|line| !line.trim().is_empty() && !is_comment(line, &self.options))
+            .cloned()
+    }
+
+    /// Prepares the pipeline for the chunks following the first chunk of the input,
+    /// whose first line was `first_line`.
+    ///
+    /// Header handling only applies to the first chunk: the header line is returned
+    /// so it can be put in front of every following chunk, and a line removed with
+    /// `-rh` is not removed again.
+    ///
+    /// # Returns
+    ///
+    /// The header line to prepend to following chunks, if the input has one
+    pub fn chunk_header(&mut self, first_line: Option<String>) -> Option<String> {
+        let opts = &mut self.options;
+        if opts.rh {
+            opts.rh = false;
+            opts.nhl = true;
+            None
+        } else if opts.header.is_none() && !opts.nhl {
+            first_line
+        } else {
+            None
+        }
+    }
+
+    /// Processes raw input lines, see [`process_input`].
+    pub fn process(&self, lines: Vec<String>) -> Result<TableData, String> {
+        let opts = &self.options;
+
+        check_limits(opts)?;
+        let lines = match opts.expand_tabs {
+            Some(width) => lines
+                .into_iter()
+                .map(|line| expand_tabs(&line, width))
+                .collect(),
+            None => lines,
+        };
+
+        // 1. Filter lines
+        let row_filter = RowFilter::new(opts)?;
+
+        // 2. Split lines into columns
+        let sep_regex = separator_regex(opts, &lines)?;
+        let redact = redaction_patterns(opts)?;
+
+        // Input that was already processed by rcol (`-in rcol`) comes pre-split, and
+        // remembers which columns of the original input it consists of. Markdown
+        // and HTML tables are split by their markup, logfmt lines into their keys and
+        // lines of a `-preset` format into its fields.
+        let (headers, rows, source_indices, line_numbers) =
+            match (opts.in_format.as_deref(), opts.preset) {
+                (Some("rcol"), _) => {
+                    let doc = intermediate::decode(&lines)?;
+                    *self.recorded.borrow_mut() = RecordedColumns::new(&doc);
+                    let rows = row_filter.apply(doc.rows);
+                    (doc.headers, rows, Some(doc.original_column_indices), None)
+                }
+                (Some(format @ ("md" | "html")), _) => {
+                    let (mut headers, mut rows) = markup_cells(if format == "md" {
+                        markup::parse_markdown(&lines)?
+                    } else {
+                        markup::parse_html(&lines)?
+                    });
+                    if opts.nhl && !headers.is_empty() {
+                        rows.insert(0, std::mem::take(&mut headers));
+                    }
+                    (headers, row_filter.apply(rows), None, None)
+                }
+                (Some("logfmt"), _) => {
+                    let lines: Vec<String> = lines
+                        .into_iter()
+                        .filter(|line| !is_comment(line, opts) && row_filter.line_passes(line))
+                        .map(|line| redact_line(&line, &redact).into_owned())
+                        .collect();
+                    let (headers, mut rows) = markup_cells(logfmt::parse_logfmt(&lines));
+                    rows.retain(|row| row_filter.row_passes(row));
+                    (headers, rows, None, None)
+                }
+                (_, Some(preset)) => {
+                    let lines: Vec<String> = lines
+                        .into_iter()
+                        .filter(|line| !is_comment(line, opts) && row_filter.line_passes(line))
+                        .map(|line| redact_line(&line, &redact).into_owned())
+                        .collect();
+                    let (headers, mut rows) = markup_cells(preset.parse(&lines));
+                    rows.retain(|row| row_filter.row_passes(row));
+                    (headers, rows, None, None)
+                }
+                _ if lays_out_list(opts) => {
+                    let items: Vec<String> = lines
+                        .iter()
+                        .map(|line| opts.trim.apply(line))
+                        .filter(|line| !line.trim().is_empty() && !is_comment(line, opts))
+                        .filter(|line| row_filter.line_passes(line))
+                        .map(|line| redact_line(line, &redact).into_owned())
+                        .collect();
+                    let columns = opts.fill_cols.unwrap_or_else(|| {
+                        let widest = items.iter().map(|item| item.width()).max().unwrap_or(0);
+                        (opts.fill_width.unwrap_or(0) + 2) / (widest + 2)
+                    });
+                    let rows = fill_layout(items, columns.max(1), opts.fill_across);
+                    (Vec::new(), rows, None, None)
+                }
+                _ if opts.unbox => {
+                    let lines: Vec<String> = lines
+                        .into_iter()
+                        .filter(|line| !markup::is_rule_line(line))
+                        .collect();
+                    let (headers, mut rows, _) =
+                        split_lines(&lines, &sep_regex, &row_filter, &redact, opts);
+                    // Group separator rows of a previous `-gcol`
+                    rows.retain(|row| !row.iter().all(|c| c.is_empty()));
+                    (headers, rows, None, None)
+                }
+                _ => {
+                    let (headers, rows, line_numbers) =
+                        split_lines(&lines, &sep_regex, &row_filter, &redact, opts);
+                    (headers, rows, None, Some(line_numbers))
+                }
+            };
+        row_filter.check_columns(&headers, opts)?;
+        self.finish(
+            headers,
+            rows,
+            source_indices,
+            line_numbers.as_deref(),
+            &sep_regex,
+        )
+    }
+
+    /// Processes borrowed input lines like [`process`](Self::process), e.g. the lines
+    /// of a memory-mapped file.
+    ///
+    /// Text split at separators is read in place: every line is copied once into a
+    /// shared buffer that its cells refer to, without an owned line first. Other
+    /// input (`-in`, `-preset`, `-unbox`, `-expand-tabs`, `-fill-cols`) is copied
+    /// into owned lines first.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(TableData)` containing the processed table structure
+    /// - `Err(String)` if processing fails, see [`process_input`]
+    pub fn process_borrowed(&self, lines: &[&str]) -> Result<TableData, String> {
+        let opts = &self.options;
+        let in_place = opts.expand_tabs.is_none()
+            && !matches!(
+                opts.in_format.as_deref(),
+                Some("rcol" | "md" | "html" | "logfmt")
+            )
+            && opts.preset.is_none()
+            && !opts.unbox
+            && !lays_out_list(opts);
+        if !in_place {
+            return self.process(lines.iter().map(|line| line.to_string()).collect());
+        }
+
+        check_limits(opts)?;
+        let row_filter = RowFilter::new(opts)?;
+        let sep_regex = separator_regex(opts, lines)?;
+        let redact = redaction_patterns(opts)?;
+        let (headers, rows, line_numbers) =
+            split_lines(lines, &sep_regex, &row_filter, &redact, opts);
+        row_filter.check_columns(&headers, opts)?;
+        self.finish(headers, rows, None, Some(&line_numbers), &sep_regex)
+    }
+
+    /// Shapes the split input into the table and reports the warnings, the steps
+    /// after splitting of [`process`](Self::process).
+    fn finish(
+        &self,
+        headers: Vec<Cell>,
+        rows: Vec<Vec<Cell>>,
+        source_indices: Option<Vec<usize>>,
+        line_numbers: Option<&[usize]>,
+        sep_regex: &Regex,
+    ) -> Result<TableData, String> {
+        let opts = &self.options;
+        let mut warnings = Vec::new();
+        let mut data = self.shape_table(
+            headers,
+            rows,
+            source_indices,
+            line_numbers,
+            sep_regex,
+            &mut warnings,
+        )?;
+        self.report(warnings)?;
+        // A list laid out in columns has no header row
+        if opts.hide_header || (lays_out_list(opts) && opts.header.is_none()) {
+            data.headers.clear();
+        }
+        Ok(data)
+    }
+
+    /// Processes a table that is already split into cells, see [`process_rows`].
+    pub fn process_rows(
+        &self,
+        headers: Vec<Cell>,
+        rows: Vec<Vec<Cell>>,
+    ) -> Result<TableData, String> {
+        let row_filter = RowFilter::new(&self.options)?;
+        let rows = row_filter.apply(rows);
+        row_filter.check_columns(&headers, &self.options)?;
+        let header: Vec<String> = self.options.header.iter().cloned().collect();
+        let sep_regex = separator_regex(&self.options, &header)?;
+        let mut warnings = Vec::new();
+        let data = self.shape_table(headers, rows, None, None, &sep_regex, &mut warnings)?;
+        self.report(warnings)?;
+        Ok(data)
+    }
+
+    /// Transforms, redacts, selects, sorts and groups split table data.
+    ///
+    /// # Arguments
+    ///
+    /// * `headers` - Header cells
+    /// * `rows` - Filtered data rows
+    /// * `source_indices` - Original input column of every column, if the data was pre-processed by rcol
+    /// * `line_numbers` - Input line of every row, if the rows were split from text lines
+    /// * `sep_regex` - Regex used to split `-header`
+    /// * `warnings` - Collects the problems worked around
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(TableData)` containing the processed table structure
+    /// - `Err(String)` if a redaction pattern or column specification is invalid
+    fn shape_table(
+        &self,
+        mut headers: Vec<Cell>,
+        mut rows: Vec<Vec<Cell>>,
+        source_indices: Option<Vec<usize>>,
+        line_numbers: Option<&[usize]>,
+        sep_regex: &Regex,
+        warnings: &mut Vec<Warning>,
+    ) -> Result<TableData, String> {
+        let opts = &self.options;
+        let redact_regexes = redaction_patterns(opts)?;
+        let Specs {
+            columns,
+            renames,
+            inserts,
+            splits,
+            join_keys,
+            mut fills,
+            deltas,
+            replacements,
+            case_ops,
+            datecols,
+            durations,
+            bars,
+            roundings,
+            humanized,
+            pivot,
+        } = Specs::parse(opts)?;
+
+        if headers.is_empty() && rows.is_empty() {
+            return Ok(TableData {
+                headers,
+                rows,
+                original_column_indices: Vec::new(),
+            });
+        }
+
+        for transform in &self.transforms {
+            for row in rows.iter_mut() {
+                transform.transform_row(&headers, row);
+            }
+        }
+
+        // Redact secrets before anything can reach the output
+        redact_table(&mut headers, &mut rows, &redact_regexes);
+
+        // Rows with more or fewer cells than the header
+        let width = match &opts.header {
+            Some(header) if headers.is_empty() => sep_regex
+                .split(&redact_fields(header, &redact_regexes, sep_regex))
+                .count(),
+            _ => expected_width(&headers, &rows),
+        };
+        fix_ragged_rows(&mut rows, width, &fills, line_numbers, opts, warnings)?;
+
+        if let Some(lines) = &self.join {
+            let (other_headers, other_rows, _) = split_lines(
+                lines,
+                sep_regex,
+                &RowFilter::default(),
+                &redact_regexes,
+                opts,
+            );
+            rows = join_tables(
+                &mut headers,
+                rows,
+                (other_headers, other_rows),
+                join_keys,
+                opts,
+            )?;
+        }
+
+        let total_rows = rows.len();
+        if opts.skip_bad_rows {
+            rows.retain(|row| well_formed(row, width, &fills));
+        }
+        split_columns(splits, &mut headers, &mut rows, &mut fills);
+
+        // 3. Column selection and reordering
+        let mut col_indices = selected_columns(columns, &headers, &rows, opts)?;
+        headers = output_headers(&headers, &col_indices, sep_regex, &redact_regexes, opts);
+        rename_headers(&mut headers, &renames, opts.lang)?;
+        let (selected, empty_rows) = select_cells(rows, &col_indices, &fills, opts);
+        rows = selected;
+        // Empty rows aren't malformed rows of -skip-bad-rows
+        let mut total_rows = total_rows - empty_rows;
+        if opts.sample.is_some() || opts.shuffle {
+            let mut rng = opts.seed.map_or_else(Rng::from_entropy, Rng::new);
+            let before = rows.len();
+            // Separator rows have no place among randomly chosen rows
+            rows.retain(|row| !is_separator(row));
+            rows = sample_rows(rows, opts.sample, opts.shuffle, &mut rng);
+            // Nor are rows left out of the sample malformed rows
+            total_rows -= before - rows.len();
+        }
+        // Constant columns, counted by the column numbers of all later steps
+        for insert in &inserts {
+            insert.apply(&mut headers, &mut rows, opts.lang)?;
+            col_indices.insert(insert.position, NO_INPUT_COLUMN);
+        }
+        // Substitutions, before anything is computed from or sorted by the values
+        edit_cells(
+            &mut headers,
+            &mut rows,
+            &replacements,
+            &case_ops,
+            &durations,
+            col_indices.len(),
+            opts,
+        )?;
+
+        // Computed columns, available for sorting and grouping like any other column
+        let has_headers = headers.iter().any(|h| !h.is_empty());
+        add_deltas(&mut headers, &mut rows, &deltas, has_headers, opts)?;
+        if rows.len() < total_rows {
+            warnings.push(Warning::SkippedRows {
+                dropped: total_rows - rows.len(),
+                total: total_rows,
+            });
+        }
+
+        // Collapse identical adjacent rows like `uniq -c`, the count can be sorted by
+        if opts.uniq_c {
+            rows = count_adjacent_rows(rows);
+            headers.push(count_header(has_headers));
+        }
+        let num_cols = col_indices.len() + deltas.len() + usize::from(opts.uniq_c);
+        let formatted_columns = datecols
+            .iter()
+            .map(|date| ("datecol", date.column))
+            .chain(roundings.iter().map(|&(col, _)| ("precision", col)))
+            .chain(humanized.iter().map(|&(col, _)| ("humanize", col)));
+        for (option, col) in formatted_columns {
+            if col >= num_cols {
+                return Err(opts
+                    .lang
+                    .tr("exceeds-output-columns", &[&option, &(col + 1), &num_cols]));
+            }
+        }
+
+        // The frequency summary replaces the table
+        if let Some(idx) = resolve_output_column(opts.freq, num_cols, "freq", opts, warnings)? {
+            reformat_dates(&mut rows, &datecols);
+            return Ok(frequency_table(&headers, &rows, idx, has_headers, opts));
+        }
+        if opts.stats {
+            return Ok(stats_table(&headers, &rows, opts));
+        }
+        if let Some(pivot) = &pivot {
+            if pivot.max_column() >= num_cols {
+                return Err(opts.lang.tr(
+                    "exceeds-output-columns",
+                    &[&"pivot", &(pivot.max_column() + 1), &num_cols],
+                ));
+            }
+            reformat_dates(&mut rows, &datecols);
+            return Ok(pivot.table(&headers, &rows, opts.numfmt, opts.precision));
+        }
+
+        // 4. Sorting
+        let sort_idx = resolve_output_column(opts.sortcol, num_cols, "sortcol", opts, warnings)?;
+        let group_idx = resolve_output_column(opts.gcol, num_cols, "gcol", opts, warnings)?;
+        if let Some(idx) = sort_idx {
+            rows = sort_rows(rows, idx, &datecols, opts);
+        }
+
+        // Reverse row order (before grouping, so group values stay on the first row of a group)
+        if opts.reverse {
+            rows.reverse();
+        }
+
+        // Reformatted after sorting, groups are formed on the new values (e.g. per day)
+        reformat_dates(&mut rows, &datecols);
+        format_numbers(&mut rows, &roundings, &humanized);
+        if let Some(style) = opts.numfmt_out {
+            restyle_numbers(&mut rows, opts.numfmt, style);
+        }
+
+        // 5. Grouping
+        if let Some(idx) = group_idx {
+            rows = group_rows(rows, idx, opts);
+            if opts.gcount {
+                headers.push(count_header(has_headers));
+            }
+        }
+
+        // Charts, before computed columns are colored
+        add_charts(&mut rows, &bars, num_cols, opts)?;
+        if opts.color {
+            for row in rows.iter_mut() {
+                for cell in row.iter_mut().skip(col_indices.len()).take(deltas.len()) {
+                    *cell = color_signed(cell).into();
+                }
+            }
+        }
+
+        if opts.drop_empty_cols {
+            drop_empty_columns(&mut headers, &mut rows, &mut col_indices);
+        }
+
+        // Map back to the columns of the original input when it was pre-processed by rcol
+        let original_column_indices = match source_indices {
+            Some(source) => col_indices
+                .iter()
+                .map(|&i| source.get(i).copied().unwrap_or(i))
+                .collect(),
+            None => col_indices,
+        };
+
+        Ok(TableData {
+            headers,
+            rows,
+            original_column_indices,
+        })
+    }
+}
+
+/// The specifications of the options that are checked before any input is
+/// processed, see [`Pipeline::validate`].
+struct Specs {
+    /// Selected input columns, all if empty
+    columns: Vec<usize>,
+    renames: Vec<Rename>,
+    inserts: Vec<Insert>,
+    splits: Vec<Split>,
+    /// Key columns of the input and of the joined table
+    join_keys: (usize, usize),
+    fills: HashMap<usize, Cell>,
+    deltas: Vec<Delta>,
+    replacements: Vec<Replace>,
+    case_ops: Vec<(usize, CaseOp)>,
+    datecols: Vec<DateCol>,
+    durations: Vec<(usize, bool)>,
+    bars: Vec<BarSpec>,
+    roundings: Vec<(usize, usize)>,
+    humanized: Vec<(usize, bool)>,
+    pivot: Option<Pivot>,
+}
+
+impl Specs {
+    /// Parses the specifications of `opts`.
+    fn parse(opts: &ProcessOptions) -> Result<Self, String> {
+        let renames = opts
+            .rename
+            .iter()
+            .map(|spec| parse_renames(spec))
+            .collect::<Result<Vec<_>, _>>()?
+            .concat();
+        let inserts = opts
+            .insert
+            .iter()
+            .map(|spec| Insert::parse(spec))
+            .collect::<Result<Vec<_>, _>>()?;
+        let splits = opts
+            .split
+            .iter()
+            .map(|spec| Split::parse(spec))
+            .collect::<Result<Vec<_>, _>>()?;
+        let join_keys = match &opts.join_on {
+            Some(spec) => parse_join_on(spec)?,
+            None => (0, 0),
+        };
+        let fills = opts
+            .fill
+            .iter()
+            .map(|spec| parse_fill(spec))
+            .collect::<Result<HashMap<_, _>, _>>()?;
+        let deltas = opts
+            .delta
+            .iter()
+            .map(|spec| Delta::parse(spec))
+            .collect::<Result<Vec<_>, _>>()?;
+        let replacements = opts
+            .replace
+            .iter()
+            .map(|spec| Replace::parse(spec))
+            .collect::<Result<Vec<_>, _>>()?;
+        let case_ops = opts
+            .transform
+            .iter()
+            .map(|spec| parse_case_ops(spec))
+            .collect::<Result<Vec<_>, _>>()?
+            .concat();
+        let datecols = opts
+            .datecol
+            .iter()
+            .map(|spec| DateCol::parse(spec))
+            .collect::<Result<Vec<_>, _>>()?;
+        let durations = opts
+            .duration
+            .iter()
+            .map(|spec| parse_duration_spec(spec))
+            .collect::<Result<Vec<_>, _>>()?;
+        let bars = opts
+            .bar
+            .iter()
+            .map(|spec| BarSpec::parse(spec))
+            .collect::<Result<Vec<_>, _>>()?;
+        let roundings = opts
+            .round
+            .iter()
+            .map(|spec| parse_round_spec(spec))
+            .collect::<Result<Vec<_>, _>>()?;
+        let humanized = opts
+            .humanize
+            .iter()
+            .map(|spec| parse_humanize_spec(spec))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            columns: parse_columns(&opts.columns)?,
+            renames,
+            inserts,
+            splits,
+            join_keys,
+            fills,
+            deltas,
+            replacements,
+            case_ops,
+            datecols,
+            durations,
+            bars,
+            roundings,
+            humanized,
+            pivot: opts.pivot.as_deref().map(Pivot::parse).transpose()?,
+        })
+    }
+}
+
+/// Parses the column specifications: single columns (`3`) and ranges (`1:3`,
+/// reversed for `3:1`).
+///
+/// # Returns
+///
+/// - `Ok(Vec<usize>)` with the 0-based input columns in output order
+/// - `Err(String)` if a specification is not a 1-based column or range
+fn parse_columns(specs: &[String]) -> Result<Vec<usize>, String> {
+    let mut col_indices: Vec<usize> = Vec::new();
+    for col_spec in specs {
+        if col_spec.contains(':') {
+            // Range
+            let parts: Vec<&str> = col_spec.split(':').collect();
+            if parts.len() == 2 {
+                let start: usize = parts[0]
+                    .parse()
+                    .map_err(|_| format!("Invalid range start: {}", parts[0]))?;
+                let end: usize = parts[1]
+                    .parse()
+                    .map_err(|_| format!("Invalid range end: {}", parts[1]))?;
+                // 1-based to 0-based
+                if start == 0 || end == 0 {
+                    return Err("Column numbers must be 1-based".to_string());
+                }
+                if start <= end {
+                    for i in start..=end {
+                        col_indices.push(i - 1);
+                    }
+                } else {
+                    // A reverse range like 3:1 lists the columns backwards
+                    let mut i = start;
+                    while i >= end {
+                        col_indices.push(i - 1);
+                        if i == 0 {
+                            break;
+                        } // Should not happen due to check above
+                        i -= 1;
+                    }
+                }
+            } else {
+                return Err(format!("Invalid range format: {}", col_spec));
+            }
+        } else {
+            // Single number
+            let idx: usize = col_spec
+                .parse()
+                .map_err(|_| format!("Invalid column number: {}", col_spec))?;
+            if idx == 0 {
+                return Err("Column numbers must be 1-based".to_string());
+            }
+            col_indices.push(idx - 1);
+        }
+    }
+    Ok(col_indices)
+}
+
+/// Checks the options that must be at least 1 or not empty.
+fn check_limits(opts: &ProcessOptions) -> Result<(), String> {
+    if opts.max_cols == Some(0) {
+        return Err("--max-cols must be at least 1".to_string());
+    }
+    if opts.fill_cols == Some(0) {
+        return Err("--fill-cols must be at least 1".to_string());
+    }
+    if opts.comment.as_deref() == Some("") {
+        return Err("--comment must not be empty".to_string());
+    }
+    if opts.expand_tabs == Some(0) {
+        return Err("--expand-tabs must be at least 1".to_string());
+    }
+    Ok(())
+}
+
+/// Splits raw text lines into a header and data rows.
+///
+/// The header line (first line, unless `-rh`, `-nhl` or `-header` say otherwise) is
+/// taken off the input before filtering so it can't be dropped by a pattern. All
+/// remaining lines must pass the line filters and the `-where` conditions.
+///
+/// # Arguments
+///
+/// * `lines` - Raw input lines
+/// * `sep_regex` - Regex matching the column separator
+/// * `row_filter` - Line filters and `-where` conditions
+/// * `redact` - Redaction patterns, applied to every line before it is split
+/// * `opts` - Processing options (checks `-rh`, `-nhl` and `-header`)
+///
+/// # Returns
+///
+/// The header cells (empty if the input has no header line), the data rows and
+/// the 1-based input line of every row
+fn split_lines<S: AsRef<str>>(
+    lines: &[S],
+    sep_regex: &Regex,
+    row_filter: &RowFilter,
+    redact: &[Regex],
+    opts: &ProcessOptions,
+) -> (Vec<Cell>, Vec<Vec<Cell>>, Vec<usize>) {
+    // Rows of a box-drawn table are split at its vertical rules
+    // With `-max-cols N` the rest of the line after N-1 separators is the last cell.
+    // Rows are allocated for `capacity` cells, the number of header cells. The
+    // cells share the (redacted) line instead of copying their text.
+    let split = |line: &str, capacity: usize| -> Vec<Cell> {
+        let line: Arc<str> = Arc::from(redact_fields(line, redact, sep_regex));
+        let mut cells = opts
+            .unbox
+            .then(|| markup::split_boxed(&line))
+            .flatten()
+            .map(|cells| cells.into_iter().map(Cell::from).collect())
+            .unwrap_or_else(|| {
+                if opts.quoted {
+                    return split_quoted(&line, sep_regex, opts.max_cols, opts.keep_quotes);
+                }
+                let mut cells = Vec::with_capacity(capacity);
+                let part = |part| Cell::part_of(&line, part);
+                match opts.max_cols {
+                    Some(n) => cells.extend(sep_regex.splitn(&line, n).map(part)),
+                    None => cells.extend(sep_regex.split(&line).map(part)),
+                }
+                cells
+            });
+        if opts.trim_cells {
+            for cell in cells.iter_mut() {
+                *cell = cell.slice(cell.trim());
+            }
+        }
+        cells
+    };
+
+    // Comment lines are dropped, a run of blank lines between data rows becomes
+    // one separator row with `-keep-blank`
+    let mut lines = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| (i + 1, opts.trim.apply(line.as_ref())))
+        .filter(|(_, line)| !is_comment(line, opts))
+        .peekable();
+    while lines.next_if(|(_, line)| line.trim().is_empty()).is_some() {}
+
+    let mut headers = Vec::new();
+    if opts.rh {
+        lines.next(); // Remove first line
+    } else if opts.header.is_none() && !opts.nhl {
+        // Treat first line as header
+        if let Some((_, line)) = lines.next() {
+            headers = split(line, 0);
+        }
+    }
+
+    // Handle input lines: blank lines are `None`, lines failing the filters `Some(None)`
+    let lines: Vec<(usize, &str)> = lines.collect();
+    let split_lines = parallel::map(lines, opts.parallel, |(number, line)| {
+        let parts = (!line.trim().is_empty()).then(|| {
+            row_filter
+                .line_passes(line)
+                .then(|| split(line, headers.len()))
+                .filter(|parts| row_filter.row_passes(parts))
+        });
+        (number, parts)
+    });
+    let mut rows = Vec::new();
+    let mut line_numbers = Vec::new();
+    let mut blank = None;
+    for (number, parts) in split_lines {
+        let Some(parts) = parts else {
+            blank = blank.or(Some(number));
+            continue;
+        };
+        let Some(parts) = parts else {
+            continue;
+        };
+        if let Some(blank_line) = blank.filter(|_| opts.keep_blank && !rows.is_empty()) {
+            rows.push(Vec::new());
+            line_numbers.push(blank_line);
+        }
+        blank = None;
+        rows.push(parts);
+        line_numbers.push(number);
+    }
+    (headers, rows, line_numbers)
+}
+
+/// Replaces the tabs of a line with spaces up to the next tab stop (`-expand-tabs`).
+///
+/// Columns are counted in display width, so wide characters take two columns
+/// like in a terminal.
+///
+/// # Arguments
+///
+/// * `line` - The input line
+/// * `width` - Distance of the tab stops, at least 1
+///
+/// # Returns
+///
+/// The line without tabs
+pub fn expand_tabs(line: &str, width: usize) -> String {
+    let mut expanded = String::with_capacity(line.len());
+    let mut column = 0;
+    for c in line.chars() {
+        if c == '\t' {
+            let spaces = width - column % width;
+            expanded.extend(std::iter::repeat_n(' ', spaces));
+            column += spaces;
+        } else {
+            expanded.push(c);
+            column += c.width().unwrap_or(0);
+        }
+    }
+    expanded
+}
+
+/// Checks whether the lines are list items to lay out in columns (`-fill-cols`, `-fill-width`).
+fn lays_out_list(opts: &ProcessOptions) -> bool {
+    opts.fill_cols.is_some() || opts.fill_width.is_some()
+}
+
+/// Lays out list items in columns (`-fill-cols`, `-fill-across`).
+///
+/// Down, then across (like `ls`) fills the first column before the second one,
+/// across, then down fills the rows one after the other. Columns that would stay
+/// empty are left out, missing cells at the end are empty.
+///
+/// # Arguments
+///
+/// * `items` - The list items, one per cell
+/// * `columns` - Maximum number of columns, at least 1
+/// * `across` - Fill across, then down
+///
+/// # Returns
+///
+/// The rows of the layout
+fn fill_layout(items: Vec<String>, columns: usize, across: bool) -> Vec<Vec<Cell>> {
+    let num_rows = items.len().div_ceil(columns);
+    let width = if across {
+        columns.min(items.len())
+    } else {
+        items.len().div_ceil(num_rows.max(1))
+    };
+    let mut rows = vec![vec![Cell::default(); width]; num_rows];
+    for (i, item) in items.into_iter().enumerate() {
+        let (row, col) = if across {
+            (i / columns, i % columns)
+        } else {
+            (i % num_rows, i / num_rows)
+        };
+        rows[row][col] = item.into();
+    }
+    rows
+}
+
+/// Converts the cells of a table parsed from markup, logfmt or a `-preset`.
+///
+/// Those parsers unescape the text of their cells, so the cells don't share a line.
+fn markup_cells((headers, rows): markup::MarkupTable) -> (Vec<Cell>, Vec<Vec<Cell>>) {
+    fn cells(row: Vec<String>) -> Vec<Cell> {
+        row.into_iter().map(Cell::from).collect()
+    }
+    (cells(headers), rows.into_iter().map(cells).collect())
+}
+
+/// Checks whether a row has the expected number of cells, or misses only cells
+/// that have a `-fill` default. Separator rows without cells always pass.
+fn well_formed(row: &[Cell], width: usize, fills: &HashMap<usize, Cell>) -> bool {
+    row.len() == width
+        || row.is_empty()
+        || (row.len() < width && (row.len()..width).all(|i| fills.contains_key(&i)))
+}
+
+/// Handles rows with more or fewer cells than the header (`-strict`, `-ragged`).
+///
+/// # Arguments
+///
+/// * `rows` - Data rows, changed in place
+/// * `width` - Number of cells of a well-formed row
+/// * `fills` - `-fill` defaults of missing cells
+/// * `line_numbers` - Input line of every row, for the messages
+/// * `opts` - Processing options (checks `-strict`, `-ragged` and the separator)
+/// * `warnings` - Collects the dropped rows
+///
+/// # Returns
+///
+/// - `Ok(())` if the rows were fixed up
+/// - `Err(String)` naming the first malformed row with `-strict`
+fn fix_ragged_rows(
+    rows: &mut Vec<Vec<Cell>>,
+    width: usize,
+    fills: &HashMap<usize, Cell>,
+    line_numbers: Option<&[usize]>,
+    opts: &ProcessOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<(), String> {
+    if opts.strict
+        && let Some(i) = rows.iter().position(|row| !well_formed(row, width, fills))
+    {
+        let found = rows[i].len();
+        let problem = match line_numbers.and_then(|lines| lines.get(i)) {
+            Some(line) => opts.lang.tr("line-columns", &[line, &found, &width]),
+            None => opts.lang.tr("row-columns", &[&(i + 1), &found, &width]),
+        };
+        return Err(format!("{} (--strict)", problem));
+    }
+    match opts.ragged {
+        // Missing cells are filled when the columns are selected
+        Ragged::Pad => {}
+        Ragged::Truncate => {
+            for row in rows.iter_mut() {
+                row.truncate(width);
+            }
+        }
+        Ragged::MergeLast if width > 0 => {
+            let joiner = match &opts.sep_regex {
+                None if opts.sep != "auto" => opts.sep.as_str(),
+                _ => " ",
+            };
+            for row in rows.iter_mut().filter(|row| row.len() > width) {
+                let overflow = row.split_off(width - 1);
+                row.push(overflow.join(joiner).into());
+            }
+        }
+        Ragged::MergeLast => {}
+        Ragged::Drop => {
+            let mut kept = Vec::with_capacity(rows.len());
+            for (i, row) in std::mem::take(rows).into_iter().enumerate() {
+                if well_formed(&row, width, fills) {
+                    kept.push(row);
+                    continue;
+                }
+                warnings.push(Warning::DroppedRow {
+                    line: line_numbers.and_then(|lines| lines.get(i)).copied(),
+                    row: i + 1,
+                    found: row.len(),
+                    expected: width,
+                });
+            }
+            *rows = kept;
+        }
+    }
+    Ok(())
+}
+
+/// Splits input columns for `-split`, from the rightmost column so the numbers
+/// of all splits refer to the input. The `-fill` defaults move with their columns.
+fn split_columns(
+    mut splits: Vec<Split>,
+    headers: &mut Vec<Cell>,
+    rows: &mut [Vec<Cell>],
+    fills: &mut HashMap<usize, Cell>,
+) {
+    splits.sort_by_key(|split| Reverse(split.column));
+    for split in &splits {
+        let added = split.apply(headers, rows) - 1;
+        *fills = std::mem::take(fills)
+            .into_iter()
+            .map(|(col, text)| {
+                let col = if col > split.column { col + added } else { col };
+                (col, text)
+            })
+            .collect();
+    }
+}
+
+/// Returns the input columns to output: the selected ones, or all columns.
+///
+/// # Returns
+///
+/// - `Ok(Vec<usize>)` with the 0-based input column of every output column
+/// - `Err(String)` if a selected column doesn't exist with `-strict`
+fn selected_columns(
+    columns: Vec<usize>,
+    headers: &[Cell],
+    rows: &[Vec<Cell>],
+    opts: &ProcessOptions,
+) -> Result<Vec<usize>, String> {
+    let available = rows
+        .iter()
+        .map(|r| r.len())
+        .max()
+        .unwrap_or(0)
+        .max(headers.len());
+    if columns.is_empty() {
+        return Ok((0..available).collect());
+    }
+    if opts.strict
+        && let Some(idx) = columns.iter().find(|&&idx| idx >= available)
+    {
+        let problem = opts
+            .lang
+            .tr("exceeds-input-columns", &[&(idx + 1), &available]);
+        return Err(format!("{} (--strict)", problem));
+    }
+    Ok(columns)
+}
+
+/// Returns the headers of the output columns: the selected input headers, or
+/// the (redacted) `-header` cut to the number of output columns.
+fn output_headers(
+    headers: &[Cell],
+    col_indices: &[usize],
+    sep_regex: &Regex,
+    redact: &[Regex],
+    opts: &ProcessOptions,
+) -> Vec<Cell> {
+    let mut output: Vec<Cell> = match &opts.header {
+        Some(header) => sep_regex
+            .split(&redact_fields(header, redact, sep_regex))
+            .map(Cell::from)
+            .collect(),
+        None => col_indices
+            .iter()
+            .map(|&idx| headers.get(idx).cloned().unwrap_or_default())
+            .collect(),
+    };
+    output.resize(col_indices.len(), Cell::default());
+    output
+}
+
+/// Builds the output rows from the selected input columns.
+///
+/// The new rows share the text of the input cells, rows with all columns in input
+/// order are kept as they are. Missing cells get their `-fill` default.
+///
+/// # Returns
+///
+/// The output rows and the number of empty rows dropped by `-drop-empty-rows`
+fn select_cells(
+    rows: Vec<Vec<Cell>>,
+    col_indices: &[usize],
+    fills: &HashMap<usize, Cell>,
+    opts: &ProcessOptions,
+) -> (Vec<Vec<Cell>>, usize) {
+    let in_order = col_indices.iter().enumerate().all(|(i, &idx)| i == idx);
+    let mut new_rows = Vec::with_capacity(rows.len());
+    let mut empty_rows = 0;
+    for mut row in rows {
+        // Separator row of `-keep-blank`
+        if row.is_empty() {
+            new_rows.push(vec![Cell::default(); col_indices.len()]);
+            continue;
+        }
+        let new_row = if in_order && row.len() >= col_indices.len() {
+            row.truncate(col_indices.len());
+            row
+        } else {
+            col_indices
+                .iter()
+                .map(|&idx| {
+                    row.get(idx)
+                        .or(fills.get(&idx))
+                        .cloned()
+                        .unwrap_or_default()
+                })
+                .collect()
+        };
+        if opts.drop_empty_rows && new_row.iter().all(|cell| cell.is_empty()) {
+            empty_rows += 1;
+            continue;
+        }
+        new_rows.push(new_row);
+    }
+    (new_rows, empty_rows)
+}
+
+/// Applies `-replace`, `-transform` and `-duration` to the output columns;
+/// `-transform` normalizes the header too.
+///
+/// # Arguments
+///
+/// * `headers` - Output headers
+/// * `rows` - Output rows
+/// * `replacements`, `case_ops`, `durations` - The parsed options
+/// * `num_cols` - Number of output columns
+/// * `opts` - Processing options (`-parallel`, `-precision`)
+///
+/// # Returns
+///
+/// - `Ok(())` if the cells were edited
+/// - `Err(String)` if an option refers to a column beyond the output columns
+fn edit_cells(
+    headers: &mut [Cell],
+    rows: &mut [Vec<Cell>],
+    replacements: &[Replace],
+    case_ops: &[(usize, CaseOp)],
+    durations: &[(usize, bool)],
+    num_cols: usize,
+    opts: &ProcessOptions,
+) -> Result<(), String> {
+    let check = |option: &str, col: usize| {
+        if col >= num_cols {
+            return Err(opts
+                .lang
+                .tr("exceeds-output-columns", &[&option, &(col + 1), &num_cols]));
+        }
+        Ok(())
+    };
+    for rule in replacements {
+        check("replace", rule.column)?;
+        parallel::for_each(rows, opts.parallel, |row| {
+            if !is_separator(row) {
+                row[rule.column] = rule.apply(&row[rule.column]).into();
+            }
+        });
+    }
+    for &(col, op) in case_ops {
+        check("transform", col)?;
+        for cell in rows
+            .iter_mut()
+            .filter_map(|row| row.get_mut(col))
+            .chain(headers.get_mut(col))
+        {
+            *cell = op.apply(cell).into();
+        }
+    }
+    for &(col, seconds) in durations {
+        check("duration", col)?;
+        for cell in rows.iter_mut().filter_map(|row| row.get_mut(col)) {
+            if let Some(value) = parse_duration(cell) {
+                *cell = if seconds {
+                    format_decimal(value, opts.precision)
+                } else {
+                    format_duration(value)
+                }
+                .into();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Appends a computed column for every `-delta`. With `-skip-bad-rows` rows
+/// without two numbers to compute from are dropped.
+///
+/// # Returns
+///
+/// - `Ok(())` if the columns were added
+/// - `Err(String)` if a delta refers to a column beyond the output columns
+fn add_deltas(
+    headers: &mut Vec<Cell>,
+    rows: &mut Vec<Vec<Cell>>,
+    deltas: &[Delta],
+    has_headers: bool,
+    opts: &ProcessOptions,
+) -> Result<(), String> {
+    for delta in deltas {
+        let count = headers.len();
+        if let Some(col) = [delta.minuend, delta.subtrahend]
+            .into_iter()
+            .find(|&c| c >= count)
+        {
+            return Err(opts
+                .lang
+                .tr("exceeds-output-columns", &[&"delta", &(col + 1), &count]));
+        }
+        if opts.skip_bad_rows {
+            rows.retain(|row| delta.operands(row).is_some() || is_separator(row));
+        }
+        let header = if has_headers {
+            delta.header(headers).into()
+        } else {
+            Cell::default()
+        };
+        for row in rows.iter_mut() {
+            let value = delta.compute(row, opts.precision);
+            row.push(value.into());
+        }
+        headers.push(header);
+    }
+    Ok(())
+}
+
+/// Collapses identical adjacent rows like `uniq -c` and appends their count.
+/// Separator rows are kept as they are, with an empty count.
+fn count_adjacent_rows(rows: Vec<Vec<Cell>>) -> Vec<Vec<Cell>> {
+    let mut counted: Vec<(Vec<Cell>, usize)> = Vec::new();
+    for row in rows {
+        match counted.last_mut() {
+            Some((last, count)) if *last == row && !is_separator(&row) => *count += 1,
+            _ => counted.push((row, 1)),
+        }
+    }
+    counted
+        .into_iter()
+        .map(|(mut row, count)| {
+            let count = if is_separator(&row) {
+                Cell::default()
+            } else {
+                count.to_string().into()
+            };
+            row.push(count);
+            row
+        })
+        .collect()
+}
+
+/// Sorts the rows by the `-sortcol` output column: chronologically for a
+/// `-datecol` column, by address with `-sort-ip`, else with [`compare_cells`].
+fn sort_rows(
+    mut rows: Vec<Vec<Cell>>,
+    idx: usize,
+    datecols: &[DateCol],
+    opts: &ProcessOptions,
+) -> Vec<Vec<Cell>> {
+    if let Some(date) = datecols.iter().find(|d| d.column == idx) {
+        // On the values as they were read
+        return sort_by_parsed(rows, opts.sort_desc, |row| {
+            date.timestamp(&row[date.column])
+        });
+    }
+    if opts.sort_ip {
+        return sort_by_parsed(rows, opts.sort_desc, |row| ip_sort_key(&row[idx]));
+    }
+    rows.sort_by(|a, b| {
+        let ord = compare_cells(&a[idx], &b[idx], opts);
+        if opts.sort_desc { ord.reverse() } else { ord }
+    });
+    rows
+}
+
+/// Groups sorted rows by the `-gcol` output column.
+///
+/// A separator row without cells goes between the groups, so it can't be
+/// mistaken for a data row whose cells are all empty. Group changes are detected
+/// on the collation key; repeated values are hidden unless `-gcolval`. With
+/// `-gcount` the number of rows of the group is appended, shown like the group value.
+///
+/// # Arguments
+///
+/// * `rows` - Sorted data rows
+/// * `idx` - 0-based output column to group by
+/// * `opts` - Processing options (`-gcolval`, `-gcount` and the collation)
+///
+/// # Returns
+///
+/// The rows with separator rows between the groups
+fn group_rows(rows: Vec<Vec<Cell>>, idx: usize, opts: &ProcessOptions) -> Vec<Vec<Cell>> {
+    let mut grouped_rows = Vec::new();
+    let mut last_val = String::new();
+    let mut first = true;
+    // Index of the first row and number of rows of every group
+    let mut groups: Vec<(usize, usize)> = Vec::new();
+    for mut row in rows {
+        // Only the value of a new group is copied
+        let changed = match collation_key(&row[idx], opts) {
+            val if first || val != last_val.as_str() => {
+                last_val = val.into_owned();
+                true
+            }
+            _ => false,
+        };
+        if changed && !first {
+            grouped_rows.push(Vec::new());
+        }
+        if changed {
+            groups.push((grouped_rows.len(), 0));
+        }
+        if let Some((_, count)) = groups.last_mut() {
+            *count += 1;
+        }
+        if !changed && !opts.gcolval {
+            row[idx] = Cell::default();
+        }
+        grouped_rows.push(row);
+        first = false;
+    }
+
+    if opts.gcount {
+        for row in grouped_rows.iter_mut().filter(|row| !row.is_empty()) {
+            row.push(Cell::default());
+        }
+        for (start, count) in groups {
+            let shown = if opts.gcolval { count } else { 1 };
+            let count = Cell::from(count.to_string());
+            for row in &mut grouped_rows[start..start + shown] {
+                *row.last_mut().unwrap() = count.clone();
+            }
+        }
+    }
+    grouped_rows
+}
+
+/// Draws the `-bar` and `-spark` charts into their columns.
+///
+/// # Returns
+///
+/// - `Ok(())` if the charts were drawn
+/// - `Err(String)` if a chart refers to a column beyond the output columns
+fn add_charts(
+    rows: &mut [Vec<Cell>],
+    bars: &[BarSpec],
+    num_cols: usize,
+    opts: &ProcessOptions,
+) -> Result<(), String> {
+    let chart_columns = bars
+        .iter()
+        .map(|bar| ("bar", bar.column + 1))
+        .chain(opts.spark.iter().map(|&col| ("spark", col)));
+    for (option, col) in chart_columns {
+        if col == 0 || col > num_cols {
+            return Err(opts
+                .lang
+                .tr("exceeds-output-columns", &[&option, &col, &num_cols]));
+        }
+    }
+    for bar in bars {
+        bar.apply(rows, opts.bar_values);
+    }
+    for &col in &opts.spark {
+        for row in rows.iter_mut() {
+            if let Some(line) = row.get(col - 1).and_then(|cell| sparkline(cell)) {
+                row[col - 1] = line.into();
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Summarizes the values of a column for `-freq`.
+///
+/// Every distinct value gets a row with its count and percentage of the rows
+/// (with `-precision` decimal places, default 1), and with `-freq-bar` a bar of
+/// `#` scaled to the most frequent value. Rows are sorted by descending count,
+/// values with the same count keep the order of their first occurrence.
+///
+/// # Arguments
+///
+/// * `headers` - Headers of the table, the header of `col` labels the value column
+/// * `rows` - Data rows, separator rows are not counted
+/// * `col` - 0-based output column to summarize
+/// * `has_headers` - Whether the summary gets headers
+/// * `opts` - Processing options (`-freq-bar`, `-precision`, `-lang` of the headers)
+///
+/// # Returns
+///
+/// The summary table with the columns value, count, percent and bar
+fn frequency_table(
+    headers: &[Cell],
+    rows: &[Vec<Cell>],
+    col: usize,
+    has_headers: bool,
+    opts: &ProcessOptions,
+) -> TableData {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    let mut positions: HashMap<&str, usize> = HashMap::new();
+    let mut total = 0;
+    for row in rows.iter().filter(|row| !is_separator(row)) {
+        let value = row.get(col).map_or("", Cell::as_str);
+        let pos = *positions.entry(value).or_insert_with(|| {
+            counts.push((value.to_string(), 0));
+            counts.len() - 1
+        });
+        counts[pos].1 += 1;
+        total += 1;
+    }
+    // Stable, so equal counts stay in the order of the input
+    counts.sort_by_key(|(_, count)| Reverse(*count));
+
+    let max = counts.first().map_or(0, |(_, count)| *count);
+    let rows = counts
+        .into_iter()
+        .map(|(value, count)| {
+            let percent = 100.0 * count as f64 / total as f64;
+            let mut row = vec![
+                Cell::from(value),
+                count.to_string().into(),
+                format_decimal(percent, opts.precision.or(Some(1))).into(),
+            ];
+            if let Some(width) = opts.freq_bar {
+                row.push("#".repeat((count * width).div_ceil(max)).into());
+            }
+            row
+        })
+        .collect();
+
+    let mut names: Vec<Cell> = vec![
+        headers
+            .get(col)
+            .filter(|h| !h.is_empty())
+            .cloned()
+            .unwrap_or_else(|| opts.lang.tr("freq-value", &[]).into()),
+        opts.lang.tr("freq-count", &[]).into(),
+        opts.lang.tr("freq-percent", &[]).into(),
+    ];
+    if opts.freq_bar.is_some() {
+        names.push(Cell::default());
+    }
+    let original_column_indices = (0..names.len()).collect();
+    let headers = if has_headers { names } else { Vec::new() };
+    TableData {
+        headers,
+        rows,
+        original_column_indices,
+    }
+}
+
+/// Summarizes the numeric columns for `-stats`.
+///
+/// A column is numeric if it has at least one number and all its other cells are
+/// empty. Each gets a row with the column name (its number without headers), the
+/// count of numbers, minimum, maximum, mean, median and sample standard deviation,
+/// formatted with `-precision`. Other columns are left out.
+///
+/// # Arguments
+///
+/// * `headers` - Headers of the table, may be empty
+/// * `rows` - Data rows
+/// * `opts` - Processing options (`-precision`, `-lang` of the headers)
+///
+/// # Returns
+///
+/// The summary table with one row per numeric column
+fn stats_table(headers: &[Cell], rows: &[Vec<Cell>], opts: &ProcessOptions) -> TableData {
+    let ncols = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let mut summary = Vec::new();
+    for col in 0..ncols {
+        let cells = rows.iter().filter_map(|row| row.get(col)).map(|c| c.trim());
+        let Some(mut values) = cells
+            .filter(|c| !c.is_empty())
+            .map(|c| match parse_number(c)? {
+                Number::Int(n) => Some(n as f64),
+                Number::Float(f) => Some(f),
+            })
+            .collect::<Option<Vec<f64>>>()
+            .filter(|values| !values.is_empty())
+        else {
+            continue;
+        };
+        values.sort_by(f64::total_cmp);
+
+        let n = values.len();
+        let mean = values.iter().sum::<f64>() / n as f64;
+        let median = if n % 2 == 0 {
+            (values[n / 2 - 1] + values[n / 2]) / 2.0
+        } else {
+            values[n / 2]
+        };
+        let variance = if n > 1 {
+            values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1) as f64
+        } else {
+            0.0
+        };
+        let name = match headers.get(col) {
+            Some(h) if !h.is_empty() => h.clone(),
+            _ => (col + 1).to_string().into(),
+        };
+        let mut row = vec![name, n.to_string().into()];
+        row.extend(
+            [values[0], values[n - 1], mean, median, variance.sqrt()]
+                .map(|v| Cell::from(format_decimal(v, opts.precision))),
+        );
+        summary.push(row);
+    }
+
+    let headers = [
+        "stats-column",
+        "stats-count",
+        "stats-min",
+        "stats-max",
+        "stats-mean",
+        "stats-median",
+        "stats-stddev",
+    ]
+    .map(|key| Cell::from(opts.lang.tr(key, &[])));
+    TableData {
+        original_column_indices: (0..headers.len()).collect(),
+        headers: headers.to_vec(),
+        rows: summary,
+    }
+}
+
+/// Parses a `-duration` specification `N:seconds` or `N:hms`.
+///
+/// # Returns
+///
+/// - `Ok((column, seconds))` with the 0-based column and whether to write seconds
+/// - `Err(String)` if the specification is invalid
+fn parse_duration_spec(spec: &str) -> Result<(usize, bool), String> {
+    let invalid = || format!("Invalid duration '{}': expected N:seconds or N:hms", spec);
+    let (col, format) = spec.split_once(':').ok_or_else(invalid)?;
+    let col: usize = col.trim().parse().map_err(|_| invalid())?;
+    if col == 0 {
+        return Err("Column numbers must be 1-based".to_string());
+    }
+    match format {
+        "seconds" => Ok((col - 1, true)),
+        "hms" => Ok((col - 1, false)),
+        _ => Err(invalid()),
+    }
+}
+
+/// Parses a `-precision N:PLACES` specification.
+///
+/// # Returns
+///
+/// - `Ok((column, places))` with the 0-based output column
+/// - `Err(String)` if the specification is malformed
+fn parse_round_spec(spec: &str) -> Result<(usize, usize), String> {
+    let invalid = || format!("Invalid precision '{}': expected PLACES or N:PLACES", spec);
+    let (col, places) = spec.split_once(':').ok_or_else(invalid)?;
+    let col: usize = col.trim().parse().map_err(|_| invalid())?;
+    let places: usize = places.trim().parse().map_err(|_| invalid())?;
+    if col == 0 {
+        return Err("Column numbers must be 1-based".to_string());
+    }
+    Ok((col - 1, places))
+}
+
+/// Parses a `-humanize N[:iec]` specification.
+///
+/// # Returns
+///
+/// - `Ok((column, iec))` with the 0-based output column and whether powers of 1024 are used
+/// - `Err(String)` if the specification is malformed
+fn parse_humanize_spec(spec: &str) -> Result<(usize, bool), String> {
+    let invalid = || format!("Invalid humanize '{}': expected N or N:iec", spec);
+    let (col, iec) = match spec.split_once(':') {
+        Some((col, "iec")) => (col, true),
+        Some(_) => return Err(invalid()),
+        None => (spec, false),
+    };
+    let col: usize = col.trim().parse().map_err(|_| invalid())?;
+    if col == 0 {
+        return Err("Column numbers must be 1-based".to_string());
+    }
+    Ok((col - 1, iec))
+}
+
+/// Rounds the numbers of `-precision N:PLACES` columns and writes those of
+/// `-humanize` columns with unit prefixes, other cells are kept.
+fn format_numbers(
+    rows: &mut [Vec<Cell>],
+    roundings: &[(usize, usize)],
+    humanized: &[(usize, bool)],
+) {
+    let value = |cell: &str| match parse_number(cell)? {
+        Number::Int(n) => Some(n as f64),
+        Number::Float(n) => Some(n),
+    };
+    for row in rows.iter_mut() {
+        for &(col, places) in roundings {
+            if let Some(cell) = row.get_mut(col)
+                && let Some(v) = value(cell)
+            {
+                *cell = format_decimal(v, Some(places)).into();
+            }
+        }
+        for &(col, iec) in humanized {
+            if let Some(cell) = row.get_mut(col)
+                && let Some(v) = value(cell)
+            {
+                *cell = humanize(v, iec).into();
+            }
+        }
+    }
+}
+
+/// Sorts rows by a key parsed from their cells, rows without a key go last in
+/// their original order.
+///
+/// # Arguments
+///
+/// * `rows` - Rows to sort
+/// * `desc` - Sort descending (`-sort-desc`)
+/// * `key` - Parses the sort key of a row
+///
+/// # Returns
+///
+/// The sorted rows
+fn sort_by_parsed<K: Ord>(
+    rows: Vec<Vec<Cell>>,
+    desc: bool,
+    key: impl Fn(&[Cell]) -> Option<K>,
+) -> Vec<Vec<Cell>> {
+    let mut keyed: Vec<_> = rows.into_iter().map(|row| (key(&row), row)).collect();
+    keyed.sort_by(|(a, _), (b, _)| compare_parsed(a, b, desc));
+    keyed.into_iter().map(|(_, row)| row).collect()
+}
+
+/// Compares two parsed sort keys like [`sort_by_parsed`]: cells without a key
+/// go last, also when sorting descending.
+fn compare_parsed<K: Ord>(a: &Option<K>, b: &Option<K>, desc: bool) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) if desc => b.cmp(a),
+        (Some(a), Some(b)) => a.cmp(b),
+        _ => a.is_none().cmp(&b.is_none()),
+    }
+}
+
+/// Compares two cells of the `-sortcol` column in ascending order: as numbers
+/// if both are numbers, as durations like `5m30s` if both are durations, and
+/// as text otherwise.
+fn compare_cells(a: &str, b: &str, opts: &ProcessOptions) -> Ordering {
+    if let (Some(num_a), Some(num_b)) = (
+        parse_localized(a, opts.numfmt),
+        parse_localized(b, opts.numfmt),
+    ) {
+        num_a.partial_cmp(&num_b).unwrap_or(Ordering::Equal)
+    } else if let (Some(dur_a), Some(dur_b)) = (parse_duration(a), parse_duration(b)) {
+        dur_a.partial_cmp(&dur_b).unwrap_or(Ordering::Equal)
+    } else {
+        collation_key(a, opts).cmp(&collation_key(b, opts))
+    }
+}
+
+/// Parses an address for `-sort-ip`: IPv4 or IPv6, optionally with a CIDR prefix
+/// (`10.0.0.0/8`), a port (`10.0.0.1:443`, `[::1]:22`) or a zone (`fe80::1%eth0`).
+///
+/// # Returns
+///
+/// The sort key (IPv4 before IPv6, then address, prefix length and port), or
+/// `None` if the cell is no address
+fn ip_sort_key(cell: &str) -> Option<(u8, u128, u8, u16)> {
+    let cell = cell.trim();
+    let (addr, port) = if let Some(rest) = cell.strip_prefix('[') {
+        let (addr, rest) = rest.split_once(']')?;
+        let port = match rest.strip_prefix(':') {
+            Some(port) => port.parse().ok()?,
+            None if rest.is_empty() => 0,
+            None => return None,
+        };
+        (addr, port)
+    } else {
+        match cell.rsplit_once(':') {
+            // IPv4 with port, IPv6 addresses contain more than one colon
+            Some((addr, port)) if !addr.contains(':') => (addr, port.parse().ok()?),
+            _ => (cell, 0),
+        }
+    };
+    let (addr, prefix) = match addr.split_once('/') {
+        Some((addr, prefix)) => (addr, Some(prefix.parse::<u8>().ok()?)),
+        None => (addr, None),
+    };
+    let addr = addr.split_once('%').map_or(addr, |(addr, _zone)| addr);
+    match addr.parse::<IpAddr>().ok()? {
+        IpAddr::V4(v4) => Some((4, u32::from(v4).into(), prefix.unwrap_or(32), port)),
+        IpAddr::V6(v6) => Some((6, u128::from(v6), prefix.unwrap_or(128), port)),
+    }
+}
+
+/// Rewrites the cells of `-datecol` columns with an output format, cells that
+/// can't be reformatted are kept.
+fn reformat_dates(rows: &mut [Vec<Cell>], datecols: &[DateCol]) {
+    for date in datecols {
+        for row in rows.iter_mut() {
+            if let Some(value) = row.get(date.column).and_then(|cell| date.reformat(cell)) {
+                row[date.column] = value.into();
+            }
+        }
+    }
+}
+
+/// Rewrites all number cells in `style` (`-numfmt-out`), other cells are kept.
+fn restyle_numbers(rows: &mut [Vec<Cell>], fmt: Option<NumFmt>, style: NumStyle) {
+    for cell in rows.iter_mut().flatten() {
+        if let Some(value) = restyle_number(cell, fmt, style) {
+            *cell = value.into();
+        }
+    }
+}
+
+/// Parses the key columns of `-on N[:M]`.
+///
+/// # Returns
+///
+/// - `Ok((n, m))` with the 0-based key column of the input and of the joined table
+/// - `Err(String)` if the columns aren't 1-based column numbers
+fn parse_join_on(spec: &str) -> Result<(usize, usize), String> {
+    let invalid = || format!("Invalid --on '{}': expected N or N:M", spec);
+    let (input, other) = spec.split_once(':').unwrap_or((spec, spec));
+    let input: usize = input.trim().parse().map_err(|_| invalid())?;
+    let other: usize = other.trim().parse().map_err(|_| invalid())?;
+    if input == 0 || other == 0 {
+        return Err("Column numbers must be 1-based".to_string());
+    }
+    Ok((input - 1, other - 1))
+}
+
+/// Joins the rows of another table to the input rows with the same key (`-join`).
+///
+/// Every pair of matching rows becomes one row: the input cells, padded to the
+/// width of the input, followed by the cells of the other row without its key.
+/// The other table needn't be sorted. Separator rows of the input are kept
+/// unless the rows of the joined table are kept.
+///
+/// # Arguments
+///
+/// * `headers` - Header cells of the input, the other headers are appended
+/// * `rows` - Data rows of the input
+/// * `other` - Headers and rows of the joined table
+/// * `keys` - 0-based key columns of the input and of the joined table
+/// * `opts` - Processing options, `join_type` decides which unmatched rows are kept
+///
+/// # Returns
+///
+/// - `Ok(Vec)` with the joined rows
+/// - `Err(String)` if a key column is beyond its table
+fn join_tables(
+    headers: &mut Vec<Cell>,
+    rows: Vec<Vec<Cell>>,
+    other: (Vec<Cell>, Vec<Vec<Cell>>),
+    keys: (usize, usize),
+    opts: &ProcessOptions,
+) -> Result<Vec<Vec<Cell>>, String> {
+    let (other_headers, mut other_rows) = other;
+    other_rows.retain(|row| !row.is_empty());
+    let width = rows
+        .iter()
+        .map(Vec::len)
+        .max()
+        .unwrap_or(0)
+        .max(headers.len());
+    let other_width = other_rows
+        .iter()
+        .map(Vec::len)
+        .max()
+        .unwrap_or(0)
+        .max(other_headers.len());
+    for (key, count, message) in [
+        (keys.0, width, "on-exceeds-input"),
+        (keys.1, other_width, "on-exceeds-joined"),
+    ] {
+        if key >= count && count > 0 {
+            return Err(opts.lang.tr(message, &[&(key + 1), &count]));
+        }
+    }
+
+    // The cells of a row of the other table without its key
+    let other_cells = |row: &[Cell]| -> Vec<Cell> {
+        (0..other_width)
+            .filter(|&i| i != keys.1)
+            .map(|i| row.get(i).cloned().unwrap_or_default())
+            .collect()
+    };
+    let key = |row: &[Cell], col: usize| row.get(col).map_or("", |c| c.trim()).to_string();
+    let index = |rows: &[Vec<Cell>], col: usize| {
+        let mut index: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, row) in rows.iter().enumerate().filter(|(_, row)| !row.is_empty()) {
+            index.entry(key(row, col)).or_default().push(i);
+        }
+        index
+    };
+
+    if !headers.is_empty() || !other_headers.is_empty() {
+        headers.resize(width, Cell::default());
+        headers.extend(other_cells(&other_headers));
+    }
+    let joined = |mut row: Vec<Cell>, other: &[Cell]| {
+        row.resize(width, Cell::default());
+        row.extend(other_cells(other));
+        row
+    };
+
+    let mut out = Vec::new();
+    if opts.join_type == JoinType::Right {
+        let matches = index(&rows, keys.0);
+        for other in &other_rows {
+            match matches.get(&key(other, keys.1)) {
+                Some(found) => {
+                    for &i in found {
+                        out.push(joined(rows[i].clone(), other));
+                    }
+                }
+                None => {
+                    let mut row = vec![Cell::default(); width.max(keys.0 + 1)];
+                    row[keys.0] = other.get(keys.1).cloned().unwrap_or_default();
+                    out.push(joined(row, other));
+                }
+            }
+        }
+        return Ok(out);
+    }
+    let matches = index(&other_rows, keys.1);
+    for row in rows {
+        if row.is_empty() {
+            out.push(row);
+            continue;
+        }
+        match matches.get(&key(&row, keys.0)) {
+            Some(found) => {
+                for &i in found {
+                    out.push(joined(row.clone(), &other_rows[i]));
+                }
+            }
+            None if opts.join_type == JoinType::Left => out.push(joined(row, &[])),
+            None => {}
+        }
+    }
+    Ok(out)
+}
+
+/// Removes the columns without a non-empty data cell (`-drop-empty-cols`).
+///
+/// A table without data rows is kept as it is.
+///
+/// # Arguments
+///
+/// * `headers` - Header cells
+/// * `rows` - Data rows
+/// * `indices` - Input columns of the leading output columns, removed with them
+fn drop_empty_columns(headers: &mut Vec<Cell>, rows: &mut [Vec<Cell>], indices: &mut Vec<usize>) {
+    if rows.iter().all(|row| is_separator(row)) {
+        return;
+    }
+    let width = rows
+        .iter()
+        .map(Vec::len)
+        .max()
+        .unwrap_or(0)
+        .max(headers.len());
+    let keep: Vec<bool> = (0..width)
+        .map(|i| {
+            rows.iter()
+                .any(|row| row.get(i).is_some_and(|c| !c.is_empty()))
+        })
+        .collect();
+    retain_columns(headers, &keep);
+    retain_columns(indices, &keep);
+    for row in rows.iter_mut() {
+        retain_columns(row, &keep);
+    }
+}
+
+/// Keeps the cells whose column is marked in `keep`.
+fn retain_columns<T>(cells: &mut Vec<T>, keep: &[bool]) {
+    let mut col = 0;
+    cells.retain(|_| {
+        col += 1;
+        keep[col - 1]
+    });
+}
+
+/// Checks whether a row is a separator row, e.g. between groups.
+///
+/// `-gcol` separators have no cells, those of `-keep-blank` and of tables read back
+/// from earlier output have empty cells.
+fn is_separator(row: &[Cell]) -> bool {
+    row.iter().all(|c| c.is_empty())
+}
+
+/// Header of a `-gcount` or `-uniq-c` column, empty if the table has no headers.
+fn count_header(has_headers: bool) -> Cell {
+    if has_headers {
+        Cell::from("count")
+    } else {
+        Cell::default()
+    }
+}
+
+/// Checks whether a line starts with the `-comment` marker (after white space).
+fn is_comment(line: &str, opts: &ProcessOptions) -> bool {
+    opts.comment
+        .as_deref()
+        .is_some_and(|marker| line.trim_start().starts_with(marker))
+}
+
+/// Splits a line at the separators outside of single or double quotes (`-quoted`).
+///
+/// Like a shell, `"John Smith"` and `'a; b'` stay one cell, and quotes are removed
+/// from the cells unless `keep_quotes` is set. A quote without a closing quote is
+/// ordinary text.
+///
+/// # Arguments
+///
+/// * `line` - The input line
+/// * `sep_regex` - Regex matching a separator
+/// * `max_cols` - Split into at most this many cells (`-max-cols`)
+/// * `keep_quotes` - Keep the quotes in the cells (`-keep-quotes`)
+///
+/// # Returns
+///
+/// The cells of the line; cells without quotes to remove share the line
+fn split_quoted(
+    line: &Arc<str>,
+    sep_regex: &Regex,
+    max_cols: Option<usize>,
+    keep_quotes: bool,
+) -> Vec<Cell> {
+    // Byte ranges of the quoted parts, including the quotes
+    let mut quoted = Vec::new();
+    let mut open: Option<(usize, char)> = None;
+    for (i, c) in line.char_indices() {
+        match open {
+            None if c == '"' || c == '\'' => open = Some((i, c)),
+            Some((start, q)) if c == q => {
+                quoted.push(start..i + 1);
+                open = None;
+            }
+            _ => {}
+        }
+    }
+
+    // Byte ranges of the cells
+    let mut cells = Vec::new();
+    let mut start = 0;
+    for m in sep_regex.find_iter(line) {
+        if max_cols.is_some_and(|n| cells.len() + 1 >= n) {
+            break;
+        }
+        if m.is_empty() || quoted.iter().any(|r| r.contains(&m.start())) {
+            continue;
+        }
+        cells.push(start..m.start());
+        start = m.end();
+    }
+    cells.push(start..line.len());
+
+    cells
+        .into_iter()
+        .map(|cell| {
+            if keep_quotes || !quoted.iter().any(|r| cell.contains(&r.start)) {
+                return Cell::part_of(line, &line[cell]);
+            }
+            // Remove the quotes of the quoted parts, back to front
+            let mut text = line[cell.clone()].to_string();
+            for r in quoted.iter().rev().filter(|r| cell.contains(&r.start)) {
+                text.remove(r.end - 1 - cell.start);
+                text.remove(r.start - cell.start);
+            }
+            text.into()
+        })
+        .collect()
+}
+
+/// Returns the number of cells a well-formed row has: the header width, or
+/// without a header the most common row width (the widest on a tie).
+fn expected_width(headers: &[Cell], rows: &[Vec<Cell>]) -> usize {
+    if !headers.is_empty() {
+        return headers.len();
+    }
+    let mut counts: HashMap<usize, usize> = HashMap::new();
+    for row in rows {
+        *counts.entry(row.len()).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .max_by_key(|&(width, count)| (count, width))
+        .map_or(0, |(width, _)| width)
+}
+
+/// Parses a `-fill N:TEXT` default for missing cells of input column N.
+///
+/// # Returns
+///
+/// - `Ok((index, text))` with the 0-based input column
+/// - `Err(String)` if the spec is not of the form `N:TEXT` with a 1-based N
+fn parse_fill(spec: &str) -> Result<(usize, Cell), String> {
+    let (col, text) = spec
+        .split_once(':')
+        .ok_or_else(|| format!("Invalid --fill '{}': expected N:TEXT", spec))?;
+    match col.parse::<usize>() {
+        Ok(n) if n > 0 => Ok((n - 1, text.into())),
+        _ => Err(format!(
+            "Invalid --fill '{}': N must be a 1-based column number",
+            spec
+        )),
+    }
+}
+
+/// Returns the regex splitting input lines into cells.
+///
+/// `-sep-regex` is used as given, `-sep auto` detects the separator from `lines`,
+/// any other `-sep` is a literal string of one or more characters. With `-mb` a
+/// run of separators counts as one; the default blank separator then matches
+/// runs of any whitespace.
+///
+/// # Returns
+///
+/// - `Ok(Regex)` matching one separator
+/// - `Err(String)` if `-sep-regex` is not a valid regex
+fn separator_regex<S: AsRef<str>>(opts: &ProcessOptions, lines: &[S]) -> Result<Regex, String> {
+    let pattern = if let Some(pattern) = &opts.sep_regex {
+        pattern.clone()
+    } else if opts.sep == "auto" {
+        detect_separator(lines).1.to_string()
+    } else if opts.mb && opts.sep == " " {
+        // More blanks -> split by one or more whitespace
+        return Ok(Regex::new(r"\s+").unwrap());
+    } else {
+        // Escape the separator if it's a special regex character
+        regex::escape(&opts.sep)
+    };
+    let pattern = if opts.mb {
+        format!("(?:{})+", pattern)
+    } else {
+        pattern
+    };
+    Regex::new(&pattern).map_err(|e| format!("Invalid --sep-regex: {}", e))
+}
+
+/// Separators tried by `-sep auto`: name and regex, in order of preference.
+const SEPARATOR_CANDIDATES: [(&str, &str); 6] = [
+    ("tab", "\t"),
+    ("comma", ","),
+    ("semicolon", ";"),
+    ("pipe", r"\|"),
+    ("multiple spaces", " {2,}"),
+    ("whitespace", r"\s+"),
+];
+
+/// Number of non-empty lines `-sep auto` looks at.
+const SEPARATOR_SAMPLE: usize = 20;
+
+/// Detects the separator of the input for `-sep auto`.
+///
+/// Splits the first non-empty lines with every candidate (tab, comma, semicolon,
+/// pipe, two or more spaces, any whitespace) and picks the one splitting the most
+/// lines into the same number of at least two cells; ties go to the earlier
+/// candidate. Without such a candidate the input is split at whitespace.
+///
+/// # Arguments
+///
+/// * `lines` - Input lines
+///
+/// # Returns
+///
+/// The name of the separator (e.g. `tab`) and its regex
+pub fn detect_separator<S: AsRef<str>>(lines: &[S]) -> (&'static str, &'static str) {
+    let sample: Vec<&str> = lines
+        .iter()
+        .map(|l| l.as_ref().trim())
+        .filter(|l| !l.is_empty())
+        .take(SEPARATOR_SAMPLE)
+        .collect();
+
+    let mut best = SEPARATOR_CANDIDATES[SEPARATOR_CANDIDATES.len() - 1];
+    let mut best_lines = 0;
+    for candidate in SEPARATOR_CANDIDATES {
+        let regex = Regex::new(candidate.1).unwrap();
+        let mut counts: HashMap<usize, usize> = HashMap::new();
+        for line in &sample {
+            *counts.entry(regex.split(line).count()).or_default() += 1;
+        }
+        let consistent = counts
+            .into_iter()
+            .filter(|&(cells, _)| cells >= 2)
+            .map(|(_, lines)| lines)
+            .max()
+            .unwrap_or(0);
+        if consistent > best_lines {
+            best = candidate;
+            best_lines = consistent;
+        }
+    }
+    best
+}
+
+/// Compiled row filters: `-filter`, `-filter-not` and `-where`.
+#[derive(Default)]
+struct RowFilter {
+    filter: Vec<Regex>,
+    filter_not: Vec<Regex>,
+    conditions: Vec<WhereCondition>,
+    /// Most cells of a row checked against the conditions, for [`RowFilter::check_columns`]
+    widest: AtomicUsize,
+}
+
+impl RowFilter {
+    /// Compiles the filter patterns and conditions of the options.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(RowFilter)` with all patterns and conditions compiled
+    /// - `Err(String)` if a regex or condition is invalid
+    fn new(opts: &ProcessOptions) -> Result<Self, String> {
+        Ok(Self {
+            filter: compile_patterns(&opts.filter, "filter")?,
+            filter_not: compile_patterns(&opts.filter_not, "filter-not")?,
+            conditions: opts
+                .r#where
+                .iter()
+                .map(|spec| WhereCondition::parse(spec))
+                .collect::<Result<Vec<_>, _>>()?,
+            widest: Default::default(),
+        })
+    }
+
+    /// Checks the `-where` columns against the input columns with `-strict`.
+    ///
+    /// Without `-strict` a missing cell is taken as empty. The input columns are
+    /// the headers and the widest row checked against the conditions so far.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if every condition tests an input column
+    /// - `Err(String)` naming the first condition beyond the input with `-strict`
+    fn check_columns(&self, headers: &[Cell], opts: &ProcessOptions) -> Result<(), String> {
+        let available = self.widest.load(AtomicOrdering::Relaxed).max(headers.len());
+        if !opts.strict || available == 0 {
+            return Ok(());
+        }
+        match self.conditions.iter().find(|c| c.column() >= available) {
+            Some(condition) => {
+                let col = condition.column() + 1;
+                let problem = opts.lang.tr(
+                    "option-exceeds-input-columns",
+                    &[&"where", &col, &available],
+                );
+                Err(format!("{} (--strict)", problem))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Checks a raw line against the `-filter` / `-filter-not` patterns.
+    fn line_passes(&self, line: &str) -> bool {
+        (self.filter.is_empty() || self.filter.iter().any(|re| re.is_match(line)))
+            && !self.filter_not.iter().any(|re| re.is_match(line))
+    }
+
+    /// Checks split cells against the `-where` conditions.
+    fn row_passes(&self, row: &[Cell]) -> bool {
+        self.widest.fetch_max(row.len(), AtomicOrdering::Relaxed);
+        self.conditions.iter().all(|c| c.matches(row))
+    }
+
+    /// Keeps the pre-split rows passing all filters, matching patterns on the
+    /// cells joined with a blank.
+    fn apply(&self, rows: Vec<Vec<Cell>>) -> Vec<Vec<Cell>> {
+        rows.into_iter()
+            .filter(|row| self.line_passes(&row.join(" ")) && self.row_passes(row))
+            .collect()
+    }
+}
+
+/// Comparison operator of a `-where` condition.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum WhereOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Match,
+    NotMatch,
+}
+
+/// A single `-where` condition such as `3>100` or `2=~^prod`.
+///
+/// The column refers to the 1-based **input** column, so rows can be filtered
+/// on columns that are not part of the output. `-highlight` uses the same
+/// conditions on output columns.
+#[derive(Debug)]
+pub(crate) struct WhereCondition {
+    col: usize,
+    op: WhereOp,
+    value: String,
+    regex: Option<Regex>,
+}
+
+impl WhereCondition {
+    /// Parses a condition of the form `<column><op><value>`.
+    ///
+    /// Supported operators are `=`/`==`, `!=`, `<`, `<=`, `>`, `>=` (numeric when both
+    /// sides are numbers, otherwise `=`/`!=` compare text) and `=~`/`!~` (regex match).
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - The condition as given on the command line
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(WhereCondition)` if the condition is well-formed
+    /// - `Err(String)` if the column, operator or regex is invalid
+    pub(crate) fn parse(spec: &str) -> Result<Self, String> {
+        let digits = spec.chars().take_while(|c| c.is_ascii_digit()).count();
+        let col: usize = spec[..digits]
+            .parse()
+            .map_err(|_| format!("Invalid where condition (missing column): {}", spec))?;
+        if col == 0 {
+            return Err("Column numbers must be 1-based".to_string());
+        }
+
+        let rest = &spec[digits..];
+        const OPS: [(&str, WhereOp); 9] = [
+            ("=~", WhereOp::Match),
+            ("!~", WhereOp::NotMatch),
+            (">=", WhereOp::Ge),
+            ("<=", WhereOp::Le),
+            ("!=", WhereOp::Ne),
+            ("==", WhereOp::Eq),
+            (">", WhereOp::Gt),
+            ("<", WhereOp::Lt),
+            ("=", WhereOp::Eq),
+        ];
+        let (token, op) = OPS
+            .iter()
+            .find(|(token, _)| rest.starts_with(token))
+            .ok_or_else(|| format!("Invalid where condition (unknown operator): {}", spec))?;
+        let value = rest[token.len()..].to_string();
+
+        let regex = match op {
+            WhereOp::Match | WhereOp::NotMatch => {
+                Some(Regex::new(&value).map_err(|e| format!("Invalid where regex: {}", e))?)
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            col: col - 1,
+            op: *op,
+            value,
+            regex,
+        })
+    }
+
+    /// Returns the 0-based column the condition tests.
+    pub(crate) fn column(&self) -> usize {
+        self.col
+    }
+
+    /// Checks whether a row satisfies the condition.
+    ///
+    /// Missing cells are treated as empty strings. Ordering comparisons on
+    /// non-numeric values never match.
+    pub(crate) fn matches(&self, row: &[Cell]) -> bool {
+        let cell = row.get(self.col).map(|s| s.as_str()).unwrap_or("");
+        if let Some(re) = &self.regex {
+            return re.is_match(cell) == (self.op == WhereOp::Match);
+        }
+
+        let numbers = (cell.parse::<f64>(), self.value.parse::<f64>());
+        match (self.op, numbers) {
+            (WhereOp::Eq, (Ok(a), Ok(b))) => a == b,
+            (WhereOp::Ne, (Ok(a), Ok(b))) => a != b,
+            (WhereOp::Eq, _) => cell == self.value,
+            (WhereOp::Ne, _) => cell != self.value,
+            (WhereOp::Lt, (Ok(a), Ok(b))) => a < b,
+            (WhereOp::Le, (Ok(a), Ok(b))) => a <= b,
+            (WhereOp::Gt, (Ok(a), Ok(b))) => a > b,
+            (WhereOp::Ge, (Ok(a), Ok(b))) => a >= b,
+            _ => false,
+        }
+    }
+}
+
+/// Validates a 1-based output column number given to `-sortcol`, `-gcol` or `-freq`.
+///
+/// Column numbers outside the selected output columns are an error, so that a
+/// sort or grouping that "didn't work" is reported instead of silently skipped.
+/// With `-lenient` they are clamped into range with a warning instead.
+///
+/// # Arguments
+///
+/// * `col` - The column number as given on the command line, if any
+/// * `count` - Number of selected output columns
+/// * `option` - Name of the option, used in messages
+/// * `opts` - Processing options (checks `-lenient`)
+/// * `warnings` - Receives the warning about a clamped or ignored column
+///
+/// # Returns
+///
+/// - `Ok(Some(index))` with the 0-based output column index
+/// - `Ok(None)` if the option was not given (or there are no columns to clamp to)
+/// - `Err(String)` if the column is out of range and `-lenient` is not set
+fn resolve_output_column(
+    col: Option<usize>,
+    count: usize,
+    option: &str,
+    opts: &ProcessOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<Option<usize>, String> {
+    let Some(col) = col else {
+        return Ok(None);
+    };
+    if col >= 1 && col <= count {
+        return Ok(Some(col - 1));
+    }
+
+    let problem = if col == 0 {
+        opts.lang.tr("column-not-1-based", &[&option, &col])
+    } else {
+        opts.lang
+            .tr("exceeds-output-columns", &[&option, &col, &count])
+    };
+    if !opts.lenient {
+        return Err(problem);
+    }
+    if count == 0 {
+        warnings.push(Warning::IgnoredColumn { problem });
+        return Ok(None);
+    }
+    let column = col.clamp(1, count);
+    warnings.push(Warning::ClampedColumn { problem, column });
+    Ok(Some(column - 1))
+}
+
+/// Returns the key used to compare text values when sorting and grouping.
+///
+/// With `-normalize-umlauts` German umlauts and `ß` are expanded to their
+/// two-letter transliterations (`ü` → `ue`, `ß` → `ss`, ...), so that e.g.
+/// `Müller` and `Mueller` sort next to each other and fall into the same group.
+/// Without it the value is compared as is.
+///
+/// # Arguments
+///
+/// * `val` - Cell value
+/// * `opts` - Processing options (checks `-normalize-umlauts`)
+///
+/// # Returns
+///
+/// The collation key, borrowing `val` when no normalization applies
+fn collation_key<'a>(val: &'a str, opts: &ProcessOptions) -> Cow<'a, str> {
+    if !opts.normalize_umlauts || val.is_ascii() {
+        return Cow::Borrowed(val);
+    }
+    let mut key = String::with_capacity(val.len() + 4);
+    for c in val.chars() {
+        match c {
+            'ä' => key.push_str("ae"),
+            'ö' => key.push_str("oe"),
+            'ü' => key.push_str("ue"),
+            'Ä' => key.push_str("Ae"),
+            'Ö' => key.push_str("Oe"),
+            'Ü' => key.push_str("Ue"),
+            'ß' => key.push_str("ss"),
+            _ => key.push(c),
+        }
+    }
+    Cow::Owned(key)
+}
+
+/// Compiles a list of regex patterns given on the command line.
+///
+/// # Arguments
+///
+/// * `patterns` - Regex patterns to compile
+/// * `option` - Name of the option the patterns came from, used in error messages
+///
+/// # Returns
+///
+/// - `Ok(Vec<Regex>)` with one compiled regex per pattern
+/// - `Err(String)` if any pattern is not a valid regex
+fn compile_patterns(patterns: &[String], option: &str) -> Result<Vec<Regex>, String> {
+    patterns
+        .iter()
+        .map(|p| Regex::new(p).map_err(|e| format!("Invalid {} regex: {}", option, e)))
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::field_reassign_with_default)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_data_creation() {
+        let data = TableData {
+            headers: vec!["Col1".into(), "Col2".into()],
+            rows: vec![vec!["A".into(), "B".into()], vec!["C".into(), "D".into()]],
+            original_column_indices: vec![0, 1],
+        };
+
+        assert_eq!(data.headers.len(), 2);
+        assert_eq!(data.rows.len(), 2);
+        assert_eq!(data.original_column_indices, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_process_simple_data() {
+        let lines = vec![
+            "Name Age".to_string(),
+            "Alice 30".to_string(),
+            "Bob 25".to_string(),
+        ];
+
+        let opts = ProcessOptions::default();
+        let result = process_input(lines, &opts).unwrap();
+
+        assert_eq!(result.headers, vec!["Name", "Age"]);
+        assert_eq!(result.rows.len(), 2);
+        assert_eq!(result.rows[0], vec!["Alice", "30"]);
+        assert_eq!(result.rows[1], vec!["Bob", "25"]);
+    }
+
+    #[test]
+    fn test_process_with_filter() {
+        let lines = vec![
+            "Name Age".to_string(),
+            "Alice 30".to_string(),
+            "Bob 25".to_string(),
+            "Charlie 35".to_string(),
+        ];
+
+        let mut opts = ProcessOptions::default();
+        opts.filter = vec!["Bob".to_string()];
+
+        let result = process_input(lines, &opts).unwrap();
+
+        // The header line is exempt from filtering
+        assert_eq!(result.headers, vec!["Name", "Age"]);
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0], vec!["Bob", "25"]);
+    }
+
+    #[test]
+    fn test_process_with_multiple_filters() {
+        let lines = vec![
+            "Name Age".to_string(),
+            "Alice 30".to_string(),
+            "Bob 25".to_string(),
+            "Charlie 35".to_string(),
+        ];
+
+        let mut opts = ProcessOptions::default();
+        opts.filter = vec!["Alice".to_string(), "Charlie".to_string()];
+
+        let result = process_input(lines, &opts).unwrap();
+
+        assert_eq!(result.headers, vec!["Name", "Age"]);
+        assert_eq!(result.rows.len(), 2);
+        assert_eq!(result.rows[0][0], "Alice");
+        assert_eq!(result.rows[1][0], "Charlie");
+    }
+
+    #[test]
+    fn test_process_with_filter_not() {
+        let lines = vec![
+            "Name Age".to_string(),
+            "Alice 30".to_string(),
+            "Bob 25".to_string(),
+            "Charlie 35".to_string(),
+        ];
+
+        let mut opts = ProcessOptions::default();
+        opts.filter_not = vec!["^B".to_string(), "Name".to_string()];
+
+        let result = process_input(lines, &opts).unwrap();
+
+        assert_eq!(result.headers, vec!["Name", "Age"]);
+        assert_eq!(result.rows.len(), 2);
+        assert_eq!(result.rows[0][0], "Alice");
+        assert_eq!(result.rows[1][0], "Charlie");
+    }
+
+    #[test]
+    fn test_process_with_where_numeric() {
+        let lines = vec![
+            "Name Age".to_string(),
+            "Alice 30".to_string(),
+            "Bob 25".to_string(),
+            "Charlie 35".to_string(),
+        ];
+
+        let mut opts = ProcessOptions::default();
+        opts.r#where = vec!["2>=30".to_string()];
+
+        let result = process_input(lines, &opts).unwrap();
+
+        assert_eq!(result.headers, vec!["Name", "Age"]);
+        assert_eq!(result.rows.len(), 2);
+        assert_eq!(result.rows[0][0], "Alice");
+        assert_eq!(result.rows[1][0], "Charlie");
+    }
+
+    #[test]
+    fn test_process_with_where_regex_and_text() {
+        let lines = vec![
+            "Host Env".to_string(),
+            "web1 prod-eu".to_string(),
+            "web2 test".to_string(),
+            "db1 prod-us".to_string(),
+        ];
+
+        let mut opts = ProcessOptions::default();
+        opts.r#where = vec!["2=~^prod".to_string(), "1!=db1".to_string()];
+
+        let result = process_input(lines, &opts).unwrap();
+
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0], vec!["web1", "prod-eu"]);
+    }
+
+    #[test]
+    fn test_process_with_fill() {
+        let lines = vec![
+            "Host Env Owner".to_string(),
+            "web1 prod alice".to_string(),
+            "web2".to_string(),
+            "db1 test".to_string(),
+        ];
 
-    let mut filtered_lines = Vec::new();
-    for line in lines {
-        if let Some(re) = &filter_regex {
-            if !re.is_match(&line) {
-                continue;
-            }
-        }
-        filtered_lines.push(line);
+        let mut opts = ProcessOptions::default();
+        opts.fill = vec!["3:n/a".to_string(), "2:unknown".to_string()];
+        opts.columns = vec!["1".to_string(), "3".to_string(), "2".to_string()];
+
+        let result = process_input(lines, &opts).unwrap();
+
+        assert_eq!(result.rows[0], vec!["web1", "alice", "prod"]);
+        assert_eq!(result.rows[1], vec!["web2", "n/a", "unknown"]);
+        assert_eq!(result.rows[2], vec!["db1", "n/a", "test"]);
+
+        opts.fill = vec!["0:x".to_string()];
+        assert!(process_input(vec!["a".to_string()], &opts).is_err());
+        opts.fill = vec!["x".to_string()];
+        assert!(process_input(vec!["a".to_string()], &opts).is_err());
     }
 
-    if filtered_lines.is_empty() {
-        return Ok(TableData {
-            headers,
-            rows,
-            original_column_indices: Vec::new(),
-        });
+    #[test]
+    fn test_detect_separator() {
+        let lines = |text: &str| text.lines().map(str::to_string).collect::<Vec<_>>();
+        assert_eq!(detect_separator(&lines("a\tb c\n1\t2 3")).0, "tab");
+        assert_eq!(detect_separator(&lines("a,b;c\n1,2;3\n4,5")).0, "comma");
+        assert_eq!(detect_separator(&lines("a;b\n1,5;2\n3;4")).0, "semicolon");
+        assert_eq!(
+            detect_separator(&lines("NAME    FULL NAME\nbob     Bob Smith")).0,
+            "multiple spaces"
+        );
+        assert_eq!(detect_separator(&lines("a b c\n1 2 3")).0, "whitespace");
+        assert_eq!(detect_separator(&lines("single")).0, "whitespace");
+
+        let mut opts = ProcessOptions::default();
+        opts.sep = "auto".to_string();
+        let table = process_input(lines("Name;Age\nAlice;30"), &opts).unwrap();
+        assert_eq!(table.headers, vec!["Name", "Age"]);
+        assert_eq!(table.rows, vec![vec!["Alice", "30"]]);
     }
 
-    // 2. Split lines into columns
-    // Determine separator regex
-    let sep_regex = if args.mb {
-        Regex::new(r"\s+").unwrap() // More blanks -> split by one or more whitespace
-    } else {
-        // Escape the separator if it's a special regex character
-        let sep_pattern = regex::escape(&args.sep);
-        Regex::new(&sep_pattern).unwrap()
-    };
+    #[test]
+    fn test_separators() {
+        let lines = |text: &str| text.lines().map(str::to_string).collect::<Vec<_>>();
+        let mut opts = ProcessOptions::default();
+        opts.sep = "::".to_string();
+        let table = process_input(lines("a::b\n1::2:3"), &opts).unwrap();
+        assert_eq!(table.rows, vec![vec!["1", "2:3"]]);
 
-    // Handle Header
-    // If -header is provided, use it.
-    // If -nhl (no headline) is NOT set, and no -header provided, assume first line is header?
-    // Requirement: "-header='...' Headerline, if the text has no headers, you can define headers."
-    // "-nhl no headline The data contains no headline."
-    // This implies:
-    // If -header is set: Use it as header.
-    // If -nhl is set: No header in data, treat all lines as data.
-    // If neither: Is the first line a header?
-    // Usually CLI tools assume no header unless specified, OR assume first line is header.
-    // "rcol reads the complete input... -header='...' Headerline, if the text has no headers, you can define headers."
-    // This suggests the input might NOT have headers by default.
-    // But -nhl says "The data contains no headline". This implies the DEFAULT is that data MIGHT have a headline?
-    // Let's look at -rh "RemoveHeader removes the first line."
-    // If -rh is used, we drop the first line.
-
-    // Let's assume:
-    // If -header is set, we use it.
-    // If -rh is set, we skip the first line of input.
-    // If -nhl is set, we treat all (remaining) lines as data.
-    // If neither -header nor -nhl is set, do we treat first line as header?
-    // Most likely, rcol treats all input as data unless told otherwise, OR it treats first line as header if not told -nhl.
-    // Given -nhl exists, it strongly suggests the default is "Expect Headline".
-    // So: Default = First line is header.
-    // -nhl = No header in input (all lines are data).
-    // -header = Use this string as header.
-    // -rh = Remove first line (maybe it was a bad header?).
-
-    let line_iter = filtered_lines.into_iter();
-
-    // Handle input lines
-    let mut first_line = true;
-    for line in line_iter {
-        if first_line {
-            first_line = false;
-            if args.rh {
-                continue; // Remove first line
-            }
-            if args.header.is_none() && !args.nhl {
-                // Treat first line as header
-                let parts: Vec<String> = sep_regex.split(&line).map(|s| s.to_string()).collect();
-                headers = parts;
-                continue;
-            }
-        }
+        opts.sep = ",".to_string();
+        opts.mb = true;
+        let table = process_input(lines("a,b\n1,,,2"), &opts).unwrap();
+        assert_eq!(table.rows, vec![vec!["1", "2"]]);
 
-        let parts: Vec<String> = sep_regex.split(&line).map(|s| s.to_string()).collect();
-        rows.push(parts);
+        opts.mb = false;
+        opts.sep_regex = Some(r"\t|;".to_string());
+        let table = process_input(lines("a;b\tc\n1\t2;3"), &opts).unwrap();
+        assert_eq!(table.headers, vec!["a", "b", "c"]);
+        assert_eq!(table.rows, vec![vec!["1", "2", "3"]]);
+
+        opts.sep_regex = Some("(".to_string());
+        assert!(
+            process_input(lines("a"), &opts)
+                .unwrap_err()
+                .contains("--sep-regex")
+        );
     }
 
-    // 3. Column Selection & Reordering
-    // Parse column specs from args.columns
-    let mut col_indices: Vec<usize> = Vec::new();
-    if !args.columns.is_empty() {
-        for col_spec in &args.columns {
-            if col_spec.contains(':') {
-                // Range
-                let parts: Vec<&str> = col_spec.split(':').collect();
-                if parts.len() == 2 {
-                    let start: usize = parts[0]
-                        .parse()
-                        .map_err(|_| format!("Invalid range start: {}", parts[0]))?;
-                    let end: usize = parts[1]
-                        .parse()
-                        .map_err(|_| format!("Invalid range end: {}", parts[1]))?;
-                    // 1-based to 0-based
-                    if start == 0 || end == 0 {
-                        return Err("Column numbers must be 1-based".to_string());
-                    }
-                    if start <= end {
-                        for i in start..=end {
-                            col_indices.push(i - 1);
-                        }
-                    } else {
-                        // Reverse range? "To rearrange the columns the columns can given in the wanted order."
-                        // Usually ranges are low:high. But if user wants 3:1, maybe?
-                        // Let's support reverse ranges if start > end.
-                        let mut i = start;
-                        while i >= end {
-                            col_indices.push(i - 1);
-                            if i == 0 {
-                                break;
-                            } // Should not happen due to check above
-                            i -= 1;
-                        }
-                    }
-                } else {
-                    return Err(format!("Invalid range format: {}", col_spec));
-                }
-            } else {
-                // Single number
-                let idx: usize = col_spec
-                    .parse()
-                    .map_err(|_| format!("Invalid column number: {}", col_spec))?;
-                if idx == 0 {
-                    return Err("Column numbers must be 1-based".to_string());
-                }
-                col_indices.push(idx - 1);
-            }
-        }
-    } else {
-        // Default: all columns.
-        // We need to know max columns to select all.
-        // We can check the first row or header.
-        let max_cols = rows.iter().map(|r| r.len()).max().unwrap_or(0);
-        let header_cols = headers.len();
-        let count = std::cmp::max(max_cols, header_cols);
-        for i in 0..count {
-            col_indices.push(i);
-        }
-    }
-
-    // Apply selection to headers and rows
-    let mut new_headers = Vec::new();
-    for &idx in &col_indices {
-        if idx < headers.len() {
-            new_headers.push(headers[idx].clone());
-        } else {
-            new_headers.push("".to_string());
-        }
+    #[test]
+    fn test_process_max_cols() {
+        let lines: Vec<String> = vec![
+            "USER PID COMMAND".into(),
+            "root 1 /sbin/init splash".into(),
+            "bob 42 vim".into(),
+        ];
+        let mut opts = ProcessOptions::default();
+        opts.max_cols = Some(3);
+        let table = process_input(lines.clone(), &opts).unwrap();
+        assert_eq!(table.headers, vec!["USER", "PID", "COMMAND"]);
+        assert_eq!(
+            table.rows,
+            vec![
+                vec!["root", "1", "/sbin/init splash"],
+                vec!["bob", "42", "vim"]
+            ]
+        );
+
+        opts.max_cols = Some(0);
+        assert!(process_input(lines, &opts).is_err());
     }
-    headers = new_headers;
 
-    // Handle explicit header argument (applied to OUTPUT columns)
-    if let Some(h) = &args.header {
-        let mut parts: Vec<String> = sep_regex.split(h).map(|s| s.to_string()).collect();
-        // Adjust length to match output columns
-        if parts.len() < col_indices.len() {
-            parts.resize(col_indices.len(), "".to_string());
-        } else if parts.len() > col_indices.len() {
-            parts.truncate(col_indices.len());
-        }
-        headers = parts;
+    #[test]
+    fn test_process_counts() {
+        let lines: Vec<String> = [
+            "Dept Name",
+            "IT Carol",
+            "IT Carol",
+            "Sales Alice",
+            "IT Carol",
+        ]
+        .map(String::from)
+        .to_vec();
+        let mut opts = ProcessOptions::default();
+        opts.uniq_c = true;
+        let table = process_input(lines.clone(), &opts).unwrap();
+        assert_eq!(table.headers, vec!["Dept", "Name", "count"]);
+        assert_eq!(
+            table.rows,
+            vec![
+                vec!["IT", "Carol", "2"],
+                vec!["Sales", "Alice", "1"],
+                vec!["IT", "Carol", "1"]
+            ]
+        );
+
+        opts.uniq_c = false;
+        opts.gcount = true;
+        opts.gcol = Some(1);
+        opts.sortcol = Some(1);
+        let table = process_input(lines, &opts).unwrap();
+        assert_eq!(table.headers, vec!["Dept", "Name", "count"]);
+        assert_eq!(
+            table.rows,
+            vec![
+                vec!["IT", "Carol", "3"],
+                vec!["", "Carol", ""],
+                vec!["", "Carol", ""],
+                vec![],
+                vec!["Sales", "Alice", "1"]
+            ]
+        );
     }
 
-    let mut new_rows = Vec::new();
-    for row in rows {
-        let mut new_row = Vec::new();
-        for &idx in &col_indices {
-            if idx < row.len() {
-                new_row.push(row[idx].clone());
-            } else {
-                new_row.push("".to_string());
-            }
+    #[test]
+    fn test_process_freq() {
+        let lines: Vec<String> = [
+            "code path",
+            "404 /a",
+            "200 /b",
+            "404 /c",
+            "500 /a",
+            "200 /a",
+        ]
+        .map(String::from)
+        .to_vec();
+        let mut opts = ProcessOptions::default();
+        opts.freq = Some(1);
+        opts.freq_bar = Some(4);
+        let table = process_input(lines.clone(), &opts).unwrap();
+        assert_eq!(table.headers, vec!["code", "count", "percent", ""]);
+        assert_eq!(
+            table.rows,
+            vec![
+                vec!["404", "2", "40.0", "####"],
+                vec!["200", "2", "40.0", "####"],
+                vec!["500", "1", "20.0", "##"]
+            ]
+        );
+
+        opts.lang = Lang::De;
+        let table = process_input(lines.clone(), &opts).unwrap();
+        assert_eq!(table.headers, vec!["code", "Anzahl", "Prozent", ""]);
+
+        opts.freq = Some(3);
+        assert!(process_input(lines, &opts).is_err());
+    }
+
+    #[test]
+    fn test_process_stats() {
+        let lines: Vec<String> = ["name size time", "a 10 1.5", "b 20", "c 3 x", "d 7 2.5"]
+            .map(String::from)
+            .to_vec();
+        let mut opts = ProcessOptions::default();
+        opts.stats = true;
+        let table = process_input(lines, &opts).unwrap();
+        assert_eq!(
+            table.headers,
+            vec!["column", "count", "min", "max", "mean", "median", "stddev"]
+        );
+        assert_eq!(
+            table.rows,
+            vec![vec!["size", "4", "3", "20", "10", "8.5", "7.2571803524"]]
+        );
+
+        opts.lang = Lang::De;
+        let lines = ["name size", "a 10"].map(String::from).to_vec();
+        let table = process_input(lines, &opts).unwrap();
+        assert_eq!(
+            table.headers,
+            vec![
+                "Spalte",
+                "Anzahl",
+                "Min",
+                "Max",
+                "Mittelwert",
+                "Median",
+                "Standardabweichung"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_process_round_and_humanize() {
+        let lines: Vec<String> = [
+            "file size ratio",
+            "a 1500000 0.5",
+            "b 980 0.126",
+            "c 2000 n/a",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let opts = ProcessOptions {
+            sortcol: Some(2),
+            round: vec!["3:2".to_string()],
+            humanize: vec!["2".to_string()],
+            ..ProcessOptions::default()
+        };
+        let data = process_input(lines.clone(), &opts).unwrap();
+        // Sorted on the values before they are humanized
+        assert_eq!(data.rows[0], ["b", "980", "0.13"]);
+        assert_eq!(data.rows[1], ["c", "2.0k", "n/a"]);
+        assert_eq!(data.rows[2], ["a", "1.5M", "0.50"]);
+
+        for (round, humanize, message) in [
+            (vec!["4:1"], vec![], "--precision 4 exceeds"),
+            (vec!["1"], vec![], "Invalid precision"),
+            (vec![], vec!["2:si"], "Invalid humanize"),
+            (vec![], vec!["0"], "1-based"),
+        ] {
+            let opts = ProcessOptions {
+                round: round.into_iter().map(String::from).collect(),
+                humanize: humanize.into_iter().map(String::from).collect(),
+                ..ProcessOptions::default()
+            };
+            let err = process_input(lines.clone(), &opts).unwrap_err();
+            assert!(err.contains(message), "{}", err);
         }
-        new_rows.push(new_row);
     }
-    rows = new_rows;
-
-    // 4. Sorting
-    if let Some(sort_col) = args.sortcol {
-        // sort_col is 1-based output column number
-        if sort_col > 0 && sort_col <= col_indices.len() {
-            let idx = sort_col - 1;
-            // Check if numeric sort is needed?
-            // "Number refers to the number of the output column."
-            // Usually text sort unless specified otherwise.
-            // Requirement doesn't explicitly say numeric sort, but "-nn no numerical don't format numerical content right adjusted"
-            // implies numerical detection.
-            // For sorting, let's stick to string sort for now, or try numeric if it looks like number?
-            // Simple string sort is safer unless we want to be fancy.
-            rows.sort_by(|a, b| {
-                let val_a = &a[idx];
-                let val_b = &b[idx];
-                // Try numeric sort if both are numbers?
-                if let (Ok(num_a), Ok(num_b)) = (val_a.parse::<f64>(), val_b.parse::<f64>()) {
-                    num_a.partial_cmp(&num_b).unwrap_or(Ordering::Equal)
-                } else {
-                    val_a.cmp(val_b)
-                }
-            });
+
+    #[test]
+    fn test_ip_sort_key() {
+        let keys: Vec<_> = [
+            "10.0.0.2",
+            "10.0.0.10:443",
+            "[::1]:22",
+            "10.0.0.0/8",
+            "fe80::1%eth0",
+        ]
+        .iter()
+        .map(|cell| ip_sort_key(cell))
+        .collect();
+        assert_eq!(keys[0], Some((4, 0x0a00_0002, 32, 0)));
+        assert_eq!(keys[1], Some((4, 0x0a00_000a, 32, 443)));
+        assert_eq!(keys[2], Some((6, 1, 128, 22)));
+        assert_eq!(keys[3], Some((4, 0x0a00_0000, 8, 0)));
+        assert!(keys[4].is_some());
+        for invalid in ["*:22", "host", "10.0.0.1:x", "10.0.0.0/x", "[::1"] {
+            assert_eq!(ip_sort_key(invalid), None, "{}", invalid);
         }
     }
 
-    // 5. Grouping
-    if let Some(gcol) = args.gcol {
-        if gcol > 0 && gcol <= col_indices.len() {
-            let idx = gcol - 1;
-            let mut last_val = String::new();
-            // We need to iterate and modify.
-            // But we also need to insert separators?
-            // "write a separator when the value in this column is different to the value in the previous line"
-            // Wait, "write a separator" - does it mean insert a row? Or just visual separator?
-            // "In the grouped column the second and all following lines of a group get the value '""'."
-            // This implies modifying the data.
-            // "write a separator" might mean a blank line or a line with dashes?
-            // Usually in these tools it means a blank line or a specific separator line.
-            // Let's assume it means inserting a separator row OR just modifying the values.
-            // "write a separator... In the grouped column..."
-            // It seems to imply TWO things:
-            // 1. Separator between groups.
-            // 2. Hiding repeated values.
-
-            // Let's implement hiding repeated values first.
-            // And for separator, maybe insert a special row? Or handle in formatter?
-            // If I insert a row here, it complicates the TableData structure (which expects uniform columns).
-            // Maybe I should add a `is_separator` flag to rows?
-            // Or just let the formatter handle it?
-            // But `process_input` returns `TableData`.
-            // Let's modify `TableData` to support separator rows?
-            // Or just insert an empty row?
-
-            // "write a separator... to group the values"
-            // Let's insert an empty row (all empty strings) between groups.
-
-            let mut grouped_rows = Vec::new();
-            let mut first = true;
-
-            for mut row in rows {
-                let val = row[idx].clone();
-                if !first && val != last_val {
-                    // Group change
-                    // Insert separator row?
-                    // Let's insert a row of empty strings.
-                    let empty_row = vec!["".to_string(); row.len()];
-                    grouped_rows.push(empty_row);
-                }
+    #[test]
+    fn test_process_comment_and_blank_lines() {
+        let lines: Vec<String> = vec![
+            "# generated".into(),
+            "".into(),
+            "Name Size".into(),
+            "a 1".into(),
+            "  # a 2".into(),
+            "".into(),
+            "   ".into(),
+            "b 3".into(),
+            "".into(),
+        ];
+        let mut opts = ProcessOptions::default();
+        opts.comment = Some("#".to_string());
+        let table = process_input(lines.clone(), &opts).unwrap();
+        assert_eq!(table.headers, vec!["Name", "Size"]);
+        assert_eq!(table.rows, vec![vec!["a", "1"], vec!["b", "3"]]);
 
-                if !first && val == last_val && !args.gcolval {
-                    // Hide value
-                    row[idx] = "".to_string();
-                }
+        opts.keep_blank = true;
+        opts.columns = vec!["2".to_string(), "1".to_string()];
+        let table = process_input(lines.clone(), &opts).unwrap();
+        assert_eq!(
+            table.rows,
+            vec![vec!["1", "a"], vec!["", ""], vec!["3", "b"]]
+        );
 
-                last_val = val;
-                grouped_rows.push(row);
-                first = false;
-            }
-            rows = grouped_rows;
-        }
+        let pipeline = Pipeline::new(opts.clone());
+        assert_eq!(pipeline.first_data_line(&lines), Some("Name Size".into()));
+
+        opts.comment = Some(String::new());
+        assert!(process_input(lines, &opts).is_err());
     }
 
-    Ok(TableData {
-        headers,
-        rows,
-        original_column_indices: col_indices,
-    })
-}
+    #[test]
+    fn test_expand_tabs() {
+        assert_eq!(expand_tabs("a\tbc\td", 4), "a   bc  d");
+        assert_eq!(expand_tabs("\tabcd\t", 4), "    abcd    ");
+        assert_eq!(expand_tabs("日本\tx", 8), "日本    x");
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let lines: Vec<String> = vec!["Name\tSize".into(), "a.txt  \t12".into()];
+        let mut opts = ProcessOptions::default();
+        opts.mb = true;
+        opts.expand_tabs = Some(8);
+        let table = process_input(lines.clone(), &opts).unwrap();
+        assert_eq!(table.rows, vec![vec!["a.txt", "12"]]);
+
+        opts.expand_tabs = Some(0);
+        assert!(process_input(lines, &opts).is_err());
+    }
 
     #[test]
-    fn test_table_data_creation() {
-        let data = TableData {
-            headers: vec!["Col1".to_string(), "Col2".to_string()],
-            rows: vec![
-                vec!["A".to_string(), "B".to_string()],
-                vec!["C".to_string(), "D".to_string()],
-            ],
-            original_column_indices: vec![0, 1],
-        };
+    fn test_fill_layout() {
+        let items: Vec<String> = ["a", "b", "c", "d", "e"].map(String::from).to_vec();
+        assert_eq!(
+            fill_layout(items.clone(), 4, false),
+            vec![vec!["a", "c", "e"], vec!["b", "d", ""]]
+        );
+        assert_eq!(
+            fill_layout(items.clone(), 2, true),
+            vec![vec!["a", "b"], vec!["c", "d"], vec!["e", ""]]
+        );
+        assert_eq!(
+            fill_layout(items[..2].to_vec(), 3, true),
+            vec![vec!["a", "b"]]
+        );
+        assert!(fill_layout(Vec::new(), 3, false).is_empty());
 
-        assert_eq!(data.headers.len(), 2);
-        assert_eq!(data.rows.len(), 2);
-        assert_eq!(data.original_column_indices, vec![0, 1]);
+        let lines: Vec<String> = vec!["# files".into(), "x".into(), "y".into(), "z".into()];
+        let mut opts = ProcessOptions::default();
+        opts.comment = Some("#".to_string());
+        opts.fill_cols = Some(2);
+        let table = process_input(lines, &opts).unwrap();
+        assert!(table.headers.is_empty());
+        assert_eq!(table.rows, vec![vec!["x", "z"], vec!["y", ""]]);
+
+        let lines: Vec<String> = vec!["four".into(), "five".into(), "six".into()];
+        opts.fill_cols = None;
+        opts.fill_width = Some(13);
+        let table = process_input(lines, &opts).unwrap();
+        assert_eq!(table.rows, vec![vec!["four", "six"], vec!["five", ""]]);
     }
 
     #[test]
-    fn test_process_simple_data() {
+    fn test_process_trim() {
+        let lines: Vec<String> = vec!["Name\tSize\t".into(), "  src\t 4 \t".into()];
+        let mut opts = ProcessOptions::default();
+        opts.sep = "\t".to_string();
+        let table = process_input(lines.clone(), &opts).unwrap();
+        assert_eq!(table.rows, vec![vec!["src", " 4"]]);
+
+        opts.trim = super::Trim::None;
+        let table = process_input(lines.clone(), &opts).unwrap();
+        assert_eq!(table.headers, vec!["Name", "Size", ""]);
+        assert_eq!(table.rows, vec![vec!["  src", " 4 ", ""]]);
+
+        opts.trim = super::Trim::Right;
+        opts.trim_cells = true;
+        let table = process_input(lines, &opts).unwrap();
+        assert_eq!(table.rows, vec![vec!["src", "4"]]);
+    }
+
+    #[test]
+    fn test_split_quoted() {
+        let blank = Regex::new(" ").unwrap();
+        assert_eq!(
+            split_quoted(
+                &Arc::from(r#"1 "John Smith" 'a b'c x"#),
+                &blank,
+                None,
+                false
+            ),
+            vec!["1", "John Smith", "a bc", "x"]
+        );
+        assert_eq!(
+            split_quoted(
+                &Arc::from(r#"1 "John Smith" 'a b'c x"#),
+                &blank,
+                Some(3),
+                true
+            ),
+            vec!["1", "\"John Smith\"", "'a b'c x"]
+        );
+        assert_eq!(
+            split_quoted(&Arc::from(r#"it's a "test"#), &blank, None, false),
+            vec!["it's", "a", "\"test"]
+        );
+    }
+
+    #[test]
+    fn test_process_skip_bad_rows() {
         let lines = vec![
-            "Name Age".to_string(),
-            "Alice 30".to_string(),
-            "Bob 25".to_string(),
+            "Host Used Free".to_string(),
+            "web1 10 90".to_string(),
+            "garbage".to_string(),
+            "web2 n/a 80".to_string(),
+            "web3 20 80 extra".to_string(),
+            "web4 30".to_string(),
         ];
 
-        let args = AppArgs::default();
-        let result = process_input(lines, &args).unwrap();
-
-        assert_eq!(result.headers, vec!["Name", "Age"]);
+        let mut opts = ProcessOptions::default();
+        opts.skip_bad_rows = true;
+        let result = process_input(lines.clone(), &opts).unwrap();
         assert_eq!(result.rows.len(), 2);
-        assert_eq!(result.rows[0], vec!["Alice", "30"]);
-        assert_eq!(result.rows[1], vec!["Bob", "25"]);
+        assert_eq!(result.rows[1], vec!["web2", "n/a", "80"]);
+
+        opts.fill = vec!["3:0".to_string()];
+        opts.delta = vec!["3-2".to_string()];
+        let result = process_input(lines, &opts).unwrap();
+        let hosts: Vec<&str> = result.rows.iter().map(|r| r[0].as_str()).collect();
+        assert_eq!(hosts, vec!["web1", "web4"]);
+
+        assert_eq!(expected_width(&[], &[vec!["a".into()]]), 1);
+        let rows = vec![vec![Cell::from("a"); 2], vec![Cell::from("b"); 3]];
+        assert_eq!(expected_width(&[], &rows), 3);
     }
 
     #[test]
-    fn test_process_with_filter() {
+    fn test_process_strict() {
         let lines = vec![
-            "Name Age".to_string(),
-            "Alice 30".to_string(),
-            "Bob 25".to_string(),
-            "Charlie 35".to_string(),
+            "Host Used Free".to_string(),
+            "web1 10 90".to_string(),
+            String::new(),
+            "web2 20".to_string(),
         ];
+        let opts = ProcessOptions {
+            strict: true,
+            ..ProcessOptions::default()
+        };
+        let err = process_input(lines.clone(), &opts).unwrap_err();
+        assert_eq!(err, "Line 4 has 2 columns, expected 3 (--strict)");
+
+        let opts = ProcessOptions {
+            strict: true,
+            fill: vec!["3:0".to_string()],
+            columns: vec!["1".to_string(), "3".to_string()],
+            ..ProcessOptions::default()
+        };
+        assert_eq!(
+            process_input(lines.clone(), &opts).unwrap().rows[1],
+            ["web2", "0"]
+        );
+
+        let opts = ProcessOptions {
+            strict: true,
+            fill: vec!["3:0".to_string()],
+            columns: vec!["4".to_string()],
+            ..ProcessOptions::default()
+        };
+        let err = process_input(lines.clone(), &opts).unwrap_err();
+        assert!(
+            err.contains("Column 4 exceeds the number of input columns (3)"),
+            "{}",
+            err
+        );
+
+        // Without --strict a missing cell is empty and the condition just fails
+        let mut opts = ProcessOptions {
+            fill: vec!["3:0".to_string()],
+            r#where: vec!["9>1".to_string()],
+            ..ProcessOptions::default()
+        };
+        assert!(process_input(lines.clone(), &opts).unwrap().rows.is_empty());
+        opts.strict = true;
+        assert_eq!(
+            process_input(lines, &opts).unwrap_err(),
+            "--where 9 exceeds the number of input columns (3) (--strict)"
+        );
+    }
 
-        let mut args = AppArgs::default();
-        args.filter = Some("Bob".to_string());
+    #[test]
+    fn test_process_ragged() {
+        let lines = vec![
+            "Host Path Size".to_string(),
+            "web1 /var/log 10".to_string(),
+            "web2 /srv/my files 20".to_string(),
+            "web3 /tmp".to_string(),
+        ];
+        let rows = |ragged| {
+            let opts = ProcessOptions {
+                ragged,
+                ..ProcessOptions::default()
+            };
+            process_input(lines.clone(), &opts).unwrap().rows
+        };
+        assert_eq!(rows(Ragged::Pad)[1], ["web2", "/srv/my", "files", "20"]);
+        assert_eq!(rows(Ragged::Pad)[2], ["web3", "/tmp", "", ""]);
+        assert_eq!(rows(Ragged::Truncate)[1], ["web2", "/srv/my", "files"]);
+        assert_eq!(rows(Ragged::Truncate)[2], ["web3", "/tmp", ""]);
+        assert_eq!(rows(Ragged::MergeLast)[1], ["web2", "/srv/my", "files 20"]);
+        let hosts: Vec<String> = rows(Ragged::Drop)
+            .into_iter()
+            .map(|r| r[0].to_string())
+            .collect();
+        assert_eq!(hosts, ["web1"]);
+    }
 
-        let result = process_input(lines, &args).unwrap();
+    #[test]
+    fn test_where_condition_parse_errors() {
+        assert!(WhereCondition::parse(">5").is_err());
+        assert!(WhereCondition::parse("0>5").is_err());
+        assert!(WhereCondition::parse("2?5").is_err());
+        assert!(WhereCondition::parse("2=~(").is_err());
+    }
 
-        // Filter is applied AFTER header extraction, so should get 1 data row
-        // But header doesn't match filter, so it gets treated as data and filtered out
-        // Actually, looking at the code: filter is applied first, then header is extracted
-        // So "Name Age" will be filtered out, and Bob becomes the header
-        assert_eq!(result.headers, vec!["Bob", "25"]);
-        assert_eq!(result.rows.len(), 0);
+    #[test]
+    fn test_where_non_numeric_ordering() {
+        let cond = WhereCondition::parse("1>10").unwrap();
+        assert!(!cond.matches(&["abc".into()]));
+        assert!(cond.matches(&["11".into()]));
+        assert!(!cond.matches(&[]));
     }
 
     #[test]
     fn test_process_with_custom_header() {
         let lines = vec!["Alice 30".to_string(), "Bob 25".to_string()];
 
-        let mut args = AppArgs::default();
-        args.header = Some("Name Age".to_string());
-        args.nhl = true;
+        let mut opts = ProcessOptions::default();
+        opts.header = Some("Name Age".to_string());
+        opts.nhl = true;
 
-        let result = process_input(lines, &args).unwrap();
+        let result = process_input(lines, &opts).unwrap();
 
         assert_eq!(result.headers, vec!["Name", "Age"]);
         assert_eq!(result.rows.len(), 2);
@@ -399,10 +3659,10 @@ mod tests {
             "Bob 25 LA".to_string(),
         ];
 
-        let mut args = AppArgs::default();
-        args.columns = vec!["1".to_string(), "3".to_string()];
+        let mut opts = ProcessOptions::default();
+        opts.columns = vec!["1".to_string(), "3".to_string()];
 
-        let result = process_input(lines, &args).unwrap();
+        let result = process_input(lines, &opts).unwrap();
 
         assert_eq!(result.headers, vec!["Name", "City"]);
         assert_eq!(result.rows[0], vec!["Alice", "NYC"]);
@@ -413,10 +3673,10 @@ mod tests {
     fn test_process_column_range() {
         let lines = vec!["A B C D".to_string(), "1 2 3 4".to_string()];
 
-        let mut args = AppArgs::default();
-        args.columns = vec!["2:4".to_string()];
+        let mut opts = ProcessOptions::default();
+        opts.columns = vec!["2:4".to_string()];
 
-        let result = process_input(lines, &args).unwrap();
+        let result = process_input(lines, &opts).unwrap();
 
         assert_eq!(result.headers, vec!["B", "C", "D"]);
         assert_eq!(result.rows[0], vec!["2", "3", "4"]);
@@ -426,10 +3686,10 @@ mod tests {
     fn test_process_column_reorder() {
         let lines = vec!["A B C".to_string(), "1 2 3".to_string()];
 
-        let mut args = AppArgs::default();
-        args.columns = vec!["3".to_string(), "1".to_string(), "2".to_string()];
+        let mut opts = ProcessOptions::default();
+        opts.columns = vec!["3".to_string(), "1".to_string(), "2".to_string()];
 
-        let result = process_input(lines, &args).unwrap();
+        let result = process_input(lines, &opts).unwrap();
 
         assert_eq!(result.headers, vec!["C", "A", "B"]);
         assert_eq!(result.rows[0], vec!["3", "1", "2"]);
@@ -444,10 +3704,10 @@ mod tests {
             "B 200".to_string(),
         ];
 
-        let mut args = AppArgs::default();
-        args.sortcol = Some(2);
+        let mut opts = ProcessOptions::default();
+        opts.sortcol = Some(2);
 
-        let result = process_input(lines, &args).unwrap();
+        let result = process_input(lines, &opts).unwrap();
 
         assert_eq!(result.rows[0][1], "100");
         assert_eq!(result.rows[1][1], "200");
@@ -463,16 +3723,55 @@ mod tests {
             "Bob 35".to_string(),
         ];
 
-        let mut args = AppArgs::default();
-        args.sortcol = Some(1);
+        let mut opts = ProcessOptions::default();
+        opts.sortcol = Some(1);
 
-        let result = process_input(lines, &args).unwrap();
+        let result = process_input(lines, &opts).unwrap();
 
         assert_eq!(result.rows[0][0], "Alice");
         assert_eq!(result.rows[1][0], "Bob");
         assert_eq!(result.rows[2][0], "Charlie");
     }
 
+    #[test]
+    fn test_process_sorting_desc() {
+        let lines = vec![
+            "Name Value".to_string(),
+            "A 100".to_string(),
+            "C 300".to_string(),
+            "B 200".to_string(),
+        ];
+
+        let mut opts = ProcessOptions::default();
+        opts.sortcol = Some(2);
+        opts.sort_desc = true;
+
+        let result = process_input(lines, &opts).unwrap();
+
+        assert_eq!(result.rows[0][1], "300");
+        assert_eq!(result.rows[1][1], "200");
+        assert_eq!(result.rows[2][1], "100");
+    }
+
+    #[test]
+    fn test_process_reverse() {
+        let lines = vec![
+            "Name Value".to_string(),
+            "A 1".to_string(),
+            "B 2".to_string(),
+            "C 3".to_string(),
+        ];
+
+        let mut opts = ProcessOptions::default();
+        opts.reverse = true;
+
+        let result = process_input(lines, &opts).unwrap();
+
+        assert_eq!(result.headers, vec!["Name", "Value"]);
+        assert_eq!(result.rows[0][0], "C");
+        assert_eq!(result.rows[2][0], "A");
+    }
+
     #[test]
     fn test_process_grouping() {
         let lines = vec![
@@ -482,10 +3781,10 @@ mod tests {
             "Engineering Charlie".to_string(),
         ];
 
-        let mut args = AppArgs::default();
-        args.gcol = Some(1);
+        let mut opts = ProcessOptions::default();
+        opts.gcol = Some(1);
 
-        let result = process_input(lines, &args).unwrap();
+        let result = process_input(lines, &opts).unwrap();
 
         // Second row should have empty dept (grouping hides repeated values)
         assert_eq!(result.rows[0][0], "Sales");
@@ -493,14 +3792,249 @@ mod tests {
         assert_eq!(result.rows[3][0], "Engineering");
     }
 
+    #[test]
+    fn test_process_grouping_normalize_umlauts() {
+        let lines = vec![
+            "Name First".to_string(),
+            "Müller Anna".to_string(),
+            "Mueller Ben".to_string(),
+            "Maier Carl".to_string(),
+        ];
+
+        let mut opts = ProcessOptions::default();
+        opts.sortcol = Some(1);
+        opts.gcol = Some(1);
+        opts.normalize_umlauts = true;
+
+        let result = process_input(lines, &opts).unwrap();
+
+        // Maier, then Müller/Mueller as one group
+        assert_eq!(result.rows.len(), 4);
+        assert_eq!(result.rows[0][0], "Maier");
+        assert!(result.rows[1].is_empty());
+        assert_eq!(result.rows[2][0], "Müller");
+        assert_eq!(result.rows[3][0], "");
+        assert_eq!(result.rows[3][1], "Ben");
+    }
+
+    #[test]
+    fn test_collation_key() {
+        let mut opts = ProcessOptions::default();
+        assert_eq!(collation_key("Straße", &opts), "Straße");
+        opts.normalize_umlauts = true;
+        assert_eq!(collation_key("Straße", &opts), "Strasse");
+        assert_eq!(collation_key("Äpfel Öl Übel", &opts), "Aepfel Oel Uebel");
+    }
+
+    #[test]
+    fn test_process_sortcol_out_of_range() {
+        let lines = vec!["Name Age".to_string(), "Bob 25".to_string()];
+
+        let mut opts = ProcessOptions::default();
+        opts.sortcol = Some(3);
+
+        let err = process_input(lines, &opts).unwrap_err();
+        assert!(err.contains("--sortcol 3 exceeds the number of output columns (2)"));
+    }
+
+    #[test]
+    fn test_process_gcol_zero() {
+        let lines = vec!["Name Age".to_string(), "Bob 25".to_string()];
+
+        let mut opts = ProcessOptions::default();
+        opts.gcol = Some(0);
+
+        assert!(process_input(lines, &opts).is_err());
+    }
+
+    #[test]
+    fn test_process_lenient_clamps_columns() {
+        let lines = vec![
+            "Name Age".to_string(),
+            "Bob 35".to_string(),
+            "Alice 25".to_string(),
+        ];
+
+        let mut opts = ProcessOptions::default();
+        opts.sortcol = Some(5);
+        opts.lenient = true;
+
+        let result = process_input(lines, &opts).unwrap();
+
+        // Clamped to the last column (Age)
+        assert_eq!(result.rows[0], vec!["Alice", "25"]);
+        assert_eq!(result.rows[1], vec!["Bob", "35"]);
+    }
+
+    #[test]
+    fn test_compare_rows() {
+        let row = |cells: &[&str]| -> Vec<Cell> { cells.iter().map(|&c| Cell::from(c)).collect() };
+        let mut opts = ProcessOptions {
+            sortcol: Some(2),
+            ..ProcessOptions::default()
+        };
+        let (a, b) = (row(&["x", "9"]), row(&["y", "10"]));
+        assert_eq!(
+            Pipeline::new(opts.clone()).compare_rows(&a, &b),
+            Ordering::Less
+        );
+        opts.sort_desc = true;
+        assert_eq!(
+            Pipeline::new(opts.clone()).compare_rows(&a, &b),
+            Ordering::Greater
+        );
+        opts.sort_ip = true;
+        let (ip, text) = (row(&["x", "10.0.0.1"]), row(&["y", "host"]));
+        assert_eq!(
+            Pipeline::new(opts.clone()).compare_rows(&ip, &text),
+            Ordering::Less
+        );
+        assert!(Pipeline::new(opts.clone()).merges());
+        assert!(!Pipeline::new(opts.clone()).streams());
+
+        opts.round = vec!["2:1".to_string()];
+        assert!(!Pipeline::new(opts.clone()).merges());
+        opts.sortcol = None;
+        assert!(Pipeline::new(opts.clone()).merges());
+        assert_eq!(Pipeline::new(opts).compare_rows(&a, &b), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_process_borrowed() {
+        let lines = ["Name,Age,City", "Bob,35,Berlin", "", "Alice,30,Paris"];
+        let owned: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        for opts in [
+            ProcessOptions {
+                columns: vec!["3".to_string(), "1".to_string()],
+                sortcol: Some(1),
+                ..ProcessOptions::default()
+            },
+            ProcessOptions {
+                filter: vec!["Bob".to_string()],
+                keep_blank: true,
+                ..ProcessOptions::default()
+            },
+            ProcessOptions {
+                expand_tabs: Some(4),
+                ..ProcessOptions::default()
+            },
+        ] {
+            let pipeline = Pipeline::new(opts);
+            let borrowed = pipeline.process_borrowed(&lines).unwrap();
+            let data = pipeline.process(owned.clone()).unwrap();
+            assert_eq!(borrowed.headers, data.headers);
+            assert_eq!(borrowed.rows, data.rows);
+        }
+    }
+
+    #[test]
+    fn test_pipeline_warnings() {
+        let lines = vec!["Name Age".to_string(), "Bob 35".to_string()];
+        let opts = ProcessOptions {
+            sortcol: Some(5),
+            lenient: true,
+            ..ProcessOptions::default()
+        };
+        let pipeline = Pipeline::new(opts.clone());
+        pipeline.process(lines.clone()).unwrap();
+        let warnings = pipeline.take_warnings();
+        assert_eq!(
+            warnings,
+            [Warning::ClampedColumn {
+                problem: "--sortcol 5 exceeds the number of output columns (2)".to_string(),
+                column: 2
+            }]
+        );
+        assert_eq!(
+            warnings[0].message(Lang::En),
+            "--sortcol 5 exceeds the number of output columns (2), using column 2"
+        );
+        assert!(pipeline.take_warnings().is_empty());
+
+        // Fatal with -strict
+        let opts = ProcessOptions {
+            strict: true,
+            ..opts
+        };
+        let err = Pipeline::new(opts).process(lines).unwrap_err();
+        assert!(err.ends_with("using column 2 (--strict)"), "{}", err);
+    }
+
+    #[test]
+    fn test_pipeline_validate() {
+        let valid = ProcessOptions {
+            columns: vec!["3:1".to_string()],
+            sortcol: Some(9),
+            ..ProcessOptions::default()
+        };
+        assert!(Pipeline::new(valid).validate().is_ok());
+
+        for invalid in [
+            ProcessOptions {
+                columns: vec!["0".to_string()],
+                ..ProcessOptions::default()
+            },
+            ProcessOptions {
+                delta: vec!["x".to_string()],
+                ..ProcessOptions::default()
+            },
+            ProcessOptions {
+                sep_regex: Some("(".to_string()),
+                ..ProcessOptions::default()
+            },
+            ProcessOptions {
+                max_cols: Some(0),
+                ..ProcessOptions::default()
+            },
+        ] {
+            assert!(Pipeline::new(invalid).validate().is_err());
+        }
+    }
+
+    #[test]
+    fn test_pipeline_join() {
+        let lines = |text: &str| text.lines().map(String::from).collect::<Vec<_>>();
+        let input = lines("host ip\nweb1 10.0.0.1\ndb1 10.0.0.2\nweb2 10.0.0.3");
+        let owners = lines("owner host\nalice web1\nbob web1\neve x9");
+        let join = |join_type| {
+            let opts = ProcessOptions {
+                join_on: Some("1:2".to_string()),
+                join_type,
+                ..ProcessOptions::default()
+            };
+            let mut pipeline = Pipeline::new(opts);
+            pipeline.join(owners.clone());
+            pipeline.process(input.clone()).unwrap()
+        };
+
+        let table = join(JoinType::Inner);
+        assert_eq!(table.headers, ["host", "ip", "owner"]);
+        assert_eq!(
+            table.rows,
+            [["web1", "10.0.0.1", "alice"], ["web1", "10.0.0.1", "bob"]]
+        );
+        let table = join(JoinType::Left);
+        assert_eq!(table.rows.len(), 4);
+        assert_eq!(table.rows[3], ["web2", "10.0.0.3", ""]);
+        let table = join(JoinType::Right);
+        assert_eq!(table.rows.len(), 3);
+        assert_eq!(table.rows[2], ["x9", "", "eve"]);
+
+        assert_eq!(parse_join_on("2"), Ok((1, 1)));
+        assert_eq!(parse_join_on("1:3"), Ok((0, 2)));
+        for invalid in ["0", "x", "1:", "1:0"] {
+            assert!(parse_join_on(invalid).is_err(), "{}", invalid);
+        }
+    }
+
     #[test]
     fn test_process_with_mb() {
         let lines = vec!["Name    Age".to_string(), "Alice   30".to_string()];
 
-        let mut args = AppArgs::default();
-        args.mb = true;
+        let mut opts = ProcessOptions::default();
+        opts.mb = true;
 
-        let result = process_input(lines, &args).unwrap();
+        let result = process_input(lines, &opts).unwrap();
 
         assert_eq!(result.headers, vec!["Name", "Age"]);
         assert_eq!(result.rows[0], vec!["Alice", "30"]);
@@ -514,13 +4048,13 @@ mod tests {
             "Alice 30".to_string(),
         ];
 
-        let mut args = AppArgs::default();
-        args.rh = true;
+        let mut opts = ProcessOptions::default();
+        opts.rh = true;
         // Also need to tell it there's no header in remaining lines
-        args.nhl = true;
-        args.header = Some("Name Age".to_string());
+        opts.nhl = true;
+        opts.header = Some("Name Age".to_string());
 
-        let result = process_input(lines, &args).unwrap();
+        let result = process_input(lines, &opts).unwrap();
 
         // -rh removes first line, -nhl treats rest as data, custom header applied
         assert_eq!(result.headers, vec!["Name", "Age"]);
@@ -533,25 +4067,111 @@ mod tests {
     fn test_process_no_headline() {
         let lines = vec!["Alice 30".to_string(), "Bob 25".to_string()];
 
-        let mut args = AppArgs::default();
-        args.nhl = true;
-        args.header = Some("Name Age".to_string()); // Need to provide header when using -nhl
+        let mut opts = ProcessOptions::default();
+        opts.nhl = true;
+        opts.header = Some("Name Age".to_string()); // Need to provide header when using -nhl
 
-        let result = process_input(lines, &args).unwrap();
+        let result = process_input(lines, &opts).unwrap();
 
         // With -nhl and custom header, header is set and all lines are data
         assert_eq!(result.headers, vec!["Name", "Age"]);
         assert_eq!(result.rows.len(), 2);
     }
 
+    struct OwnerLookup;
+
+    impl Transform for OwnerLookup {
+        fn transform_row(&self, _headers: &[Cell], row: &mut Vec<Cell>) {
+            row.resize(3, Cell::default());
+            if row[2].is_empty() {
+                row[2] = if row[0].starts_with("db") {
+                    "dba"
+                } else {
+                    "web"
+                }
+                .into();
+            }
+        }
+    }
+
+    struct Trim;
+
+    impl Transform for Trim {
+        fn transform_cell(&self, _col: usize, cell: &mut Cell) {
+            *cell = cell.slice(cell.trim_matches('*'));
+        }
+    }
+
+    #[test]
+    fn test_pipeline_transforms() {
+        let lines = vec![
+            "Host Load Owner".to_string(),
+            "web1 *3* x".to_string(),
+            "db1 1".to_string(),
+        ];
+        let mut opts = ProcessOptions::default();
+        opts.columns = vec!["1".to_string(), "3".to_string(), "2".to_string()];
+        opts.sortcol = Some(3);
+
+        let mut pipeline = Pipeline::new(opts);
+        pipeline
+            .add_transform(Box::new(Trim))
+            .add_transform(Box::new(OwnerLookup));
+        let result = pipeline.process(lines).unwrap();
+
+        assert_eq!(result.rows[0], vec!["db1", "dba", "1"]);
+        assert_eq!(result.rows[1], vec!["web1", "x", "3"]);
+    }
+
     #[test]
     fn test_process_empty_input() {
         let lines = vec![];
-        let args = AppArgs::default();
+        let opts = ProcessOptions::default();
 
-        let result = process_input(lines, &args).unwrap();
+        let result = process_input(lines, &opts).unwrap();
 
         assert!(result.headers.is_empty());
         assert!(result.rows.is_empty());
     }
+
+    #[test]
+    fn test_select_columns() {
+        let lines = vec![
+            "a b c".to_string(),
+            "1 2 3".to_string(),
+            "4 5".to_string(),
+            "6 7 8 9".to_string(),
+        ];
+        let select = |columns: &[&str], fill: &[&str]| {
+            let opts = ProcessOptions {
+                columns: columns.iter().map(|c| c.to_string()).collect(),
+                fill: fill.iter().map(|f| f.to_string()).collect(),
+                ..ProcessOptions::default()
+            };
+            process_input(lines.clone(), &opts).unwrap()
+        };
+
+        let data = select(&["3", "1", "3"], &["3:-"]);
+        assert_eq!(data.headers, ["c", "a", "c"]);
+        assert_eq!(
+            data.rows,
+            [["3", "1", "3"], ["-", "4", "-"], ["8", "6", "8"]]
+        );
+
+        // Columns in input order, extra cells are cut off
+        let data = select(&["1:3"], &[]);
+        assert_eq!(
+            data.rows,
+            [["1", "2", "3"], ["4", "5", ""], ["6", "7", "8"]]
+        );
+
+        let opts = ProcessOptions {
+            sep: ",".to_string(),
+            trim_cells: true,
+            ..ProcessOptions::default()
+        };
+        let data = process_input(vec![" x , y".to_string(), "1 ,  2 ".to_string()], &opts).unwrap();
+        assert_eq!(data.headers, ["x", "y"]);
+        assert_eq!(data.rows, [["1", "2"]]);
+    }
 }
@@ -0,0 +1,112 @@
+//! Random samples and shuffling of rows (`-sample`, `-shuffle`).
+
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+/// A small pseudo random number generator (SplitMix64).
+///
+/// Good enough to pick rows, and the same seed gives the same rows on every
+/// platform (`-seed`).
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    /// Creates a generator from a seed.
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// Creates a generator with a seed that differs from run to run.
+    pub fn from_entropy() -> Self {
+        Self(RandomState::new().hash_one(std::process::id()))
+    }
+
+    /// Returns the next 64 random bits.
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a uniformly distributed number below `n`, which must not be 0.
+    pub fn below(&mut self, n: usize) -> usize {
+        // Rejecting the incomplete last range avoids a bias to small numbers
+        let n = n as u64;
+        let zone = u64::MAX - u64::MAX % n;
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return (x % n) as usize;
+            }
+        }
+    }
+}
+
+/// Keeps a uniform random sample of the rows and/or shuffles them.
+///
+/// # Arguments
+///
+/// * `rows` - The rows
+/// * `sample` - Number of rows to keep, in their original order unless `shuffle`
+/// * `shuffle` - Put the (kept) rows into random order
+/// * `rng` - Source of the random choices
+///
+/// # Returns
+///
+/// The kept rows
+pub fn sample_rows<T>(rows: Vec<T>, sample: Option<usize>, shuffle: bool, rng: &mut Rng) -> Vec<T> {
+    let n = sample.unwrap_or(rows.len()).min(rows.len());
+    // Partial Fisher-Yates shuffle: the first n indices are a random sample
+    let mut indices: Vec<usize> = (0..rows.len()).collect();
+    for i in 0..n {
+        let j = i + rng.below(indices.len() - i);
+        indices.swap(i, j);
+    }
+    indices.truncate(n);
+    if !shuffle {
+        indices.sort_unstable();
+    }
+
+    let mut rows: Vec<Option<T>> = rows.into_iter().map(Some).collect();
+    indices.into_iter().filter_map(|i| rows[i].take()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rng_below() {
+        let mut rng = Rng::new(42);
+        let mut seen = [false; 5];
+        for _ in 0..100 {
+            seen[rng.below(5)] = true;
+        }
+        assert!(seen.iter().all(|&s| s));
+        assert_eq!(Rng::new(7).next_u64(), Rng::new(7).next_u64());
+    }
+
+    #[test]
+    fn test_sample_rows() {
+        let rows: Vec<usize> = (0..20).collect();
+        let sample = sample_rows(rows.clone(), Some(5), false, &mut Rng::new(1));
+        assert_eq!(sample.len(), 5);
+        assert!(sample.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(
+            sample,
+            sample_rows(rows.clone(), Some(5), false, &mut Rng::new(1))
+        );
+
+        let mut shuffled = sample_rows(rows.clone(), None, true, &mut Rng::new(1));
+        assert_ne!(shuffled, rows);
+        shuffled.sort_unstable();
+        assert_eq!(shuffled, rows);
+
+        assert_eq!(
+            sample_rows(rows.clone(), Some(50), false, &mut Rng::new(1)),
+            rows
+        );
+    }
+}
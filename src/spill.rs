@@ -0,0 +1,312 @@
+//! Processed rows kept in a temporary file instead of in memory (`-low-mem`).
+//!
+//! The rows arrive in runs, e.g. the processed chunks of the input, and are read
+//! back one after the other, or merged if every run is sorted.
+
+use crate::cell::Cell;
+use std::cmp::Ordering;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+
+/// Rows read from a run at a time while merging.
+const BATCH: usize = 256;
+
+/// Number of spill files created by this process, for unique names.
+static SPILLS: AtomicUsize = AtomicUsize::new(0);
+
+/// Rows spilled to disk in runs.
+///
+/// The first run stays in memory until a second one arrives, so input that fits
+/// into one run never touches the disk. Every row is written as a JSON array on
+/// a line of its own; the file is removed when the spill is dropped.
+pub struct Spill {
+    /// The first run, as long as it is the only one
+    pending: Option<Vec<Vec<Cell>>>,
+    file: Option<SpillFile>,
+    /// Byte range of every run in the file
+    runs: Vec<(u64, u64)>,
+}
+
+/// The temporary file of a [`Spill`], removed on drop.
+struct SpillFile {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+impl Default for Spill {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Spill {
+    /// Creates an empty spill; the file is only created once it is needed.
+    pub fn new() -> Self {
+        Self {
+            pending: None,
+            file: None,
+            runs: Vec::new(),
+        }
+    }
+
+    /// Adds a run of rows after the runs added so far.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if the run was kept
+    /// - `Err(io::Error)` if the temporary file can't be created or written
+    pub fn push(&mut self, rows: Vec<Vec<Cell>>) -> io::Result<()> {
+        if self.file.is_none() && self.pending.is_none() {
+            self.pending = Some(rows);
+            return Ok(());
+        }
+        if let Some(first) = self.pending.take() {
+            self.write_run(&first)?;
+        }
+        self.write_run(&rows)
+    }
+
+    /// Appends a run to the file, creating it with the first run.
+    fn write_run(&mut self, rows: &[Vec<Cell>]) -> io::Result<()> {
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => self.file.insert(create_file()?),
+        };
+        let start = file.writer.stream_position()?;
+        for row in rows {
+            serde_json::to_writer(&mut file.writer, row)?;
+            file.writer.write_all(b"\n")?;
+        }
+        let end = file.writer.stream_position()?;
+        self.runs.push((start, end));
+        Ok(())
+    }
+
+    /// Reads the rows back.
+    ///
+    /// # Arguments
+    ///
+    /// * `order` - Merges the runs in this order if given, every run must be
+    ///   sorted by it; rows that compare equal keep the order of their runs.
+    ///   Without an order the runs follow each other.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(SpilledRows)` iterating over the rows
+    /// - `Err(io::Error)` if the file can't be written
+    pub fn into_rows<F>(mut self, order: Option<F>) -> io::Result<SpilledRows<F>>
+    where
+        F: Fn(&[Cell], &[Cell]) -> Ordering,
+    {
+        let pending = self.pending.take().unwrap_or_default().into_iter();
+        let file = match self.file.take() {
+            Some(mut file) => {
+                file.writer.flush()?;
+                Some(file)
+            }
+            None => None,
+        };
+        let runs = self
+            .runs
+            .iter()
+            .map(|&(start, end)| Run {
+                pos: start,
+                end,
+                rows: Vec::new(),
+            })
+            .collect();
+        Ok(SpilledRows {
+            pending,
+            file,
+            runs,
+            order,
+            current: 0,
+        })
+    }
+}
+
+/// Creates a temporary file that no other process or spill uses.
+fn create_file() -> io::Result<SpillFile> {
+    let n = SPILLS.fetch_add(1, AtomicOrdering::Relaxed);
+    let path = std::env::temp_dir().join(format!("rcol-spill-{}-{}.jsonl", std::process::id(), n));
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(&path)?;
+    Ok(SpillFile {
+        path,
+        writer: BufWriter::new(file),
+    })
+}
+
+/// The unread part of a run in the spill file.
+struct Run {
+    /// Offset of the next unread row in the file
+    pos: u64,
+    end: u64,
+    /// Rows read ahead, in reverse order
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Run {
+    /// Returns the next row without taking it, reading ahead if needed.
+    fn peek(&mut self, file: &File) -> io::Result<Option<&Vec<Cell>>> {
+        if self.rows.is_empty() && self.pos < self.end {
+            let mut reader = BufReader::new(file);
+            reader.seek(SeekFrom::Start(self.pos))?;
+            let mut line = String::new();
+            while self.rows.len() < BATCH && self.pos < self.end {
+                line.clear();
+                self.pos += reader.read_line(&mut line)? as u64;
+                self.rows.push(serde_json::from_str(&line)?);
+            }
+            self.rows.reverse();
+        }
+        Ok(self.rows.last())
+    }
+
+    /// Takes the row returned by the last [`Run::peek`].
+    fn take(&mut self) -> Option<Vec<Cell>> {
+        self.rows.pop()
+    }
+}
+
+/// The rows of a [`Spill`], see [`Spill::into_rows`].
+pub struct SpilledRows<F> {
+    /// The only run, if it was never written
+    pending: std::vec::IntoIter<Vec<Cell>>,
+    file: Option<SpillFile>,
+    runs: Vec<Run>,
+    order: Option<F>,
+    /// Run that is read while the runs follow each other
+    current: usize,
+}
+
+impl<F> SpilledRows<F>
+where
+    F: Fn(&[Cell], &[Cell]) -> Ordering,
+{
+    fn next_row(&mut self) -> io::Result<Option<Vec<Cell>>> {
+        if let Some(row) = self.pending.next() {
+            return Ok(Some(row));
+        }
+        let Some(file) = &self.file else {
+            return Ok(None);
+        };
+        let file = file.writer.get_ref();
+        let Some(order) = &self.order else {
+            while let Some(run) = self.runs.get_mut(self.current) {
+                if run.peek(file)?.is_some() {
+                    return Ok(run.take());
+                }
+                self.current += 1;
+            }
+            return Ok(None);
+        };
+
+        // The smallest next row of all runs, the first run wins a tie
+        let mut smallest: Option<(usize, &Vec<Cell>)> = None;
+        for (i, run) in self.runs.iter_mut().enumerate() {
+            if let Some(row) = run.peek(file)? {
+                match smallest {
+                    Some((_, min)) if order(row, min) != Ordering::Less => {}
+                    _ => smallest = Some((i, row)),
+                }
+            }
+        }
+        let Some((i, _)) = smallest else {
+            return Ok(None);
+        };
+        Ok(self.runs[i].take())
+    }
+}
+
+impl<F> Iterator for SpilledRows<F>
+where
+    F: Fn(&[Cell], &[Cell]) -> Ordering,
+{
+    type Item = io::Result<Vec<Cell>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_row().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(values: &[&str]) -> Vec<Vec<Cell>> {
+        values.iter().map(|&v| vec![Cell::from(v)]).collect()
+    }
+
+    fn read(spill: Spill, sorted: bool) -> Vec<Cell> {
+        let order = |a: &[Cell], b: &[Cell]| a[0].len().cmp(&b[0].len());
+        spill
+            .into_rows(sorted.then_some(order))
+            .unwrap()
+            .map(|row| row.unwrap().remove(0))
+            .collect()
+    }
+
+    #[test]
+    fn test_spill_in_memory() {
+        let mut spill = Spill::new();
+        spill.push(rows(&["a", "b\nc"])).unwrap();
+        assert!(spill.file.is_none());
+        assert_eq!(read(spill, true), ["a", "b\nc"]);
+    }
+
+    #[test]
+    fn test_spill_merge() {
+        let mut spill = Spill::new();
+        spill.push(rows(&["1", "333", "4444"])).unwrap();
+        spill.push(rows(&["x", "22", "ccc"])).unwrap();
+        spill.push(rows(&[])).unwrap();
+        spill.push(rows(&["\"q\"", "55555"])).unwrap();
+        let path = spill.file.as_ref().unwrap().path.clone();
+        assert!(path.exists());
+
+        let mut unsorted = Spill::new();
+        unsorted.push(rows(&["b", "a"])).unwrap();
+        unsorted.push(rows(&["c"])).unwrap();
+        assert_eq!(read(unsorted, false), ["b", "a", "c"]);
+
+        // Equal lengths keep the order of the runs
+        assert_eq!(
+            read(spill, true),
+            ["1", "x", "22", "333", "ccc", "\"q\"", "4444", "55555"]
+        );
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_spill_batches() {
+        let mut spill = Spill::new();
+        let numbers: Vec<Cell> = (0..BATCH * 3).map(|i| format!("{:04}", i).into()).collect();
+        let (even, odd): (Vec<_>, Vec<_>) = numbers
+            .iter()
+            .partition(|n| n.ends_with(['0', '2', '4', '6', '8']));
+        spill
+            .push(even.iter().map(|&n| vec![n.clone()]).collect())
+            .unwrap();
+        spill
+            .push(odd.iter().map(|&n| vec![n.clone()]).collect())
+            .unwrap();
+        let merged: Vec<Cell> = spill
+            .into_rows(Some(|a: &[Cell], b: &[Cell]| a.cmp(b)))
+            .unwrap()
+            .map(|row| row.unwrap().remove(0))
+            .collect();
+        assert_eq!(merged, numbers);
+    }
+}
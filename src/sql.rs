@@ -0,0 +1,436 @@
+use crate::cell::Cell;
+use crate::numeric::{Number, parse_number};
+use crate::processor::TableData;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Column type inferred from the cells of a column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlType {
+    Integer,
+    Real,
+    Text,
+}
+
+impl SqlType {
+    /// Returns the type name used in `CREATE TABLE`.
+    pub fn name(self) -> &'static str {
+        match self {
+            SqlType::Integer => "INTEGER",
+            SqlType::Real => "REAL",
+            SqlType::Text => "TEXT",
+        }
+    }
+}
+
+/// Returns the data rows of a table without the separator rows of `-gcol` grouping.
+pub fn data_rows(data: &TableData) -> impl Iterator<Item = &Vec<Cell>> {
+    data.rows
+        .iter()
+        .filter(|row| !row.iter().all(|c| c.is_empty()))
+}
+
+/// Returns the SQL column names of a table.
+///
+/// Headers without the `-` right-alignment marker, `column_N` for empty headers
+/// and tables without headers.
+pub fn column_names(data: &TableData) -> Vec<String> {
+    let width = data_rows(data).map(|r| r.len()).max().unwrap_or(0);
+    (0..width.max(data.headers.len()))
+        .map(|i| {
+            match data
+                .headers
+                .get(i)
+                .map(|h| h.strip_prefix('-').unwrap_or(h))
+            {
+                Some(h) if !h.is_empty() => h.to_string(),
+                _ => format!("column_{}", i + 1),
+            }
+        })
+        .collect()
+}
+
+/// Infers the type of every column: `INTEGER` if all non-empty cells are integers,
+/// `REAL` if they are all numbers, `TEXT` otherwise.
+///
+/// Numbers are recognized like with `-infer-types` (see [`parse_number`]); a column
+/// without any non-empty cell is `TEXT`.
+pub fn column_types(data: &TableData, count: usize) -> Vec<SqlType> {
+    (0..count)
+        .map(|i| {
+            let mut cells = data_rows(data)
+                .filter_map(|row| row.get(i))
+                .filter(|c| !c.is_empty())
+                .peekable();
+            if cells.peek().is_none() {
+                return SqlType::Text;
+            }
+            cells.fold(SqlType::Integer, |ty, cell| {
+                match (ty, parse_number(cell)) {
+                    (SqlType::Text, _) | (_, None) => SqlType::Text,
+                    (SqlType::Integer, Some(Number::Int(_))) => SqlType::Integer,
+                    _ => SqlType::Real,
+                }
+            })
+        })
+        .collect()
+}
+
+/// Quotes an SQL identifier with double quotes unless it is a plain name.
+///
+/// A dotted name like `main.pods` is quoted part by part, so it still refers to
+/// a table in a schema.
+pub fn quote_identifier(name: &str) -> String {
+    name.split('.')
+        .map(|part| {
+            let plain = part.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if plain {
+                part.to_string()
+            } else {
+                format!("\"{}\"", part.replace('"', "\"\""))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Returns a cell as SQL literal: `NULL` for empty cells, numbers unquoted in
+/// numeric columns, anything else as string literal with `'` doubled.
+pub fn literal(cell: &str, ty: SqlType) -> String {
+    if cell.is_empty() {
+        "NULL".to_string()
+    } else if ty == SqlType::Text {
+        format!("'{}'", cell.replace('\'', "''"))
+    } else {
+        cell.to_string()
+    }
+}
+
+/// Writes a table as SQL statements (`-sql`).
+///
+/// Writes an optional `CREATE TABLE` statement with the inferred column types and
+/// one `INSERT INTO` statement per data row. Missing cells of ragged rows are `NULL`.
+///
+/// # Arguments
+///
+/// * `data` - Table data to write
+/// * `table` - Name of the table
+/// * `create` - Whether to write the `CREATE TABLE` statement (`-sql-create`)
+/// * `out` - Destination of the output
+///
+/// # Returns
+///
+/// - `Ok(())` if output succeeds
+/// - `Err(io::Error)` if writing fails
+pub fn write_sql(
+    data: &TableData,
+    table: &str,
+    create: bool,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    let names = column_names(data);
+    let types = column_types(data, names.len());
+    let table = quote_identifier(table);
+    let columns = names
+        .iter()
+        .map(|n| quote_identifier(n))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if create {
+        writeln!(out, "CREATE TABLE {} (", table)?;
+        for (i, (name, ty)) in names.iter().zip(&types).enumerate() {
+            let comma = if i + 1 < names.len() { "," } else { "" };
+            writeln!(out, "  {} {}{}", quote_identifier(name), ty.name(), comma)?;
+        }
+        writeln!(out, ");")?;
+    }
+    for row in data_rows(data) {
+        let values = types
+            .iter()
+            .enumerate()
+            .map(|(i, &ty)| literal(row.get(i).map_or("", Cell::as_str), ty))
+            .collect::<Vec<_>>()
+            .join(", ");
+        writeln!(
+            out,
+            "INSERT INTO {} ({}) VALUES ({});",
+            table, columns, values
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes a table into a SQLite database (`-sqlite`, `-table`).
+///
+/// The database file is created if needed, the table too: its columns are named
+/// and typed like the `-sql-create` statement. Rows are appended to an existing
+/// table in one transaction; empty cells are `NULL`.
+///
+/// # Arguments
+///
+/// * `data` - Table data to write
+/// * `path` - Path of the database file
+/// * `table` - Name of the table
+///
+/// # Returns
+///
+/// - `Ok(usize)` with the number of inserted rows
+/// - `Err(String)` if the database can't be opened or a statement fails, e.g.
+///   because an existing table has other columns
+#[cfg(feature = "sqlite")]
+pub fn write_sqlite(data: &TableData, path: &Path, table: &str) -> Result<usize, String> {
+    if column_names(data).is_empty() {
+        return Ok(0);
+    }
+    let sql_error = |e: rusqlite::Error| format!("SQLite error in {}: {}", path.display(), e);
+
+    let mut conn = rusqlite::Connection::open(path).map_err(sql_error)?;
+    let tx = conn.transaction().map_err(sql_error)?;
+    let count = insert_table(&tx, data, table).map_err(sql_error)?;
+    tx.commit().map_err(sql_error)?;
+    Ok(count)
+}
+
+/// Creates `table` if it doesn't exist and inserts the data rows of a table.
+///
+/// # Returns
+///
+/// - `Ok(usize)` with the number of inserted rows
+/// - `Err(rusqlite::Error)` if a statement fails
+#[cfg(feature = "sqlite")]
+fn insert_table(
+    conn: &rusqlite::Connection,
+    data: &TableData,
+    table: &str,
+) -> rusqlite::Result<usize> {
+    use rusqlite::types::Value;
+
+    let names = column_names(data);
+    let types = column_types(data, names.len());
+    let table = quote_identifier(table);
+    let definitions = names
+        .iter()
+        .zip(&types)
+        .map(|(name, ty)| format!("{} {}", quote_identifier(name), ty.name()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    conn.execute(
+        &format!("CREATE TABLE IF NOT EXISTS {} ({})", table, definitions),
+        [],
+    )?;
+
+    let columns = names
+        .iter()
+        .map(|n| quote_identifier(n))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let placeholders = vec!["?"; names.len()].join(", ");
+    let mut insert = conn.prepare(&format!(
+        "INSERT INTO {} ({}) VALUES ({})",
+        table, columns, placeholders
+    ))?;
+    let mut count = 0;
+    for row in data_rows(data) {
+        let values = types.iter().enumerate().map(|(i, &ty)| {
+            let cell = row.get(i).map_or("", Cell::as_str);
+            match (ty, parse_number(cell)) {
+                _ if cell.is_empty() => Value::Null,
+                (SqlType::Integer, Some(Number::Int(n))) => Value::Integer(n),
+                (SqlType::Real, Some(Number::Int(n))) => Value::Real(n as f64),
+                (SqlType::Real, Some(Number::Float(n))) => Value::Real(n),
+                _ => Value::Text(cell.to_string()),
+            }
+        });
+        insert.execute(rusqlite::params_from_iter(values))?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Name of the table a `-query` selects from.
+pub const QUERY_TABLE: &str = "t";
+
+/// Runs an SQL query over a table (`-query`).
+///
+/// The table is loaded into an in-memory SQLite database as [`QUERY_TABLE`],
+/// with the column names and types of `-sql-create`. The result columns are
+/// named like the query names them; `NULL` becomes an empty cell.
+///
+/// # Arguments
+///
+/// * `data` - Table data to query
+/// * `query` - The SQL statement, e.g. `SELECT name, sum(size) FROM t GROUP BY name`
+///
+/// # Returns
+///
+/// - `Ok(TableData)` with the result of the query
+/// - `Err(String)` if the query fails
+#[cfg(feature = "sqlite")]
+pub fn query_table(data: &TableData, query: &str) -> Result<TableData, String> {
+    use crate::processor::NO_INPUT_COLUMN;
+    use rusqlite::types::ValueRef;
+
+    let sql_error = |e: rusqlite::Error| format!("SQL error: {}", e);
+    let conn = rusqlite::Connection::open_in_memory().map_err(sql_error)?;
+    if !column_names(data).is_empty() {
+        insert_table(&conn, data, QUERY_TABLE).map_err(sql_error)?;
+    }
+
+    let mut stmt = conn.prepare(query).map_err(sql_error)?;
+    let headers: Vec<Cell> = stmt.column_names().into_iter().map(Cell::from).collect();
+    let width = headers.len();
+    let rows = stmt
+        .query_map([], |row| {
+            (0..width)
+                .map(|i| {
+                    Ok(match row.get_ref(i)? {
+                        ValueRef::Null => Cell::default(),
+                        ValueRef::Integer(n) => n.to_string().into(),
+                        ValueRef::Real(n) => n.to_string().into(),
+                        ValueRef::Text(text) | ValueRef::Blob(text) => {
+                            String::from_utf8_lossy(text).into()
+                        }
+                    })
+                })
+                .collect::<rusqlite::Result<Vec<Cell>>>()
+        })
+        .map_err(sql_error)?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(sql_error)?;
+
+    // Result columns named like an input column keep its number (-num)
+    let names = column_names(data);
+    let original_column_indices = headers
+        .iter()
+        .map(|name| match names.iter().position(|n| n == name) {
+            Some(i) => data
+                .original_column_indices
+                .get(i)
+                .copied()
+                .unwrap_or(NO_INPUT_COLUMN),
+            None => NO_INPUT_COLUMN,
+        })
+        .collect();
+    Ok(TableData {
+        headers,
+        rows,
+        original_column_indices,
+    })
+}
+
+/// Fails like [`query_table`] does when rcol is built without the `sqlite` feature.
+#[cfg(not(feature = "sqlite"))]
+pub fn query_table(_data: &TableData, _query: &str) -> Result<TableData, String> {
+    Err("rcol was built without the 'sqlite' feature".to_string())
+}
+
+/// Fails like [`write_sqlite`] does when rcol is built without the `sqlite` feature.
+#[cfg(not(feature = "sqlite"))]
+pub fn write_sqlite(_data: &TableData, _path: &Path, _table: &str) -> Result<usize, String> {
+    Err("rcol was built without the 'sqlite' feature".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(headers: &[&str], rows: &[&[&str]]) -> TableData {
+        TableData {
+            headers: headers.iter().map(|&s| Cell::from(s)).collect(),
+            rows: rows
+                .iter()
+                .map(|r| r.iter().map(|&s| Cell::from(s)).collect())
+                .collect(),
+            original_column_indices: (0..headers.len()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_write_sql() {
+        let data = table(
+            &["NAME", "-RESTARTS", "CPU", "ZIP", "my col"],
+            &[
+                &["web", "3", "0.5", "01234", "it's"],
+                &["", "", "", "", ""],
+                &["db", "", "1", "99999", "x"],
+            ],
+        );
+        let mut out = Vec::new();
+        write_sql(&data, "pods", true, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "CREATE TABLE pods (\n  NAME TEXT,\n  RESTARTS INTEGER,\n  CPU REAL,\n  ZIP TEXT,\n  \"my col\" TEXT\n);\n\
+             INSERT INTO pods (NAME, RESTARTS, CPU, ZIP, \"my col\") VALUES ('web', 3, 0.5, '01234', 'it''s');\n\
+             INSERT INTO pods (NAME, RESTARTS, CPU, ZIP, \"my col\") VALUES ('db', NULL, 1, '99999', 'x');\n"
+        );
+    }
+
+    #[test]
+    fn test_quote_identifier() {
+        assert_eq!(quote_identifier("pods"), "pods");
+        assert_eq!(quote_identifier("main.pods"), "main.pods");
+        assert_eq!(quote_identifier("1st"), "\"1st\"");
+        assert_eq!(quote_identifier("a\"b"), "\"a\"\"b\"");
+    }
+
+    #[test]
+    #[cfg(feature = "sqlite")]
+    fn test_write_sqlite() {
+        let path = std::env::temp_dir().join(format!("rcol-test-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let data = table(
+            &["Host", "-Load", "Port"],
+            &[&["db", "0.5", "5432"], &["web", "3", ""]],
+        );
+        assert_eq!(write_sqlite(&data, &path, "hosts").unwrap(), 2);
+        // Appends to the existing table
+        assert_eq!(write_sqlite(&data, &path, "hosts").unwrap(), 2);
+
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        let (count, load, nulls): (i64, f64, i64) = conn
+            .query_row(
+                "SELECT COUNT(*), SUM(Load), SUM(Port IS NULL) FROM hosts",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!((count, load, nulls), (4, 7.0, 2));
+        let port_type: String = conn
+            .query_row("SELECT typeof(Port) FROM hosts LIMIT 1", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(port_type, "integer");
+        drop(conn);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "sqlite")]
+    fn test_query_table() {
+        use crate::processor::NO_INPUT_COLUMN;
+
+        let data = table(
+            &["name", "-size"],
+            &[
+                &["a", "150"],
+                &["b", "50"],
+                &["", ""],
+                &["a", "200"],
+                &["c", "120"],
+            ],
+        );
+        let result = query_table(
+            &data,
+            "SELECT name, sum(size) AS total FROM t WHERE size > 100 GROUP BY name ORDER BY 2 DESC",
+        )
+        .unwrap();
+        assert_eq!(result.headers, ["name", "total"]);
+        assert_eq!(result.rows, [["a", "350"], ["c", "120"]]);
+        assert_eq!(result.original_column_indices, [0, NO_INPUT_COLUMN]);
+
+        let result = query_table(&data, "SELECT avg(size), max(name) FROM t").unwrap();
+        assert_eq!(result.rows, [["130", "c"]]);
+        assert!(query_table(&data, "SELECT nope FROM t").is_err());
+    }
+}
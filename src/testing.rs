@@ -0,0 +1,173 @@
+//! Helpers for scenario tests of the full rcol pipeline (`test-util` feature).
+//!
+//! [`run`] parses a command line like the `rcol` binary, processes a fixture
+//! string and formats the result in-process, [`assert_golden`] compares the
+//! output with a golden file and panics with a line diff if they differ:
+//!
+//! ```
+//! use rcol::testing::run;
+//!
+//! let out = run(&["--csv"], "Name Age\nAlice 30\n").unwrap();
+//! assert_eq!(out, "Name,Age\nAlice,30\n");
+//! ```
+//!
+//! Golden files are (re)written instead of compared when the environment variable
+//! `RCOL_BLESS` is set, e.g. `RCOL_BLESS=1 cargo test --features test-util`.
+
+use crate::args::AppArgs;
+use crate::formatter::{FormatOptions, format_to_string};
+use crate::processor::{Pipeline, ProcessOptions};
+use clap::Parser;
+use std::fs;
+use std::path::Path;
+
+/// Environment variable that makes [`assert_golden`] write the golden files.
+pub const BLESS_VAR: &str = "RCOL_BLESS";
+
+/// Runs the rcol pipeline on `input` as if it was piped into `rcol ARGS`.
+///
+/// The configuration file is not read, so the output only depends on `args`.
+/// Options that read other input or write elsewhere (`-file`, `-cmd`, `-since`,
+/// `-sqlite`) are rejected.
+///
+/// # Arguments
+///
+/// * `args` - Command line arguments without the program name
+/// * `input` - Text read instead of standard input
+///
+/// # Returns
+///
+/// - `Ok(String)` with the formatted output
+/// - `Err(String)` with the argument, processing or formatting error
+pub fn run(args: &[&str], input: &str) -> Result<String, String> {
+    let args = AppArgs::try_parse_from(std::iter::once("rcol").chain(args.iter().copied()))
+        .map_err(|e| e.to_string())?;
+    let unsupported = [
+        ("--file", args.file.is_some()),
+        ("--cmd", args.cmd.is_some()),
+        ("--since", args.since.is_some()),
+        ("--sqlite", args.sqlite.is_some()),
+    ];
+    if let Some((flag, _)) = unsupported.iter().find(|(_, set)| *set) {
+        return Err(format!("{} is not supported in pipeline tests", flag));
+    }
+
+    let lines = if args.null {
+        input.split_terminator('\0').map(str::to_string).collect()
+    } else {
+        input.lines().map(str::to_string).collect()
+    };
+    let data = Pipeline::new(ProcessOptions::from(&args)).process(lines)?;
+    format_to_string(&data, &FormatOptions::from(&args)).map_err(|e| e.to_string())
+}
+
+/// Compares `actual` with the golden file at `path`.
+///
+/// With `RCOL_BLESS` set the golden file is written instead (creating missing
+/// directories), so new scenarios and intended output changes are recorded by
+/// running the tests once with the variable.
+///
+/// # Panics
+///
+/// If the golden file is missing or differs from `actual`, with a diff of the
+/// lines (`-` expected, `+` actual) in the message.
+pub fn assert_golden(path: impl AsRef<Path>, actual: &str) {
+    let path = path.as_ref();
+    if std::env::var_os(BLESS_VAR).is_some() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).unwrap_or_else(|e| panic!("{}: {}", dir.display(), e));
+        }
+        fs::write(path, actual).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        return;
+    }
+
+    let expected = fs::read_to_string(path).unwrap_or_else(|e| {
+        panic!(
+            "cannot read golden file {}: {} (set {} to create it)",
+            path.display(),
+            e,
+            BLESS_VAR
+        )
+    });
+    if expected != actual {
+        panic!(
+            "output differs from golden file {} (set {} to update it):\n{}",
+            path.display(),
+            BLESS_VAR,
+            diff_lines(&expected, actual)
+        );
+    }
+}
+
+/// Builds a line diff of two texts: unchanged lines start with two spaces,
+/// removed lines with `- `, added lines with `+ `.
+///
+/// # Arguments
+///
+/// * `expected` - The old text
+/// * `actual` - The new text
+///
+/// # Returns
+///
+/// The diff, one line per line of both texts
+pub fn diff_lines(expected: &str, actual: &str) -> String {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+
+    // Length of the longest common subsequence of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            diff.push_str(&format!("  {}\n", old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            diff.push_str(&format!("- {}\n", old[i]));
+            i += 1;
+        } else {
+            diff.push_str(&format!("+ {}\n", new[j]));
+            j += 1;
+        }
+    }
+    if expected.ends_with('\n') != actual.ends_with('\n') {
+        diff.push_str("(the texts differ in the newline at the end)\n");
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run() {
+        assert_eq!(run(&[], "a b\n1 2\n").unwrap(), " a   b \n 1   2 \n");
+        assert!(
+            run(&["--file", "x.txt"], "")
+                .unwrap_err()
+                .contains("--file")
+        );
+        assert!(run(&["--no-such-option"], "").is_err());
+    }
+
+    #[test]
+    fn test_diff_lines() {
+        assert_eq!(diff_lines("a\nb\nc\n", "a\nx\nc\n"), "  a\n- b\n+ x\n  c\n");
+        assert_eq!(
+            diff_lines("a", "a\n"),
+            "  a\n(the texts differ in the newline at the end)\n"
+        );
+    }
+}
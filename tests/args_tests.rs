@@ -1,5 +1,9 @@
+#![cfg(feature = "cli")]
+
 use clap::Parser;
 use rcol::args::AppArgs;
+use rcol::formatter::FormatOptions;
+use rcol::processor::ProcessOptions;
 
 #[test]
 fn test_default_args() {
@@ -14,64 +18,150 @@ fn test_default_args() {
 
 #[test]
 fn test_parse_simple_flags() {
-    let args = AppArgs::try_parse_from(&["rcol", "--pp", "--csv"]).unwrap();
+    let args = AppArgs::try_parse_from(["rcol", "--pp", "--csv"]).unwrap();
     assert!(args.pp);
     assert!(args.csv);
 }
 
 #[test]
 fn test_parse_short_flags() {
-    let args = AppArgs::try_parse_from(&["rcol", "-p", "-n"]).unwrap();
+    let args = AppArgs::try_parse_from(["rcol", "-p", "-n"]).unwrap();
     assert!(args.pp);
     assert!(args.num);
 }
 
 #[test]
 fn test_parse_args_with_file() {
-    let args = AppArgs::try_parse_from(&["rcol", "--file", "test.txt"]).unwrap();
+    let args = AppArgs::try_parse_from(["rcol", "--file", "test.txt"]).unwrap();
     assert_eq!(args.file, Some("test.txt".to_string()));
 }
 
 #[test]
 fn test_parse_args_with_header() {
-    let args = AppArgs::try_parse_from(&["rcol", "--header", "Col1 Col2"]).unwrap();
+    let args = AppArgs::try_parse_from(["rcol", "--header", "Col1 Col2"]).unwrap();
     assert_eq!(args.header, Some("Col1 Col2".to_string()));
 }
 
 #[test]
 fn test_parse_args_with_separator() {
-    let args = AppArgs::try_parse_from(&["rcol", "--sep", ","]).unwrap();
+    let args = AppArgs::try_parse_from(["rcol", "--sep", ","]).unwrap();
     assert_eq!(args.sep, ",");
 }
 
 #[test]
 fn test_parse_args_with_columns() {
-    let args = AppArgs::try_parse_from(&["rcol", "1", "2", "3"]).unwrap();
+    let args = AppArgs::try_parse_from(["rcol", "1", "2", "3"]).unwrap();
     assert_eq!(args.columns.len(), 3);
     assert_eq!(args.columns[0], "1");
 }
 
 #[test]
 fn test_parse_args_width() {
-    let args = AppArgs::try_parse_from(&["rcol", "-w", "3"]).unwrap();
+    let args = AppArgs::try_parse_from(["rcol", "-w", "3"]).unwrap();
     assert_eq!(args.w, 3);
 }
 
 #[test]
 fn test_parse_args_sortcol() {
-    let args = AppArgs::try_parse_from(&["rcol", "--sortcol", "2"]).unwrap();
+    let args = AppArgs::try_parse_from(["rcol", "--sortcol", "2"]).unwrap();
     assert_eq!(args.sortcol, Some(2));
 }
 
 #[test]
 fn test_parse_args_gcol() {
-    let args = AppArgs::try_parse_from(&["rcol", "--gcol", "1", "--gcolval"]).unwrap();
+    let args = AppArgs::try_parse_from(["rcol", "--gcol", "1", "--gcolval"]).unwrap();
     assert_eq!(args.gcol, Some(1));
     assert!(args.gcolval);
 }
 
+#[test]
+fn test_parse_args_precision() {
+    let args = AppArgs::try_parse_from(["rcol", "--precision", "2"]).unwrap();
+    assert_eq!(args.computed_precision(), Some(2));
+    assert_eq!(AppArgs::default().computed_precision(), None);
+
+    let args = AppArgs::try_parse_from(["rcol", "--precision=3:2", "--precision=1"]).unwrap();
+    assert_eq!(args.computed_precision(), Some(1));
+    assert_eq!(ProcessOptions::from(&args).round, vec!["3:2".to_string()]);
+    assert!(AppArgs::try_parse_from(["rcol", "--precision", "x"]).is_err());
+}
+
 #[test]
 fn test_parse_args_filter() {
-    let args = AppArgs::try_parse_from(&["rcol", "--filter", "test.*"]).unwrap();
-    assert_eq!(args.filter, Some("test.*".to_string()));
+    let args = AppArgs::try_parse_from(["rcol", "--filter", "test.*"]).unwrap();
+    assert_eq!(args.filter, vec!["test.*".to_string()]);
+}
+
+#[test]
+fn test_parse_args_where() {
+    let args = AppArgs::try_parse_from(["rcol", "--where", "3>100", "-W", "2=~^prod"]).unwrap();
+    assert_eq!(
+        args.r#where,
+        vec!["3>100".to_string(), "2=~^prod".to_string()]
+    );
+}
+
+#[test]
+fn test_parse_args_multiple_filters() {
+    let args = AppArgs::try_parse_from([
+        "rcol",
+        "-F",
+        "Alice",
+        "-F",
+        "Bob",
+        "--filter-not",
+        "Charlie",
+    ])
+    .unwrap();
+    assert_eq!(args.filter, vec!["Alice".to_string(), "Bob".to_string()]);
+    assert_eq!(args.filter_not, vec!["Charlie".to_string()]);
+}
+
+#[test]
+fn test_args_into_options() {
+    let args = AppArgs::try_parse_from([
+        "rcol", "--json", "--header", "A B", "-S", "2", "--file", "x.txt", "2", "1",
+    ])
+    .unwrap();
+
+    let process = ProcessOptions::from(&args);
+    assert_eq!(process.sortcol, Some(2));
+    assert_eq!(process.columns, vec!["2".to_string(), "1".to_string()]);
+
+    let format = FormatOptions::from(&args);
+    assert_eq!(format.format, "json");
+    assert!(format.ts);
+    assert_eq!(format.source.as_deref(), Some("x.txt"));
+}
+
+#[test]
+fn test_output_format_precedence() {
+    let args = AppArgs::try_parse_from(["rcol"]).unwrap();
+    assert_eq!(args.output_format(), "ascii");
+    let args = AppArgs::try_parse_from(["rcol", "--csv", "--emit", "rcol"]).unwrap();
+    assert_eq!(args.output_format(), "rcol");
+    let args = AppArgs::try_parse_from(["rcol", "--csv", "--format", "yaml"]).unwrap();
+    assert_eq!(args.output_format(), "yaml");
+}
+
+#[test]
+fn test_color_choice() {
+    let args = AppArgs::try_parse_from(["rcol", "--color", "never"]).unwrap();
+    assert!(!args.color_enabled());
+    let args = AppArgs::try_parse_from(["rcol", "--color", "always"]).unwrap();
+    assert_eq!(args.color_enabled(), cfg!(feature = "color"));
+    let args = AppArgs::try_parse_from(["rcol", "--color", "always", "--json"]).unwrap();
+    assert!(!args.color_enabled());
+    assert!(AppArgs::try_parse_from(["rcol", "--color", "sometimes"]).is_err());
+}
+
+#[test]
+fn test_sql_table_name() {
+    let args = AppArgs::try_parse_from(["rcol", "--sql", "1", "2"]).unwrap();
+    assert_eq!(args.output_format(), "sql");
+    assert_eq!(args.columns, vec!["1".to_string(), "2".to_string()]);
+    assert_eq!(FormatOptions::from(&args).sql_table, "data");
+
+    let args = AppArgs::try_parse_from(["rcol", "--sql=pods"]).unwrap();
+    assert_eq!(FormatOptions::from(&args).sql_table, "pods");
 }
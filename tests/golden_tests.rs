@@ -0,0 +1,45 @@
+//! Golden-file scenario tests
+//!
+//! Every scenario runs the pipeline on a file of `tests/data` and compares the
+//! output with `tests/golden/<scenario>.out`. Run with `RCOL_BLESS=1` to update
+//! the golden files after an intended output change.
+
+#![cfg(feature = "test-util")]
+
+use rcol::testing::{assert_golden, run};
+use std::fs;
+use std::path::PathBuf;
+
+fn scenario(name: &str, data: &str, args: &[&str]) {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests");
+    let input = fs::read_to_string(dir.join("data").join(data)).unwrap();
+    let output = run(args, &input).unwrap();
+    assert_golden(dir.join("golden").join(format!("{}.out", name)), &output);
+}
+
+#[test]
+fn test_golden_ascii() {
+    scenario("ascii", "simple.txt", &[]);
+    scenario("ascii_pp", "numeric.txt", &["--pp"]);
+    scenario(
+        "ascii_grouped",
+        "grouping.txt",
+        &["--gcol=1", "--sortcol=1", "--cs"],
+    );
+}
+
+#[test]
+fn test_golden_documentation_formats() {
+    scenario("adoc", "numeric.txt", &["--adoc"]);
+    scenario("rst", "numeric.txt", &["--rst"]);
+    scenario("jira", "irregular.txt", &["--jira"]);
+    scenario("screen_reader", "irregular.txt", &["--screen-reader"]);
+}
+
+#[test]
+fn test_golden_structured_formats() {
+    scenario("json", "numeric.txt", &["--json"]);
+    scenario("sql", "numeric.txt", &["--sql-create"]);
+    #[cfg(feature = "html")]
+    scenario("html", "grouping.txt", &["--html"]);
+}